@@ -0,0 +1,36 @@
+//! Full versus range semantic tokens over a large note
+//!
+//! Run with `cargo bench --bench semantic_tokens`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use patto::parser;
+use patto::semantic_token::{get_semantic_tokens, get_semantic_tokens_range};
+
+/// A note of `sections` top-level lines, each with nested lines, links and a task
+fn large_note(sections: usize) -> String {
+    let mut text = String::new();
+    for i in 0..sections {
+        text.push_str(&format!("section {i} [note{i}] {{@anchor s{i}}}\n"));
+        text.push_str(&format!(
+            "\titem [a{i}] `code` {{@task status=todo due=2999-01-01}}\n"
+        ));
+        text.push_str(&format!(
+            "\t\tdetail [b{i}#sec] [https://example.com/{i}]\n"
+        ));
+    }
+    text
+}
+
+fn bench_semantic_tokens(c: &mut Criterion) {
+    let ast = parser::parse_text(&large_note(10_000)).ast;
+    c.bench_function("semantic_tokens_full", |b| {
+        b.iter(|| get_semantic_tokens(black_box(&ast)))
+    });
+    // About one screen in the middle of the note
+    c.bench_function("semantic_tokens_range", |b| {
+        b.iter(|| get_semantic_tokens_range(black_box(&ast), 15_000, 15_060))
+    });
+}
+
+criterion_group!(benches, bench_semantic_tokens);
+criterion_main!(benches);
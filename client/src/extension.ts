@@ -56,7 +56,15 @@ function getPattoConfiguration() {
 	return {
 		markdown: {
 			defaultFlavor: config.get<string>('markdown.defaultFlavor', 'standard')
-		}
+		},
+		rename: {
+			alwaysRenameFile: config.get<boolean>('rename.alwaysRenameFile', false)
+		},
+		links: {
+			ignoreCase: config.get<boolean>('links.ignoreCase', false),
+			ignoreSeparators: config.get<boolean>('links.ignoreSeparators', false)
+		},
+		locale: config.get<string>('locale', 'en')
 	};
 }
 
@@ -133,7 +141,8 @@ function taskLabel(task: any): string {
 		if (dueStr) parts.push(`[due:${dueStr}]`);
 	}
 
-	parts.push(task.text as string);
+	// section context, e.g. "Project X › Subtask"
+	parts.push(task.heading ? `${task.heading} › ${task.text}` : task.text as string);
 
 	// status chip (only show non-todo)
 	if (task.status === 'Doing')  parts.push('[doing]');
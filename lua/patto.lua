@@ -131,6 +131,8 @@ return {
   filetypes = { 'patto' },
   single_file_support = true,
   root_markers = {'.git'},
+  -- Language of diagnostic messages: 'en' or 'ja'
+  init_options = { locale = 'en' },
   capabilities = {
     offsetEncoding = { 'utf-8' },
     textDocument = {
@@ -147,6 +149,17 @@ return {
       markdown = {
         defaultFlavor = 'standard',  -- 'standard', 'obsidian', or 'github'
       },
+      rename = {
+        alwaysRenameFile = false,  -- rename the current note from any position, not only the first line
+      },
+      links = {
+        ignoreCase = false,        -- resolve [My Note] to my note.pn
+        ignoreSeparators = false,  -- resolve [my note] to my_note.pn
+      },
+      -- Severity by diagnostic code: 'error', 'warning', 'information', 'hint' or 'off'
+      diagnostics = {},            -- e.g. { ['link-collision'] = 'off' }
+      -- Render saved notes into outputDir (relative to the workspace root)
+      -- export = { outputDir = 'site', format = 'html' },  -- format: 'markdown' or 'html'
     },
   },
   -- To Enable LSP-based folding: vim.lsp.config('patto_lsp', { lsp_folding = true })
@@ -1,8 +1,28 @@
 local default_port = 9527
 local warned = false
 
+local port_filename = ".patto-preview-tui.port"
+
+-- The port a running patto-preview-tui recorded in the workspace, if any
+local function read_port_file()
+  local found = vim.fs.find(port_filename, {
+    upward = true,
+    path = vim.fs.dirname(vim.api.nvim_buf_get_name(0)),
+  })[1]
+  if not found then
+    return nil
+  end
+  local file = io.open(found, "r")
+  if not file then
+    return nil
+  end
+  local port = tonumber(file:read("*l"))
+  file:close()
+  return port
+end
+
 local function get_port()
-  return vim.g.patto_preview_tui_port or default_port
+  return vim.g.patto_preview_tui_port or read_port_file() or default_port
 end
 
 local function is_port_open(host, port)
@@ -71,14 +91,28 @@ return {
   capabilities = {
     offsetEncoding = { 'utf-8' },
   },
+  -- Let the preview follow the cursor
+  on_attach = function(client, bufnr)
+    vim.api.nvim_create_autocmd("CursorMoved", {
+      buffer = bufnr,
+      callback = function()
+        client:notify("patto/cursorMoved", {
+          uri = vim.uri_from_bufnr(bufnr),
+          line = vim.api.nvim_win_get_cursor(0)[1] - 1,
+        })
+      end,
+    })
+  end,
   docs = {
     description = [[
 https://github.com/ompugao/patto
 patto-preview-tui, a terminal preview with a TCP LSP bridge for Patto Note.
 
 This config connects to the `patto-preview-tui` TCP LSP server so the
-terminal preview stays in sync with unsaved buffers. The TUI must be
-running before opening a .pn file. Customize the port via:
+terminal preview stays in sync with unsaved buffers and follows the cursor.
+The TUI must be running before opening a .pn file. The port is read from
+the `.patto-preview-tui.port` file the TUI writes in its workspace, or
+fixed via:
   let g:patto_preview_tui_port = 9527
     ]],
   },
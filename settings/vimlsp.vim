@@ -15,9 +15,15 @@ function! s:setup_server() abort
                 \ 'cmd': ['patto-preview', '--preview-lsp-stdio'],
                 \ 'allowlist': ['patto'],
                 \ })
-    " patto-preview-tui: connect via TCP (port g:patto_preview_tui_port, default 9527).
+    " patto-preview-tui: connect via TCP (port g:patto_preview_tui_port, else the
+    " port the TUI wrote to .patto-preview-tui.port, else 9527).
     " The TUI must already be running before opening a .pn file.
-    let l:tui_port = get(g:, 'patto_preview_tui_port', 9527)
+    let l:tui_port = 9527
+    let l:port_file = findfile('.patto-preview-tui.port', expand('%:p:h') . ';')
+    if l:port_file !=# '' && !empty(readfile(l:port_file))
+        let l:tui_port = str2nr(readfile(l:port_file)[0])
+    endif
+    let l:tui_port = get(g:, 'patto_preview_tui_port', l:tui_port)
     if s:is_port_open('127.0.0.1', l:tui_port)
         let s:patto_preview_tui_client_id = lsp#register_server({
                     \ 'name': 'patto-preview-tui',
@@ -16,6 +16,21 @@ struct Cli {
     /// theme, light or dark
     #[arg(short, long, value_name = "THEME")]
     theme: String,
+    /// highlight code blocks when rendering (requires the html-highlight feature)
+    #[arg(long)]
+    highlight: bool,
+    /// prepend a table of contents built from headings
+    #[arg(long)]
+    toc: bool,
+    /// render math to MathML instead of leaving it to MathJax (requires the html-mathml feature)
+    #[arg(long)]
+    mathml: bool,
+    /// join adjacent text lines into paragraphs
+    #[arg(long)]
+    paragraphs: bool,
+    /// turn bare http(s) URLs in text into links
+    #[arg(long)]
+    autolink: bool,
 
     /// debug log file
     #[arg(short, long, value_name = "FILE")]
@@ -24,15 +39,13 @@ struct Cli {
     verbose: Verbosity<InfoLevel>,
 }
 
+use patto::export;
 use patto::parser;
 use patto::renderer;
-use patto::renderer::Renderer;
 
 use clap_verbosity_flag::{InfoLevel, Verbosity};
 use std::fs::File;
 
-const PATTO_CSS: &str = include_str!("../../assets/patto-html.css");
-
 fn init_logger(filter_level: log::LevelFilter, logfile: Option<PathBuf>) {
     let mut loggers = Vec::new();
     if let Some(filename) = logfile {
@@ -49,48 +62,36 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Cli::parse();
     init_logger(args.verbose.log_level_filter(), args.debuglogfile);
 
-    let text = fs::read_to_string(&args.file).expect("cannot read input file");
     let parser::ParserResult {
         ast: rootnode,
         parse_errors: _,
-    } = parser::parse_text(&text);
+    } = parser::parse_file(&args.file)?;
+    if args.autolink {
+        parser::autolink_urls(&rootnode);
+    }
 
-    let options = renderer::HtmlRendererOptions {};
+    let options = renderer::HtmlRendererOptions {
+        highlight_code: args.highlight,
+        mathml: args.mathml,
+        ..Default::default()
+    }
+    .with_toc(args.toc)
+    .with_paragraphs(args.paragraphs);
     let renderer = renderer::HtmlRenderer::new(options);
 
-    let theme_class = match args.theme.as_str() {
-        "dark" => "theme-dark",
-        _ => "theme-light",
-    };
-    let hljs_theme = if args.theme == "dark" { "-dark" } else { "" };
-
+    let title = args
+        .file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
     let mut writer = BufWriter::new(fs::File::create(&args.output)?);
-    write!(
-        writer,
-        r#"<!DOCTYPE html>
-<html lang="en" class="{theme_class}">
-<head>
-<meta charset="utf-8">
-<meta name="viewport" content="width=device-width, initial-scale=1.0">
-<style>
-{PATTO_CSS}
-</style>
-<link rel="stylesheet" href="https://cdnjs.cloudflare.com/ajax/libs/highlight.js/11.9.0/styles/github{hljs_theme}.min.css">
-<script src="https://cdnjs.cloudflare.com/ajax/libs/highlight.js/11.9.0/highlight.min.js"></script>
-<script>hljs.highlightAll();</script>
-<script id="MathJax-script" async src="https://cdn.jsdelivr.net/npm/mathjax@3/es5/tex-mml-chtml.js"></script>
-</head>
-<body>
-<script type="module">
-import mermaid from 'https://cdn.jsdelivr.net/npm/mermaid@11/dist/mermaid.esm.min.mjs';
-mermaid.initialize({{ startOnLoad: true, theme: 'forest' }});
-</script>
-<div class="patto-container">
-"#
+    export::write_html_page(
+        &mut writer,
+        &renderer,
+        &rootnode,
+        &title,
+        args.theme == "dark",
     )?;
-    renderer.format(&rootnode, &mut writer)?;
-    writeln!(writer, "</div>")?;
-    writeln!(writer, "</body>")?;
-    writeln!(writer, "</html>")?;
+    writer.flush()?;
     Ok(())
 }
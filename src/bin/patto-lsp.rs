@@ -5,6 +5,7 @@ use std::sync::{Arc, Mutex};
 use tower_lsp::{LspService, Server};
 
 use patto::lsp::{lsp_config::load_config, paper::PaperCatalog, Backend, PattoSettings};
+use patto::repository::DEFAULT_NOTE_EXTENSION;
 
 #[derive(ClapParser)]
 #[command(version, about, long_about=None)]
@@ -14,6 +15,10 @@ struct Cli {
 
     #[arg(long)]
     debuglogfile: Option<String>,
+
+    /// Extension of note files [default: the config's note_extension, or pn]
+    #[arg(long, value_name = "EXT")]
+    note_extension: Option<String>,
 }
 
 fn init_logger(filter_level: log::LevelFilter, logfile: Option<String>) {
@@ -54,6 +59,12 @@ async fn main() {
         }
     };
 
+    let note_extension = args
+        .note_extension
+        .map(|ext| ext.trim_start_matches('.').to_string())
+        .or_else(|| config.as_ref().and_then(|cfg| cfg.note_extension()))
+        .unwrap_or_else(|| DEFAULT_NOTE_EXTENSION.to_string());
+
     let stdin = tokio::io::stdin();
     let stdout = tokio::io::stdout();
 
@@ -67,6 +78,7 @@ async fn main() {
             paper_catalog: shared_catalog.clone(),
             settings: Arc::new(Mutex::new(PattoSettings::default())),
             last_valid_task_snapshots: Arc::new(dashmap::DashMap::new()),
+            note_extension: note_extension.clone(),
         }
     });
     log::info!("Patto Language Server Protocol started");
@@ -42,6 +42,18 @@ struct Cli {
     /// Disable frontmatter (only affects Obsidian flavor)
     #[arg(long)]
     no_frontmatter: bool,
+
+    /// Render quotes starting with NOTE:, TIP:, WARNING:, etc. as callouts
+    #[arg(long)]
+    callouts: bool,
+
+    /// Add the title, a task summary and the next deadline to the frontmatter
+    #[arg(long)]
+    metadata: bool,
+
+    /// Turn bare http(s) URLs in text into links
+    #[arg(long)]
+    autolink: bool,
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -61,21 +73,27 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     if args.no_frontmatter {
         options = options.with_frontmatter(false);
     }
+    options = options.with_callouts(args.callouts);
+    options = options.with_metadata(args.metadata);
+    if let Some(stem) = args.file.as_ref().and_then(|path| path.file_stem()) {
+        options = options.with_title(stem.to_string_lossy());
+    }
 
     // Read input (from file or stdin)
-    let text = match &args.file {
-        Some(path) => fs::read_to_string(path)?,
+    let parser::ParserResult {
+        ast: rootnode,
+        parse_errors,
+    } = match &args.file {
+        Some(path) => parser::parse_file(path)?,
         None => {
             let mut buffer = String::new();
             io::stdin().read_to_string(&mut buffer)?;
-            buffer
+            parser::parse_text(&buffer)
         }
     };
-
-    let parser::ParserResult {
-        ast: rootnode,
-        parse_errors,
-    } = parser::parse_text(&text);
+    if args.autolink {
+        parser::autolink_urls(&rootnode);
+    }
 
     // Warn about parse errors but continue (to stderr)
     if !parse_errors.is_empty() {
@@ -3,14 +3,17 @@ use crate::config;
 use crate::image_cache::ImageCache;
 use crate::search::{SearchDirection, SearchState};
 use crate::tasks::TasksPanel;
+use crate::theme;
 use crate::wrap::{elem_height, total_height, WrapConfig};
 use crossterm::event::{KeyCode, KeyModifiers};
 use patto::{
     line_tracker::LineTracker,
     parser,
     repository::Repository,
+    stats::NoteStats,
     tui_renderer::{self, DocElement, FocusableItem, LinkAction, RenderedDoc},
 };
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
 /// Action returned by `App::handle_key()` to signal side-effects to the caller.
@@ -50,6 +53,8 @@ pub(crate) struct App {
     /// Workspace root directory.
     pub(crate) root_dir: PathBuf,
     pub(crate) rendered_doc: RenderedDoc,
+    /// Word, line and task counts of the current note, shown in the title bar.
+    pub(crate) note_stats: NoteStats,
     pub(crate) scroll_offset: usize,
     pub(crate) viewport_height: usize,
     /// Terminal width in columns, updated each frame by `draw_content`.
@@ -58,11 +63,21 @@ pub(crate) struct App {
     pub(crate) wrap: bool,
     /// String prepended to continuation rows when wrap is on (vim `showbreak`).
     pub(crate) showbreak: String,
+    /// Columns between tab stops when expanding tabs.
+    pub(crate) tab_width: usize,
     pub(crate) line_tracker: LineTracker,
     /// Index into `rendered_doc.focusables` of the currently focused item. None = no focus.
     pub(crate) focused_item_idx: Option<usize>,
     /// Navigation history for back-navigation.
     pub(crate) nav_history: Vec<NavigationEntry>,
+    /// Last scroll offset of each visited file, restored when it is shown again.
+    pub(crate) scroll_positions: HashMap<PathBuf, usize>,
+    /// Source rows of the collapsed lines of each file.
+    pub(crate) collapsed: HashMap<PathBuf, HashSet<usize>>,
+    /// Content last passed to `re_render`, re-rendered when a fold is toggled.
+    content: String,
+    /// Whether `z` was pressed and the next key completes a fold command.
+    pending_fold: bool,
     /// Image loading, caching, and display.
     pub(crate) images: ImageCache,
     /// Backlinks/two-hop-links panel.
@@ -75,8 +90,15 @@ pub(crate) struct App {
     pub(crate) tui_config: config::TuiConfig,
     /// syntect theme name for code block syntax highlighting.
     pub(crate) syntax_theme: String,
+    /// Color theme (loaded once at startup).
+    pub(crate) theme: theme::Theme,
     /// Active incremental search state. `None` when no search is active.
     pub(crate) search: Option<SearchState>,
+    /// One-off message shown in the status bar until the next key press.
+    pub(crate) status_message: Option<String>,
+    /// System clipboard, opened on first use. Kept alive so that the copied
+    /// text stays available on X11, where the owner serves it.
+    clipboard: Option<arboard::Clipboard>,
 }
 
 impl App {
@@ -91,23 +113,33 @@ impl App {
             rendered_doc: RenderedDoc {
                 elements: Vec::new(),
                 focusables: Vec::new(),
-                anchors: std::collections::HashMap::new(),
+                anchors: HashMap::new(),
+                folds: BTreeMap::new(),
             },
+            note_stats: NoteStats::default(),
             scroll_offset: 0,
             viewport_height: 24,
             viewport_width: 0,
             wrap: true,
             showbreak: "↪ ".to_string(),
+            tab_width: tui_renderer::DEFAULT_TAB_WIDTH,
             line_tracker: LineTracker::new().expect("Failed to create line tracker"),
             focused_item_idx: None,
             nav_history: Vec::new(),
+            scroll_positions: HashMap::new(),
+            collapsed: HashMap::new(),
+            content: String::new(),
+            pending_fold: false,
             images: ImageCache::new(protocol_override),
             backlinks: BacklinksPanel::new(),
             tasks: TasksPanel::new(),
             task_preview_state: None,
             tui_config: config::TuiConfig::default(),
             syntax_theme: String::new(),
+            theme: theme::Theme::default(),
             search: None,
+            status_message: None,
+            clipboard: None,
         }
     }
 
@@ -164,12 +196,71 @@ impl App {
         self.scroll_offset = self.total_display_height().saturating_sub(1);
     }
 
+    /// Record the scroll offset of the current file.
+    fn remember_scroll(&mut self) {
+        self.scroll_positions
+            .insert(self.file_path.clone(), self.scroll_offset);
+    }
+
+    /// Scroll offset last recorded for `path`, or 0 for a file not seen yet.
+    fn remembered_scroll(&self, path: &Path) -> usize {
+        self.scroll_positions.get(path).copied().unwrap_or(0)
+    }
+
     // --- Rendering ---
 
     pub(crate) fn re_render(&mut self, content: &str) {
         let result =
             parser::parse_text_with_persistent_line_tracking(content, &mut self.line_tracker);
-        self.rendered_doc = tui_renderer::render_ast(&result.ast, Some(self.syntax_theme.as_str()));
+        let no_folds = HashSet::new();
+        self.rendered_doc = tui_renderer::render_ast_folded(
+            &result.ast,
+            Some(self.syntax_theme.as_str()),
+            &self.theme.doc_colors(),
+            self.tab_width,
+            self.collapsed.get(&self.file_path).unwrap_or(&no_folds),
+        );
+        self.note_stats = NoteStats::from_ast(&result.ast, content.lines().count());
+        self.content = content.to_string();
+    }
+
+    /// Collapse or expand the innermost foldable line around the focused item,
+    /// or around the top of the viewport when nothing is focused.
+    fn toggle_fold(&mut self) {
+        let row = self
+            .source_line_of_focused_item()
+            .unwrap_or_else(|| self.source_line_at_offset())
+            - 1;
+        let Some(header) = self
+            .rendered_doc
+            .folds
+            .range(..=row)
+            .rev()
+            .find(|(_, last)| **last >= row)
+            .map(|(header, _)| *header)
+        else {
+            return;
+        };
+        let collapsed = self.collapsed.entry(self.file_path.clone()).or_default();
+        if !collapsed.remove(&header) {
+            collapsed.insert(header);
+        }
+        let content = std::mem::take(&mut self.content);
+        self.focused_item_idx = None;
+        self.re_render(&content);
+        // Keep the folded line in view when it was above the viewport
+        if header + 1 < self.source_line_at_offset() {
+            self.scroll_to_source_line(header + 1);
+        }
+    }
+
+    /// Re-render the current file from new content, keeping the scroll
+    /// position, clamped to the new content height.
+    pub(crate) fn reload(&mut self, content: &str) {
+        self.re_render(content);
+        let max = self.total_display_height().saturating_sub(1);
+        self.scroll_offset = self.scroll_offset.min(max);
+        self.remember_scroll();
     }
 
     /// Return a reference to the currently focused item, if any.
@@ -178,6 +269,36 @@ impl App {
             .and_then(|idx| self.rendered_doc.focusables.get(idx))
     }
 
+    /// Copy the target of the focused item (note name, URL or image path) to
+    /// the system clipboard and report the outcome in the status bar.
+    fn copy_focused_target(&mut self) {
+        let Some(fi) = self.focused_item() else {
+            return;
+        };
+        let text = match &fi.action {
+            LinkAction::OpenNote {
+                name,
+                anchor: Some(anchor),
+            } => format!("{}#{}", name, anchor),
+            LinkAction::OpenNote { name, anchor: None } => name.clone(),
+            LinkAction::JumpToAnchor { anchor } => format!("#{}", anchor),
+            LinkAction::OpenUrl(url) => url.clone(),
+            LinkAction::ViewImage(src) => src.clone(),
+        };
+        if self.clipboard.is_none() {
+            self.clipboard = arboard::Clipboard::new().ok();
+        }
+        let copied = self
+            .clipboard
+            .as_mut()
+            .is_some_and(|clipboard| clipboard.set_text(text.clone()).is_ok());
+        self.status_message = Some(if copied {
+            format!("copied: {}", text)
+        } else {
+            "clipboard unavailable".to_string()
+        });
+    }
+
     // --- Focus ---
 
     /// Indices (into `rendered_doc.focusables`) of focusable items visible in the viewport.
@@ -254,12 +375,15 @@ impl App {
     // --- Navigation ---
 
     /// Navigate to a wiki-linked note. Saves current state in history.
-    pub(crate) fn open_note(&mut self, name: &str, anchor: Option<&str>) -> bool {
-        let target_path = if name.ends_with(".pn") {
-            self.root_dir.join(name)
-        } else {
-            self.root_dir.join(format!("{}.pn", name))
-        };
+    pub(crate) fn open_note(
+        &mut self,
+        repository: &Repository,
+        name: &str,
+        anchor: Option<&str>,
+    ) -> bool {
+        let target_path = self
+            .root_dir
+            .join(repository.note_file_name(repository.strip_note_extension(name)));
 
         if !target_path.exists() || !target_path.is_file() {
             return false;
@@ -274,12 +398,13 @@ impl App {
             file_path: self.file_path.clone(),
             scroll_offset: self.scroll_offset,
         });
+        self.remember_scroll();
 
+        self.scroll_offset = self.remembered_scroll(&target_path);
         self.file_path = target_path;
-        self.scroll_offset = 0;
         self.focused_item_idx = None;
         self.images.fullscreen_src = None;
-        self.re_render(&content);
+        self.reload(&content);
 
         if let Some(anchor_text) = anchor {
             self.scroll_to_anchor(anchor_text);
@@ -304,12 +429,13 @@ impl App {
             file_path: self.file_path.clone(),
             scroll_offset: self.scroll_offset,
         });
+        self.remember_scroll();
 
         self.file_path = path.to_path_buf();
-        self.scroll_offset = 0;
+        self.scroll_offset = self.remembered_scroll(path);
         self.focused_item_idx = None;
         self.images.fullscreen_src = None;
-        self.re_render(&content);
+        self.reload(&content);
         true
     }
 
@@ -320,11 +446,12 @@ impl App {
                 Ok(c) => c,
                 Err(_) => return false,
             };
+            self.remember_scroll();
             self.file_path = entry.file_path;
             self.scroll_offset = entry.scroll_offset;
             self.focused_item_idx = None;
             self.images.fullscreen_src = None;
-            self.re_render(&content);
+            self.reload(&content);
             true
         } else {
             false
@@ -482,6 +609,8 @@ impl App {
         modifiers: KeyModifiers,
         viewport_height: usize,
     ) -> AppAction {
+        self.status_message = None;
+
         // Mode priority: tasks panel > backlinks popup > search input > normal
         if self.tasks.visible {
             return self.handle_tasks_key(repository, code, modifiers).await;
@@ -496,6 +625,13 @@ impl App {
             return AppAction::None;
         }
 
+        if std::mem::take(&mut self.pending_fold) {
+            if let (KeyCode::Char('a'), KeyModifiers::NONE) = (code, modifiers) {
+                self.toggle_fold();
+                return AppAction::None;
+            }
+        }
+
         self.handle_normal_key(repository, code, modifiers, viewport_height)
             .await
     }
@@ -520,7 +656,7 @@ impl App {
             (KeyCode::Enter, _) => {
                 if let Some((name, line)) = self.backlinks.resolve_cursor() {
                     self.backlinks.close();
-                    if self.open_note(&name, None) {
+                    if self.open_note(repository, &name, None) {
                         if line > 0 {
                             self.scroll_to_line(line);
                         }
@@ -542,7 +678,10 @@ impl App {
         modifiers: KeyModifiers,
     ) -> AppAction {
         match (code, modifiers) {
-            (KeyCode::Char('q'), _) | (KeyCode::Esc, _) | (KeyCode::Char('T'), _) => {
+            (KeyCode::Char('q'), _)
+            | (KeyCode::Esc, _)
+            | (KeyCode::Char('T'), _)
+            | (KeyCode::Char('t'), KeyModifiers::NONE) => {
                 self.close_tasks_panel();
             }
             (KeyCode::Char('r'), KeyModifiers::NONE) | (KeyCode::Char('R'), _) => {
@@ -573,6 +712,7 @@ impl App {
                                 file_path: self.file_path.clone(),
                                 scroll_offset: self.scroll_offset,
                             });
+                            self.remember_scroll();
                             self.file_path = path;
                             self.re_render(&content);
                         }
@@ -811,7 +951,7 @@ impl App {
             }
 
             // --- Tasks ---
-            (KeyCode::Char('T'), _) => {
+            (KeyCode::Char('T'), _) | (KeyCode::Char('t'), KeyModifiers::NONE) => {
                 self.open_tasks_panel(repository).await;
             }
 
@@ -823,7 +963,7 @@ impl App {
                 self.images.decrease_height();
             }
 
-            // --- Enter: activate focused item or close fullscreen ---
+            // --- Enter: activate focused item, close fullscreen or fold ---
             (KeyCode::Enter, _) => {
                 if self.images.fullscreen_src.is_some() {
                     self.images.fullscreen_src = None;
@@ -833,7 +973,7 @@ impl App {
                             self.images.fullscreen_src = Some(src.clone());
                         }
                         LinkAction::OpenNote { name, anchor } => {
-                            if self.open_note(name, anchor.as_deref()) {
+                            if self.open_note(repository, name, anchor.as_deref()) {
                                 self.backlinks.refresh(repository, &self.file_path).await;
                             }
                         }
@@ -852,12 +992,29 @@ impl App {
                                 let abs = self.root_dir.join(url.as_str());
                                 format!("file://{}", abs.to_string_lossy())
                             };
-                            let _ = open::that_detached(&target);
+                            // Detached: the browser never blocks the event loop
+                            self.status_message = Some(match open::that_detached(&target) {
+                                Ok(()) => format!("opened: {}", target),
+                                Err(e) => format!("failed to open {}: {}", target, e),
+                            });
                         }
                     }
+                } else {
+                    // Nothing focused: fold the section at the top of the viewport
+                    self.toggle_fold();
                 }
             }
 
+            // --- Folding (`za`) ---
+            (KeyCode::Char('z'), KeyModifiers::NONE) => {
+                self.pending_fold = true;
+            }
+
+            // --- Copy focused target ---
+            (KeyCode::Char('y'), KeyModifiers::NONE) => {
+                self.copy_focused_target();
+            }
+
             // --- Back navigation ---
             (KeyCode::Backspace, _)
             | (KeyCode::Char('H'), _)
@@ -879,7 +1036,7 @@ impl App {
             (KeyCode::Char('r'), _) | (KeyCode::Char('l'), KeyModifiers::CONTROL) => {
                 self.images.clear();
                 let content = std::fs::read_to_string(&self.file_path).unwrap_or_default();
-                self.re_render(&content);
+                self.reload(&content);
             }
             (KeyCode::Char('w'), _) => {
                 self.wrap = !self.wrap;
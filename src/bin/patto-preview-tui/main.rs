@@ -5,6 +5,7 @@ mod image_cache;
 mod math_render;
 mod search;
 mod tasks;
+mod theme;
 mod ui;
 mod wrap;
 
@@ -17,9 +18,11 @@ use crossterm::{
 use futures::StreamExt;
 use patto::repository::{Repository, RepositoryMessage};
 use ratatui::Terminal;
+use serde::Deserialize;
 use std::io;
 use std::path::PathBuf;
 use std::sync::Arc;
+use tokio::sync::mpsc;
 use tower_lsp::lsp_types::*;
 use tower_lsp::{jsonrpc, Client, LanguageServer, LspService, Server};
 use url::Url;
@@ -51,7 +54,12 @@ struct Args {
     #[arg(long, default_value = "↪ ")]
     showbreak: String,
 
-    /// TCP port for the preview LSP bridge (enabled by default)
+    /// Columns between tab stops; tabs expand to the next stop.
+    #[arg(long, default_value_t = 4)]
+    tab_width: usize,
+
+    /// TCP port for the preview LSP bridge (enabled by default). When it is
+    /// taken, the next ones are tried in turn.
     #[arg(long, default_value_t = 9527)]
     lsp_port: u16,
 
@@ -60,18 +68,45 @@ struct Args {
     goto_line: Option<usize>,
 }
 
+/// How many ports, starting at `--lsp-port`, the preview LSP bridge tries
+const LSP_PORT_ATTEMPTS: u16 = 10;
+
+/// File in the workspace directory holding the bridge port, for editors to find it
+const LSP_PORT_FILENAME: &str = ".patto-preview-tui.port";
+
+/// Params of the `patto/cursorMoved` notification sent by editors
+#[derive(Debug, Deserialize)]
+struct CursorMovedParams {
+    uri: Url,
+    /// 0-indexed line of the cursor
+    line: u32,
+}
+
+/// A cursor position in a note, forwarded from the bridge to the app loop
+type CursorMoved = (PathBuf, usize);
+
 struct PreviewLspBackend {
     client: Client,
     repository: Arc<Repository>,
+    cursor_tx: mpsc::UnboundedSender<CursorMoved>,
 }
 
 impl PreviewLspBackend {
-    fn new(client: Client, repository: Arc<Repository>) -> Self {
-        Self { client, repository }
+    fn new(
+        client: Client,
+        repository: Arc<Repository>,
+        cursor_tx: mpsc::UnboundedSender<CursorMoved>,
+    ) -> Self {
+        Self {
+            client,
+            repository,
+            cursor_tx,
+        }
     }
 
-    async fn handle_text_change(&self, uri: Url, text: String) {
-        let normalized = Repository::normalize_url_percent_encoding(&uri);
+    /// Path of the workspace note `uri` points at, if any
+    async fn note_path(&self, uri: &Url) -> Option<PathBuf> {
+        let normalized = Repository::normalize_url_percent_encoding(uri);
         let Ok(path) = normalized.to_file_path() else {
             self.client
                 .log_message(
@@ -79,13 +114,13 @@ impl PreviewLspBackend {
                     format!("Preview LSP ignoring non-file URI: {}", normalized),
                 )
                 .await;
-            return;
+            return None;
         };
 
         let path = std::fs::canonicalize(&path).unwrap_or(path);
 
-        if path.extension().and_then(|s| s.to_str()) != Some("pn") {
-            return;
+        if !self.repository.is_note_path(&path) {
+            return None;
         }
 
         if !path.starts_with(&self.repository.root_dir) {
@@ -98,10 +133,23 @@ impl PreviewLspBackend {
                     ),
                 )
                 .await;
-            return;
+            return None;
         }
 
-        self.repository.handle_live_file_change(path, text).await;
+        Some(path)
+    }
+
+    async fn handle_text_change(&self, uri: Url, text: String) {
+        if let Some(path) = self.note_path(&uri).await {
+            self.repository.handle_live_file_change(path, text).await;
+        }
+    }
+
+    /// Handle `patto/cursorMoved`: scroll the preview to the editor's cursor
+    async fn cursor_moved(&self, params: CursorMovedParams) {
+        if let Some(path) = self.note_path(&params.uri).await {
+            let _ = self.cursor_tx.send((path, params.line as usize));
+        }
     }
 }
 
@@ -151,8 +199,33 @@ impl LanguageServer for PreviewLspBackend {
     }
 }
 
-async fn start_preview_lsp_server(repository: Arc<Repository>, port: u16) -> std::io::Result<()> {
-    let listener = tokio::net::TcpListener::bind(("127.0.0.1", port)).await?;
+/// Start the preview LSP bridge on the first free port from `first_port` on,
+/// returning the port it listens on. Errors accepting connections are sent
+/// to `error_tx` since the terminal is in raw mode by then.
+async fn start_preview_lsp_server(
+    repository: Arc<Repository>,
+    first_port: u16,
+    cursor_tx: mpsc::UnboundedSender<CursorMoved>,
+    error_tx: mpsc::UnboundedSender<String>,
+) -> std::io::Result<u16> {
+    let last_port = first_port.saturating_add(LSP_PORT_ATTEMPTS - 1);
+    let mut bound = None;
+    for port in first_port..=last_port {
+        match tokio::net::TcpListener::bind(("127.0.0.1", port)).await {
+            Ok(listener) => {
+                bound = Some((listener, port));
+                break;
+            }
+            Err(e) if e.kind() == io::ErrorKind::AddrInUse => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    let Some((listener, port)) = bound else {
+        return Err(io::Error::new(
+            io::ErrorKind::AddrInUse,
+            format!("ports {}..={} are all in use", first_port, last_port),
+        ));
+    };
     eprintln!("Preview TUI LSP server listening on 127.0.0.1:{}", port);
 
     tokio::spawn(async move {
@@ -160,22 +233,47 @@ async fn start_preview_lsp_server(repository: Arc<Repository>, port: u16) -> std
             match listener.accept().await {
                 Ok((stream, _addr)) => {
                     let repo = repository.clone();
+                    let cursor_tx = cursor_tx.clone();
                     tokio::spawn(async move {
                         let (reader, writer) = tokio::io::split(stream);
-                        let (service, socket) =
-                            LspService::new(|client| PreviewLspBackend::new(client, repo.clone()));
+                        let (service, socket) = LspService::build(|client| {
+                            PreviewLspBackend::new(client, repo.clone(), cursor_tx.clone())
+                        })
+                        .custom_method("patto/cursorMoved", PreviewLspBackend::cursor_moved)
+                        .finish();
                         Server::new(reader, writer, socket).serve(service).await;
                         //eprintln!("Preview TUI LSP connection {} closed", addr);
                     });
                 }
-                Err(_err) => {
-                    //eprintln!("Preview TUI LSP accept error: {err}");
+                Err(err) => {
+                    let _ = error_tx.send(format!("Preview TUI LSP accept error: {err}"));
+                    // Errors such as running out of file descriptors persist
+                    // for a while; don't spin on them
+                    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
                 }
             }
         }
     });
 
-    Ok(())
+    Ok(port)
+}
+
+/// Record the bridge port in the workspace so editors can connect to it
+fn write_lsp_port_file(dir: &std::path::Path, port: u16) {
+    if let Err(e) = std::fs::write(dir.join(LSP_PORT_FILENAME), port.to_string()) {
+        eprintln!("Failed to write {}: {}", LSP_PORT_FILENAME, e);
+    }
+}
+
+/// Remove the port file, unless another preview has since written its own port
+fn remove_lsp_port_file(dir: &std::path::Path, port: Option<u16>) {
+    let Some(port) = port else {
+        return;
+    };
+    let path = dir.join(LSP_PORT_FILENAME);
+    if std::fs::read_to_string(&path).is_ok_and(|content| content.trim() == port.to_string()) {
+        let _ = std::fs::remove_file(path);
+    }
 }
 
 /// Build the shell command string from the editor config, substituting `{file}`, `{line}`,
@@ -244,7 +342,21 @@ async fn main() -> anyhow::Result<()> {
     });
 
     // Start preview LSP server
-    start_preview_lsp_server(repository.clone(), args.lsp_port).await?;
+    let (cursor_tx, mut cursor_rx) = mpsc::unbounded_channel();
+    let (lsp_error_tx, mut lsp_error_rx) = mpsc::unbounded_channel();
+    let lsp_port =
+        match start_preview_lsp_server(repository.clone(), args.lsp_port, cursor_tx, lsp_error_tx)
+            .await
+        {
+            Ok(port) => {
+                write_lsp_port_file(&dir, port);
+                Some(port)
+            }
+            Err(e) => {
+                eprintln!("Preview TUI LSP bridge disabled: {}", e);
+                None
+            }
+        };
 
     // Read initial content
     let initial_content = std::fs::read_to_string(&file_path)?;
@@ -255,10 +367,12 @@ async fn main() -> anyhow::Result<()> {
         app.wrap = false;
     }
     app.showbreak = args.showbreak.clone();
+    app.tab_width = args.tab_width;
     let tui_config = config::TuiConfig::load();
     app.syntax_theme = tui_config.syntax_theme.clone();
     app.images.background_color = tui_config.image_background.to_rgb();
     app.tui_config = tui_config;
+    app.theme = theme::Theme::load();
     app.re_render(&initial_content);
 
     // Query the terminal size now (crossterm works without raw mode) so that
@@ -333,6 +447,7 @@ async fn main() -> anyhow::Result<()> {
                                             .arg(&cmd)
                                             .spawn()
                                             .and_then(|mut c| c.wait());
+                                        remove_lsp_port_file(&dir, lsp_port);
                                         std::process::exit(0);
                                     }
                                     EditorAction::Background => {
@@ -358,7 +473,7 @@ async fn main() -> anyhow::Result<()> {
                 match msg {
                     Ok(RepositoryMessage::FileChanged(path, _metadata, content)) => {
                         if path == app.file_path {
-                            app.re_render(&content);
+                            app.reload(&content);
                             app.backlinks.refresh(&repository, &app.file_path).await;
                             app.tasks.refresh(&repository);
                         }
@@ -376,6 +491,15 @@ async fn main() -> anyhow::Result<()> {
                     }
                 }
             }
+            Some((path, line)) = cursor_rx.recv() => {
+                // The editor's cursor moved: follow it if it is in this note
+                if path == app.file_path {
+                    app.scroll_to_source_line(line + 1);
+                }
+            }
+            Some(message) = lsp_error_rx.recv() => {
+                app.status_message = Some(message);
+            }
             _ = display_tick.tick() => {
                 // Periodic redraw: live elapsed time on Doing tasks is recomputed
                 // from Local::now() during rendering, so just waking the loop is enough.
@@ -388,6 +512,7 @@ async fn main() -> anyhow::Result<()> {
     disable_raw_mode()?;
     execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
     terminal.show_cursor()?;
+    remove_lsp_port_file(&dir, lsp_port);
 
     // Force exit to stop background file watcher task
     std::process::exit(0);
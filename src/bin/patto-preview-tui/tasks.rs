@@ -1,7 +1,7 @@
 use chrono::{Local, NaiveDateTime, TimeDelta};
 use patto::{
-    parser::{AstNodeKind, Deadline, Property, TaskStatus},
-    repository::Repository,
+    parser::{AstNodeKind, Deadline, DeadlineStatus, Property, TaskStatus},
+    repository::{Repository, TaskFilter},
 };
 use tower_lsp::lsp_types::Url;
 use tui_widget_list::ListState;
@@ -82,6 +82,7 @@ fn extract_task_meta(
 
                 let started_at_dt = started_at.as_ref().and_then(|dl| match dl {
                     Deadline::DateTime(dt) => Some(*dt),
+                    Deadline::DateTimeTz(dt) => Some(patto::parser::local_naive(dt)),
                     _ => None,
                 });
 
@@ -171,45 +172,40 @@ impl DeadlineCategory {
 
 /// Classify a `Deadline` into a display category relative to today.
 pub(crate) fn deadline_category(due: &Deadline) -> DeadlineCategory {
-    let today = Local::now().date_naive();
-    match due {
-        Deadline::Date(d) => {
-            let diff = (*d - today).num_days();
-            if diff < 0 {
-                DeadlineCategory::Overdue
-            } else if diff == 0 {
-                DeadlineCategory::Today
-            } else if diff == 1 {
-                DeadlineCategory::Tomorrow
-            } else {
-                // days until the next Saturday (weekday 5 in chrono = Saturday)
-                use chrono::Datelike;
-                let days_until_sat =
-                    (5 - today.weekday().num_days_from_monday() as i64).rem_euclid(7);
-                if diff <= days_until_sat {
-                    DeadlineCategory::ThisWeek
-                } else {
-                    // end of current month
-                    let month_end = if today.month() == 12 {
-                        chrono::NaiveDate::from_ymd_opt(today.year() + 1, 1, 1)
-                    } else {
-                        chrono::NaiveDate::from_ymd_opt(today.year(), today.month() + 1, 1)
-                    }
-                    .map(|d| d.pred_opt().unwrap_or(d));
-                    if let Some(end) = month_end {
-                        if *d <= end {
-                            DeadlineCategory::ThisMonth
-                        } else {
-                            DeadlineCategory::Later
-                        }
-                    } else {
-                        DeadlineCategory::Later
-                    }
-                }
-            }
-        }
-        Deadline::DateTime(dt) => deadline_category(&Deadline::Date(dt.date())),
-        Deadline::Uninterpretable(_) => DeadlineCategory::Uninterpretable,
+    let now = Local::now().naive_local();
+    match due.status(now) {
+        DeadlineStatus::Overdue => DeadlineCategory::Overdue,
+        DeadlineStatus::Today => DeadlineCategory::Today,
+        DeadlineStatus::None => DeadlineCategory::Uninterpretable,
+        DeadlineStatus::Upcoming => match due.date() {
+            Some(d) => upcoming_category(d, now.date()),
+            None => DeadlineCategory::Uninterpretable,
+        },
+    }
+}
+
+/// Bucket a day after `today` into tomorrow, this week, this month or later.
+fn upcoming_category(d: chrono::NaiveDate, today: chrono::NaiveDate) -> DeadlineCategory {
+    use chrono::Datelike;
+    let diff = (d - today).num_days();
+    if diff <= 1 {
+        return DeadlineCategory::Tomorrow;
+    }
+    // days until the next Saturday (weekday 5 in chrono = Saturday)
+    let days_until_sat = (5 - today.weekday().num_days_from_monday() as i64).rem_euclid(7);
+    if diff <= days_until_sat {
+        return DeadlineCategory::ThisWeek;
+    }
+    // end of current month
+    let month_end = if today.month() == 12 {
+        chrono::NaiveDate::from_ymd_opt(today.year() + 1, 1, 1)
+    } else {
+        chrono::NaiveDate::from_ymd_opt(today.year(), today.month() + 1, 1)
+    }
+    .map(|d| d.pred_opt().unwrap_or(d));
+    match month_end {
+        Some(end) if d <= end => DeadlineCategory::ThisMonth,
+        _ => DeadlineCategory::Later,
     }
 }
 
@@ -397,7 +393,7 @@ impl TasksPanel {
 
     /// Re-fetch tasks from the repository and rebuild the flat entry list.
     pub(crate) fn refresh(&mut self, repository: &Repository) {
-        let tasks = repository.aggregate_tasks();
+        let tasks = repository.aggregate_tasks(&TaskFilter::default());
         self.rebuild_entries(tasks);
         // Keep or reset selection
         if self
@@ -437,7 +433,12 @@ impl TasksPanel {
     /// Rebuild flat entry list from raw task data, grouping by deadline category.
     fn rebuild_entries(
         &mut self,
-        tasks: Vec<(tower_lsp::lsp_types::Url, patto::parser::AstNode, Deadline)>,
+        tasks: Vec<(
+            tower_lsp::lsp_types::Url,
+            patto::parser::AstNode,
+            Deadline,
+            Option<String>,
+        )>,
     ) {
         // Group into ordered categories
         let category_order = [
@@ -453,11 +454,16 @@ impl TasksPanel {
 
         let mut buckets: Vec<Vec<TaskEntry>> = vec![Vec::new(); category_order.len()];
 
-        for (uri, node, due) in &tasks {
+        for (uri, node, due, _) in &tasks {
             let cat = deadline_category(due);
             let due_str = match due {
                 Deadline::Date(d) => d.format("%Y-%m-%d").to_string(),
                 Deadline::DateTime(dt) => dt.format("%Y-%m-%d").to_string(),
+                Deadline::DateTimeTz(dt) => patto::parser::local_naive(dt)
+                    .format("%Y-%m-%d")
+                    .to_string(),
+                Deadline::TimeRange { start, .. } => start.format("%Y-%m-%d").to_string(),
+                Deadline::Recurring(recurrence) => recurrence.to_string(),
                 Deadline::Uninterpretable(s) => s.clone(),
             };
             let file_name = uri
@@ -0,0 +1,120 @@
+use std::str::FromStr;
+
+use patto::tui_renderer::DocColors;
+use ratatui::style::Color;
+use serde::Deserialize;
+
+/// Color theme (`~/.config/patto/theme.toml`).
+///
+/// Each role accepts a color name (`"cyan"`, `"light-blue"`, ...), an indexed
+/// color (`"208"`) or a hex string like `"#rrggbb"`.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    /// Title badge and scroll percentage in the title bar.
+    pub title: Color,
+    /// Wiki links.
+    pub link: Color,
+    /// URL links and embeds.
+    pub url: Color,
+    /// Headings. `None` keeps the surrounding text color.
+    pub heading: Option<Color>,
+    /// Icon of completed tasks.
+    pub task_done: Color,
+    /// Background of the status bar.
+    pub status_bar: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        let doc = DocColors::default();
+        Self {
+            title: Color::Cyan,
+            link: doc.wiki_link,
+            url: doc.url,
+            heading: doc.heading,
+            task_done: doc.task_done,
+            status_bar: Color::DarkGray,
+        }
+    }
+}
+
+/// `theme.toml` as written by the user, before the colors are parsed.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct RawTheme {
+    title: Option<String>,
+    link: Option<String>,
+    url: Option<String>,
+    heading: Option<String>,
+    task_done: Option<String>,
+    status_bar: Option<String>,
+}
+
+/// Parse the color configured for `role`, warning and falling back to
+/// `default` when the value is not a valid color.
+fn parse_color(role: &str, value: Option<&str>, default: Color) -> Color {
+    let Some(value) = value else {
+        return default;
+    };
+    Color::from_str(value.trim()).unwrap_or_else(|_| {
+        eprintln!(
+            "Warning: invalid color '{}' for '{}' in theme.toml, using the default",
+            value, role
+        );
+        default
+    })
+}
+
+impl Theme {
+    /// Load the theme from `~/.config/patto/theme.toml`.
+    /// Returns the built-in theme if the file is missing or cannot be parsed.
+    pub fn load() -> Self {
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .map(std::path::PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME").map(|h| std::path::PathBuf::from(h).join(".config"))
+            })
+            .unwrap_or_else(|| std::path::PathBuf::from(".config"));
+        let path = config_dir.join("patto").join("theme.toml");
+
+        let Ok(contents) = std::fs::read_to_string(&path) else {
+            return Self::default();
+        };
+
+        let raw: RawTheme = toml::from_str(&contents).unwrap_or_else(|e| {
+            eprintln!("Warning: failed to parse {}: {}", path.display(), e);
+            RawTheme::default()
+        });
+        Self::from_raw(&raw)
+    }
+
+    fn from_raw(raw: &RawTheme) -> Self {
+        let default = Self::default();
+        let heading = match raw.heading.as_deref() {
+            Some(value) => Some(parse_color(
+                "heading",
+                Some(value),
+                default.heading.unwrap_or_default(),
+            )),
+            None => default.heading,
+        };
+        Self {
+            title: parse_color("title", raw.title.as_deref(), default.title),
+            link: parse_color("link", raw.link.as_deref(), default.link),
+            url: parse_color("url", raw.url.as_deref(), default.url),
+            heading,
+            task_done: parse_color("task-done", raw.task_done.as_deref(), default.task_done),
+            status_bar: parse_color("status-bar", raw.status_bar.as_deref(), default.status_bar),
+        }
+    }
+
+    /// Colors used when rendering the document.
+    pub fn doc_colors(&self) -> DocColors {
+        DocColors {
+            wiki_link: self.link,
+            url: self.url,
+            heading: self.heading,
+            task_done: self.task_done,
+        }
+    }
+}
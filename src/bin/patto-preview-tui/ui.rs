@@ -81,7 +81,7 @@ fn draw_title_bar(frame: &mut Frame, area: Rect, app: &App) {
             " ◉ patto ",
             Style::default()
                 .fg(Color::Black)
-                .bg(Color::Cyan)
+                .bg(app.theme.title)
                 .add_modifier(Modifier::BOLD),
         ),
         Span::styled(
@@ -97,18 +97,33 @@ fn draw_title_bar(frame: &mut Frame, area: Rect, app: &App) {
         ),
     ]);
 
-    // Right-side: pos + percentage, right-aligned
-    let right_text = format!("{}│{}", pos, pct);
+    let stats = &app.note_stats;
+    let task_total = stats.tasks.todo + stats.tasks.doing + stats.tasks.paused + stats.tasks.done;
+    let mut stats_text = format!(
+        " {} words · {} min ",
+        stats.words, stats.reading_time_minutes
+    );
+    if task_total > 0 {
+        stats_text.push_str(&format!("· ✓{}/{} ", stats.tasks.done, task_total));
+    }
+
+    // Right-side: stats + pos + percentage, right-aligned
+    let right_text = format!("{}│{}│{}", stats_text, pos, pct);
     let right_len = right_text.chars().count() as u16;
     let left_len = area.width.saturating_sub(right_len);
 
     let right = Line::from(vec![
+        Span::styled(
+            stats_text,
+            Style::default().fg(Color::DarkGray).bg(Color::Black),
+        ),
+        Span::styled("│", Style::default().fg(Color::DarkGray).bg(Color::Black)),
         Span::styled(pos, Style::default().fg(Color::DarkGray).bg(Color::Black)),
         Span::styled("│", Style::default().fg(Color::DarkGray).bg(Color::Black)),
         Span::styled(
             pct,
             Style::default()
-                .fg(Color::Cyan)
+                .fg(app.theme.title)
                 .bg(Color::Black)
                 .add_modifier(Modifier::BOLD),
         ),
@@ -779,6 +794,19 @@ fn draw_status_bar(frame: &mut Frame, area: Rect, app: &App) {
         }
     }
 
+    // A one-off message replaces the hints until the next key press.
+    if let Some(message) = &app.status_message {
+        frame.render_widget(
+            Paragraph::new(Line::from(Span::styled(
+                message.clone(),
+                Style::default().fg(Color::Yellow),
+            )))
+            .style(Style::default().bg(app.theme.status_bar)),
+            left_area,
+        );
+        return;
+    }
+
     // Normal mode: render hint bar on the left.
     let focused_action = app.focused_item().map(|fi| &fi.action);
 
@@ -824,6 +852,8 @@ fn draw_status_bar(frame: &mut Frame, area: Rect, app: &App) {
         };
         spans.push(key_badge(key));
         spans.push(hint_desc(desc));
+        spans.push(key_badge("y"));
+        spans.push(hint_desc("copy"));
     }
 
     spans.push(hint_sep());
@@ -831,7 +861,7 @@ fn draw_status_bar(frame: &mut Frame, area: Rect, app: &App) {
     // Group 5: Tools
     spans.push(key_badge("b"));
     spans.push(hint_desc("backlinks"));
-    spans.push(key_badge("T"));
+    spans.push(key_badge("t/T"));
     spans.push(hint_desc("tasks"));
     spans.push(key_badge("+/-"));
     spans.push(hint_desc(&format!("img({})", app.images.height_rows)));
@@ -848,7 +878,7 @@ fn draw_status_bar(frame: &mut Frame, area: Rect, app: &App) {
     }
 
     frame.render_widget(
-        Paragraph::new(Line::from(spans)).style(Style::default().bg(Color::DarkGray)),
+        Paragraph::new(Line::from(spans)).style(Style::default().bg(app.theme.status_bar)),
         left_area,
     );
 }
@@ -94,7 +94,7 @@ impl PreviewLspBackend {
 
         let path = std::fs::canonicalize(&path).unwrap_or(path);
 
-        if path.extension().and_then(|s| s.to_str()) != Some("pn") {
+        if !self.repository.is_note_path(&path) {
             return;
         }
 
@@ -1,6 +1,6 @@
 use std::env;
-use std::fs;
 use std::io::{self, Read};
+use std::path::Path;
 use std::process;
 
 use patto::parser;
@@ -8,18 +8,16 @@ use patto::parser;
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args: Vec<String> = env::args().collect();
 
-    let content = if args.len() > 1 {
+    let result = if args.len() > 1 {
         // Read from file
-        fs::read_to_string(&args[1])?
+        parser::parse_file(Path::new(&args[1]))?
     } else {
         // Read from stdin
         let mut buffer = String::new();
         io::stdin().read_to_string(&mut buffer)?;
-        buffer
+        parser::parse_text(&buffer)
     };
 
-    let result = parser::parse_text(&content);
-
     if result.parse_errors.is_empty() {
         eprintln!("✓ Syntax is valid.");
         process::exit(0);
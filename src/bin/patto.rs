@@ -0,0 +1,346 @@
+//! patto - Export and lint a directory of patto notes
+//!
+//! Usage:
+//!   patto watch ./notes --out ./site
+//!   patto watch ./notes --out ./export --format md
+//!   patto build ./notes --out ./site --css
+//!   patto check ./notes --format json
+//!   patto import ./markdown -o ./notes --mode lossy
+//!
+//! `watch` renders every note below DIR into OUT, keeping the relative
+//! structure, then re-renders notes as they change and removes the output of
+//! deleted notes until interrupted.
+//!
+//! `build` renders DIR into a static site: WikiLinks point at the pages of
+//! their notes, local images are copied and `index.html` lists every note.
+//!
+//! `check` reports parse errors, links to missing notes, links to missing
+//! anchors and duplicate anchors as `file:line:col: message`, and exits with a
+//! nonzero status when anything was found. Suitable for pre-commit hooks and CI.
+//!
+//! `import` converts a markdown file or directory to patto, like
+//! `patto-markdown-importer`.
+
+use std::path::{Path, PathBuf};
+use std::process;
+
+use clap::{Parser as ClapParser, Subcommand, ValueEnum};
+use tokio::sync::broadcast::error::RecvError;
+use tower_lsp::lsp_types::Url;
+
+use patto::check;
+use patto::export::{self, ExportFormat, SiteOptions};
+use patto::importer::cli::ImportArgs;
+use patto::markdown::MarkdownFlavor;
+use patto::parser;
+use patto::repository::{Repository, RepositoryMessage, DEFAULT_NOTE_EXTENSION};
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum FormatArg {
+    /// Standalone HTML pages
+    Html,
+    /// CommonMark-compatible markdown
+    Md,
+}
+
+impl From<FormatArg> for ExportFormat {
+    fn from(format: FormatArg) -> Self {
+        match format {
+            FormatArg::Html => ExportFormat::Html,
+            FormatArg::Md => ExportFormat::Markdown,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum CheckFormat {
+    /// `file:line:col: message`, one problem per line
+    Text,
+    /// JSON array of problems
+    Json,
+}
+
+#[derive(ClapParser)]
+#[command(version, about = "Export, lint and import patto notes")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Render every note into OUT, then re-render notes as they change
+    Watch {
+        /// Notes directory
+        #[arg(value_name = "DIR")]
+        dir: PathBuf,
+
+        /// Output directory
+        #[arg(long, value_name = "OUT")]
+        out: PathBuf,
+
+        /// Output format
+        #[arg(long, value_enum, default_value = "html")]
+        format: FormatArg,
+
+        /// Turn bare http(s) URLs in text into links
+        #[arg(long)]
+        autolink: bool,
+
+        /// Extension of note files
+        #[arg(long, value_name = "EXT", default_value = DEFAULT_NOTE_EXTENSION)]
+        note_extension: String,
+    },
+    /// Render every note into a static HTML site with cross-note links
+    Build {
+        /// Notes directory
+        #[arg(value_name = "DIR")]
+        dir: PathBuf,
+
+        /// Output directory
+        #[arg(long, value_name = "OUT")]
+        out: PathBuf,
+
+        /// Prepend a table of contents built from headings to every page
+        #[arg(long)]
+        toc: bool,
+
+        /// Embed the patto stylesheet into every page
+        #[arg(long)]
+        css: bool,
+
+        /// Use the dark theme of the stylesheet
+        #[arg(long)]
+        dark: bool,
+
+        /// Turn bare http(s) URLs in text into links
+        #[arg(long)]
+        autolink: bool,
+
+        /// Extension of note files
+        #[arg(long, value_name = "EXT", default_value = DEFAULT_NOTE_EXTENSION)]
+        note_extension: String,
+    },
+    /// Lint every note and exit with status 1 when any problem is found
+    #[command(
+        long_about = "Scans every note file below DIR and reports parse errors, links to \
+                      missing notes, links to missing anchors and duplicate anchors.\n\n\
+                      Exits with status 1 when any problem is found."
+    )]
+    Check {
+        /// Notes directory
+        #[arg(value_name = "DIR")]
+        dir: PathBuf,
+
+        /// Output format
+        #[arg(long, value_enum, default_value = "text")]
+        format: CheckFormat,
+
+        /// Extension of note files
+        #[arg(long, value_name = "EXT", default_value = DEFAULT_NOTE_EXTENSION)]
+        note_extension: String,
+    },
+    /// Convert markdown files to patto format
+    #[command(
+        long_about = "Imports markdown files to patto format with three modes:\n\n\
+                      - strict: Stop on first unsupported feature\n\
+                      - lossy: Continue on errors, drop unsupported features\n\
+                      - preserve: Wrap unsupported features in code blocks\n\n\
+                      INPUT may be a markdown file or a directory, which is converted\n\
+                      recursively into the output directory.\n\
+                      If no input file is specified, reads from stdin.\n\
+                      If no output file is specified, writes to stdout.\n\
+                      The conversion report is printed to stderr."
+    )]
+    Import(ImportArgs),
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Cli::parse();
+    match args.command {
+        Command::Watch {
+            dir,
+            out,
+            format,
+            autolink,
+            note_extension,
+        } => watch(&dir, &out, format.into(), autolink, &note_extension).await,
+        Command::Build {
+            dir,
+            out,
+            toc,
+            css,
+            dark,
+            autolink,
+            note_extension,
+        } => {
+            let options = SiteOptions { toc, css, dark };
+            build(&dir, &out, options, autolink, &note_extension).await
+        }
+        Command::Check {
+            dir,
+            format,
+            note_extension,
+        } => run_check(&dir, format, &note_extension).await,
+        Command::Import(args) => patto::importer::cli::run(args),
+    }
+}
+
+/// Scan `dir` and return the repository once every note is parsed
+async fn load_repository(dir: &Path, note_extension: &str) -> Repository {
+    // The initial scan runs on a spawned task and, on this multi-threaded
+    // runtime, may finish before we subscribe; the scan state covers that case.
+    let repository = Repository::with_note_extension(dir.to_path_buf(), note_extension);
+    let mut rx = repository.subscribe();
+    while !repository.scan_state().completed {
+        match rx.recv().await {
+            Ok(RepositoryMessage::ScanCompleted { .. }) => break,
+            Ok(_) | Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => break,
+        }
+    }
+
+    repository
+}
+
+async fn watch(
+    dir: &Path,
+    out: &Path,
+    format: ExportFormat,
+    autolink: bool,
+    note_extension: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = std::fs::canonicalize(dir)?;
+    std::fs::create_dir_all(out)?;
+    let out = std::fs::canonicalize(out)?;
+
+    let repository = load_repository(&dir, note_extension).await;
+    let mut notes: Vec<PathBuf> = repository
+        .ast_map
+        .iter()
+        .filter_map(|entry| entry.key().to_file_path().ok())
+        .collect();
+    notes.sort();
+    for note in &notes {
+        if let Err(e) = export_note(&repository, &out, note, format, autolink) {
+            eprintln!("Failed to export {}: {}", note.display(), e);
+        }
+    }
+    eprintln!(
+        "✓ Exported {} note(s) to {}, watching {} for changes",
+        notes.len(),
+        out.display(),
+        dir.display()
+    );
+
+    let mut rx = repository.subscribe();
+    repository.start_watcher().await?;
+    loop {
+        match rx.recv().await {
+            Ok(RepositoryMessage::FileChanged(note, _, _)) => report_export(
+                &note,
+                export_note(&repository, &out, &note, format, autolink),
+            ),
+            Ok(RepositoryMessage::FileAdded(path, _)) => {
+                let note = dir.join(path);
+                report_export(
+                    &note,
+                    export_note(&repository, &out, &note, format, autolink),
+                )
+            }
+            Ok(RepositoryMessage::FileRemoved(path)) => {
+                let note = dir.join(path);
+                match export::remove_exported_note(&dir, &out, &note, format) {
+                    Ok(Some(target)) => eprintln!("✓ Removed {}", target.display()),
+                    Ok(None) => {}
+                    Err(e) => eprintln!("Failed to remove the output of {}: {}", note.display(), e),
+                }
+            }
+            Ok(_) | Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => break,
+        }
+    }
+    Ok(())
+}
+
+async fn build(
+    dir: &Path,
+    out: &Path,
+    options: SiteOptions,
+    autolink: bool,
+    note_extension: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = std::fs::canonicalize(dir)?;
+    let repository = load_repository(&dir, note_extension).await;
+    if autolink {
+        for entry in repository.ast_map.iter() {
+            parser::autolink_urls(entry.value());
+        }
+    }
+    let summary = export::build_site(&repository, out, options)?;
+    eprintln!(
+        "✓ Built {} page(s) and copied {} image(s) into {}",
+        summary.pages,
+        summary.images,
+        out.display()
+    );
+    if summary.broken_links > 0 {
+        eprintln!("  {} link(s) to missing notes", summary.broken_links);
+    }
+    Ok(())
+}
+
+/// Render one note of `repository` into `out`; `None` if it is not indexed
+fn export_note(
+    repository: &Repository,
+    out: &Path,
+    note: &Path,
+    format: ExportFormat,
+    autolink: bool,
+) -> std::io::Result<Option<PathBuf>> {
+    let ast = Url::from_file_path(note)
+        .ok()
+        .map(|uri| Repository::normalize_url_percent_encoding(&uri))
+        .and_then(|uri| repository.ast_map.get(&uri).map(|e| e.value().clone()));
+    let Some(ast) = ast else {
+        return Ok(None);
+    };
+    if autolink {
+        parser::autolink_urls(&ast);
+    }
+    let flavor = MarkdownFlavor::Standard;
+    export::export_note(&ast, &repository.root_dir, out, note, format, flavor).map(Some)
+}
+
+fn report_export(note: &Path, result: std::io::Result<Option<PathBuf>>) {
+    match result {
+        Ok(Some(target)) => eprintln!("✓ Exported {} to {}", note.display(), target.display()),
+        Ok(None) => {}
+        Err(e) => eprintln!("Failed to export {}: {}", note.display(), e),
+    }
+}
+
+async fn run_check(
+    dir: &Path,
+    format: CheckFormat,
+    note_extension: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = std::fs::canonicalize(dir)?;
+    let repository = load_repository(&dir, note_extension).await;
+    let problems = check::check(&repository, &dir);
+
+    match format {
+        CheckFormat::Text => {
+            for p in &problems {
+                println!("{}:{}:{}: {}", p.file, p.line, p.column, p.message);
+            }
+        }
+        CheckFormat::Json => println!("{}", serde_json::to_string_pretty(&problems)?),
+    }
+
+    if !problems.is_empty() {
+        process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,145 @@
+//! Vault-wide linting behind `patto check`
+//!
+//! Reports parse errors, links to missing notes, links to missing anchors and
+//! anchors defined more than once in the notes of a scanned [`Repository`].
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Serialize;
+use tower_lsp::lsp_types::Url;
+
+use crate::diagnostic_translator::DiagnosticTranslator;
+use crate::parser::{self, AstNode, AstNodeKind, Location, Property};
+use crate::repository::{find_anchor, Repository};
+
+/// A single lint finding
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    /// Path relative to the checked directory
+    pub file: String,
+    /// 1-indexed line
+    pub line: usize,
+    /// 1-indexed byte column
+    pub column: usize,
+    /// `parse-error`, `unreadable`, `dangling-link`, `broken-anchor` or `duplicate-anchor`
+    pub kind: &'static str,
+    pub message: String,
+}
+
+impl Problem {
+    fn new(file: &str, location: &Location, kind: &'static str, message: String) -> Self {
+        Self {
+            file: file.to_string(),
+            line: location.row + 1,
+            column: location.span.0 + 1,
+            kind,
+            message,
+        }
+    }
+}
+
+/// Collect the problems of every indexed note, ordered by file and position
+pub fn check(repository: &Repository, dir: &Path) -> Vec<Problem> {
+    repository.parse_cached_notes();
+    let translator = DiagnosticTranslator::default();
+    let mut notes: Vec<(Url, String)> = repository
+        .ast_map
+        .iter()
+        .filter_map(|entry| {
+            let path = entry.key().to_file_path().ok()?;
+            Some((entry.key().clone(), relative_name(&path, dir)))
+        })
+        .collect();
+    notes.sort_by(|a, b| a.1.cmp(&b.1));
+    let names: HashMap<Url, String> = notes.iter().cloned().collect();
+
+    let mut problems = Vec::new();
+    for (uri, name) in &notes {
+        let Some(ast) = repository.ast_map.get(uri).map(|e| e.value().clone()) else {
+            continue;
+        };
+
+        let Ok(path) = uri.to_file_path() else {
+            continue;
+        };
+        match parser::parse_file(&path) {
+            Ok(result) => {
+                for error in result.parse_errors {
+                    let Some(diagnostic) = translator.translate(&error) else {
+                        continue;
+                    };
+                    let message = diagnostic.message;
+                    let message = message.lines().next().unwrap_or_default().to_string();
+                    problems.push(Problem::new(name, error.location(), "parse-error", message));
+                }
+            }
+            Err(e) => problems.push(Problem::new(
+                name,
+                &Location::default(),
+                "unreadable",
+                e.to_string(),
+            )),
+        }
+
+        let mut wikilinks = vec![];
+        Repository::gather_wikilinks(&ast, &mut wikilinks);
+        for (link, anchor, location) in &wikilinks {
+            if link.is_empty() {
+                // Self-links are resolved against this note
+                if let Some(anchor) = anchor {
+                    if find_anchor(&ast, anchor).is_none() {
+                        let message = format!("anchor '#{}' not found in this note", anchor);
+                        problems.push(Problem::new(name, location, "broken-anchor", message));
+                    }
+                }
+            } else if repository.link_to_path(link).is_none() {
+                let message = format!("link to missing note '{}'", link);
+                problems.push(Problem::new(name, location, "dangling-link", message));
+            }
+        }
+
+        let mut anchors: HashMap<String, usize> = HashMap::new();
+        gather_anchors(&ast, &mut |anchor, location| {
+            if let Some(first_row) = anchors.get(anchor) {
+                let message = format!(
+                    "duplicate anchor '#{}' (first defined on line {})",
+                    anchor,
+                    first_row + 1
+                );
+                problems.push(Problem::new(name, location, "duplicate-anchor", message));
+            } else {
+                anchors.insert(anchor.to_string(), location.row);
+            }
+        });
+    }
+
+    for (uri, location, anchor) in repository.find_broken_anchors() {
+        let Some(name) = names.get(&uri) else {
+            continue;
+        };
+        let message = format!("anchor '#{}' not found in the linked note", anchor);
+        problems.push(Problem::new(name, &location, "broken-anchor", message));
+    }
+
+    problems.sort_by(|a, b| (&a.file, a.line, a.column).cmp(&(&b.file, b.line, b.column)));
+    problems
+}
+
+/// Call `f` with every anchor defined in the AST, in document order
+fn gather_anchors(parent: &AstNode, f: &mut impl FnMut(&str, &Location)) {
+    if let AstNodeKind::Line { ref properties } = &parent.kind() {
+        for prop in properties {
+            if let Property::Anchor { name, location } = prop {
+                f(name, location);
+            }
+        }
+    }
+    for child in parent.value().children.lock().unwrap().iter() {
+        gather_anchors(child, f);
+    }
+}
+
+fn relative_name(path: &Path, dir: &Path) -> String {
+    path.strip_prefix(dir).unwrap_or(path).display().to_string()
+}
@@ -1,9 +1,12 @@
+use serde::Deserialize;
 use std::borrow::Cow;
+use std::collections::HashMap;
 use tower_lsp::lsp_types::DiagnosticSeverity;
 
 use crate::parser::{ParserError, PestErrorInfo, PestErrorVariantInfo, Rule};
 
 const DEFAULT_DOCS_BASE_URL: &str = "https://github.com/ompugao/patto/wiki/Diagnostic-Errors";
+const JA_DOCS_BASE_URL: &str = "https://github.com/ompugao/patto/wiki/Diagnostic-Errors-ja";
 
 #[derive(Debug, Clone)]
 pub struct FriendlyDiagnostic {
@@ -13,31 +16,119 @@ pub struct FriendlyDiagnostic {
     pub severity: DiagnosticSeverity,
 }
 
+/// How the diagnostics of a code are reported, as configured by the user
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosticLevel {
+    Error,
+    Warning,
+    #[serde(alias = "info")]
+    Information,
+    Hint,
+    /// Not reported at all
+    Off,
+}
+
+/// Language of diagnostic messages; codes are the same in every locale
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    En,
+    Ja,
+}
+
+impl Locale {
+    /// Parse a language tag such as `ja` or `ja-JP`; unknown languages are English
+    pub fn from_tag(tag: &str) -> Self {
+        let language = tag.split(['-', '_']).next().unwrap_or_default();
+        if language.eq_ignore_ascii_case("ja") {
+            Locale::Ja
+        } else {
+            Locale::En
+        }
+    }
+}
+
+/// Levels by diagnostic code, e.g. `{"invalid-img": "warning", "link-collision": "off"}`
+pub type DiagnosticConfig = HashMap<String, DiagnosticLevel>;
+
 #[derive(Debug)]
 pub struct DiagnosticTranslator {
     docs_base_url: &'static str,
+    locale: Locale,
+    config: DiagnosticConfig,
 }
 
 impl DiagnosticTranslator {
     pub fn new() -> Self {
+        Self::with_config(DiagnosticConfig::new())
+    }
+
+    pub fn with_config(config: DiagnosticConfig) -> Self {
+        Self::localized(Locale::default(), config)
+    }
+
+    pub fn with_locale(locale: Locale) -> Self {
+        Self::localized(locale, DiagnosticConfig::new())
+    }
+
+    pub fn localized(locale: Locale, config: DiagnosticConfig) -> Self {
+        let docs_base_url = match locale {
+            Locale::En => DEFAULT_DOCS_BASE_URL,
+            Locale::Ja => JA_DOCS_BASE_URL,
+        };
         Self {
-            docs_base_url: DEFAULT_DOCS_BASE_URL,
+            docs_base_url,
+            locale,
+            config,
+        }
+    }
+
+    /// Pick the message of the translator's locale
+    fn tr(&self, en: &'static str, ja: &'static str) -> &'static str {
+        match self.locale {
+            Locale::En => en,
+            Locale::Ja => ja,
         }
     }
 
-    pub fn translate(&self, error: &ParserError) -> FriendlyDiagnostic {
-        match error {
+    /// Translate a parse error; `None` if its code is configured `off`
+    pub fn translate(&self, error: &ParserError) -> Option<FriendlyDiagnostic> {
+        let mut diagnostic = match error {
             ParserError::InvalidIndentation(_) => self.invalid_indentation_message(),
             ParserError::ParseError(_, info) => self.translate_pest_error(info),
+        };
+        diagnostic.severity = self.severity(diagnostic.code.as_deref(), diagnostic.severity)?;
+        Some(diagnostic)
+    }
+
+    /// The severity to report a diagnostic with `code` at: `default` unless
+    /// configured otherwise, or `None` if the code is configured `off`
+    pub fn severity(
+        &self,
+        code: Option<&str>,
+        default: DiagnosticSeverity,
+    ) -> Option<DiagnosticSeverity> {
+        match code.and_then(|code| self.config.get(code)) {
+            None => Some(default),
+            Some(DiagnosticLevel::Error) => Some(DiagnosticSeverity::ERROR),
+            Some(DiagnosticLevel::Warning) => Some(DiagnosticSeverity::WARNING),
+            Some(DiagnosticLevel::Information) => Some(DiagnosticSeverity::INFORMATION),
+            Some(DiagnosticLevel::Hint) => Some(DiagnosticSeverity::HINT),
+            Some(DiagnosticLevel::Off) => None,
         }
     }
 
     fn invalid_indentation_message(&self) -> FriendlyDiagnostic {
-        let primary = "Inconsistent indentation";
-        let help = "Use tabs to indent nested blocks. Child lines must be indented exactly one tab deeper than their parent.";
+        let primary = self.tr("Inconsistent indentation", "インデントが揃っていません");
+        let help = self.tr(
+            "Use tabs to indent nested blocks. Child lines must be indented exactly one tab deeper than their parent.",
+            "入れ子のブロックはタブでインデントしてください。子の行は親の行よりちょうどタブ1つ分深くインデントする必要があります。",
+        );
         let examples = ["Heading", "\tChild line", "\t\tNested child"];
         FriendlyDiagnostic::new(
-            compose_message(primary, help, &examples),
+            self.compose_message(primary, help, &examples),
             Some("invalid-indentation"),
             self.docs_base_url,
         )
@@ -62,17 +153,23 @@ impl DiagnosticTranslator {
                 }
             }
             PestErrorVariantInfo::CustomError { message } => {
-                let composed = compose_message("Invalid syntax", message, &[]);
+                let primary = self.tr("Invalid syntax", "構文が正しくありません");
+                let composed = self.compose_message(primary, message, &[]);
                 FriendlyDiagnostic::new(composed, Some("syntax-error"), self.docs_base_url)
             }
         }
     }
 
     pub fn embed_error(&self) -> FriendlyDiagnostic {
-        let primary = "Invalid embed syntax";
-        let help = "Use [@embed ...] with a URL or local file path. \
+        let primary = self.tr("Invalid embed syntax", "埋め込みの構文が正しくありません");
+        let help = self.tr(
+            "Use [@embed ...] with a URL or local file path. \
             Local paths must start with ./ or ../\n\
-            Note: bare filenames like file.pdf are not valid; use ./file.pdf";
+            Note: bare filenames like file.pdf are not valid; use ./file.pdf",
+            "[@embed ...] には URL かローカルファイルのパスを指定してください。\
+            ローカルのパスは ./ か ../ で始める必要があります。\n\
+            注意: file.pdf のようなファイル名だけの指定は無効です。./file.pdf と書いてください",
+        );
         let examples = [
             "[@embed https://example.com/video]",
             "[@embed https://example.com/video My Title]",
@@ -82,7 +179,7 @@ impl DiagnosticTranslator {
             r#"[@embed My Title ./path/to/file.pdf]   ← local paths require ./"#,
         ];
         FriendlyDiagnostic::new_with_severity(
-            compose_message(primary, help, &examples),
+            self.compose_message(primary, help, &examples),
             Some("invalid-embed"),
             self.docs_base_url,
             DiagnosticSeverity::WARNING,
@@ -90,9 +187,13 @@ impl DiagnosticTranslator {
     }
 
     pub fn img_error(&self) -> FriendlyDiagnostic {
-        let primary = "Invalid image syntax";
-        let help = "Use [@img ...] with a URL or local file path and an optional alt text. \
-            Local paths must start with ./ or ../";
+        let primary = self.tr("Invalid image syntax", "画像の構文が正しくありません");
+        let help = self.tr(
+            "Use [@img ...] with a URL or local file path and an optional alt text. \
+            Local paths must start with ./ or ../",
+            "[@img ...] には URL かローカルファイルのパスと、省略可能な代替テキストを指定してください。\
+            ローカルのパスは ./ か ../ で始める必要があります",
+        );
         let examples = [
             "[@img https://example.com/photo.jpg]",
             "[@img https://example.com/photo.jpg My Caption]",
@@ -102,7 +203,7 @@ impl DiagnosticTranslator {
             r#"[@img My Caption ./path/to/image.jpg]   ← local paths require ./"#,
         ];
         FriendlyDiagnostic::new_with_severity(
-            compose_message(primary, help, &examples),
+            self.compose_message(primary, help, &examples),
             Some("invalid-img"),
             self.docs_base_url,
             DiagnosticSeverity::WARNING,
@@ -110,110 +211,157 @@ impl DiagnosticTranslator {
     }
 
     fn link_error(&self) -> FriendlyDiagnostic {
-        let primary = "Invalid link syntax";
-        let help = "Wrap links in [ ] and include a note name, anchor, URL, or file path.";
+        let primary = self.tr("Invalid link syntax", "リンクの構文が正しくありません");
+        let help = self.tr(
+            "Wrap links in [ ] and include a note name, anchor, URL, or file path.",
+            "リンクは [ ] で囲み、ノート名、アンカー、URL、またはファイルパスを含めてください。",
+        );
         let examples = [
             "[ProjectPlan]",
             "[ProjectPlan#milestones]",
             "[https://example.com]",
         ];
         FriendlyDiagnostic::new(
-            compose_message(primary, help, &examples),
+            self.compose_message(primary, help, &examples),
             Some("invalid-link"),
             self.docs_base_url,
         )
     }
 
     fn command_error(&self) -> FriendlyDiagnostic {
-        let primary = "Unknown or malformed command";
-        let help = "Commands look like [@command-name optional-args]. Available commands include @code, @math, @quote, @table, and @img.";
+        let primary = self.tr(
+            "Unknown or malformed command",
+            "不明または不正なコマンドです",
+        );
+        let help = self.tr(
+            "Commands look like [@command-name optional-args]. Available commands include @code, @math, @quote, @table, and @img.",
+            "コマンドは [@コマンド名 省略可能な引数] の形で書きます。使えるコマンドは @code、@math、@quote、@table、@img などです。",
+        );
         let examples = ["[@code rust]", "[@math]", "[@quote]"];
         FriendlyDiagnostic::new(
-            compose_message(primary, help, &examples),
+            self.compose_message(primary, help, &examples),
             Some("invalid-command"),
             self.docs_base_url,
         )
     }
 
     fn property_error(&self) -> FriendlyDiagnostic {
-        let primary = "Invalid property syntax";
-        let help = "Properties use {@name key=value ...}. Separate each key/value with spaces and close the property with }.";
+        let primary = self.tr(
+            "Invalid property syntax",
+            "プロパティの構文が正しくありません",
+        );
+        let help = self.tr(
+            "Properties use {@name key=value ...}. Separate each key/value with spaces and close the property with }.",
+            "プロパティは {@name key=value ...} の形で書きます。キーと値の組はスペースで区切り、} で閉じてください。",
+        );
         let examples = ["{@tag project=patto}", "{@task status=todo due=2024-12-31}"];
         FriendlyDiagnostic::new(
-            compose_message(primary, help, &examples),
+            self.compose_message(primary, help, &examples),
             Some("invalid-property"),
             self.docs_base_url,
         )
     }
 
     fn task_error(&self) -> FriendlyDiagnostic {
-        let primary = "Invalid task syntax";
-        let help = "Tasks use {@task status=<todo|doing|done> due=<YYYY-MM-DD or YYYY-MM-DDThh:mm>}. Provide both status and due date.";
+        let primary = self.tr("Invalid task syntax", "タスクの構文が正しくありません");
+        let help = self.tr(
+            "Tasks use {@task status=<todo|doing|done> due=<YYYY-MM-DD or YYYY-MM-DDThh:mm>}. Provide both status and due date.",
+            "タスクは {@task status=<todo|doing|done> due=<YYYY-MM-DD または YYYY-MM-DDThh:mm>} の形で書きます。status と due の両方を指定してください。",
+        );
         let examples = [
             "{@task status=todo due=2024-12-31}",
             "{@task status=doing due=2024-12-31T14:00}",
         ];
         FriendlyDiagnostic::new(
-            compose_message(primary, help, &examples),
+            self.compose_message(primary, help, &examples),
             Some("invalid-task"),
             self.docs_base_url,
         )
     }
 
     fn anchor_error(&self) -> FriendlyDiagnostic {
-        let primary = "Invalid anchor";
-        let help = "Anchors start with # and may contain letters, numbers, _ or -. Example: #ProjectAlpha.";
+        let primary = self.tr("Invalid anchor", "アンカーが正しくありません");
+        let help = self.tr(
+            "Anchors start with # and may contain letters, numbers, _ or -. Example: #ProjectAlpha.",
+            "アンカーは # で始まり、文字、数字、_、- を含められます。例: #ProjectAlpha",
+        );
         let examples = ["#inbox", "[#ProjectAlpha]", "[MyNote#section]"];
         FriendlyDiagnostic::new(
-            compose_message(primary, help, &examples),
+            self.compose_message(primary, help, &examples),
             Some("invalid-anchor"),
             self.docs_base_url,
         )
     }
 
     fn inline_code_error(&self) -> FriendlyDiagnostic {
-        let primary = "Malformed inline code";
-        let help = "Inline code is written as [` code `]. Make sure both the opening [` and closing `] markers are present.";
+        let primary = self.tr(
+            "Malformed inline code",
+            "インラインコードの書き方が正しくありません",
+        );
+        let help = self.tr(
+            "Inline code is written as [` code `]. Make sure both the opening [` and closing `] markers are present.",
+            "インラインコードは [` code `] と書きます。開始の [` と終了の `] の両方があるか確認してください。",
+        );
         let examples = ["[` println!(\"hello\"); `]"];
         FriendlyDiagnostic::new(
-            compose_message(primary, help, &examples),
+            self.compose_message(primary, help, &examples),
             Some("invalid-inline-code"),
             self.docs_base_url,
         )
     }
 
     fn inline_math_error(&self) -> FriendlyDiagnostic {
-        let primary = "Malformed inline math";
-        let help = "Inline math is written as [$ formula $]. Ensure you have both the opening [$ and closing $] markers.";
+        let primary = self.tr(
+            "Malformed inline math",
+            "インライン数式の書き方が正しくありません",
+        );
+        let help = self.tr(
+            "Inline math is written as [$ formula $]. Ensure you have both the opening [$ and closing $] markers.",
+            "インライン数式は [$ formula $] と書きます。開始の [$ と終了の $] の両方があるか確認してください。",
+        );
         let examples = ["[$ a^2 + b^2 = c^2 $]"];
         FriendlyDiagnostic::new(
-            compose_message(primary, help, &examples),
+            self.compose_message(primary, help, &examples),
             Some("invalid-inline-math"),
             self.docs_base_url,
         )
     }
 
     fn decoration_error(&self) -> FriendlyDiagnostic {
-        let primary = "Malformed text decoration";
-        let help = "Decorations such as bold or italics must wrap content inside [ ]. Example: [* bold *] or [/ italic /].";
+        let primary = self.tr(
+            "Malformed text decoration",
+            "文字装飾の書き方が正しくありません",
+        );
+        let help = self.tr(
+            "Decorations such as bold or italics must wrap content inside [ ]. Example: [* bold *] or [/ italic /].",
+            "太字や斜体などの装飾は内容を [ ] で囲む必要があります。例: [* bold *]、[/ italic /]",
+        );
         let examples = ["[* bold *]", "[/ emphasis /]"];
         FriendlyDiagnostic::new(
-            compose_message(primary, help, &examples),
+            self.compose_message(primary, help, &examples),
             Some("invalid-decoration"),
             self.docs_base_url,
         )
     }
 
     fn statement_error(&self, positives: &[Rule], info: &PestErrorInfo) -> FriendlyDiagnostic {
-        let primary = self
-            .describe_expectations(positives)
-            .map(|desc| format!("Couldn't understand this line – expected {}.", desc))
-            .unwrap_or_else(|| "Couldn't understand this line.".to_string());
-        let detail = summary_from_message(&info.message).unwrap_or_else(|| {
-            "Check for missing brackets, unmatched commands, or typos in this line.".to_string()
+        let primary = match (self.locale, self.describe_expectations(positives)) {
+            (Locale::En, Some(desc)) => {
+                format!("Couldn't understand this line – expected {}.", desc)
+            }
+            (Locale::En, None) => "Couldn't understand this line.".to_string(),
+            (Locale::Ja, Some(desc)) => format!("この行を解釈できません – {}が必要です。", desc),
+            (Locale::Ja, None) => "この行を解釈できません。".to_string(),
+        };
+        let detail = self.parser_summary(info).unwrap_or_else(|| {
+            self.tr(
+                "Check for missing brackets, unmatched commands, or typos in this line.",
+                "この行に括弧の閉じ忘れ、対応していないコマンド、誤字がないか確認してください。",
+            )
+            .to_string()
         });
         FriendlyDiagnostic::new(
-            compose_message(&primary, &detail, &[]),
+            self.compose_message(&primary, &detail, &[]),
             Some("line-parse-error"),
             self.docs_base_url,
         )
@@ -221,14 +369,21 @@ impl DiagnosticTranslator {
 
     fn generic_error(&self, positives: &[Rule], info: &PestErrorInfo) -> FriendlyDiagnostic {
         let expectation = self.describe_expectations(positives);
-        let primary = expectation
-            .map(|desc| format!("Unexpected text – expected {}.", desc))
-            .unwrap_or_else(|| "Patto couldn't understand this part.".to_string());
-        let detail = summary_from_message(&info.message).unwrap_or_else(|| {
-            "Make sure brackets, commands, and properties are written correctly.".to_string()
+        let primary = match (self.locale, expectation) {
+            (Locale::En, Some(desc)) => format!("Unexpected text – expected {}.", desc),
+            (Locale::En, None) => "Patto couldn't understand this part.".to_string(),
+            (Locale::Ja, Some(desc)) => format!("予期しないテキストです – {}が必要です。", desc),
+            (Locale::Ja, None) => "この部分を解釈できません。".to_string(),
+        };
+        let detail = self.parser_summary(info).unwrap_or_else(|| {
+            self.tr(
+                "Make sure brackets, commands, and properties are written correctly.",
+                "括弧、コマンド、プロパティが正しく書かれているか確認してください。",
+            )
+            .to_string()
         });
         FriendlyDiagnostic::new(
-            compose_message(&primary, &detail, &[]),
+            self.compose_message(&primary, &detail, &[]),
             Some("syntax-error"),
             self.docs_base_url,
         )
@@ -241,17 +396,30 @@ impl DiagnosticTranslator {
 
         let mut names: Vec<String> = positives
             .iter()
-            .map(|rule| rule_display_name(*rule).to_string())
+            .map(|rule| rule_display_name(*rule, self.locale).to_string())
             .collect();
         names.sort();
         names.dedup();
 
-        match names.len() {
-            0 => None,
-            1 => Some(names.remove(0)),
-            _ => Some(format!("one of {}", names.join(", "))),
+        match (names.len(), self.locale) {
+            (0, _) => None,
+            (1, _) => Some(names.remove(0)),
+            (_, Locale::En) => Some(format!("one of {}", names.join(", "))),
+            (_, Locale::Ja) => Some(format!("{}のいずれか", names.join("、"))),
         }
     }
+
+    /// The parser's own summary of the error, which is only available in English
+    fn parser_summary(&self, info: &PestErrorInfo) -> Option<String> {
+        match self.locale {
+            Locale::En => summary_from_message(&info.message),
+            Locale::Ja => None,
+        }
+    }
+
+    fn compose_message(&self, primary: &str, help: &str, examples: &[&str]) -> String {
+        compose_message(primary, help, examples, self.tr("Examples:", "例:"))
+    }
 }
 
 impl Default for DiagnosticTranslator {
@@ -350,7 +518,11 @@ impl ErrorCategory {
         if rules.iter().any(|rule| {
             matches!(
                 rule,
-                Rule::statement | Rule::statement_nestable | Rule::raw_sentence | Rule::line
+                Rule::statement
+                    | Rule::statement_nestable
+                    | Rule::nested_line
+                    | Rule::raw_sentence
+                    | Rule::line
             )
         }) {
             return Some(ErrorCategory::Statement);
@@ -449,7 +621,10 @@ fn is_decoration_rule(rule: Rule) -> bool {
     )
 }
 
-fn rule_display_name(rule: Rule) -> Cow<'static, str> {
+fn rule_display_name(rule: Rule, locale: Locale) -> Cow<'static, str> {
+    if let Some(name) = ja_rule_display_name(rule).filter(|_| locale == Locale::Ja) {
+        return Cow::Borrowed(name);
+    }
     match rule {
         Rule::command_code => Cow::Borrowed("code block command"),
         Rule::command_math => Cow::Borrowed("math block command"),
@@ -473,13 +648,48 @@ fn rule_display_name(rule: Rule) -> Cow<'static, str> {
         Rule::symbol_underline => Cow::Borrowed("underline marker (_)"),
         Rule::symbol_deleted => Cow::Borrowed("strikethrough marker (-)"),
         Rule::statement => Cow::Borrowed("line content"),
-        Rule::statement_nestable => Cow::Borrowed("nested line content"),
+        Rule::statement_nestable | Rule::nested_line => Cow::Borrowed("nested line content"),
         Rule::raw_sentence => Cow::Borrowed("plain text"),
+        Rule::expr_escaped => Cow::Borrowed("escaped character"),
+        Rule::expr_comment | Rule::expr_line_comment => Cow::Borrowed("comment"),
         _ => Cow::Owned(format!("{:?}", rule).replace('_', " ").to_string()),
     }
 }
 
-fn compose_message(primary: &str, help: &str, examples: &[&str]) -> String {
+fn ja_rule_display_name(rule: Rule) -> Option<&'static str> {
+    let name = match rule {
+        Rule::command_code => "コードブロックのコマンド",
+        Rule::command_math => "数式ブロックのコマンド",
+        Rule::command_quote => "引用ブロックのコマンド",
+        Rule::command_table => "表のコマンド",
+        Rule::expr_command => "コマンド",
+        Rule::expr_wiki_link => "Wikiリンク",
+        Rule::expr_url_link => "URLリンク",
+        Rule::expr_local_file_link => "ローカルファイルへのリンク",
+        Rule::expr_mail_link => "メールアドレスのリンク",
+        Rule::expr_embed => "埋め込みコマンド",
+        Rule::expr_img => "画像コマンド",
+        Rule::expr_code_inline => "インラインコード",
+        Rule::expr_math_inline => "インライン数式",
+        Rule::expr_property => "プロパティ",
+        Rule::expr_anchor => "アンカー",
+        Rule::expr_task => "タスク",
+        Rule::expr_builtin_symbols => "文字装飾",
+        Rule::symbol_bold => "太字の記号 (*)",
+        Rule::symbol_italic => "斜体の記号 (/)",
+        Rule::symbol_underline => "下線の記号 (_)",
+        Rule::symbol_deleted => "取り消し線の記号 (-)",
+        Rule::statement => "行の内容",
+        Rule::statement_nestable | Rule::nested_line => "入れ子の行の内容",
+        Rule::raw_sentence => "テキスト",
+        Rule::expr_escaped => "エスケープされた文字",
+        Rule::expr_comment | Rule::expr_line_comment => "コメント",
+        _ => return None,
+    };
+    Some(name)
+}
+
+fn compose_message(primary: &str, help: &str, examples: &[&str], examples_label: &str) -> String {
     let mut sections = Vec::new();
     if !primary.trim().is_empty() {
         sections.push(primary.trim().to_string());
@@ -488,7 +698,7 @@ fn compose_message(primary: &str, help: &str, examples: &[&str]) -> String {
         sections.push(help.trim().to_string());
     }
     if !examples.is_empty() {
-        let mut block = String::from("Examples:");
+        let mut block = String::from(examples_label);
         for example in examples {
             block.push('\n');
             block.push_str("  ");
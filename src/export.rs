@@ -0,0 +1,347 @@
+//! Writing notes out as markdown files or standalone HTML pages
+
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use crate::markdown::{MarkdownFlavor, MarkdownRendererOptions};
+use crate::parser::{AstNode, AstNodeKind};
+use crate::renderer::{HtmlRenderer, HtmlRendererOptions, LinkStatus, MarkdownRenderer, Renderer};
+use crate::repository::Repository;
+
+/// Stylesheet embedded into exported HTML pages
+pub const PATTO_CSS: &str = include_str!("../assets/patto-html.css");
+
+/// File format notes are exported to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Markdown,
+    Html,
+}
+
+impl ExportFormat {
+    /// Extension of exported files, without the dot
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Html => "html",
+        }
+    }
+}
+
+/// Where the note at `note` below `root` is exported to: the same relative
+/// path below `out_dir`, with the extension of `format`
+pub fn export_path(
+    root: &Path,
+    out_dir: &Path,
+    note: &Path,
+    format: ExportFormat,
+) -> Option<PathBuf> {
+    let relative = note.strip_prefix(root).ok()?;
+    Some(out_dir.join(relative).with_extension(format.extension()))
+}
+
+/// Render `ast` as a markdown document or an HTML page titled `title`
+pub fn render_note(
+    ast: &AstNode,
+    title: &str,
+    format: ExportFormat,
+    flavor: MarkdownFlavor,
+) -> io::Result<Vec<u8>> {
+    let mut output = Vec::new();
+    match format {
+        ExportFormat::Markdown => {
+            let options = MarkdownRendererOptions::new(flavor).with_title(title);
+            MarkdownRenderer::new(options).format(ast, &mut output)?;
+        }
+        ExportFormat::Html => {
+            let renderer = HtmlRenderer::new(HtmlRendererOptions::default());
+            write_html_page(&mut output, &renderer, ast, title, false)?;
+        }
+    }
+    Ok(output)
+}
+
+/// Render the note at `note` below `root` into `out_dir`, creating
+/// directories as needed, and return the path written to
+pub fn export_note(
+    ast: &AstNode,
+    root: &Path,
+    out_dir: &Path,
+    note: &Path,
+    format: ExportFormat,
+    flavor: MarkdownFlavor,
+) -> io::Result<PathBuf> {
+    let target = export_path(root, out_dir, note, format).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is not below {}", note.display(), root.display()),
+        )
+    })?;
+    let title = note
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    let content = render_note(ast, &title, format, flavor)?;
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&target, content)?;
+    Ok(target)
+}
+
+/// Delete the exported file of the note at `note` below `root`, returning its
+/// path if there was one
+pub fn remove_exported_note(
+    root: &Path,
+    out_dir: &Path,
+    note: &Path,
+    format: ExportFormat,
+) -> io::Result<Option<PathBuf>> {
+    let Some(target) = export_path(root, out_dir, note, format) else {
+        return Ok(None);
+    };
+    match fs::remove_file(&target) {
+        Ok(()) => Ok(Some(target)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Options of [`build_site`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SiteOptions {
+    /// Prepend a table of contents built from headings to every page
+    pub toc: bool,
+    /// Embed the patto stylesheet into every page
+    pub css: bool,
+    /// Use the dark theme of the stylesheet
+    pub dark: bool,
+}
+
+/// What [`build_site`] wrote
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SiteSummary {
+    /// Notes rendered, not counting the index page
+    pub pages: usize,
+    /// Local images copied next to the pages
+    pub images: usize,
+    /// WikiLinks to notes that don't exist
+    pub broken_links: usize,
+}
+
+/// Render every note of `repository` into `out_dir` as a browsable site:
+/// one page per note at the note's relative path, WikiLinks rewritten to
+/// relative hrefs of those pages, the local images they show copied
+/// alongside, and an `index.html` listing all notes
+pub fn build_site(
+    repository: &Repository,
+    out_dir: &Path,
+    options: SiteOptions,
+) -> io::Result<SiteSummary> {
+    let root = &repository.root_dir;
+    let mut notes: Vec<(PathBuf, AstNode)> = repository
+        .ast_map
+        .iter()
+        .filter_map(|entry| {
+            let path = entry.key().to_file_path().ok()?;
+            path.starts_with(root)
+                .then(|| (path, entry.value().clone()))
+        })
+        .collect();
+    notes.sort_by(|a, b| a.0.cmp(&b.0));
+    let pages: HashMap<&Path, String> = notes
+        .iter()
+        .filter_map(|(path, _)| Some((path.as_path(), page_href(path.strip_prefix(root).ok()?))))
+        .collect();
+
+    // Every link is resolved once, with the repository's link normalization
+    let mut summary = SiteSummary::default();
+    let mut targets = HashMap::new();
+    for (_, ast) in &notes {
+        let mut wikilinks = vec![];
+        Repository::gather_wikilinks(ast, &mut wikilinks);
+        for (link, _, _) in wikilinks
+            .into_iter()
+            .filter(|(link, _, _)| !link.is_empty())
+        {
+            let href = repository
+                .link_to_path(&link)
+                .and_then(|path| pages.get(path.as_path()).cloned());
+            match href {
+                Some(href) => {
+                    targets.insert(link, href);
+                }
+                None => summary.broken_links += 1,
+            }
+        }
+    }
+    let targets = Arc::new(targets);
+
+    let mut images = BTreeSet::new();
+    for (path, ast) in &notes {
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        let target = out_dir
+            .join(relative)
+            .with_extension(ExportFormat::Html.extension());
+        let site_root = "../".repeat(relative.components().count() - 1);
+        let targets = Arc::clone(&targets);
+        let renderer = HtmlRenderer::new(
+            HtmlRendererOptions::default()
+                .with_toc(options.toc)
+                .with_link_resolver(move |link| match targets.get(link) {
+                    Some(href) => LinkStatus::Exists {
+                        href: format!("{site_root}{href}"),
+                    },
+                    None => LinkStatus::Broken,
+                }),
+        );
+        let title = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy())
+            .unwrap_or_default();
+        let mut content = Vec::new();
+        write_page(&mut content, &title, options, &|output| {
+            renderer.format(ast, output)
+        })?;
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&target, content)?;
+        summary.pages += 1;
+
+        let note_dir = path.parent().unwrap_or(root);
+        images.extend(local_images(ast, note_dir, root));
+    }
+
+    // Images keep their place relative to the notes, so their srcs stay valid
+    for image in &images {
+        let target = out_dir.join(image.strip_prefix(root).unwrap_or(image));
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(image, &target)?;
+        summary.images += 1;
+    }
+
+    let mut index = Vec::new();
+    write_page(&mut index, "Notes", options, &|output| {
+        write!(output, "<ul class=\"patto-index\">")?;
+        for (path, _) in &notes {
+            let name = path.strip_prefix(root).unwrap_or(path).with_extension("");
+            write!(
+                output,
+                "<li><a class=\"wikilink\" href=\"{}\">{}</a></li>",
+                pages[path.as_path()],
+                html_escape::encode_text(&name.to_string_lossy())
+            )?;
+        }
+        writeln!(output, "</ul>")
+    })?;
+    fs::create_dir_all(out_dir)?;
+    fs::write(out_dir.join("index.html"), index)?;
+    Ok(summary)
+}
+
+/// Percent-encoded href of the page of the note at `relative`
+fn page_href(relative: &Path) -> String {
+    let page = relative.with_extension(ExportFormat::Html.extension());
+    page.components()
+        .map(|component| urlencoding::encode(&component.as_os_str().to_string_lossy()).into_owned())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Existing image files below `root` shown by `ast`, whose srcs are relative to `note_dir`
+fn local_images(ast: &AstNode, note_dir: &Path, root: &Path) -> Vec<PathBuf> {
+    let mut images = vec![];
+    if let AstNodeKind::Image { src, .. } = ast.kind() {
+        // Absolute srcs would be copied outside of the site
+        let relative = Path::new(src);
+        if !src.contains("://") && relative.is_relative() {
+            if let Ok(path) = fs::canonicalize(note_dir.join(relative)) {
+                if path.starts_with(root) && path.is_file() {
+                    images.push(path);
+                }
+            }
+        }
+    }
+    for content in ast.value().contents.lock().unwrap().iter() {
+        images.extend(local_images(content, note_dir, root));
+    }
+    for child in ast.value().children.lock().unwrap().iter() {
+        images.extend(local_images(child, note_dir, root));
+    }
+    images
+}
+
+/// Write a standalone HTML page with the patto stylesheet around the output of `renderer`
+pub fn write_html_page(
+    output: &mut dyn Write,
+    renderer: &HtmlRenderer,
+    ast: &AstNode,
+    title: &str,
+    dark: bool,
+) -> io::Result<()> {
+    let options = SiteOptions {
+        css: true,
+        dark,
+        ..Default::default()
+    };
+    write_page(output, title, options, &|output| {
+        renderer.format(ast, output)
+    })
+}
+
+/// Write an HTML page around the output of `body`
+fn write_page(
+    output: &mut dyn Write,
+    title: &str,
+    options: SiteOptions,
+    body: &dyn Fn(&mut dyn Write) -> io::Result<()>,
+) -> io::Result<()> {
+    let theme_class = if options.dark {
+        "theme-dark"
+    } else {
+        "theme-light"
+    };
+    let hljs_theme = if options.dark { "-dark" } else { "" };
+    let title = html_escape::encode_text(title);
+    let style = if options.css {
+        format!("<style>\n{PATTO_CSS}\n</style>\n")
+    } else {
+        String::new()
+    };
+    write!(
+        output,
+        r#"<!DOCTYPE html>
+<html lang="en" class="{theme_class}">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1.0">
+<title>{title}</title>
+{style}<link rel="stylesheet" href="https://cdnjs.cloudflare.com/ajax/libs/highlight.js/11.9.0/styles/github{hljs_theme}.min.css">
+<script src="https://cdnjs.cloudflare.com/ajax/libs/highlight.js/11.9.0/highlight.min.js"></script>
+<script>hljs.highlightAll();</script>
+<script id="MathJax-script" async src="https://cdn.jsdelivr.net/npm/mathjax@3/es5/tex-mml-chtml.js"></script>
+</head>
+<body>
+<script type="module">
+import mermaid from 'https://cdn.jsdelivr.net/npm/mermaid@11/dist/mermaid.esm.min.mjs';
+mermaid.initialize({{ startOnLoad: true, theme: 'forest' }});
+</script>
+<div class="patto-container">
+"#
+    )?;
+    body(output)?;
+    writeln!(output, "</div>")?;
+    writeln!(output, "</body>")?;
+    writeln!(output, "</html>")?;
+    Ok(())
+}
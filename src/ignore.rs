@@ -0,0 +1,161 @@
+//! `.gitignore`-style rules for paths that are never scanned or watched.
+//!
+//! Patterns come from [`DEFAULT_IGNORE_PATTERNS`], the `ignore` list of
+//! `.patto.toml` and a `.pattoignore` file, in that order. Each pattern is a
+//! glob with the usual gitignore conventions:
+//!
+//! - a pattern without `/` matches a file or directory name at any depth
+//! - a pattern with a leading or inner `/` matches the path from the root
+//! - a trailing `/` only matches directories
+//! - a leading `!` re-includes what an earlier pattern ignored
+//!
+//! The last matching pattern wins, and nothing below an ignored directory is
+//! visited.
+
+use std::path::Path;
+
+use glob::{MatchOptions, Pattern};
+
+/// Name of the ignore file in the notes directory
+pub const IGNORE_FILENAME: &str = ".pattoignore";
+
+/// Patterns applied before any configured ones: dotfiles and dot-directories
+pub const DEFAULT_IGNORE_PATTERNS: &[&str] = &[".*"];
+
+#[derive(Debug, Clone)]
+struct Rule {
+    pattern: Pattern,
+    negated: bool,
+    dir_only: bool,
+    anchored: bool,
+}
+
+impl Rule {
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let anchored = line.contains('/');
+        let pattern = Pattern::new(line.trim_start_matches('/')).ok()?;
+        Some(Self {
+            pattern,
+            negated,
+            dir_only,
+            anchored,
+        })
+    }
+
+    fn matches(&self, rel_path: &str, name: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+        if self.anchored {
+            self.pattern.matches_with(rel_path, options)
+        } else {
+            self.pattern.matches_with(name, options)
+        }
+    }
+}
+
+/// Compiled ignore patterns of a notes directory
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreRules {
+    rules: Vec<Rule>,
+}
+
+impl IgnoreRules {
+    /// Compile `patterns`; blank lines, `#` comments and invalid globs are skipped
+    pub fn new<S: AsRef<str>>(patterns: &[S]) -> Self {
+        Self {
+            rules: patterns
+                .iter()
+                .filter_map(|p| Rule::parse(p.as_ref()))
+                .collect(),
+        }
+    }
+
+    /// The default patterns, then `configured`, then the lines of the
+    /// `.pattoignore` file in `root_dir` if there is one
+    pub fn load(root_dir: &Path, configured: &[String]) -> Self {
+        let ignore_file =
+            std::fs::read_to_string(root_dir.join(IGNORE_FILENAME)).unwrap_or_default();
+        let patterns: Vec<&str> = DEFAULT_IGNORE_PATTERNS
+            .iter()
+            .copied()
+            .chain(configured.iter().map(String::as_str))
+            .chain(ignore_file.lines())
+            .collect();
+        Self::new(&patterns)
+    }
+
+    /// Whether `rel_path`, relative to the notes directory, is ignored by
+    /// itself, without looking at its parent directories
+    pub fn is_ignored(&self, rel_path: &Path, is_dir: bool) -> bool {
+        let rel_path = rel_path.to_string_lossy().replace('\\', "/");
+        let name = rel_path.rsplit('/').next().unwrap_or(&rel_path);
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matches(&rel_path, name, is_dir))
+            .is_some_and(|rule| !rule.negated)
+    }
+
+    /// Whether `rel_path` or any of its parent directories is ignored
+    pub fn is_excluded(&self, rel_path: &Path, is_dir: bool) -> bool {
+        let mut ancestors: Vec<&Path> = rel_path
+            .ancestors()
+            .skip(1)
+            .filter(|p| !p.as_os_str().is_empty())
+            .collect();
+        ancestors.reverse();
+        ancestors.iter().any(|dir| self.is_ignored(dir, true)) || self.is_ignored(rel_path, is_dir)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_patterns_skip_dotfiles() {
+        let rules = IgnoreRules::load(Path::new("/nonexistent"), &[]);
+        assert!(rules.is_excluded(Path::new(".git/config.pn"), false));
+        assert!(rules.is_excluded(Path::new("notes/.draft.pn"), false));
+        assert!(!rules.is_excluded(Path::new("notes/draft.pn"), false));
+    }
+
+    #[test]
+    fn gitignore_conventions() {
+        let rules = IgnoreRules::new(&[
+            "# comment",
+            "node_modules",
+            "/archive/",
+            "*.tmp.pn",
+            "logs/*.pn",
+            "!logs/keep.pn",
+        ]);
+        assert!(rules.is_excluded(Path::new("node_modules/pkg/readme.pn"), false));
+        assert!(rules.is_excluded(Path::new("deep/node_modules/x.pn"), false));
+        assert!(rules.is_excluded(Path::new("archive/old.pn"), false));
+        // Anchored to the root and only matching directories
+        assert!(!rules.is_excluded(Path::new("notes/archive/old.pn"), false));
+        assert!(!rules.is_excluded(Path::new("archive"), false));
+        assert!(rules.is_excluded(Path::new("a/b.tmp.pn"), false));
+        assert!(rules.is_excluded(Path::new("logs/today.pn"), false));
+        assert!(!rules.is_excluded(Path::new("logs/keep.pn"), false));
+        assert!(!rules.is_excluded(Path::new("logs/sub/today.pn"), false));
+    }
+}
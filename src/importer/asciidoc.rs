@@ -0,0 +1,1072 @@
+//! AsciiDoc to Patto converter
+//!
+//! Converts AsciiDoc content to patto format. AsciiDoc blocks are delimited by
+//! whole lines, so the input is read line by line instead of through an event
+//! parser; the AST is built the same way as in the markdown converter.
+
+use super::converter::{heading_fontsize, render_patto, ImportError, ImportResult};
+use super::options::{ImportMode, ImportOptions, MarkdownInputFlavor};
+use super::report::{ConversionReport, ImportWarning, WarningKind};
+use crate::parser::{AstNode, AstNodeKind, Deadline, Location, Property, TaskStatus};
+use regex::Regex;
+use std::sync::OnceLock;
+use std::time::Instant;
+
+/// Admonition labels, kept as the lead of the converted quote
+const ADMONITIONS: [&str; 5] = ["NOTE", "TIP", "IMPORTANT", "WARNING", "CAUTION"];
+
+/// Attribute lines given above a block: `[[id]]`, `[source,rust]` and `.Title`
+#[derive(Default)]
+struct BlockAttributes {
+    anchor: Option<String>,
+    style: Option<String>,
+    title: Option<String>,
+}
+
+impl BlockAttributes {
+    /// First positional attribute, e.g. `source` in `[source,rust]`
+    fn block_style(&self) -> Option<&str> {
+        self.style
+            .as_deref()
+            .and_then(|style| style.split(',').next())
+            .map(str::trim)
+    }
+
+    /// Second positional attribute, the language of a source block
+    fn source_lang(&self) -> &str {
+        self.style
+            .as_deref()
+            .and_then(|style| style.split(',').nth(1))
+            .map(str::trim)
+            .unwrap_or("")
+    }
+
+    fn admonition(&self) -> Option<&'static str> {
+        let style = self.block_style()?;
+        ADMONITIONS.iter().copied().find(|label| *label == style)
+    }
+
+    fn properties(&self) -> Option<Vec<Property>> {
+        self.anchor.as_ref().map(|name| vec![anchor_property(name)])
+    }
+}
+
+/// A paragraph being collected until a blank line or another block
+struct Paragraph<'a> {
+    line: usize,
+    lines: Vec<&'a str>,
+    attrs: BlockAttributes,
+}
+
+/// AsciiDoc to Patto importer
+pub struct AsciidocImporter {
+    options: ImportOptions,
+}
+
+impl AsciidocImporter {
+    /// Create a new importer with the given options
+    pub fn new(options: ImportOptions) -> Self {
+        Self { options }
+    }
+
+    /// Import AsciiDoc content to patto format
+    pub fn import(
+        &self,
+        asciidoc: &str,
+        input_path: &str,
+        output_path: &str,
+    ) -> Result<ImportResult, ImportError> {
+        let start_time = Instant::now();
+
+        // Markdown flavors do not apply to AsciiDoc input
+        let mut report = ConversionReport::new(
+            input_path,
+            output_path,
+            self.options.mode,
+            MarkdownInputFlavor::Standard,
+        );
+        report.statistics.total_lines = asciidoc.lines().count();
+
+        let root = AstNode::new("", 0, None, Some(AstNodeKind::Dummy));
+        let lines: Vec<&str> = asciidoc.lines().collect();
+        self.convert_lines(&lines, 1, &root, &mut report)?;
+
+        let patto_content = render_patto(&root)?;
+
+        report.statistics.converted_lines =
+            report.statistics.total_lines - report.statistics.failed_lines;
+        report.duration_ms = start_time.elapsed().as_millis() as u64;
+
+        Ok(ImportResult {
+            ast: root,
+            patto_content,
+            report,
+        })
+    }
+
+    /// Convert a run of lines starting at `first_line` (1-indexed) into children of `root`
+    fn convert_lines(
+        &self,
+        lines: &[&str],
+        first_line: usize,
+        root: &AstNode,
+        report: &mut ConversionReport,
+    ) -> Result<(), ImportError> {
+        let mut attrs = BlockAttributes::default();
+        let mut paragraph: Option<Paragraph> = None;
+        let mut i = 0;
+
+        while i < lines.len() {
+            let line = lines[i].trim_end();
+            let line_number = first_line + i;
+            i += 1;
+
+            if let Some(delimiter) = block_delimiter(line) {
+                if let Some(p) = paragraph.take() {
+                    self.add_paragraph(p, root, report);
+                }
+                // The block runs to its matching delimiter, or to the end of input
+                let end = lines[i..]
+                    .iter()
+                    .position(|l| l.trim_end() == delimiter)
+                    .map_or(lines.len(), |offset| i + offset);
+                let block_attrs = std::mem::take(&mut attrs);
+                self.convert_block(
+                    delimiter,
+                    &lines[i..end],
+                    line_number,
+                    block_attrs,
+                    root,
+                    report,
+                )?;
+                i = (end + 1).min(lines.len());
+                continue;
+            }
+
+            if line.is_empty() {
+                if let Some(p) = paragraph.take() {
+                    self.add_paragraph(p, root, report);
+                }
+                continue;
+            }
+
+            // Line comments
+            if line.starts_with("//") {
+                continue;
+            }
+
+            if paragraph.is_none() {
+                // Document attributes have no patto counterpart
+                if attribute_entry_regex().is_match(line) {
+                    report.statistics.increment_feature("attributes");
+                    continue;
+                }
+                if let Some(caps) = block_anchor_regex().captures(line) {
+                    let name = caps.get(1).or(caps.get(2)).unwrap().as_str();
+                    attrs.anchor = Some(name.to_string());
+                    continue;
+                }
+                if let Some(caps) = block_attribute_regex().captures(line) {
+                    attrs.style = Some(caps[1].to_string());
+                    continue;
+                }
+                if let Some(caps) = block_title_regex().captures(line) {
+                    attrs.title = Some(caps[1].to_string());
+                    continue;
+                }
+            }
+
+            if let Some(caps) = heading_regex().captures(line) {
+                if let Some(p) = paragraph.take() {
+                    self.add_paragraph(p, root, report);
+                }
+                let attrs = std::mem::take(&mut attrs);
+                self.add_heading(caps[1].len(), &caps[2], line_number, attrs, root, report);
+                continue;
+            }
+
+            if let Some(caps) = list_item_regex().captures(line) {
+                if let Some(p) = paragraph.take() {
+                    self.add_paragraph(p, root, report);
+                }
+                let marker = &caps[1];
+                // `**` and `..` nest one level per marker character
+                let depth = if marker.starts_with(['*', '.']) {
+                    marker.len()
+                } else {
+                    1
+                };
+                let attrs = std::mem::take(&mut attrs);
+                self.add_list_item(depth, &caps[2], line_number, attrs, root, report);
+                continue;
+            }
+
+            if line == "'''" {
+                if let Some(p) = paragraph.take() {
+                    self.add_paragraph(p, root, report);
+                }
+                root.add_child(AstNode::horizontal_line("---", line_number, None));
+                report.statistics.increment_feature("horizontal_rules");
+                continue;
+            }
+
+            if let Some(caps) = block_macro_regex().captures(line) {
+                if let Some(p) = paragraph.take() {
+                    self.add_paragraph(p, root, report);
+                }
+                let attrs = std::mem::take(&mut attrs);
+                match &caps[1] {
+                    "image" => {
+                        let alt = caps[3].split(',').next().unwrap_or("").trim();
+                        let alt = if alt.is_empty() { None } else { Some(alt) };
+                        let line_node = AstNode::line("", line_number, None, attrs.properties());
+                        line_node.add_content(AstNode::image("", line_number, None, &caps[2], alt));
+                        root.add_child(line_node);
+                        report.statistics.increment_feature("images");
+                    }
+                    "include" => {
+                        self.unsupported("include", "Includes", &[line], line_number, root, report)?
+                    }
+                    _ => self.unsupported(
+                        "block_macro",
+                        "Block macros",
+                        &[line],
+                        line_number,
+                        root,
+                        report,
+                    )?,
+                }
+                continue;
+            }
+
+            paragraph
+                .get_or_insert_with(|| Paragraph {
+                    line: line_number,
+                    lines: Vec::new(),
+                    attrs: std::mem::take(&mut attrs),
+                })
+                .lines
+                .push(line);
+        }
+
+        if let Some(p) = paragraph.take() {
+            self.add_paragraph(p, root, report);
+        }
+        Ok(())
+    }
+
+    /// Convert a delimited block whose content is `body`
+    fn convert_block(
+        &self,
+        delimiter: &str,
+        body: &[&str],
+        line: usize,
+        attrs: BlockAttributes,
+        root: &AstNode,
+        report: &mut ConversionReport,
+    ) -> Result<(), ImportError> {
+        // Tables carry their title as the caption; other blocks get a title line
+        if !delimiter.starts_with('|') {
+            if let Some(title) = attrs.title.as_deref() {
+                root.add_child(self.text_line(title, line, None, report));
+            }
+        }
+
+        match delimiter.chars().next() {
+            Some('-') if delimiter != "--" => {
+                let lang = match attrs.block_style() {
+                    Some("source") => attrs.source_lang(),
+                    _ => "",
+                };
+                root.add_child(code_line(lang, body, line, attrs.properties()));
+                report.statistics.increment_feature("code_blocks");
+            }
+            Some('.') => {
+                root.add_child(code_line("", body, line, attrs.properties()));
+                report.statistics.increment_feature("code_blocks");
+            }
+            Some('_') => {
+                let quote = self.quote_line(None, body, line, attrs.properties(), report);
+                root.add_child(quote);
+                report.statistics.increment_feature("blockquotes");
+            }
+            Some('=') if attrs.admonition().is_some() => {
+                let label = attrs.admonition();
+                let quote = self.quote_line(label, body, line, attrs.properties(), report);
+                root.add_child(quote);
+                report.statistics.increment_feature("admonitions");
+            }
+            // Comment blocks
+            Some('/') => {}
+            Some('|') => {
+                root.add_child(self.table_line(body, line, &attrs, report));
+                report.statistics.increment_feature("tables");
+            }
+            Some('+') => {
+                let mut lines = vec![delimiter];
+                lines.extend_from_slice(body);
+                lines.push(delimiter);
+                self.unsupported(
+                    "passthrough",
+                    "Passthrough blocks",
+                    &lines,
+                    line,
+                    root,
+                    report,
+                )?;
+            }
+            // Example, sidebar and open blocks only group their content
+            _ => {
+                if let Some(name) = attrs.anchor.as_deref() {
+                    root.add_child(AstNode::line(
+                        "",
+                        line,
+                        None,
+                        Some(vec![anchor_property(name)]),
+                    ));
+                }
+                self.convert_lines(body, line + 1, root, report)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn add_heading(
+        &self,
+        level: usize,
+        title: &str,
+        line: usize,
+        attrs: BlockAttributes,
+        root: &AstNode,
+        report: &mut ConversionReport,
+    ) {
+        // `=` is the document title and `==` a first-level section, matching
+        // markdown's `#` and `##`
+        let level = level.min(6) as u8;
+        let (contents, mut properties) = self.convert_inline(title, line, report);
+        properties.extend(attrs.properties().unwrap_or_default());
+        let properties = if properties.is_empty() {
+            None
+        } else {
+            Some(properties)
+        };
+
+        let line_node = AstNode::line("", line, None, properties);
+        if level == 1 {
+            line_node.add_contents(contents);
+            root.add_child(line_node);
+            root.add_child(AstNode::horizontal_line("---", line, None));
+        } else if heading_fontsize(level) > 0 {
+            let decoration =
+                AstNode::decoration("", line, None, heading_fontsize(level), false, false, false);
+            decoration.add_contents(contents);
+            line_node.add_content(decoration);
+            root.add_child(line_node);
+        } else {
+            line_node.add_contents(contents);
+            root.add_child(line_node);
+        }
+
+        report.statistics.increment_feature("headings");
+        report.add_warning(ImportWarning {
+            line,
+            column: None,
+            kind: WarningKind::LossyConversion,
+            feature: "heading".to_string(),
+            message: match (level, heading_fontsize(level)) {
+                (1, _) => "Converted document title to text with horizontal line".to_string(),
+                (level, 0) => format!("Converted level {} section to plain text", level - 1),
+                (level, fontsize) => format!(
+                    "Converted level {} section to emphasized text (fontsize {})",
+                    level - 1,
+                    fontsize
+                ),
+            },
+            suggestion: None,
+        });
+    }
+
+    fn add_list_item(
+        &self,
+        depth: usize,
+        text: &str,
+        line: usize,
+        attrs: BlockAttributes,
+        root: &AstNode,
+        report: &mut ConversionReport,
+    ) {
+        let (checked, text) = match checklist_regex().captures(text) {
+            Some(caps) => (
+                Some(&caps[1] != " "),
+                caps.get(2).map_or("", |m| m.as_str()),
+            ),
+            None => (None, text),
+        };
+
+        let (contents, mut properties) = self.convert_inline(text, line, report);
+        properties.extend(attrs.properties().unwrap_or_default());
+        if let Some(checked) = checked {
+            properties.push(Property::Task {
+                status: if checked {
+                    TaskStatus::Done
+                } else {
+                    TaskStatus::Todo
+                },
+                status_is_canonical: true,
+                due: Deadline::Uninterpretable(String::new()),
+                scheduled: None,
+                completed_at: None,
+                started_at: None,
+                time_spent: None,
+                location: Location::default(),
+            });
+            report.statistics.increment_feature("tasks");
+        }
+        let properties = if properties.is_empty() {
+            None
+        } else {
+            Some(properties)
+        };
+
+        let line_node = AstNode::line("", line, None, properties);
+        line_node.add_contents(contents);
+        add_child_at_depth(root, line_node, depth);
+        report.statistics.increment_feature("lists");
+    }
+
+    fn add_paragraph(&self, paragraph: Paragraph, root: &AstNode, report: &mut ConversionReport) {
+        let Paragraph { line, lines, attrs } = paragraph;
+        if let Some(title) = attrs.title.as_deref() {
+            root.add_child(self.text_line(title, line, None, report));
+        }
+
+        match attrs.block_style() {
+            Some("source") | Some("listing") | Some("literal") => {
+                let lang = match attrs.block_style() {
+                    Some("source") => attrs.source_lang(),
+                    _ => "",
+                };
+                root.add_child(code_line(lang, &lines, line, attrs.properties()));
+                report.statistics.increment_feature("code_blocks");
+                return;
+            }
+            Some("quote") | Some("verse") => {
+                let quote = self.quote_line(None, &lines, line, attrs.properties(), report);
+                root.add_child(quote);
+                report.statistics.increment_feature("blockquotes");
+                return;
+            }
+            _ => {}
+        }
+
+        if let Some(label) = attrs.admonition() {
+            let quote = self.quote_line(Some(label), &lines, line, attrs.properties(), report);
+            root.add_child(quote);
+            report.statistics.increment_feature("admonitions");
+            return;
+        }
+
+        // `NOTE: text` paragraphs
+        if let Some((label, rest)) = lines[0].split_once(": ") {
+            if let Some(label) = ADMONITIONS.iter().copied().find(|l| *l == label) {
+                let mut body = vec![rest];
+                body.extend_from_slice(&lines[1..]);
+                let quote = self.quote_line(Some(label), &body, line, attrs.properties(), report);
+                root.add_child(quote);
+                report.statistics.increment_feature("admonitions");
+                return;
+            }
+        }
+
+        // Indented paragraphs are literal
+        if lines[0].starts_with([' ', '\t']) {
+            let lines: Vec<&str> = lines.iter().map(|l| l.trim_start()).collect();
+            root.add_child(code_line("", &lines, line, attrs.properties()));
+            report.statistics.increment_feature("code_blocks");
+            return;
+        }
+
+        root.add_child(self.text_line(&lines.join(" "), line, attrs.properties(), report));
+    }
+
+    /// A line holding converted inline text, with any inline anchors as properties
+    fn text_line(
+        &self,
+        text: &str,
+        line: usize,
+        properties: Option<Vec<Property>>,
+        report: &mut ConversionReport,
+    ) -> AstNode {
+        let (contents, mut anchors) = self.convert_inline(text, line, report);
+        anchors.extend(properties.unwrap_or_default());
+        let properties = if anchors.is_empty() {
+            None
+        } else {
+            Some(anchors)
+        };
+        let line_node = AstNode::line("", line, None, properties);
+        line_node.add_contents(contents);
+        line_node
+    }
+
+    /// A line holding a quote; each paragraph of `body` becomes one quote line,
+    /// the first led by the admonition label if any
+    fn quote_line(
+        &self,
+        label: Option<&str>,
+        body: &[&str],
+        line: usize,
+        properties: Option<Vec<Property>>,
+        report: &mut ConversionReport,
+    ) -> AstNode {
+        let mut paragraphs: Vec<String> = Vec::new();
+        let mut current: Vec<&str> = Vec::new();
+        for l in body.iter().map(|l| l.trim()) {
+            if l.is_empty() {
+                if !current.is_empty() {
+                    paragraphs.push(current.join(" "));
+                    current.clear();
+                }
+            } else {
+                current.push(l);
+            }
+        }
+        if !current.is_empty() {
+            paragraphs.push(current.join(" "));
+        }
+        if let Some(label) = label {
+            match paragraphs.first_mut() {
+                Some(first) => *first = format!("{}: {}", label, first),
+                None => paragraphs.push(format!("{}:", label)),
+            }
+        }
+
+        let quote = AstNode::quote("", line, None);
+        for (offset, paragraph) in paragraphs.iter().enumerate() {
+            let (contents, anchors) = self.convert_inline(paragraph, line + offset, report);
+            let anchors = if anchors.is_empty() {
+                None
+            } else {
+                Some(anchors)
+            };
+            let content = AstNode::quotecontent("", line + offset, None, anchors);
+            content.add_contents(contents);
+            quote.add_child(content);
+        }
+        let line_node = AstNode::line("", line, None, properties);
+        line_node.add_content(quote);
+        line_node
+    }
+
+    /// A line holding a table; the first row of a `|===` block sets the column count
+    fn table_line(
+        &self,
+        body: &[&str],
+        line: usize,
+        attrs: &BlockAttributes,
+        report: &mut ConversionReport,
+    ) -> AstNode {
+        let mut cells: Vec<String> = Vec::new();
+        let mut columns: Option<usize> = None;
+        for l in body.iter().map(|l| l.trim()) {
+            if l.is_empty() {
+                if columns.is_none() && !cells.is_empty() {
+                    columns = Some(cells.len());
+                }
+                continue;
+            }
+            match l.strip_prefix('|') {
+                Some(row) => {
+                    let row_cells: Vec<String> =
+                        row.split('|').map(|c| c.trim().to_string()).collect();
+                    if columns.is_none() && cells.is_empty() && row_cells.len() > 1 {
+                        columns = Some(row_cells.len());
+                    }
+                    cells.extend(row_cells);
+                }
+                // Continuation of the previous cell
+                None => match cells.last_mut() {
+                    Some(cell) => {
+                        cell.push(' ');
+                        cell.push_str(l);
+                    }
+                    None => cells.push(l.to_string()),
+                },
+            }
+        }
+
+        let table = AstNode::table("", line, None, attrs.title.as_deref());
+        let columns = columns.unwrap_or(cells.len()).max(1);
+        for (offset, row_cells) in cells.chunks(columns).enumerate() {
+            let row = AstNode::tablerow("", line + offset + 1, None);
+            for cell in row_cells {
+                let column = AstNode::tablecolumn("", line + offset + 1, None);
+                let (contents, _) = self.convert_inline(cell, line + offset + 1, report);
+                column.add_contents(contents);
+                row.add_content(column);
+            }
+            table.add_child(row);
+        }
+        let line_node = AstNode::line("", line, None, attrs.properties());
+        line_node.add_content(table);
+        line_node
+    }
+
+    /// Handle a construct patto cannot express according to the import mode
+    fn unsupported(
+        &self,
+        feature: &str,
+        description: &str,
+        lines: &[&str],
+        line: usize,
+        root: &AstNode,
+        report: &mut ConversionReport,
+    ) -> Result<(), ImportError> {
+        match self.options.mode {
+            ImportMode::Strict => Err(ImportError {
+                line,
+                message: format!(
+                    "{} are not supported by patto: {}",
+                    description,
+                    lines.first().map_or("", |l| l.trim())
+                ),
+            }),
+            ImportMode::Lossy => {
+                report.add_warning(ImportWarning {
+                    line,
+                    column: None,
+                    kind: WarningKind::UnsupportedFeature,
+                    feature: feature.to_string(),
+                    message: format!("Dropped {}: {}", feature, lines.join(" ").trim()),
+                    suggestion: Some("Inline the content or use patto markup instead".to_string()),
+                });
+                report.statistics.increment_unsupported(feature);
+                Ok(())
+            }
+            ImportMode::Preserve => {
+                root.add_child(code_line("asciidoc", lines, line, None));
+                report.add_warning(ImportWarning {
+                    line,
+                    column: None,
+                    kind: WarningKind::PreservedContent,
+                    feature: feature.to_string(),
+                    message: format!("Preserved {} in code block for manual editing", feature),
+                    suggestion: None,
+                });
+                Ok(())
+            }
+        }
+    }
+
+    /// Convert inline markup to patto nodes; inline `[[id]]` anchors are
+    /// returned separately as properties of the enclosing line
+    fn convert_inline(
+        &self,
+        text: &str,
+        line: usize,
+        report: &mut ConversionReport,
+    ) -> (Vec<AstNode>, Vec<Property>) {
+        let mut anchors = Vec::new();
+        for caps in inline_anchor_regex().captures_iter(text) {
+            anchors.push(anchor_property(&caps[1]));
+        }
+        let text = inline_anchor_regex().replace_all(text, "");
+        let text = text.trim();
+
+        let mut contents = Vec::new();
+        let mut plain = String::new();
+        let mut last = 0;
+        for caps in inline_regex().captures_iter(text) {
+            let whole = caps.get(0).unwrap();
+            plain.push_str(&text[last..whole.start()]);
+            last = whole.end();
+
+            let node = if let Some(target) = caps.name("xref").or(caps.name("ref")) {
+                report.statistics.increment_feature("cross_references");
+                xref_node(target.as_str(), line)
+            } else if let Some(src) = caps.name("img") {
+                let alt = caps.name("img_alt").map(|m| m.as_str().trim());
+                report.statistics.increment_feature("images");
+                AstNode::image("", line, None, src.as_str(), alt.filter(|a| !a.is_empty()))
+            } else if let Some(target) = caps.name("link").or(caps.name("url")) {
+                let title = caps
+                    .name("link_text")
+                    .or(caps.name("url_text"))
+                    .map(|m| m.as_str().trim())
+                    .filter(|t| !t.is_empty());
+                report.statistics.increment_feature("links");
+                link_node(target.as_str(), title, line)
+            } else if let Some(code) = caps.name("code") {
+                report.statistics.increment_feature("inline_code");
+                let inline_code = AstNode::code(code.as_str(), line, None, "", true);
+                inline_code.add_content(AstNode::codecontent(code.as_str(), line, None));
+                inline_code
+            } else {
+                // Constrained emphasis only applies at word boundaries
+                let bounded = !text[..whole.start()]
+                    .chars()
+                    .next_back()
+                    .is_some_and(char::is_alphanumeric)
+                    && !text[whole.end()..]
+                        .chars()
+                        .next()
+                        .is_some_and(char::is_alphanumeric);
+                if !bounded {
+                    plain.push_str(whole.as_str());
+                    continue;
+                }
+                let (inner, bold) = match caps.name("bold") {
+                    Some(m) => (m.as_str(), true),
+                    None => (&caps["italic"], false),
+                };
+                let decoration = AstNode::decoration(
+                    inner,
+                    line,
+                    None,
+                    if bold { 1 } else { 0 },
+                    !bold,
+                    false,
+                    false,
+                );
+                decoration.add_content(AstNode::text(inner, line, None));
+                decoration
+            };
+
+            if !plain.is_empty() {
+                contents.push(AstNode::text(&plain, line, None));
+                plain.clear();
+            }
+            contents.push(node);
+        }
+        plain.push_str(&text[last..]);
+        if !plain.is_empty() {
+            contents.push(AstNode::text(&plain, line, None));
+        }
+
+        (contents, anchors)
+    }
+}
+
+/// Closing and opening delimiter of a block, e.g. `----` or `|===`
+fn block_delimiter(line: &str) -> Option<&str> {
+    if line == "--" || line == "|===" {
+        return Some(line);
+    }
+    let mut chars = line.chars();
+    let first = chars.next()?;
+    if line.len() >= 4 && "-._=*/+".contains(first) && chars.all(|c| c == first) {
+        Some(line)
+    } else {
+        None
+    }
+}
+
+/// A line wrapping a code block with the given lines
+fn code_line(
+    lang: &str,
+    lines: &[&str],
+    line: usize,
+    properties: Option<Vec<Property>>,
+) -> AstNode {
+    let code = AstNode::code("", line, None, lang, false);
+    for (offset, l) in lines.iter().enumerate() {
+        code.add_child(AstNode::codecontent(l, line + offset + 1, None));
+    }
+    let line_node = AstNode::line("", line, None, properties);
+    line_node.add_content(code);
+    line_node
+}
+
+/// Wikilink for an `xref:` or `<<...>>` target; targets without a document
+/// refer to an anchor of the current note
+fn xref_node(target: &str, line: usize) -> AstNode {
+    let (document, anchor) = match target.split_once('#') {
+        Some((document, anchor)) => (document, Some(anchor)),
+        None if target.contains(".adoc") => (target, None),
+        None => ("", Some(target)),
+    };
+    let note = document.trim_end_matches(".adoc");
+    AstNode::wikilink("", line, None, note, anchor.filter(|a| !a.is_empty()))
+}
+
+/// Link for a `link:` macro or bare URL; links to other AsciiDoc documents become wikilinks
+fn link_node(target: &str, title: Option<&str>, line: usize) -> AstNode {
+    if target.ends_with(".adoc") || target.contains(".adoc#") {
+        return xref_node(target, line);
+    }
+    AstNode::link("", line, None, target, title)
+}
+
+fn anchor_property(name: &str) -> Property {
+    Property::Anchor {
+        name: name.to_string(),
+        location: Location::default(),
+    }
+}
+
+/// Add a list item at the given depth, nesting it under the last item one level up
+fn add_child_at_depth(root: &AstNode, child: AstNode, depth: usize) {
+    if depth <= 1 {
+        root.add_child(child);
+        return;
+    }
+    let last = root.value().children.lock().unwrap().last().cloned();
+    match last {
+        Some(parent) => add_child_at_depth(&parent, child, depth - 1),
+        None => root.add_child(child),
+    }
+}
+
+fn heading_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^(={1,6})\s+(.+)$").unwrap())
+}
+
+fn list_item_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^\s*(\*+|-|\.+|\d+\.)\s+(.*)$").unwrap())
+}
+
+fn checklist_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^\[([ xX*])\]\s*(.*)$").unwrap())
+}
+
+fn attribute_entry_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^:!?[\w-]+!?:").unwrap())
+}
+
+fn block_anchor_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"^(?:\[\[([^\[\],]+)(?:,[^\]]*)?\]\]|\[#([\w-]+)[^\]]*\])$").unwrap()
+    })
+}
+
+fn block_attribute_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^\[([^\[\]]*)\]$").unwrap())
+}
+
+fn block_title_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^\.([^.\s].*)$").unwrap())
+}
+
+fn block_macro_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^([a-zA-Z][\w-]*)::(\S*?)\[(.*)\]$").unwrap())
+}
+
+fn inline_anchor_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\s*\[\[([^\[\],]+)(?:,[^\]]*)?\]\]").unwrap())
+}
+
+fn inline_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(concat!(
+            r"xref:(?P<xref>[^\[\s]+)\[[^\]]*\]",
+            r"|<<(?P<ref>[^,>]+)(?:,[^>]*)?>>",
+            r"|image:(?P<img>[^\[\s:][^\[\s]*)\[(?P<img_alt>[^\]]*)\]",
+            r"|link:(?P<link>[^\[\s]+)\[(?P<link_text>[^\]]*)\]",
+            r"|(?P<url>https?://[^\s\[]+)(?:\[(?P<url_text>[^\]]*)\])?",
+            r"|`(?P<code>[^`]+)`",
+            r"|\*(?P<bold>[^*\s](?:[^*]*[^*\s])?)\*",
+            r"|_(?P<italic>[^_\s](?:[^_]*[^_\s])?)_",
+        ))
+        .unwrap()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn import_lossy(adoc: &str) -> ImportResult {
+        let importer = AsciidocImporter::new(ImportOptions::new(ImportMode::Lossy));
+        importer.import(adoc, "test.adoc", "test.pn").unwrap()
+    }
+
+    fn import_strict(adoc: &str) -> Result<ImportResult, ImportError> {
+        let importer = AsciidocImporter::new(ImportOptions::new(ImportMode::Strict));
+        importer.import(adoc, "test.adoc", "test.pn")
+    }
+
+    fn import_preserve(adoc: &str) -> ImportResult {
+        let importer = AsciidocImporter::new(ImportOptions::new(ImportMode::Preserve));
+        importer.import(adoc, "test.adoc", "test.pn").unwrap()
+    }
+
+    #[test]
+    fn test_paragraph_lines_joined() {
+        let result = import_lossy("Hello\nworld\n\nNext paragraph");
+        assert_eq!(result.patto_content, "Hello world\nNext paragraph\n");
+    }
+
+    #[test]
+    fn test_headings() {
+        let result = import_lossy("= Title\n:toc:\n\n== Section\n\n=== Sub\n\n==== Deep");
+        assert_eq!(
+            result.patto_content,
+            "Title\n---\n[** Section]\n[* Sub]\nDeep\n"
+        );
+        assert_eq!(result.report.statistics.feature_counts["headings"], 4);
+    }
+
+    #[test]
+    fn test_heading_anchor() {
+        let result = import_lossy("[[setup]]\n== Setup");
+        assert_eq!(result.patto_content.trim(), "[** Setup] #setup");
+    }
+
+    #[test]
+    fn test_nested_lists() {
+        let result = import_lossy("* one\n** nested\n* two\n\n. first\n.. second");
+        assert_eq!(
+            result.patto_content,
+            "one\n\tnested\ntwo\nfirst\n\tsecond\n"
+        );
+    }
+
+    #[test]
+    fn test_checklist() {
+        let result = import_lossy("* [x] done\n* [ ] todo");
+        assert!(result.patto_content.contains("done {@task status=done}"));
+        assert!(result.patto_content.contains("todo {@task status=todo}"));
+    }
+
+    #[test]
+    fn test_source_block() {
+        let result = import_lossy("[source,rust]\n----\nfn main() {\n}\n----\nafter");
+        assert_eq!(
+            result.patto_content,
+            "[@code rust]\n\tfn main() {\n\t}\nafter\n"
+        );
+    }
+
+    #[test]
+    fn test_listing_block_without_language() {
+        let result = import_lossy("----\nplain\n----");
+        assert!(result.patto_content.starts_with("[@code]\n\tplain\n"));
+    }
+
+    #[test]
+    fn test_admonition_paragraph() {
+        let result = import_lossy("NOTE: Mind the gap.");
+        assert_eq!(result.patto_content, "[@quote]\n\tNOTE: Mind the gap.\n");
+    }
+
+    #[test]
+    fn test_admonition_block() {
+        let result = import_lossy("[WARNING]\n====\nHot surface.\n\nDo not touch.\n====");
+        assert_eq!(
+            result.patto_content,
+            "[@quote]\n\tWARNING: Hot surface.\n\tDo not touch.\n"
+        );
+        assert_eq!(result.report.statistics.feature_counts["admonitions"], 1);
+    }
+
+    #[test]
+    fn test_quote_block() {
+        let result = import_lossy("____\nQuoted text\n____");
+        assert_eq!(result.patto_content, "[@quote]\n\tQuoted text\n");
+    }
+
+    #[test]
+    fn test_cross_references() {
+        let result = import_lossy(
+            "See <<setup>>, <<usage,Usage>>, xref:guide.adoc#install[Install] and xref:faq.adoc[FAQ].",
+        );
+        assert_eq!(
+            result.patto_content.trim(),
+            "See [#setup], [#usage], [guide#install] and [faq]."
+        );
+    }
+
+    #[test]
+    fn test_inline_anchor() {
+        let result = import_lossy("Target line [[here]]");
+        assert_eq!(result.patto_content.trim(), "Target line #here");
+    }
+
+    #[test]
+    fn test_inline_markup() {
+        let result = import_lossy("A *bold* and _italic_ `code` in snake_case_name");
+        assert_eq!(
+            result.patto_content.trim(),
+            "A [* bold] and [/ italic] [` code `] in snake_case_name"
+        );
+    }
+
+    #[test]
+    fn test_links_and_images() {
+        let result = import_lossy(
+            "Visit https://example.com[Example] or https://rust-lang.org\n\nimage::cat.png[A cat]",
+        );
+        assert!(result
+            .patto_content
+            .contains("Visit [Example https://example.com] or [https://rust-lang.org]"));
+        assert!(result.patto_content.contains("[@img cat.png \"A cat\"]"));
+    }
+
+    #[test]
+    fn test_table() {
+        let result = import_lossy(".Sizes\n|===\n|Name |Size\n\n|a |1\n|===");
+        assert_eq!(
+            result.patto_content,
+            "[@table caption=\"Sizes\"]\n\tName\tSize\n\ta\t1\n"
+        );
+    }
+
+    #[test]
+    fn test_comments_skipped() {
+        let result = import_lossy("// a comment\n////\nblock comment\n////\nkept");
+        assert_eq!(result.patto_content, "kept\n");
+    }
+
+    #[test]
+    fn test_strict_mode_fails_on_include() {
+        let result = import_strict("intro\n\ninclude::chapter.adoc[]");
+        let err = result.unwrap_err();
+        assert_eq!(err.line, 3);
+        assert!(err.message.contains("Includes are not supported"));
+    }
+
+    #[test]
+    fn test_strict_mode_fails_on_block_macro() {
+        let result = import_strict("toc::[]");
+        assert!(result.unwrap_err().message.contains("Block macros"));
+    }
+
+    #[test]
+    fn test_lossy_mode_drops_include() {
+        let result = import_lossy("include::chapter.adoc[]\nafter");
+        assert_eq!(result.patto_content, "after\n");
+        assert!(result
+            .report
+            .warnings
+            .iter()
+            .any(|w| w.feature == "include"));
+        assert_eq!(result.report.statistics.unsupported_features["include"], 1);
+    }
+
+    #[test]
+    fn test_preserve_mode_wraps_include() {
+        let result = import_preserve("include::chapter.adoc[]");
+        assert_eq!(
+            result.patto_content,
+            "[@code asciidoc]\n\tinclude::chapter.adoc[]\n"
+        );
+    }
+
+    #[test]
+    fn test_preserve_mode_wraps_passthrough() {
+        let result = import_preserve("++++\n<div>raw</div>\n++++");
+        assert!(result
+            .patto_content
+            .contains("[@code asciidoc]\n\t++++\n\t<div>raw</div>\n\t++++\n"));
+    }
+}
@@ -26,7 +26,7 @@ impl std::fmt::Display for ImportError {
 
 impl std::error::Error for ImportError {}
 
-/// Result of markdown import
+/// Result of an import
 #[derive(Debug)]
 pub struct ImportResult {
     /// Converted patto AST (root node)
@@ -96,18 +96,7 @@ impl MarkdownImporter {
         let ast = self.convert_to_ast(markdown, &mut report)?;
 
         // Render AST to patto string format using PattoRenderer
-        let renderer = PattoRenderer::new();
-        let mut patto_content = Vec::new();
-        renderer
-            .format(&ast, &mut patto_content)
-            .map_err(|e| ImportError {
-                line: 0,
-                message: format!("Failed to render AST: {}", e),
-            })?;
-        let patto_content = String::from_utf8(patto_content).map_err(|e| ImportError {
-            line: 0,
-            message: format!("Invalid UTF-8 in output: {}", e),
-        })?;
+        let patto_content = render_patto(&ast)?;
 
         report.statistics.converted_lines =
             report.statistics.total_lines - report.statistics.failed_lines;
@@ -158,6 +147,8 @@ impl MarkdownImporter {
         let mut heading_level: u8 = 0;
         let mut heading_contents: Vec<AstNode> = Vec::new();
         let mut current_line_node: Option<AstNode> = None;
+        let mut footnote_label: Option<String> = None; // Inside a footnote definition
+        let mut footnotes: Vec<(String, Vec<AstNode>)> = Vec::new(); // Definitions and their paragraphs
         let mut pending_contents: Vec<AstNode> = Vec::new();
         let mut in_emphasis = false;
         let mut in_strong = false;
@@ -185,15 +176,14 @@ impl MarkdownImporter {
                         }
                         Tag::List(ordered) => {
                             // Flush any pending line content before nested list
-                            if let Some(line_node) = current_line_node.take() {
-                                if !pending_contents.is_empty() {
-                                    for content in pending_contents.drain(..) {
-                                        line_node.add_content(content);
-                                    }
-                                }
-                                // Add task property if applicable
-                                if let Some(checked) = current_task_status.take() {
-                                    self.add_task_property_to_line(&line_node, checked, report);
+                            if current_line_node.take().is_some() {
+                                // Rebuild the line node with its task property, if any
+                                let properties = current_task_status.take().map(|checked| {
+                                    vec![self.task_property(&pending_contents, checked, report)]
+                                });
+                                let line_node = AstNode::line("", current_line, None, properties);
+                                for content in pending_contents.drain(..) {
+                                    line_node.add_content(content);
                                 }
                                 // Add to parent (use indent_level to find correct parent)
                                 if let Some(ref list_root) = list_root_node {
@@ -279,7 +269,7 @@ impl MarkdownImporter {
                                     Some(AstNode::line("", current_line, None, None));
                             }
                         }
-                        Tag::FootnoteDefinition(_) => match self.options.mode {
+                        Tag::FootnoteDefinition(label) => match self.options.mode {
                             ImportMode::Strict => {
                                 return Err(ImportError {
                                     line: current_line,
@@ -290,12 +280,17 @@ impl MarkdownImporter {
                                 report.add_warning(ImportWarning {
                                     line: current_line,
                                     column: None,
-                                    kind: WarningKind::UnsupportedFeature,
+                                    kind: WarningKind::LossyConversion,
                                     feature: "footnote".to_string(),
-                                    message: "Dropped footnote definition".to_string(),
-                                    suggestion: Some("Move footnote content inline".to_string()),
+                                    message: format!(
+                                        "Moved footnote [^{}] to the Notes section",
+                                        label
+                                    ),
+                                    suggestion: None,
                                 });
-                                report.statistics.increment_unsupported("footnotes");
+                                report.statistics.increment_feature("footnotes");
+                                footnote_label = Some(label.to_string());
+                                footnotes.push((label.to_string(), Vec::new()));
                             }
                             ImportMode::Preserve => {}
                         },
@@ -317,13 +312,13 @@ impl MarkdownImporter {
                                 // Add horizontal line
                                 let hr = AstNode::horizontal_line("---", current_line, None);
                                 root.add_child(hr);
-                            } else {
-                                // H2-H6: bold decoration
+                            } else if heading_fontsize(heading_level) > 0 {
+                                // H2, H3: bold decoration sized by level
                                 let decoration = AstNode::decoration(
                                     "",
                                     current_line,
                                     None,
-                                    1,
+                                    heading_fontsize(heading_level),
                                     false,
                                     false,
                                     false,
@@ -334,21 +329,30 @@ impl MarkdownImporter {
                                 let line_node = AstNode::line("", current_line, None, None);
                                 line_node.add_content(decoration);
                                 root.add_child(line_node);
+                            } else {
+                                // H4-H6: plain text, as patto has no smaller size
+                                let line_node = AstNode::line("", current_line, None, None);
+                                for content in heading_contents.drain(..) {
+                                    line_node.add_content(content);
+                                }
+                                root.add_child(line_node);
                             }
                             report.add_warning(ImportWarning {
                                 line: current_line,
                                 column: None,
                                 kind: WarningKind::LossyConversion,
                                 feature: "heading".to_string(),
-                                message: format!(
-                                    "Converted h{} heading to {}",
-                                    heading_level,
-                                    if heading_level == 1 {
-                                        "text with horizontal line"
-                                    } else {
-                                        "emphasized text"
+                                message: match (heading_level, heading_fontsize(heading_level)) {
+                                    (1, _) => "Converted h1 heading to text with horizontal line"
+                                        .to_string(),
+                                    (level, 0) => {
+                                        format!("Converted h{} heading to plain text", level)
                                     }
-                                ),
+                                    (level, fontsize) => format!(
+                                        "Converted h{} heading to emphasized text (fontsize {})",
+                                        level, fontsize
+                                    ),
+                                },
                                 suggestion: None,
                             });
                         }
@@ -362,56 +366,9 @@ impl MarkdownImporter {
                         }
                         TagEnd::Item => {
                             // Finalize the line node with task property if applicable
-                            let properties = if let Some(checked) = current_task_status.take() {
-                                // Extract text content to get due date
-                                let text: String = pending_contents
-                                    .iter()
-                                    .map(|n| n.extract_str().to_string())
-                                    .collect::<Vec<_>>()
-                                    .join("");
-
-                                let status = if checked {
-                                    TaskStatus::Done
-                                } else {
-                                    TaskStatus::Todo
-                                };
-                                let due = self
-                                    .extract_due_date(&text)
-                                    .and_then(|d| {
-                                        chrono::NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok()
-                                    })
-                                    .map(Deadline::Date)
-                                    .unwrap_or(Deadline::Uninterpretable(String::new()));
-
-                                let scheduled = self
-                                    .extract_scheduled_date(&text)
-                                    .and_then(|d| {
-                                        chrono::NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok()
-                                    })
-                                    .map(Deadline::Date);
-
-                                let completed_at = self
-                                    .extract_completed_at_date(&text)
-                                    .and_then(|d| {
-                                        chrono::NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok()
-                                    })
-                                    .map(Deadline::Date);
-
-                                report.statistics.increment_feature("tasks");
-
-                                Some(vec![Property::Task {
-                                    status,
-                                    status_is_canonical: true,
-                                    due,
-                                    scheduled,
-                                    completed_at,
-                                    started_at: None,
-                                    time_spent: None,
-                                    location: crate::parser::Location::default(),
-                                }])
-                            } else {
-                                None
-                            };
+                            let properties = current_task_status.take().map(|checked| {
+                                vec![self.task_property(&pending_contents, checked, report)]
+                            });
 
                             // Create line node with properties
                             let line_node = AstNode::line("", current_line, None, properties);
@@ -495,13 +452,21 @@ impl MarkdownImporter {
                                 pending_contents.push(link_node);
                             }
                         }
+                        TagEnd::FootnoteDefinition => {
+                            footnote_label = None;
+                        }
                         TagEnd::Paragraph => {
                             // Finalize paragraph as line
                             if let Some(line_node) = current_line_node.take() {
                                 for content in pending_contents.drain(..) {
                                     line_node.add_content(content);
                                 }
-                                if !in_blockquote {
+                                if footnote_label.is_some() {
+                                    // Footnote bodies are appended at the end of the document
+                                    if let Some((_, lines)) = footnotes.last_mut() {
+                                        lines.push(line_node);
+                                    }
+                                } else if !in_blockquote {
                                     root.add_child(line_node);
                                 } else if let Some(quote) = quote_node.as_ref() {
                                     // Add as quote content
@@ -645,15 +610,31 @@ impl MarkdownImporter {
                         });
                     }
                     ImportMode::Lossy => {
+                        // Link to the anchored note in the trailing Notes section
+                        let anchor = footnote_anchor(&name);
+                        let link = AstNode::wikilink(
+                            &format!("[#{}]", anchor),
+                            current_line,
+                            None,
+                            "",
+                            Some(&anchor),
+                        );
+                        if in_heading {
+                            heading_contents.push(link);
+                        } else {
+                            pending_contents.push(link);
+                        }
                         report.add_warning(ImportWarning {
                             line: current_line,
                             column: None,
-                            kind: WarningKind::UnsupportedFeature,
+                            kind: WarningKind::LossyConversion,
                             feature: "footnote_ref".to_string(),
-                            message: format!("Dropped footnote reference [^{}]", name),
-                            suggestion: Some("Move footnote content inline".to_string()),
+                            message: format!(
+                                "Converted footnote reference [^{}] to [#{}]",
+                                name, anchor
+                            ),
+                            suggestion: None,
                         });
-                        report.statistics.increment_unsupported("footnotes");
                     }
                     ImportMode::Preserve => {
                         let text = AstNode::text(&format!("[^{}]", name), current_line, None);
@@ -672,6 +653,33 @@ impl MarkdownImporter {
             root.add_child(line_node);
         }
 
+        // Append collected footnotes under a Notes heading, each anchored for its references
+        if !footnotes.is_empty() {
+            let heading = AstNode::line("", current_line, None, None);
+            let decoration = AstNode::decoration("", current_line, None, 1, false, false, false);
+            decoration.add_content(AstNode::text("Notes", current_line, None));
+            heading.add_content(decoration);
+            for (label, lines) in footnotes {
+                let anchor = Property::Anchor {
+                    name: footnote_anchor(&label),
+                    location: crate::parser::Location::default(),
+                };
+                let note = AstNode::line("", current_line, None, Some(vec![anchor]));
+                let mut lines = lines.into_iter();
+                if let Some(first) = lines.next() {
+                    for content in first.value().contents.lock().unwrap().iter() {
+                        note.add_content(content.clone());
+                    }
+                }
+                // Further paragraphs of the footnote nest under its first line
+                for line in lines {
+                    note.add_child(line);
+                }
+                heading.add_child(note);
+            }
+            root.add_child(heading);
+        }
+
         Ok(root)
     }
 
@@ -765,20 +773,20 @@ impl MarkdownImporter {
         }
     }
 
-    /// Add task property to a line node
-    fn add_task_property_to_line(
+    /// Build the task property of a checked or unchecked list item, reading
+    /// due, scheduled and completion dates from its text
+    fn task_property(
         &self,
-        _line_node: &AstNode,
+        contents: &[AstNode],
         checked: bool,
         report: &mut ConversionReport,
-    ) {
-        // Extract due date from line content
-        let contents = _line_node.value().contents.lock().unwrap();
-        let mut text = String::new();
-        for content in contents.iter() {
-            text.push_str(content.extract_str());
-        }
-        drop(contents);
+    ) -> Property {
+        let text: String = contents.iter().map(|n| n.extract_str()).collect();
+        let parse_date = |d: String| {
+            chrono::NaiveDate::parse_from_str(&d, "%Y-%m-%d")
+                .ok()
+                .map(Deadline::Date)
+        };
 
         let status = if checked {
             TaskStatus::Done
@@ -787,17 +795,23 @@ impl MarkdownImporter {
         };
         let due = self
             .extract_due_date(&text)
-            .and_then(|d| chrono::NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok())
-            .map(Deadline::Date);
+            .and_then(parse_date)
+            .unwrap_or(Deadline::Uninterpretable(String::new()));
+        let scheduled = self.extract_scheduled_date(&text).and_then(parse_date);
+        let completed_at = self.extract_completed_at_date(&text).and_then(parse_date);
 
-        // Create new line node with task property
-        // Note: Due to how AstNode works, we can't easily modify the kind after creation
-        // The task property is tracked in the report for now
         report.statistics.increment_feature("tasks");
 
-        // For now, we'll track this but the actual property setting
-        // would require modifying how AstNode is created
-        let _ = (status, due);
+        Property::Task {
+            status,
+            status_is_canonical: true,
+            due,
+            scheduled,
+            completed_at,
+            started_at: None,
+            time_spent: None,
+            location: crate::parser::Location::default(),
+        }
     }
 
     fn extract_due_date(&self, text: &str) -> Option<String> {
@@ -906,6 +920,43 @@ impl MarkdownImporter {
     }
 }
 
+/// Render an imported AST to patto text
+pub(super) fn render_patto(ast: &AstNode) -> Result<String, ImportError> {
+    let renderer = PattoRenderer::new();
+    let mut patto_content = Vec::new();
+    renderer
+        .format(ast, &mut patto_content)
+        .map_err(|e| ImportError {
+            line: 0,
+            message: format!("Failed to render AST: {}", e),
+        })?;
+    String::from_utf8(patto_content).map_err(|e| ImportError {
+        line: 0,
+        message: format!("Invalid UTF-8 in output: {}", e),
+    })
+}
+
+/// Anchor name for a footnote label; characters that cannot appear in an anchor become `-`
+fn footnote_anchor(label: &str) -> String {
+    let label: String = label
+        .chars()
+        .map(|c| {
+            if c.is_whitespace() || c == ']' || c == '}' {
+                '-'
+            } else {
+                c
+            }
+        })
+        .collect();
+    format!("fn-{}", label)
+}
+
+/// Decoration size for an H2-H6 heading: H2 is `[** ]`, H3 `[* ]`, and H4 or
+/// smaller has no size left
+pub(super) fn heading_fontsize(level: u8) -> isize {
+    (4 - level as isize).max(0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -973,7 +1024,38 @@ mod tests {
     #[test]
     fn test_heading_conversion_h2() {
         let result = import_lossy("## Subtitle");
-        assert!(result.patto_content.contains("[* Subtitle]"));
+        assert!(result.patto_content.contains("[** Subtitle]"));
+        assert!(result.report.warnings[0].message.contains("fontsize 2"));
+    }
+
+    #[test]
+    fn test_heading_conversion_h3() {
+        let result = import_lossy("### Section");
+        assert!(result.patto_content.contains("[* Section]"));
+        assert!(!result.patto_content.contains("[** Section]"));
+    }
+
+    #[test]
+    fn test_heading_conversion_h4() {
+        let result = import_lossy("#### Detail");
+        assert_eq!(result.patto_content.trim(), "Detail");
+        assert!(result.report.warnings[0].message.contains("plain text"));
+    }
+
+    #[test]
+    fn test_heading_levels_roundtrip() {
+        let result = import_lossy(
+            "## Chapter
+
+### Section
+",
+        );
+        let parsed = crate::parser::parse_text(&result.patto_content);
+        let mut output = Vec::new();
+        PattoRenderer::new()
+            .format(&parsed.ast, &mut output)
+            .unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), result.patto_content);
     }
 
     #[test]
@@ -1053,6 +1135,51 @@ mod tests {
             .contains("{@task status=todo due=2024-12-31}"));
     }
 
+    #[test]
+    fn test_done_task_with_due_date_emoji() {
+        let result = import_lossy("- [x] done 📅 2024-12-31");
+        assert!(result
+            .patto_content
+            .contains("{@task status=done due=2024-12-31}"));
+    }
+
+    #[test]
+    fn test_task_with_nested_list() {
+        // A task item followed by a nested list is flushed before the list starts
+        let result = import_lossy("- [ ] parent 📅 2024-12-31\n  - [x] child\n");
+        assert!(
+            result
+                .patto_content
+                .contains("parent 📅 2024-12-31 {@task status=todo due=2024-12-31}"),
+            "{}",
+            result.patto_content
+        );
+        assert!(result.patto_content.contains("child {@task status=done}"));
+        assert_eq!(
+            result.report.statistics.feature_counts.get("tasks"),
+            Some(&2)
+        );
+    }
+
+    #[test]
+    fn test_footnote_moved_to_notes() {
+        let result = import_lossy("Text with a note[^1].\n\n[^1]: The footnote text.\n");
+        let content = &result.patto_content;
+        assert!(content.contains("Text with a note[#fn-1]."), "{}", content);
+        assert!(content.contains("[* Notes]\n\tThe footnote text. #fn-1\n"));
+        assert_eq!(content.matches("The footnote text.").count(), 1);
+        assert_eq!(
+            result.report.statistics.feature_counts.get("footnotes"),
+            Some(&1)
+        );
+    }
+
+    #[test]
+    fn test_footnote_strict_errors() {
+        let result = import_strict("Text[^1].\n\n[^1]: Note.\n");
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_task_with_due_date_parentheses() {
         let result = import_lossy("- [ ] task (due: 2024-12-31)");
@@ -1,15 +1,24 @@
-//! Markdown to Patto importer module
+//! Markdown and AsciiDoc to Patto importer module
 //!
-//! This module provides functionality to convert markdown files to patto format.
+//! This module provides functionality to convert markdown and AsciiDoc files to
+//! patto format.
 //! It supports three import modes:
 //! - Strict: Stop on first unsupported feature
 //! - Lossy: Continue on errors, drop unsupported features
 //! - Preserve: Wrap unsupported features in code blocks
 
+mod asciidoc;
+pub mod cli;
 mod converter;
 mod options;
 mod report;
+mod roundtrip;
 
-pub use converter::MarkdownImporter;
+pub use asciidoc::AsciidocImporter;
+pub use converter::{ImportError, ImportResult, MarkdownImporter};
 pub use options::{ImportMode, ImportOptions, MarkdownInputFlavor};
-pub use report::{ConversionReport, ConversionStatistics, ImportWarning, WarningKind};
+pub use report::{
+    BlockDivergence, ConversionReport, ConversionStatistics, ImportWarning, RoundtripReport,
+    WarningKind,
+};
+pub use roundtrip::roundtrip_check;
@@ -226,6 +226,49 @@ impl ConversionReport {
     }
 }
 
+/// A block whose structure changed in a markdown → patto → markdown round trip
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockDivergence {
+    /// Block type, e.g. `heading`, `list_item` or `code_block`
+    pub block_type: String,
+    /// The block in the original markdown, if it had a counterpart there
+    pub original: Option<String>,
+    /// The block in the round-tripped markdown, if it had a counterpart there
+    pub roundtrip: Option<String>,
+}
+
+/// Result of a markdown → patto → markdown stability check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoundtripReport {
+    /// Flavor used for both import and export
+    pub flavor: MarkdownInputFlavor,
+    /// Intermediate patto content
+    pub patto_content: String,
+    /// Markdown rendered back from the patto AST
+    pub markdown: String,
+    /// Blocks that did not survive the round trip, in document order
+    pub divergences: Vec<BlockDivergence>,
+}
+
+impl RoundtripReport {
+    /// Whether the round trip preserved every block
+    pub fn is_stable(&self) -> bool {
+        self.divergences.is_empty()
+    }
+
+    /// Distinct block types that diverged, sorted by name
+    pub fn diverged_block_types(&self) -> Vec<&str> {
+        let mut types: Vec<&str> = self
+            .divergences
+            .iter()
+            .map(|d| d.block_type.as_str())
+            .collect();
+        types.sort_unstable();
+        types.dedup();
+        types
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,293 @@
+//! Markdown → patto → markdown round-trip check
+//!
+//! Imports markdown, renders the patto AST back to markdown and compares the
+//! two documents block by block. Blocks are compared on their type and
+//! whitespace-normalized text, so formatting differences such as list markers
+//! or line wrapping do not count as divergences.
+
+use super::converter::MarkdownImporter;
+use super::options::{ImportMode, ImportOptions, MarkdownInputFlavor};
+use super::report::{BlockDivergence, RoundtripReport};
+use crate::markdown::{MarkdownFlavor, MarkdownRendererOptions};
+use crate::renderer::{MarkdownRenderer, Renderer};
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+
+/// A block of a markdown document, reduced to what the comparison looks at
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Block {
+    block_type: &'static str,
+    /// Heading level, code language or task marker
+    detail: String,
+    text: String,
+}
+
+impl Block {
+    fn summary(&self) -> String {
+        if self.detail.is_empty() {
+            self.text.clone()
+        } else if self.text.is_empty() {
+            self.detail.clone()
+        } else {
+            format!("{} {}", self.detail, self.text)
+        }
+    }
+}
+
+/// Import `markdown` as `flavor`, render it back and report the blocks that diverged
+///
+/// The import runs in lossy mode so that unsupported features show up as
+/// divergences rather than errors.
+pub fn roundtrip_check(markdown: &str, flavor: MarkdownInputFlavor) -> RoundtripReport {
+    let importer = MarkdownImporter::new(ImportOptions::new(ImportMode::Lossy).with_flavor(flavor));
+    let patto_content;
+    let mut rendered = String::new();
+    match importer.import(markdown, "roundtrip.md", "roundtrip.pn") {
+        Ok(result) => {
+            let export_flavor = match flavor {
+                MarkdownInputFlavor::Standard => MarkdownFlavor::Standard,
+                MarkdownInputFlavor::Obsidian => MarkdownFlavor::Obsidian,
+                MarkdownInputFlavor::GitHub => MarkdownFlavor::GitHub,
+            };
+            let renderer = MarkdownRenderer::new(
+                MarkdownRendererOptions::new(export_flavor).with_frontmatter(false),
+            );
+            let mut output = Vec::new();
+            if renderer.format(&result.ast, &mut output).is_ok() {
+                rendered = String::from_utf8_lossy(&output).into_owned();
+            }
+            patto_content = result.patto_content;
+        }
+        // Lossy imports do not fail in practice; treat a failure as losing everything
+        Err(_) => patto_content = String::new(),
+    }
+
+    let divergences = diff_blocks(&blocks(markdown), &blocks(&rendered));
+    RoundtripReport {
+        flavor,
+        patto_content,
+        markdown: rendered,
+        divergences,
+    }
+}
+
+/// Split a markdown document into blocks in document order
+///
+/// List items are blocks of their own, while paragraphs inside items, quotes,
+/// footnotes and tables belong to the enclosing block.
+fn blocks(markdown: &str) -> Vec<Block> {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TASKLISTS);
+    options.insert(Options::ENABLE_FOOTNOTES);
+
+    let mut blocks: Vec<Block> = Vec::new();
+    // Indices into `blocks` of the blocks currently open
+    let mut open: Vec<usize> = Vec::new();
+
+    fn start(
+        blocks: &mut Vec<Block>,
+        open: &mut Vec<usize>,
+        block_type: &'static str,
+        detail: String,
+    ) {
+        open.push(blocks.len());
+        blocks.push(Block {
+            block_type,
+            detail,
+            text: String::new(),
+        });
+    }
+
+    for event in Parser::new_ext(markdown, options) {
+        match event {
+            Event::Start(tag) => match tag {
+                Tag::Heading { level, .. } => {
+                    start(&mut blocks, &mut open, "heading", level.to_string())
+                }
+                Tag::Paragraph if open.is_empty() => {
+                    start(&mut blocks, &mut open, "paragraph", String::new())
+                }
+                Tag::Item => start(&mut blocks, &mut open, "list_item", String::new()),
+                Tag::CodeBlock(kind) => {
+                    let lang = match kind {
+                        pulldown_cmark::CodeBlockKind::Fenced(lang) => lang.to_string(),
+                        pulldown_cmark::CodeBlockKind::Indented => String::new(),
+                    };
+                    start(&mut blocks, &mut open, "code_block", lang)
+                }
+                Tag::BlockQuote(_) => start(&mut blocks, &mut open, "blockquote", String::new()),
+                Tag::Table(_) => start(&mut blocks, &mut open, "table", String::new()),
+                Tag::HtmlBlock => start(&mut blocks, &mut open, "html", String::new()),
+                Tag::FootnoteDefinition(label) => {
+                    start(&mut blocks, &mut open, "footnote", label.to_string())
+                }
+                _ => {}
+            },
+            Event::End(tag_end) => match tag_end {
+                TagEnd::Paragraph
+                    if open
+                        .last()
+                        .is_some_and(|&i| blocks[i].block_type == "paragraph") =>
+                {
+                    open.pop();
+                }
+                TagEnd::Heading(_)
+                | TagEnd::Item
+                | TagEnd::CodeBlock
+                | TagEnd::BlockQuote(_)
+                | TagEnd::Table
+                | TagEnd::HtmlBlock
+                | TagEnd::FootnoteDefinition => {
+                    open.pop();
+                }
+                _ => {}
+            },
+            Event::Text(text) | Event::Code(text) | Event::Html(text) | Event::InlineHtml(text) => {
+                if let Some(&i) = open.last() {
+                    blocks[i].text.push(' ');
+                    blocks[i].text.push_str(&text);
+                }
+            }
+            Event::FootnoteReference(label) => {
+                if let Some(&i) = open.last() {
+                    blocks[i].text.push_str(&format!(" [^{}]", label));
+                }
+            }
+            Event::TaskListMarker(checked) => {
+                if let Some(&i) = open.last() {
+                    blocks[i].block_type = "task";
+                    blocks[i].detail = if checked { "[x]" } else { "[ ]" }.to_string();
+                }
+            }
+            Event::Rule => blocks.push(Block {
+                block_type: "rule",
+                detail: String::new(),
+                text: String::new(),
+            }),
+            _ => {}
+        }
+    }
+
+    for block in blocks.iter_mut() {
+        block.text = block.text.split_whitespace().collect::<Vec<_>>().join(" ");
+    }
+    // Items that only wrap a nested list carry no text of their own
+    blocks.retain(|b| b.block_type != "list_item" || !b.text.is_empty());
+    blocks
+}
+
+/// Align both block sequences on their longest common subsequence and report
+/// the blocks outside it; a removed and an added block of the same type at the
+/// same place are reported as one changed block
+fn diff_blocks(original: &[Block], roundtrip: &[Block]) -> Vec<BlockDivergence> {
+    let (n, m) = (original.len(), roundtrip.len());
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if original[i] == roundtrip[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut divergences = Vec::new();
+    let (mut removed, mut added): (Vec<&Block>, Vec<&Block>) = (Vec::new(), Vec::new());
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && original[i] == roundtrip[j] {
+            flush_changes(&mut removed, &mut added, &mut divergences);
+            i += 1;
+            j += 1;
+        } else if j < m && (i == n || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            added.push(&roundtrip[j]);
+            j += 1;
+        } else {
+            removed.push(&original[i]);
+            i += 1;
+        }
+    }
+    flush_changes(&mut removed, &mut added, &mut divergences);
+    divergences
+}
+
+/// Turn a run of removed and added blocks into divergences, pairing blocks of the same type
+fn flush_changes(
+    removed: &mut Vec<&Block>,
+    added: &mut Vec<&Block>,
+    divergences: &mut Vec<BlockDivergence>,
+) {
+    for block in removed.drain(..) {
+        let counterpart = added
+            .iter()
+            .position(|b| b.block_type == block.block_type)
+            .map(|pos| added.remove(pos));
+        divergences.push(BlockDivergence {
+            block_type: block.block_type.to_string(),
+            original: Some(block.summary()),
+            roundtrip: counterpart.map(Block::summary),
+        });
+    }
+    for block in added.drain(..) {
+        divergences.push(BlockDivergence {
+            block_type: block.block_type.to_string(),
+            original: None,
+            roundtrip: Some(block.summary()),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocks_normalize_whitespace() {
+        let blocks = blocks("# Title\n\nsome   text\nwrapped\n\n- [x] done\n");
+        let summaries: Vec<(&str, String)> =
+            blocks.iter().map(|b| (b.block_type, b.summary())).collect();
+        assert_eq!(
+            summaries,
+            vec![
+                ("heading", "h1 Title".to_string()),
+                ("paragraph", "some text wrapped".to_string()),
+                ("task", "[x] done".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plain_document_is_stable() {
+        let report = roundtrip_check(
+            "Intro paragraph.\n\n```rust\nfn main() {}\n```\n",
+            MarkdownInputFlavor::Standard,
+        );
+        assert!(report.is_stable(), "{:?}", report.divergences);
+    }
+
+    #[test]
+    fn test_dropped_html_is_reported() {
+        let report = roundtrip_check("intro\n\n<div>html</div>\n", MarkdownInputFlavor::Standard);
+        assert_eq!(report.diverged_block_types(), vec!["html"]);
+        assert_eq!(
+            report.divergences[0],
+            BlockDivergence {
+                block_type: "html".to_string(),
+                original: Some("<div>html</div>".to_string()),
+                roundtrip: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_changed_block_is_paired() {
+        let original = blocks("## Title\n");
+        let roundtrip = blocks("### Title\n");
+        let divergences = diff_blocks(&original, &roundtrip);
+        assert_eq!(divergences.len(), 1);
+        assert_eq!(divergences[0].original.as_deref(), Some("h2 Title"));
+        assert_eq!(divergences[0].roundtrip.as_deref(), Some("h3 Title"));
+    }
+}
@@ -0,0 +1,142 @@
+//! On-disk index of a workspace that lets a repository skip reparsing notes
+//! that did not change since the last run.
+//!
+//! For every note the index keeps its modification time, a hash of its content
+//! and the wiki links it contains, which is enough to rebuild the link graph.
+//! ASTs are not stored; notes restored from the index are parsed lazily.
+//!
+//! Only the language server keeps an index, in the user's cache directory
+//! rather than in the notes directory.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Name of the index file of the notes directory `root_dir`, unique per directory
+pub fn index_cache_file_name(root_dir: &Path) -> String {
+    let digest = Sha256::digest(root_dir.to_string_lossy().as_bytes());
+    format!("index-{}.json", &format!("{:x}", digest)[..16])
+}
+
+/// Bumped whenever the layout changes; indexes of other versions are ignored
+const INDEX_CACHE_VERSION: u32 = 1;
+
+/// A wiki link as written in a note, before it is resolved to a file
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CachedLink {
+    pub link: String,
+    pub anchor: Option<String>,
+    /// Line number (0-indexed)
+    pub line: usize,
+    /// Column range within the line (byte offsets)
+    pub col_range: (usize, usize),
+}
+
+/// What the index remembers of a single note
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CachedNote {
+    /// Modification time in nanoseconds since the Unix epoch
+    pub modified: u64,
+    /// Hex SHA-256 of the content
+    pub hash: String,
+    pub links: Vec<CachedLink>,
+}
+
+/// The index of a workspace, keyed by note path relative to the notes
+/// directory, without the extension
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct IndexCache {
+    version: u32,
+    pub notes: BTreeMap<String, CachedNote>,
+}
+
+impl IndexCache {
+    /// Read the index at `path`; a missing, unreadable or outdated index is empty
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str::<Self>(&contents).ok())
+            .filter(|cache| cache.version == INDEX_CACHE_VERSION)
+            .unwrap_or_default()
+    }
+
+    /// Write the index to `path`, replacing the previous one atomically
+    pub fn save(&mut self, path: &Path) -> anyhow::Result<()> {
+        self.version = INDEX_CACHE_VERSION;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp_path = path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string(self)?)?;
+        std::fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+}
+
+/// Hex SHA-256 of a note's content
+pub fn content_hash(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+/// Modification time of `path` in nanoseconds since the Unix epoch
+pub fn modified_time(path: &Path) -> Option<u64> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_nanos() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir
+            .path()
+            .join("patto")
+            .join(index_cache_file_name(dir.path()));
+        assert_eq!(IndexCache::load(&path), IndexCache::default());
+
+        let mut cache = IndexCache::default();
+        cache.notes.insert(
+            "sub/note".to_string(),
+            CachedNote {
+                modified: 42,
+                hash: content_hash("[other#anchor]\n"),
+                links: vec![CachedLink {
+                    link: "other".to_string(),
+                    anchor: Some("anchor".to_string()),
+                    line: 0,
+                    col_range: (0, 15),
+                }],
+            },
+        );
+        cache.save(&path).unwrap();
+        assert_eq!(IndexCache::load(&path), cache);
+
+        // Indexes written by another version are discarded
+        let outdated = std::fs::read_to_string(&path)
+            .unwrap()
+            .replace("\"version\":1", "\"version\":0");
+        std::fs::write(&path, outdated).unwrap();
+        assert_eq!(IndexCache::load(&path), IndexCache::default());
+    }
+
+    #[test]
+    fn file_name_follows_directory() {
+        let a = index_cache_file_name(Path::new("/notes/a"));
+        assert_eq!(a, index_cache_file_name(Path::new("/notes/a")));
+        assert_ne!(a, index_cache_file_name(Path::new("/notes/b")));
+        assert!(a.starts_with("index-") && a.ends_with(".json"));
+    }
+
+    #[test]
+    fn hash_follows_content() {
+        assert_eq!(content_hash("a"), content_hash("a"));
+        assert_ne!(content_hash("a"), content_hash("b"));
+        assert_eq!(content_hash("").len(), 64);
+    }
+}
@@ -1,5 +1,9 @@
+pub mod check;
 pub mod diagnostic_translator;
+pub mod export;
+pub mod ignore;
 pub mod importer;
+pub mod index_cache;
 pub mod line_tracker;
 pub mod lsp;
 pub mod markdown;
@@ -7,7 +11,8 @@ pub mod parser;
 pub mod renderer;
 pub mod repository;
 pub mod semantic_token;
-#[cfg(feature = "preview-tui")]
+pub mod stats;
+#[cfg(any(feature = "preview-tui", feature = "html-highlight"))]
 pub mod syntax_highlight;
 pub mod task;
 #[cfg(feature = "preview-tui")]
@@ -1,11 +1,9 @@
 use std::collections::hash_map::DefaultHasher;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::hash::{Hash, Hasher};
 //use std::time::Instant;
 
 pub struct LineTracker {
-    content_to_id: HashMap<u64, Vec<i64>>,
-    position_to_id: HashMap<usize, i64>,
     next_id: i64,
     line_ids: Vec<i64>,
     line_hashes: Vec<u64>,
@@ -14,14 +12,15 @@ pub struct LineTracker {
 impl LineTracker {
     pub fn new() -> anyhow::Result<Self> {
         Ok(LineTracker {
-            content_to_id: HashMap::new(),
-            position_to_id: HashMap::new(),
             next_id: 1,
             line_ids: Vec::new(),
             line_hashes: Vec::new(),
         })
     }
 
+    /// Assign an ID to every line of `content`, keeping the IDs of the
+    /// previous content for lines that stayed, moved or were shifted by
+    /// inserts and deletes; IDs are unique within a file.
     pub fn process_file_content(&mut self, content: &str) -> anyhow::Result<Vec<i64>> {
         let lines: Vec<&str> = content.lines().collect();
 
@@ -35,69 +34,60 @@ impl LineTracker {
             })
             .collect();
 
-        let mut new_content_to_id: HashMap<u64, Vec<i64>> = HashMap::new();
-        let mut new_position_to_id: HashMap<usize, i64> = HashMap::new();
-        let mut used_ids: HashSet<i64> = HashSet::new();
-        let mut result_ids = Vec::with_capacity(lines.len());
+        let mut result_ids: Vec<Option<i64>> = vec![None; lines.len()];
+        let mut reused = vec![false; self.line_ids.len()];
 
-        //let start = Instant::now();
-        // Assign IDs with simple in-memory logic
+        // Lines with unchanged content at the same position keep their ID
         for (idx, &hash) in line_hashes.iter().enumerate() {
-            let line_num = idx + 1;
-
-            let id = if let Some(&existing_id) = self.position_to_id.get(&line_num) {
-                // Same position exists
-                if let Some(existing_hash) = self.line_hashes.get(idx) {
-                    if *existing_hash == hash {
-                        // Same content at same position - reuse ID
-                        used_ids.insert(existing_id);
-                        existing_id
-                    } else {
-                        // Different content at same position - find reusable ID
-                        self.find_or_create_id(hash, &mut used_ids)
-                    }
-                } else {
-                    // Position exists but no hash (shouldn't happen)
-                    self.find_or_create_id(hash, &mut used_ids)
-                }
-            } else {
-                // New position
-                self.find_or_create_id(hash, &mut used_ids)
-            };
+            if self.line_hashes.get(idx) == Some(&hash) {
+                result_ids[idx] = Some(self.line_ids[idx]);
+                reused[idx] = true;
+            }
+        }
 
-            new_content_to_id.entry(hash).or_default().push(id);
-            new_position_to_id.insert(line_num, id);
-            result_ids.push(id);
+        // Other lines take the ID of a previous line with the same content,
+        // the closest one if the content appears several times, so moved
+        // lines keep their ID
+        let mut old_positions: HashMap<u64, Vec<usize>> = HashMap::new();
+        for (old_idx, &hash) in self.line_hashes.iter().enumerate() {
+            if !reused[old_idx] {
+                old_positions.entry(hash).or_default().push(old_idx);
+            }
+        }
+        for (idx, &hash) in line_hashes.iter().enumerate() {
+            if result_ids[idx].is_some() {
+                continue;
+            }
+            let Some(candidates) = old_positions.get_mut(&hash) else {
+                continue;
+            };
+            let Some(nearest) = (0..candidates.len()).min_by_key(|&i| candidates[i].abs_diff(idx))
+            else {
+                continue;
+            };
+            let old_idx = candidates.remove(nearest);
+            result_ids[idx] = Some(self.line_ids[old_idx]);
         }
-        //println!("-- b: {} ms", start.elapsed().as_millis());
+
+        // Everything else is new
+        let result_ids: Vec<i64> = result_ids
+            .into_iter()
+            .map(|id| {
+                id.unwrap_or_else(|| {
+                    let new_id = self.next_id;
+                    self.next_id += 1;
+                    new_id
+                })
+            })
+            .collect();
 
         // Update internal state
-        self.content_to_id = new_content_to_id;
-        self.position_to_id = new_position_to_id;
         self.line_ids = result_ids.clone();
         self.line_hashes = line_hashes;
 
         Ok(result_ids)
     }
 
-    fn find_or_create_id(&mut self, hash: u64, used_ids: &mut HashSet<i64>) -> i64 {
-        // Try to reuse existing ID for this content
-        if let Some(existing_ids) = self.content_to_id.get(&hash) {
-            for &id in existing_ids {
-                if !used_ids.contains(&id) {
-                    used_ids.insert(id);
-                    return id;
-                }
-            }
-        }
-
-        // No reusable ID found - create new one
-        let new_id = self.next_id;
-        self.next_id += 1;
-        used_ids.insert(new_id);
-        new_id
-    }
-
     pub fn get_line_id(&self, line_number: usize) -> Option<i64> {
         if line_number == 0 || line_number > self.line_ids.len() {
             None
@@ -145,4 +135,52 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_line_tracking_swapped_paragraphs() -> anyhow::Result<()> {
+        let mut tracker = LineTracker::new()?;
+
+        let ids1 = tracker.process_file_content("Title\n\nalpha 1\nalpha 2\n\nbeta 1\nbeta 2\n")?;
+        let ids2 = tracker.process_file_content("Title\n\nbeta 1\nbeta 2\n\nalpha 1\nalpha 2\n")?;
+
+        // The paragraphs keep their IDs at their new positions
+        assert_eq!(ids2[0], ids1[0]);
+        assert_eq!(&ids2[2..4], &ids1[5..7]);
+        assert_eq!(&ids2[5..7], &ids1[2..4]);
+        // The blank lines stay where they were
+        assert_eq!(ids2[1], ids1[1]);
+        assert_eq!(ids2[4], ids1[4]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_line_tracking_ids_stay_unique() -> anyhow::Result<()> {
+        let mut tracker = LineTracker::new()?;
+
+        let ids1 = tracker.process_file_content("a\nb\nc\n")?;
+        // "a" moves down past a line that reuses its old position
+        let ids2 = tracker.process_file_content("b\nc\na\nnew\nb\n")?;
+
+        assert_eq!(&ids2[0..3], &[ids1[1], ids1[2], ids1[0]]);
+        let mut unique = ids2.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), ids2.len(), "Duplicate IDs in {:?}", ids2);
+        assert!(ids2[3] > ids1[2] && ids2[4] > ids1[2]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_line_tracking_insert_shifts_lines() -> anyhow::Result<()> {
+        let mut tracker = LineTracker::new()?;
+
+        let ids1 = tracker.process_file_content("x\n\ny\n\n")?;
+        let ids2 = tracker.process_file_content("new\nx\n\ny\n\n")?;
+
+        assert_eq!(&ids2[1..], &ids1[..]);
+
+        Ok(())
+    }
 }
@@ -1,10 +1,12 @@
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use urlencoding::decode;
 
 use dashmap::DashMap;
 use str_indices::utf16::{from_byte_idx as utf16_from_byte_idx, to_byte_idx as utf16_to_byte_idx};
 
+use super::lsp_config::resolve_cache_file;
 use super::paper::{PaperCatalog, PaperProviderError};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
@@ -12,17 +14,29 @@ use tower_lsp::jsonrpc::Result;
 use tower_lsp::lsp_types::*;
 use tower_lsp::{Client, LanguageServer};
 
-use crate::diagnostic_translator::{DiagnosticTranslator, FriendlyDiagnostic};
+use crate::diagnostic_translator::{
+    DiagnosticConfig, DiagnosticTranslator, FriendlyDiagnostic, Locale,
+};
+use crate::export::{self, ExportFormat};
+use crate::index_cache::index_cache_file_name;
+use crate::lsp::formatting::{format_document, format_tables};
 use crate::lsp::task_edits::{
     collect_task_snapshots, detect_task_transitions, generate_edits_for_transition,
+    generate_toggle_edits,
 };
 use crate::markdown::{MarkdownFlavor, MarkdownRendererOptions};
 use crate::parser::{
-    self, AstNode, AstNodeKind, Deadline, ParserResult, PattoLineParser, Property, Rule, TaskStatus,
+    self, AstNode, AstNodeKind, Deadline, DeadlineStatus, ParserResult, PattoLineParser, Property,
+    Rule, TaskStatus,
 };
 use crate::renderer::{MarkdownRenderer, Renderer};
-use crate::repository::{Repository, RepositoryMessage};
-use crate::semantic_token::{get_semantic_tokens, get_semantic_tokens_range, LEGEND_TYPE};
+use crate::repository::{
+    find_anchor, gather_anchor_targets, GraphFormat, LinkNormalization, Repository,
+    RepositoryMessage, ScanState, TaskFilter,
+};
+use crate::semantic_token::{
+    get_semantic_tokens, get_semantic_tokens_range, LEGEND_MODIFIER, LEGEND_TYPE,
+};
 use pest::Parser as _;
 
 use fuzzy_matcher::skim::SkimMatcherV2;
@@ -35,6 +49,21 @@ pub struct PattoSettings {
     /// Markdown export settings
     #[serde(default)]
     markdown: MarkdownSettings,
+    /// Rename settings
+    #[serde(default)]
+    rename: RenameSettings,
+    /// Loose WikiLink matching (off by default)
+    #[serde(default)]
+    links: LinkNormalization,
+    /// Severity overrides by diagnostic code; `off` suppresses a code
+    #[serde(default)]
+    diagnostics: DiagnosticConfig,
+    /// Export of saved notes (off unless an output directory is set)
+    #[serde(default)]
+    export: ExportSettings,
+    /// Language of diagnostic messages, from the client's `initializationOptions`
+    #[serde(skip)]
+    locale: Locale,
 }
 
 #[derive(Debug, Clone, Deserialize, Default)]
@@ -45,6 +74,25 @@ pub struct MarkdownSettings {
     default_flavor: Option<String>,
 }
 
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RenameSettings {
+    /// Offer to rename the current file from anywhere in it, not only from the first line
+    #[serde(default)]
+    always_rename_file: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportSettings {
+    /// Directory saved notes are rendered into, relative to the workspace root
+    #[serde(default)]
+    output_dir: Option<PathBuf>,
+    /// `markdown` (in the default markdown flavor) or `html`
+    #[serde(default)]
+    format: ExportFormat,
+}
+
 //#[derive(Debug)]
 pub struct Backend {
     pub client: Client,
@@ -56,12 +104,19 @@ pub struct Backend {
     /// Retained across keystrokes so that mid-edit parse failures (e.g. `status=`)
     /// don't lose the `Doing` state needed to compute elapsed time on clock-out.
     pub last_valid_task_snapshots: Arc<DashMap<Url, HashMap<usize, crate::task::TaskSnapshot>>>,
+    /// Extension of note files, without the dot (`pn` unless configured)
+    pub note_extension: String,
 }
 
 fn get_node_range(from: &AstNode) -> Range {
-    let row = from.location().row as u32;
-    let s = utf16_from_byte_idx(from.extract_str(), from.location().span.0) as u32;
-    let e = utf16_from_byte_idx(from.extract_str(), from.location().span.1) as u32;
+    get_location_range(from.location())
+}
+
+fn get_location_range(location: &parser::Location) -> Range {
+    let row = location.row as u32;
+    // Spans are byte offsets into the whole line, not into the node's own text
+    let s = utf16_from_byte_idx(&location.input, location.span.0) as u32;
+    let e = utf16_from_byte_idx(&location.input, location.span.1) as u32;
     Range::new(Position::new(row, s), Position::new(row, e))
 }
 
@@ -84,29 +139,31 @@ fn get_node_range(from: &AstNode) -> Range {
 //     Some(relative_path.join("/"))
 // }
 
-fn parse_text(text: &str) -> (AstNode, Vec<Diagnostic>) {
+fn parse_text(text: &str, translator: &DiagnosticTranslator) -> (AstNode, Vec<Diagnostic>) {
     let ParserResult { ast, parse_errors } = parser::parse_text(text);
-    let translator = DiagnosticTranslator::default();
     let mut diagnostics: Vec<Diagnostic> = parse_errors
         .into_iter()
-        .map(|error| {
+        .filter_map(|error| {
             let location = error.location().clone();
+            // Spans are byte offsets into the line, clients count UTF-16 units
+            let line = location.input.as_ref();
+            let column = |byte: usize| utf16_from_byte_idx(line, byte) as u32;
             let FriendlyDiagnostic {
                 message,
                 code,
                 code_description_uri,
                 severity,
-            } = translator.translate(&error);
+            } = translator.translate(&error)?;
 
             let code_value = code.map(NumberOrString::String);
             let code_description = code_description_uri
                 .and_then(|href| Url::parse(&href).ok())
                 .map(|href| CodeDescription { href });
 
-            Diagnostic {
+            Some(Diagnostic {
                 range: Range::new(
-                    Position::new(location.row as u32, location.span.0 as u32),
-                    Position::new(location.row as u32, location.span.1 as u32),
+                    Position::new(location.row as u32, column(location.span.0)),
+                    Position::new(location.row as u32, column(location.span.1)),
                 ),
                 severity: Some(severity),
                 code: code_value,
@@ -114,13 +171,60 @@ fn parse_text(text: &str) -> (AstNode, Vec<Diagnostic>) {
                 source: Some("patto".into()),
                 message,
                 ..Diagnostic::default()
-            }
+            })
         })
         .collect();
 
     diagnostics.extend(gather_malformed_command_diagnostics(text));
     diagnostics.extend(gather_stale_started_at_diagnostics(&ast));
-    (ast, diagnostics)
+    (ast, configure_diagnostics(diagnostics, translator))
+}
+
+/// Find the lines that differ between `old` and `new`, as an edit against `old`.
+/// Returns `None` when the texts are identical.
+fn changed_line_range(old: &str, new: &str) -> Option<(Range, String)> {
+    if old == new {
+        return None;
+    }
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+    let prefix = old_lines
+        .iter()
+        .zip(&new_lines)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old_lines
+        .iter()
+        .rev()
+        .zip(new_lines.iter().rev())
+        .take(old_lines.len().min(new_lines.len()) - prefix)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let range = Range::new(
+        Position::new(prefix as u32, 0),
+        Position::new((old_lines.len() - suffix) as u32, 0),
+    );
+    Some((range, new_lines[prefix..new_lines.len() - suffix].concat()))
+}
+
+/// Apply the configured severities to diagnostics not produced by the
+/// translator, dropping the codes configured `off`
+fn configure_diagnostics(
+    diagnostics: Vec<Diagnostic>,
+    translator: &DiagnosticTranslator,
+) -> Vec<Diagnostic> {
+    diagnostics
+        .into_iter()
+        .filter_map(|mut diagnostic| {
+            let code = match &diagnostic.code {
+                Some(NumberOrString::String(code)) => Some(code.as_str()),
+                _ => None,
+            };
+            let default = diagnostic.severity.unwrap_or(DiagnosticSeverity::ERROR);
+            diagnostic.severity = Some(translator.severity(code, default)?);
+            Some(diagnostic)
+        })
+        .collect()
 }
 
 /// Scan raw text for `[@embed ...]` / `[@img ...]` patterns that failed to parse
@@ -237,18 +341,71 @@ fn gather_stale_started_at_diagnostics_impl(node: &AstNode, diags: &mut Vec<Diag
     }
 }
 
-fn gather_anchors(parent: &AstNode, anchors: &mut Vec<(String, usize)>) {
-    if let AstNodeKind::Line { ref properties } = &parent.kind() {
-        for prop in properties {
-            if let Property::Anchor { name, location } = prop {
-                anchors.push((name.to_string(), location.row));
-            }
-        }
-    }
+/// Completion items for the workspace images fuzzy-matching `query`, as paths
+/// relative to the note at `uri`
+fn image_completion_items(
+    repo: &Repository,
+    uri: &Url,
+    query: &str,
+    range: Range,
+) -> Vec<CompletionItem> {
+    let Some(note_dir) = uri
+        .to_file_path()
+        .ok()
+        .and_then(|p| p.parent().map(Path::to_path_buf))
+    else {
+        return vec![];
+    };
+    let matcher = SkimMatcherV2::default();
+    let mut scored: Vec<(i64, String)> = repo
+        .image_files
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(|image| {
+            let path = relative_file_link(&note_dir, image);
+            let score = if query.is_empty() {
+                0
+            } else {
+                matcher.fuzzy_match(&path, query)?
+            };
+            Some((score, path))
+        })
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+    scored
+        .into_iter()
+        .map(|(_, path)| CompletionItem {
+            label: path.clone(),
+            detail: Some("image".to_string()),
+            kind: Some(CompletionItemKind::FILE),
+            insert_text_format: Some(InsertTextFormat::PLAIN_TEXT),
+            text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                new_text: path,
+                range,
+            })),
+            ..Default::default()
+        })
+        .collect()
+}
 
-    for child in parent.value().children.lock().unwrap().iter() {
-        gather_anchors(child, anchors);
-    }
+/// `target` relative to `base_dir` in the `./` or `../` form that local file
+/// paths must take
+fn relative_file_link(base_dir: &Path, target: &Path) -> String {
+    let base: Vec<_> = base_dir.components().collect();
+    let target: Vec<_> = target.components().collect();
+    let common = base.iter().zip(&target).take_while(|(a, b)| a == b).count();
+    let ups = base.len() - common;
+    let prefix = if ups == 0 {
+        "./".to_string()
+    } else {
+        "../".repeat(ups)
+    };
+    let rest: Vec<_> = target[common..]
+        .iter()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect();
+    prefix + &rest.join("/")
 }
 
 #[derive(Debug, Eq, PartialEq, Clone, Deserialize, Serialize)]
@@ -259,6 +416,14 @@ pub struct TaskInformation {
     /// Human-readable label: raw line text with the task property token removed.
     pub text: String,
 
+    /// Display name of the note containing the task (its file stem)
+    #[serde(default)]
+    pub note: String,
+
+    /// Text of the section heading the task sits under
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub heading: Option<String>,
+
     pub message: String,
 
     /// The deadline of this task
@@ -282,13 +447,20 @@ pub struct TaskInformation {
 
     /// Task status
     pub status: TaskStatus,
+
+    /// Whether the deadline is overdue, due today or upcoming when the task was collected
+    #[serde(default)]
+    pub deadline_status: DeadlineStatus,
 }
 
 impl TaskInformation {
     pub fn new(location: Location, text: String, message: String, due: Deadline) -> Self {
+        let deadline_status = due.status(chrono::Local::now().naive_local());
         Self {
             location,
             text,
+            note: String::new(),
+            heading: None,
             message,
             due,
             scheduled: None,
@@ -296,6 +468,7 @@ impl TaskInformation {
             started_at: None,
             time_spent: None,
             status: TaskStatus::Todo,
+            deadline_status,
         }
     }
 }
@@ -354,11 +527,19 @@ fn task_information(
     due: &Deadline,
 ) -> TaskInformation {
     let mut info = TaskInformation::new(
-        Location::new(uri.clone(), get_node_range(line)),
+        Location::new(
+            Repository::client_url_percent_encoding(uri),
+            get_node_range(line),
+        ),
         task_label(line),
         String::new(),
         due.clone(),
     );
+    info.note = uri
+        .to_file_path()
+        .ok()
+        .and_then(|path| path.file_stem()?.to_str().map(str::to_string))
+        .unwrap_or_default();
     if let AstNodeKind::Line { properties } = &line.kind() {
         for prop in properties {
             if let Property::Task {
@@ -382,26 +563,44 @@ fn task_information(
     info
 }
 
-fn find_anchor(parent: &AstNode, anchor: &str) -> Option<AstNode> {
-    if let AstNodeKind::Line { ref properties } = &parent.kind() {
-        for prop in properties {
-            if let Property::Anchor { name, .. } = prop {
-                if name == anchor {
-                    return Some(parent.clone());
-                }
-            }
-        }
+/// Read the optional `experimental/aggregate_tasks` argument; unknown or
+/// malformed fields are ignored
+fn task_filter_from_value(value: &Value) -> TaskFilter {
+    let date = |key: &str| {
+        value
+            .get(key)
+            .and_then(|v| v.as_str())
+            .and_then(|s| chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").ok())
+    };
+    TaskFilter {
+        status: value
+            .get("status")
+            .and_then(|v| v.as_str())
+            .and_then(|s| match s {
+                "todo" => Some(TaskStatus::Todo),
+                "doing" => Some(TaskStatus::Doing),
+                "paused" => Some(TaskStatus::Paused),
+                "done" => Some(TaskStatus::Done),
+                _ => None,
+            }),
+        from: date("from"),
+        to: date("to"),
+        include_done: value
+            .get("includeDone")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
     }
+}
 
-    #[allow(clippy::map_clone)]
-    return parent
-        .value()
-        .children
-        .lock()
-        .unwrap()
-        .iter()
-        .find_map(|child| find_anchor(child, anchor))
-        .map(|x| x.clone());
+/// Serialize a wiki link to `note`, optionally pointing at `anchor`.
+///
+/// Both rename paths rebuild links through here, so a link keeps its anchor
+/// when the note is renamed and keeps its note when the anchor is renamed.
+fn format_wiki_link(note: &str, anchor: Option<&str>) -> String {
+    match anchor {
+        Some(anchor) => format!("[{}#{}]", note, anchor),
+        None => format!("[{}]", note),
+    }
 }
 
 /// Find anchor definition at the given row and column position
@@ -431,6 +630,26 @@ fn find_anchor_at_position(
     None
 }
 
+/// Locations of every definition of the anchor `name`
+fn find_anchor_definitions(parent: &AstNode, name: &str, found: &mut Vec<parser::Location>) {
+    if let AstNodeKind::Line { ref properties } = &parent.kind() {
+        for prop in properties {
+            if let Property::Anchor {
+                name: anchor,
+                location,
+            } = prop
+            {
+                if anchor == name {
+                    found.push(location.clone());
+                }
+            }
+        }
+    }
+    for child in parent.value().children.lock().unwrap().iter() {
+        find_anchor_definitions(child, name, found);
+    }
+}
+
 fn locate_node_route(parent: &AstNode, row: usize, col: usize) -> Option<Vec<AstNode>> {
     if let Some(route) = locate_node_route_impl(parent, row, col) {
         //route.reverse();
@@ -478,15 +697,37 @@ fn locate_node_route_impl(parent: &AstNode, row: usize, col: usize) -> Option<Ve
 }
 
 impl Backend {
+    /// Replace the whole text of a document, reparsing only the lines that
+    /// differ when it is already cached
+    fn replace_document_text(&self, uri: &Url, text: &str) {
+        let Ok(file_path) = uri.to_file_path() else {
+            return;
+        };
+        if let Some(repo) = self.repository.lock().unwrap().as_ref() {
+            let cached = repo.document_map.get(uri).map(|rope| rope.to_string());
+            match cached {
+                Some(old_text) => {
+                    if let Some((range, new_text)) = changed_line_range(&old_text, text) {
+                        repo.apply_edit(uri, range, &new_text);
+                    }
+                }
+                None => repo.add_file_to_graph(&file_path, text),
+            }
+        }
+    }
+
     async fn on_change(&self, params: TextDocumentItem) {
         let uri = Repository::normalize_url_percent_encoding(&params.uri);
+        self.replace_document_text(&uri, &params.text);
+        self.on_document_updated(params).await
+    }
 
-        if let Ok(file_path) = uri.to_file_path() {
-            // Update graph with new content.
-            if let Some(repo) = self.repository.lock().unwrap().as_ref() {
-                repo.add_file_to_graph(&file_path, &params.text);
-            }
+    /// Apply the clock transitions of the edited tasks and publish diagnostics
+    /// once the repository holds the new text of a document
+    async fn on_document_updated(&self, params: TextDocumentItem) {
+        let uri = Repository::normalize_url_percent_encoding(&params.uri);
 
+        if uri.to_file_path().is_ok() {
             // Fetch the new AST and compute task snapshots for this version.
             let new_ast: Option<AstNode> = self
                 .repository
@@ -532,7 +773,10 @@ impl Backend {
 
                 if !edits.is_empty() {
                     let workspace_edit = WorkspaceEdit {
-                        changes: Some([(uri.clone(), edits)].iter().cloned().collect()),
+                        changes: Some(HashMap::from([(
+                            Repository::client_url_percent_encoding(&uri),
+                            edits,
+                        )])),
                         document_changes: None,
                         change_annotations: None,
                     };
@@ -542,12 +786,99 @@ impl Backend {
         }
 
         // Parse for diagnostics (LSP-specific, not handled by repository).
-        let (_, diagnostics) = parse_text(&params.text);
+        let translator = {
+            let settings = self.settings.lock().unwrap();
+            DiagnosticTranslator::localized(settings.locale, settings.diagnostics.clone())
+        };
+        let (_, mut diagnostics) = parse_text(&params.text, &translator);
+        if let Some(repo) = self.repository.lock().unwrap().as_ref() {
+            let collisions = repo.link_collisions(&uri).into_iter().map(|other| {
+                Diagnostic {
+                    range: Range::default(),
+                    severity: Some(DiagnosticSeverity::WARNING),
+                    code: Some(NumberOrString::String("link-collision".into())),
+                    source: Some("patto".into()),
+                    message: format!(
+                        "Note name matches '{}' under link normalization; links to either are ambiguous",
+                        other
+                    ),
+                    ..Diagnostic::default()
+                }
+            });
+            diagnostics.extend(configure_diagnostics(collisions.collect(), &translator));
+        }
         self.client
-            .publish_diagnostics(params.uri.clone(), diagnostics, Some(params.version))
+            .publish_diagnostics(
+                Repository::client_url_percent_encoding(&params.uri),
+                diagnostics,
+                Some(params.version),
+            )
             .await;
     }
 
+    /// The markdown flavor named `requested`, falling back to the default
+    /// flavor of the settings, then to standard
+    fn markdown_flavor(&self, requested: Option<&str>) -> MarkdownFlavor {
+        let flavor = requested.map(|s| s.to_string()).or_else(|| {
+            self.settings
+                .lock()
+                .unwrap()
+                .markdown
+                .default_flavor
+                .clone()
+        });
+        match flavor.unwrap_or_default().to_lowercase().as_str() {
+            "obsidian" => MarkdownFlavor::Obsidian,
+            "github" => MarkdownFlavor::GitHub,
+            _ => MarkdownFlavor::Standard,
+        }
+    }
+
+    /// Bring the graph in line with a saved note, and render it into the
+    /// export directory when one is configured
+    async fn on_save(&self, uri: &Url) {
+        let Ok(path) = uri.to_file_path() else {
+            return;
+        };
+        let Some(repo) = self.repository.lock().unwrap().clone() else {
+            return;
+        };
+
+        match tokio::fs::read_to_string(&path).await {
+            Ok(content) => {
+                let cached = repo.document_map.get(uri).map(|rope| rope.to_string());
+                if cached.as_deref() != Some(content.as_str()) {
+                    repo.handle_live_file_change(path.clone(), content).await;
+                }
+            }
+            Err(e) => {
+                log::warn!("Failed to read saved note {}: {}", path.display(), e);
+                return;
+            }
+        }
+
+        let ExportSettings { output_dir, format } = self.settings.lock().unwrap().export.clone();
+        let Some(output_dir) = output_dir else {
+            return;
+        };
+        let Some(ast) = repo.ast_map.get(uri).map(|e| e.value().clone()) else {
+            return;
+        };
+        let output_dir = repo.root_dir.join(output_dir);
+        let flavor = self.markdown_flavor(None);
+        match export::export_note(&ast, &repo.root_dir, &output_dir, &path, format, flavor) {
+            Ok(target) => log::info!("Exported {} to {}", path.display(), target.display()),
+            Err(e) => {
+                self.client
+                    .log_message(
+                        MessageType::WARNING,
+                        format!("Failed to export {}: {}", path.display(), e),
+                    )
+                    .await;
+            }
+        }
+    }
+
     async fn start_repository_listener(&self) {
         let repo_guard = self.repository.lock().unwrap();
         if let Some(repo) = repo_guard.as_ref() {
@@ -586,13 +917,22 @@ impl Backend {
                                 .await;
                         }
 
-                        RepositoryMessage::ScanProgress { scanned, total } => {
+                        RepositoryMessage::ScanProgress {
+                            scanned,
+                            total,
+                            elapsed_ms,
+                        } => {
                             if progress_active {
-                                let percentage = if total > 0 {
-                                    ((scanned * 100) / total) as u32
-                                } else {
-                                    0
+                                let state = ScanState {
+                                    scanned,
+                                    total,
+                                    elapsed_ms,
+                                    completed: false,
                                 };
+                                let eta = state
+                                    .eta_ms()
+                                    .map(|ms| format!(", about {}s left", ms.div_ceil(1000)))
+                                    .unwrap_or_default();
 
                                 let _ = client
                                     .send_notification::<notification::Progress>(ProgressParams {
@@ -600,10 +940,10 @@ impl Backend {
                                         value: ProgressParamsValue::WorkDone(
                                             WorkDoneProgress::Report(WorkDoneProgressReport {
                                                 message: Some(format!(
-                                                    "{}/{} files",
-                                                    scanned, total
+                                                    "{}/{} files{}",
+                                                    scanned, total, eta
                                                 )),
-                                                percentage: Some(percentage),
+                                                percentage: Some(state.percentage()),
                                                 cancellable: Some(false),
                                             }),
                                         ),
@@ -612,7 +952,10 @@ impl Backend {
                             }
                         }
 
-                        RepositoryMessage::ScanCompleted { total_files } => {
+                        RepositoryMessage::ScanCompleted {
+                            total_files,
+                            ignored,
+                        } => {
                             if progress_active {
                                 let _ = client
                                     .send_notification::<notification::Progress>(ProgressParams {
@@ -630,7 +973,10 @@ impl Backend {
                             client
                                 .log_message(
                                     MessageType::INFO,
-                                    format!("Scan completed: {} files indexed", total_files),
+                                    format!(
+                                        "Scan completed: {} files indexed, {} ignored",
+                                        total_files, ignored
+                                    ),
                                 )
                                 .await;
                         }
@@ -674,9 +1020,8 @@ impl Backend {
                     };
                     let linkuri = repo.link_to_uri(s, &root_uri).unwrap_or(uri.clone());
                     log::debug!("linkuri: {}", linkuri);
-                    if let Some(ast) = repo.ast_map.get(&linkuri) {
-                        let mut anchors = vec![];
-                        gather_anchors(ast.value(), &mut anchors);
+                    if let Some(ast) = repo.ast(&linkuri) {
+                        let anchors = gather_anchor_targets(&ast);
                         let link_rope = repo.document_map.get(&linkuri);
                         return Some(
                             anchors
@@ -716,6 +1061,23 @@ impl Backend {
             let slicelen = slice.len_chars();
             if let Some(foundbracket) = slice.chars_at(cur_col).reversed().position(|c| c == '[') {
                 let maybelink = slicelen.saturating_sub(foundbracket);
+
+                // Image path right after `[@img `, before the alt text
+                if let Some(query) = line
+                    .slice(maybelink..cur_col)
+                    .as_str()
+                    .and_then(|s| s.strip_prefix("@img "))
+                {
+                    if !query.contains([' ', '"']) {
+                        let path_start = maybelink + "@img ".len();
+                        let start_char =
+                            utf16_from_byte_idx(line_str, line.char_to_byte(path_start)) as u32;
+                        let replacement_range =
+                            Range::new(Position::new(position.line, start_char), position);
+                        return Some(image_completion_items(repo, uri, query, replacement_range));
+                    }
+                }
+
                 let s = line.slice(maybelink..cur_col).as_str()?;
                 log::debug!(
                     "matching {}, from {}, found at {}",
@@ -724,14 +1086,13 @@ impl Backend {
                     foundbracket
                 );
 
-                if let Some(root_uri_str) = self
+                if let Some(root_path) = self
                     .root_uri
                     .lock()
                     .unwrap()
                     .as_ref()
                     .and_then(|root_uri| root_uri.to_file_path().ok())
                 {
-                    let baselen = root_uri_str.to_string_lossy().len();
                     let matcher = SkimMatcherV2::default();
                     let start_char =
                         utf16_from_byte_idx(line_str, line.char_to_byte(maybelink)) as u32;
@@ -747,14 +1108,10 @@ impl Backend {
                         .document_map
                         .iter()
                         .filter_map(|e| {
-                            let mut path = decode(
-                                &e.key().to_file_path().unwrap().to_string_lossy()[baselen + 1..],
-                            )
-                            .unwrap()
-                            .to_string();
-                            if path.ends_with(".pn") {
-                                path = path.strip_suffix(".pn").unwrap().to_string();
-                            }
+                            let file_path = e.key().to_file_path().unwrap();
+                            let rel_path = file_path.strip_prefix(&root_path).unwrap_or(&file_path);
+                            let path = decode(&rel_path.to_string_lossy()).unwrap().to_string();
+                            let path = repo.strip_note_extension(&path).to_string();
                             if matcher.fuzzy_match(&path, s).is_some() {
                                 let rope = e.value();
                                 let preview_lines = 5;
@@ -938,19 +1295,55 @@ impl Backend {
     }
 
     async fn paper_completion_items(&self, query: &str, range: &Range) -> Vec<CompletionItem> {
+        let provider = match self.paper_catalog.provider_name() {
+            Some("bibtex") => "BibTeX",
+            _ => "Zotero",
+        };
         match self.paper_catalog.search(query).await {
             Ok(papers) => papers
                 .into_iter()
-                .map(|paper: super::paper::PaperReference| CompletionItem {
-                    label: paper.title.clone(),
-                    detail: Some(format!("Zotero · {}", paper.title)),
-                    kind: Some(CompletionItemKind::REFERENCE),
-                    insert_text_format: Some(InsertTextFormat::PLAIN_TEXT),
-                    text_edit: Some(CompletionTextEdit::Edit(TextEdit {
-                        new_text: format!("{} {}", paper.title, paper.link),
-                        range: *range,
-                    })),
-                    ..Default::default()
+                .map(|paper: super::paper::PaperReference| {
+                    let byline = [
+                        paper.author_summary(),
+                        paper.year.map(|year| year.to_string()),
+                    ]
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                    let mut new_text = match &paper.citekey {
+                        Some(citekey) if self.paper_catalog.insert_citekey() => {
+                            format!("{}: {}", citekey, paper.title)
+                        }
+                        _ => paper.title.clone(),
+                    };
+                    // BibTeX entries without url or doi have no link
+                    if !paper.link.is_empty() {
+                        new_text.push(' ');
+                        new_text.push_str(&paper.link);
+                    }
+                    CompletionItem {
+                        label: paper.title.clone(),
+                        detail: Some(if byline.is_empty() {
+                            format!("{} · {}", provider, paper.title)
+                        } else {
+                            format!("{} · {} · {}", provider, byline, paper.title)
+                        }),
+                        kind: Some(CompletionItemKind::REFERENCE),
+                        // Let clients filter on the authors and year the catalog matched
+                        filter_text: Some(format!(
+                            "{} {} {}",
+                            paper.title,
+                            paper.authors.join(" "),
+                            paper.year.map(|year| year.to_string()).unwrap_or_default()
+                        )),
+                        insert_text_format: Some(InsertTextFormat::PLAIN_TEXT),
+                        text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                            new_text,
+                            range: *range,
+                        })),
+                        ..Default::default()
+                    }
                 })
                 .collect(),
             Err(PaperProviderError::NotConfigured) => Vec::new(),
@@ -971,6 +1364,16 @@ impl Backend {
 #[tower_lsp::async_trait]
 impl LanguageServer for Backend {
     async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
+        // Diagnostic messages are in English unless `initializationOptions.locale` says otherwise
+        let locale = params
+            .initialization_options
+            .as_ref()
+            .and_then(|options| options.get("locale"))
+            .and_then(|locale| locale.as_str());
+        if let Some(locale) = locale {
+            self.settings.lock().unwrap().locale = Locale::from_tag(locale);
+        }
+
         if let Some(root_uri) = params.root_uri {
             {
                 let mut backend_root_uri = self.root_uri.lock().unwrap();
@@ -988,7 +1391,19 @@ impl LanguageServer for Backend {
                 // Create repository (scanning happens in background)
                 {
                     let mut repo = self.repository.lock().unwrap();
-                    *repo = Some(Repository::new(path));
+                    let repository = match resolve_cache_file(&index_cache_file_name(&path)) {
+                        Ok(index_cache_path) => Repository::with_index_cache(
+                            path,
+                            &self.note_extension,
+                            index_cache_path,
+                        ),
+                        Err(err) => {
+                            log::warn!("failed to resolve the index cache directory: {}", err);
+                            Repository::with_note_extension(path, &self.note_extension)
+                        }
+                    };
+                    repository.set_link_normalization(self.settings.lock().unwrap().links);
+                    *repo = Some(repository);
                 } // Drop repo here
 
                 // Start listening to repository messages (including scan progress)
@@ -1019,8 +1434,14 @@ impl LanguageServer for Backend {
             server_info: None,
             capabilities: ServerCapabilities {
                 position_encoding: Some(PositionEncodingKind::UTF16), // vscode only supports utf-16 ;(
+                // Edits are applied to the repository's copy of a document, so
+                // without a workspace the client has to send the whole text
                 text_document_sync: Some(TextDocumentSyncCapability::Kind(
-                    TextDocumentSyncKind::FULL,
+                    if self.repository.lock().unwrap().is_some() {
+                        TextDocumentSyncKind::INCREMENTAL
+                    } else {
+                        TextDocumentSyncKind::FULL
+                    },
                 )),
                 completion_provider: Some(CompletionOptions {
                     resolve_provider: Some(false),
@@ -1042,6 +1463,15 @@ impl LanguageServer for Backend {
                         "experimental/tasks_review".to_string(),
                         "patto/snapshotPapers".to_string(),
                         "patto/renderAsMarkdown".to_string(),
+                        "patto/search".to_string(),
+                        "patto/queryByTag".to_string(),
+                        "patto/backlinksWithContext".to_string(),
+                        "patto/exportGraph".to_string(),
+                        "patto/listOrphans".to_string(),
+                        "patto/noteStats".to_string(),
+                        "patto/neighborhood".to_string(),
+                        "patto/insertCitation".to_string(),
+                        "patto/toggleTask".to_string(),
                     ],
                     work_done_progress_options: Default::default(),
                 }),
@@ -1068,7 +1498,7 @@ impl LanguageServer for Backend {
                                 work_done_progress_options: WorkDoneProgressOptions::default(),
                                 legend: SemanticTokensLegend {
                                     token_types: LEGEND_TYPE.into(),
-                                    token_modifiers: vec![],
+                                    token_modifiers: LEGEND_MODIFIER.into(),
                                 },
                                 range: Some(true),
                                 full: Some(SemanticTokensFullOptions::Bool(true)),
@@ -1085,6 +1515,14 @@ impl LanguageServer for Backend {
                     work_done_progress_options: Default::default(),
                 })),
                 folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+                document_highlight_provider: Some(OneOf::Left(true)),
+                selection_range_provider: Some(SelectionRangeProviderCapability::Simple(true)),
+                document_link_provider: Some(DocumentLinkOptions {
+                    resolve_provider: Some(false),
+                    work_done_progress_options: Default::default(),
+                }),
+                document_formatting_provider: Some(OneOf::Left(true)),
+                document_range_formatting_provider: Some(OneOf::Left(true)),
                 ..ServerCapabilities::default()
             },
             ..Default::default()
@@ -1127,6 +1565,11 @@ impl LanguageServer for Backend {
     }
 
     async fn shutdown(&self) -> Result<()> {
+        if let Some(repo) = self.repository.lock().unwrap().as_ref() {
+            if let Err(e) = repo.save_cache() {
+                log::warn!("Failed to save the index cache: {}", e);
+            }
+        }
         Ok(())
     }
 
@@ -1143,8 +1586,14 @@ impl LanguageServer for Backend {
         match serde_json::from_value::<PattoSettings>(settings_value) {
             Ok(new_settings) => {
                 log::info!("Updated patto settings: {:?}", new_settings);
+                if let Some(repo) = self.repository.lock().unwrap().as_ref() {
+                    repo.set_link_normalization(new_settings.links);
+                }
                 let mut settings = self.settings.lock().unwrap();
-                *settings = new_settings;
+                *settings = PattoSettings {
+                    locale: settings.locale,
+                    ..new_settings
+                };
             }
             Err(e) => {
                 log::warn!("Failed to parse patto settings: {:?}", e);
@@ -1163,11 +1612,38 @@ impl LanguageServer for Backend {
         .await
     }
 
-    async fn did_change(&self, mut params: DidChangeTextDocumentParams) {
-        self.on_change(TextDocumentItem {
+    async fn did_change(&self, params: DidChangeTextDocumentParams) {
+        let uri = Repository::normalize_url_percent_encoding(&params.text_document.uri);
+        let mut full_text = None;
+        for change in params.content_changes {
+            match change.range {
+                // Reparse only the lines the edit touches
+                Some(range) => {
+                    if let Some(repo) = self.repository.lock().unwrap().as_ref() {
+                        repo.apply_edit(&uri, range, &change.text);
+                    }
+                }
+                None => {
+                    self.replace_document_text(&uri, &change.text);
+                    full_text = Some(change.text);
+                }
+            }
+        }
+        let text = self
+            .repository
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|repo| repo.document_map.get(&uri).map(|rope| rope.to_string()))
+            .or(full_text);
+        let Some(text) = text else {
+            log::warn!("did_change: no text for {}", uri);
+            return;
+        };
+        self.on_document_updated(TextDocumentItem {
             uri: params.text_document.uri,
             language_id: "".to_string(),
-            text: std::mem::take(&mut params.content_changes[0].text),
+            text,
             version: params.text_document.version,
         })
         .await
@@ -1180,6 +1656,10 @@ impl LanguageServer for Backend {
                 format!("file {} saved!", param.text_document.uri.as_str()),
             )
             .await;
+        self.on_save(&Repository::normalize_url_percent_encoding(
+            &param.text_document.uri,
+        ))
+        .await;
     }
 
     async fn did_close(&self, params: DidCloseTextDocumentParams) {
@@ -1207,14 +1687,31 @@ impl LanguageServer for Backend {
 
         match params.command.as_str() {
             "experimental/aggregate_tasks" => {
+                // Arguments: [filter?]
+                // filter: { status?: "todo" | "doing" | "paused" | "done",
+                //           from?: "YYYY-MM-DD", to?: "YYYY-MM-DD", includeDone?: bool }
+                // from / to bound the due date inclusively; without a filter all
+                // non-done tasks are returned
+                // Returns: tasks sorted by due, each with { location, text, note,
+                //   heading?, message, due, status, deadline_status, ... }
+                let filter = params
+                    .arguments
+                    .first()
+                    .map(task_filter_from_value)
+                    .unwrap_or_default();
+
                 let repo_bind = self.repository.lock().unwrap();
                 let Some(repo) = repo_bind.as_ref() else {
                     return Ok(None);
                 };
-                let tasks = repo.aggregate_tasks();
+                let tasks = repo.aggregate_tasks(&filter);
                 let ret = json!(tasks
                     .iter()
-                    .map(|(uri, line, due)| task_information(uri, line, due))
+                    .map(|(uri, line, due, heading)| {
+                        let mut info = task_information(uri, line, due);
+                        info.heading = heading.clone();
+                        info
+                    })
                     .collect::<Vec<_>>());
                 return Ok(Some(ret));
             }
@@ -1298,41 +1795,62 @@ impl LanguageServer for Backend {
                 return Ok(Some(ret));
             }
             "experimental/retrieve_two_hop_notes" => {
+                // Arguments: [uri, options?]
+                // options: { withSnippets?: bool }
+                // Returns: [intermediary, [note, ...]] pairs sorted by note count and
+                //   then by intermediary; with snippets each pair is instead
+                //   { via, notes: [{ uri, snippet? }] }
                 let repo_bind = self.repository.lock().unwrap();
                 let Some(repo) = repo_bind.as_ref() else {
                     return Ok(None);
                 };
-                let Ok(graph) = repo.document_graph.lock() else {
-                    return Ok(None);
-                };
                 let Some(url) = params
                     .arguments
                     .first()
                     .and_then(|a| a.as_str())
                     .and_then(|url| Url::parse(url).ok())
+                    .map(|url| Repository::normalize_url_percent_encoding(&url))
                 else {
                     return Ok(None);
                 };
-                let Some(node) = graph.get(&url) else {
+                if repo.document_graph.lock().unwrap().get(&url).is_none() {
                     return Ok(None);
+                }
+                let with_snippets = params
+                    .arguments
+                    .get(1)
+                    .and_then(|options| options.get("withSnippets"))
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                let twohop_notes = repo.two_hop_notes(&url);
+                log::debug!("urls: {:?}", twohop_notes);
+                let ret = if with_snippets {
+                    json!(twohop_notes
+                        .iter()
+                        .map(|(via, notes)| json!({
+                            "via": Repository::client_url_percent_encoding(via),
+                            "notes": notes
+                                .iter()
+                                .map(|(note, snippet)| json!({
+                                    "uri": Repository::client_url_percent_encoding(note),
+                                    "snippet": snippet,
+                                }))
+                                .collect::<Vec<_>>(),
+                        }))
+                        .collect::<Vec<_>>())
+                } else {
+                    json!(twohop_notes
+                        .iter()
+                        .map(|(via, notes)| (
+                            Repository::client_url_percent_encoding(via),
+                            notes
+                                .iter()
+                                .map(|(note, _)| Repository::client_url_percent_encoding(note))
+                                .collect::<Vec<_>>(),
+                        ))
+                        .collect::<Vec<_>>())
                 };
-                let mut twohop_urls = node
-                    .iter_out()
-                    .map(|edge| {
-                        let target = edge.target();
-                        let connected_urls = target
-                            .iter_in()
-                            .map(|edge| edge.source().key().clone())
-                            .filter(|n| n != target.key() && n != &url)
-                            .collect::<Vec<Url>>();
-                        (target.key().clone(), connected_urls)
-                    })
-                    .filter(|x| !x.1.is_empty())
-                    .collect::<Vec<(Url, Vec<_>)>>();
-                twohop_urls.sort_by_key(|x| -(x.1.len() as i16));
-                twohop_urls.dedup();
-                log::debug!("urls: {:?}", twohop_urls);
-                return Ok(Some(json!(twohop_urls)));
+                return Ok(Some(ret));
             }
             "patto/snapshotPapers" => {
                 self.client
@@ -1356,81 +1874,419 @@ impl LanguageServer for Backend {
                     }
                 }
             }
-            "patto/renderAsMarkdown" => {
-                // Arguments: [uri, startLine?, endLine?, flavor?]
-                // If startLine/endLine not provided, render entire document
-                // If flavor not provided, use default from settings
-                let Some(uri_str) = params.arguments.first().and_then(|a| a.as_str()) else {
-                    return Ok(None);
-                };
-                let Ok(uri) = Url::parse(uri_str) else {
+            "patto/search" => {
+                // Arguments: [query, limit?]
+                // Returns: list of { uri, line, text, score } sorted by score descending
+                let Some(query) = params.arguments.first().and_then(|a| a.as_str()) else {
                     return Ok(None);
                 };
-                let uri = Repository::normalize_url_percent_encoding(&uri);
-
-                // Parse optional range (0-indexed, inclusive)
-                let start_line = params
+                let limit = params
                     .arguments
                     .get(1)
                     .and_then(|a| a.as_u64())
-                    .map(|n| n as usize);
-                let end_line = params
-                    .arguments
-                    .get(2)
-                    .and_then(|a| a.as_u64())
-                    .map(|n| n as usize);
+                    .map_or(50, |n| n as usize);
 
-                // Parse optional flavor, falling back to settings default, then "standard"
-                let flavor_str = params
-                    .arguments
-                    .get(3)
-                    .and_then(|a| a.as_str())
-                    .map(|s| s.to_string())
-                    .or_else(|| {
-                        self.settings
-                            .lock()
-                            .unwrap()
-                            .markdown
-                            .default_flavor
-                            .clone()
-                    })
-                    .unwrap_or_else(|| "standard".to_string());
-                let flavor = match flavor_str.to_lowercase().as_str() {
-                    "obsidian" => MarkdownFlavor::Obsidian,
-                    "github" => MarkdownFlavor::GitHub,
-                    _ => MarkdownFlavor::Standard,
+                let repo_bind = self.repository.lock().unwrap();
+                let Some(repo) = repo_bind.as_ref() else {
+                    return Ok(None);
                 };
+                return Ok(Some(json!(repo.search(query, limit))));
+            }
+            "patto/queryByTag" => {
+                // Arguments: [filter]
+                // filter: space-separated terms, each `key=value` or a bare `key`,
+                //   e.g. "project=patto priority=high"
+                // Returns: list of { location, text, tags } sorted by path and line
+                let Some(filter) = params.arguments.first().and_then(|a| a.as_str()) else {
+                    return Ok(None);
+                };
+                let filter = filter
+                    .split_whitespace()
+                    .map(|term| match term.split_once('=') {
+                        Some((key, value)) => (key.to_string(), Some(value.to_string())),
+                        None => (term.to_string(), None),
+                    })
+                    .collect::<Vec<_>>();
 
                 let repo_bind = self.repository.lock().unwrap();
                 let Some(repo) = repo_bind.as_ref() else {
                     return Ok(None);
                 };
-                let Some(ast) = repo.ast_map.get(&uri) else {
+                let lines = repo.query_by_tag(&filter);
+                let ret = json!(lines
+                    .iter()
+                    .map(|(uri, line)| {
+                        let tags = match line.kind() {
+                            AstNodeKind::Line { properties } => properties
+                                .iter()
+                                .filter_map(|prop| match prop {
+                                    Property::Tag { name, args, .. } => {
+                                        Some(json!({ "name": name, "args": args }))
+                                    }
+                                    _ => None,
+                                })
+                                .collect::<Vec<_>>(),
+                            _ => vec![],
+                        };
+                        json!({
+                            "location": Location::new(
+                                Repository::client_url_percent_encoding(uri),
+                                get_node_range(line),
+                            ),
+                            "text": line.extract_str().trim(),
+                            "tags": tags,
+                        })
+                    })
+                    .collect::<Vec<_>>());
+                return Ok(Some(ret));
+            }
+            "patto/backlinksWithContext" => {
+                // Arguments: [uri]
+                // Returns: list of { location, source_file, line, context, target_anchor }
+                //   for every link into the note, sorted by source file
+                let Some(uri) = params
+                    .arguments
+                    .first()
+                    .and_then(|a| a.as_str())
+                    .and_then(|uri| Url::parse(uri).ok())
+                else {
                     return Ok(None);
                 };
-
-                let options = MarkdownRendererOptions::new(flavor).with_frontmatter(false);
-                let renderer = MarkdownRenderer::new(options);
-                let mut output = Vec::new();
-
-                let result = if let (Some(start), Some(end)) = (start_line, end_line) {
-                    renderer.format_range(ast.value(), &mut output, start, end)
-                } else {
-                    renderer.format(ast.value(), &mut output)
+                let Ok(path) = uri.to_file_path() else {
+                    return Ok(None);
                 };
 
-                if result.is_err() {
-                    log::error!("Failed to render markdown: {:?}", result);
+                let repo_bind = self.repository.lock().unwrap();
+                let Some(repo) = repo_bind.as_ref() else {
                     return Ok(None);
+                };
+                let mut ret = Vec::new();
+                for back_link in repo.calculate_back_links(&path) {
+                    let Some(source_uri) = repo
+                        .link_to_path(&back_link.source_file)
+                        .and_then(|path| Url::from_file_path(path).ok())
+                        .map(|uri| Repository::normalize_url_percent_encoding(&uri))
+                    else {
+                        continue;
+                    };
+                    for loc in back_link.locations {
+                        // col_range holds byte offsets; LSP positions are UTF-16
+                        let line_text = repo.line_text(&source_uri, loc.line).unwrap_or_default();
+                        let (start, end) = loc.col_range;
+                        let start = utf16_from_byte_idx(&line_text, start.min(line_text.len()));
+                        let end = utf16_from_byte_idx(&line_text, end.min(line_text.len()));
+                        let range = Range::new(
+                            Position::new(loc.line as u32, start as u32),
+                            Position::new(loc.line as u32, end as u32),
+                        );
+                        ret.push(json!({
+                            "location": Location::new(
+                                Repository::client_url_percent_encoding(&source_uri),
+                                range,
+                            ),
+                            "source_file": back_link.source_file,
+                            "line": loc.line,
+                            "context": loc.context,
+                            "target_anchor": loc.target_anchor,
+                        }));
+                    }
                 }
-
-                let markdown = String::from_utf8_lossy(&output).to_string();
-                return Ok(Some(json!(markdown)));
+                return Ok(Some(json!(ret)));
             }
-            c => {
-                log::info!("unknown command: {}", c);
-            }
-        }
+            "patto/exportGraph" => {
+                // Arguments: [format?]
+                // format: "dot" (default) | "json"
+                // Returns: the document graph serialized as a string
+                let format = match params.arguments.first().and_then(|a| a.as_str()) {
+                    Some("json") => GraphFormat::Json,
+                    _ => GraphFormat::Dot,
+                };
+
+                let repo_bind = self.repository.lock().unwrap();
+                let Some(repo) = repo_bind.as_ref() else {
+                    return Ok(None);
+                };
+                return Ok(Some(json!(repo.export_graph(format))));
+            }
+            "patto/listOrphans" => {
+                // Arguments: [countSelfLinks?]
+                // countSelfLinks: when true, notes linking only to themselves
+                //   are not reported (default false)
+                // Returns: list of Locations at the top of each orphan note
+                let count_self_links = params
+                    .arguments
+                    .first()
+                    .and_then(|a| a.as_bool())
+                    .unwrap_or(false);
+
+                let repo_bind = self.repository.lock().unwrap();
+                let Some(repo) = repo_bind.as_ref() else {
+                    return Ok(None);
+                };
+                let ret = repo
+                    .orphan_notes_with(count_self_links)
+                    .into_iter()
+                    .map(|uri| {
+                        Location::new(
+                            Repository::client_url_percent_encoding(&uri),
+                            Range::default(),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                return Ok(Some(json!(ret)));
+            }
+            "patto/noteStats" => {
+                // Arguments: [uri]
+                // Returns: { words, lines, tasks: { todo, doing, paused, done },
+                //   reading_time_minutes }
+                let Some(uri) = params
+                    .arguments
+                    .first()
+                    .and_then(|a| a.as_str())
+                    .and_then(|uri| Url::parse(uri).ok())
+                else {
+                    return Ok(None);
+                };
+                let uri = Repository::normalize_url_percent_encoding(&uri);
+
+                let repo_bind = self.repository.lock().unwrap();
+                let Some(repo) = repo_bind.as_ref() else {
+                    return Ok(None);
+                };
+                return Ok(repo.note_stats(&uri).map(|stats| json!(stats)));
+            }
+            "patto/neighborhood" => {
+                // Arguments: [uri]
+                // Returns: { uri, name, outgoing, incoming, two_hop } where
+                //   outgoing / incoming are lists of { uri, name, count } with
+                //   count the number of links, and two_hop is a list of
+                //   { via: { uri, name }, notes: [{ uri, name }] }
+                let Some(uri) = params
+                    .arguments
+                    .first()
+                    .and_then(|a| a.as_str())
+                    .and_then(|uri| Url::parse(uri).ok())
+                else {
+                    return Ok(None);
+                };
+                let uri = Repository::normalize_url_percent_encoding(&uri);
+
+                let repo_bind = self.repository.lock().unwrap();
+                let Some(repo) = repo_bind.as_ref() else {
+                    return Ok(None);
+                };
+                let Some(neighborhood) = repo.neighborhood(&uri) else {
+                    return Ok(None);
+                };
+                let note = |uri: &Url| {
+                    json!({
+                        "uri": Repository::client_url_percent_encoding(uri),
+                        "name": uri.to_file_path().ok().and_then(|path| repo.note_name(&path)),
+                    })
+                };
+                let linked = |links: &[(Url, usize)]| {
+                    links
+                        .iter()
+                        .map(|(uri, count)| {
+                            let mut linked = note(uri);
+                            linked["count"] = json!(count);
+                            linked
+                        })
+                        .collect::<Vec<_>>()
+                };
+                let mut ret = note(&uri);
+                ret["outgoing"] = json!(linked(&neighborhood.outgoing));
+                ret["incoming"] = json!(linked(&neighborhood.incoming));
+                ret["two_hop"] = json!(neighborhood
+                    .two_hop
+                    .iter()
+                    .map(|(via, notes)| json!({
+                        "via": note(via),
+                        "notes": notes.iter().map(|(uri, _)| note(uri)).collect::<Vec<_>>(),
+                    }))
+                    .collect::<Vec<_>>());
+                return Ok(Some(ret));
+            }
+            "patto/insertCitation" => {
+                // Arguments: [uri, position, citekey]
+                // citekey: citation key or provider key (e.g. Zotero item key)
+                // Returns: a WorkspaceEdit inserting the formatted reference at position
+                let invalid_params = |message: &str| tower_lsp::jsonrpc::Error {
+                    code: tower_lsp::jsonrpc::ErrorCode::InvalidParams,
+                    message: message.to_string().into(),
+                    data: None,
+                };
+                let Some(uri) = params
+                    .arguments
+                    .first()
+                    .and_then(|a| a.as_str())
+                    .and_then(|uri| Url::parse(uri).ok())
+                else {
+                    return Err(invalid_params("expected a document uri"));
+                };
+                let Some(position) = params
+                    .arguments
+                    .get(1)
+                    .and_then(|a| serde_json::from_value::<Position>(a.clone()).ok())
+                else {
+                    return Err(invalid_params("expected a cursor position"));
+                };
+                let Some(citekey) = params.arguments.get(2).and_then(|a| a.as_str()) else {
+                    return Err(invalid_params("expected a citation key"));
+                };
+
+                let paper = match self.paper_catalog.find(citekey).await {
+                    Ok(Some(paper)) => paper,
+                    Ok(None) => {
+                        return Err(invalid_params(&format!(
+                            "no paper with citation key '{}' in the paper catalog",
+                            citekey
+                        )));
+                    }
+                    Err(PaperProviderError::NotConfigured) => {
+                        return Err(tower_lsp::jsonrpc::Error {
+                            code: tower_lsp::jsonrpc::ErrorCode::InvalidRequest,
+                            message: "no paper provider is configured; add a [zotero] or \
+                                      [bibtex] section to patto-lsp.toml"
+                                .into(),
+                            data: None,
+                        });
+                    }
+                    Err(err) => {
+                        return Err(tower_lsp::jsonrpc::Error {
+                            code: tower_lsp::jsonrpc::ErrorCode::InternalError,
+                            message: format!("failed to look up paper: {}", err).into(),
+                            data: None,
+                        });
+                    }
+                };
+
+                let edit = TextEdit {
+                    range: Range::new(position, position),
+                    new_text: self.paper_catalog.format_citation(&paper),
+                };
+                let workspace_edit = WorkspaceEdit {
+                    changes: Some(HashMap::from([(
+                        Repository::client_url_percent_encoding(&uri),
+                        vec![edit],
+                    )])),
+                    ..Default::default()
+                };
+                return Ok(Some(json!(workspace_edit)));
+            }
+            "patto/toggleTask" => {
+                // Arguments: [uri, position, options?]
+                // options: { stampCompletion?: bool }
+                // Returns: a WorkspaceEdit moving the task on the position's line
+                //   todo -> doing -> done -> todo, or null when the line has no task
+                let invalid_params = |message: &str| tower_lsp::jsonrpc::Error {
+                    code: tower_lsp::jsonrpc::ErrorCode::InvalidParams,
+                    message: message.to_string().into(),
+                    data: None,
+                };
+                let Some(uri) = params
+                    .arguments
+                    .first()
+                    .and_then(|a| a.as_str())
+                    .and_then(|uri| Url::parse(uri).ok())
+                    .map(|uri| Repository::normalize_url_percent_encoding(&uri))
+                else {
+                    return Err(invalid_params("expected a document uri"));
+                };
+                let Some(position) = params
+                    .arguments
+                    .get(1)
+                    .and_then(|a| serde_json::from_value::<Position>(a.clone()).ok())
+                else {
+                    return Err(invalid_params("expected a cursor position"));
+                };
+                let stamp_completion = params
+                    .arguments
+                    .get(2)
+                    .and_then(|options| options.get("stampCompletion"))
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+
+                let ast: Option<AstNode> = self
+                    .repository
+                    .lock()
+                    .unwrap()
+                    .as_ref()
+                    .and_then(|repo| repo.ast_map.get(&uri).map(|e| e.value().clone()));
+                let Some(ast) = ast else {
+                    return Ok(None);
+                };
+                let snapshots = collect_task_snapshots(&ast);
+                let Some(snapshot) = snapshots.get(&(position.line as usize)) else {
+                    return Ok(None);
+                };
+
+                let now = chrono::Local::now().naive_local();
+                let workspace_edit = WorkspaceEdit {
+                    changes: Some(HashMap::from([(
+                        Repository::client_url_percent_encoding(&uri),
+                        generate_toggle_edits(snapshot, now, stamp_completion),
+                    )])),
+                    ..Default::default()
+                };
+                return Ok(Some(json!(workspace_edit)));
+            }
+            "patto/renderAsMarkdown" => {
+                // Arguments: [uri, startLine?, endLine?, flavor?]
+                // If startLine/endLine not provided, render entire document
+                // If flavor not provided, use default from settings
+                let Some(uri_str) = params.arguments.first().and_then(|a| a.as_str()) else {
+                    return Ok(None);
+                };
+                let Ok(uri) = Url::parse(uri_str) else {
+                    return Ok(None);
+                };
+                let uri = Repository::normalize_url_percent_encoding(&uri);
+
+                // Parse optional range (0-indexed, inclusive)
+                let start_line = params
+                    .arguments
+                    .get(1)
+                    .and_then(|a| a.as_u64())
+                    .map(|n| n as usize);
+                let end_line = params
+                    .arguments
+                    .get(2)
+                    .and_then(|a| a.as_u64())
+                    .map(|n| n as usize);
+
+                let flavor = self.markdown_flavor(params.arguments.get(3).and_then(|a| a.as_str()));
+
+                let repo_bind = self.repository.lock().unwrap();
+                let Some(repo) = repo_bind.as_ref() else {
+                    return Ok(None);
+                };
+                let Some(ast) = repo.ast_map.get(&uri) else {
+                    return Ok(None);
+                };
+
+                let options = MarkdownRendererOptions::new(flavor).with_frontmatter(false);
+                let renderer = MarkdownRenderer::new(options);
+                let mut output = Vec::new();
+
+                let result = if let (Some(start), Some(end)) = (start_line, end_line) {
+                    renderer.format_range(ast.value(), &mut output, start, end)
+                } else {
+                    renderer.format(ast.value(), &mut output)
+                };
+
+                if result.is_err() {
+                    log::error!("Failed to render markdown: {:?}", result);
+                    return Ok(None);
+                }
+
+                let markdown = String::from_utf8_lossy(&output).to_string();
+                return Ok(Some(json!(markdown)));
+            }
+            c => {
+                log::info!("unknown command: {}", c);
+            }
+        }
         log::info!("unhandled command execution: {:?}", params);
         Ok(None)
     }
@@ -1480,19 +2336,17 @@ impl LanguageServer for Backend {
             let start = Range::new(Position::new(0, 0), Position::new(0, 1));
             if let Some(anchor) = anchor {
                 let range = repo
-                    .ast_map
-                    .get(&linkuri)
-                    .and_then(|r| {
-                        let linkast = r.value();
-                        find_anchor(linkast, anchor)
-                    })
+                    .ast(&linkuri)
+                    .and_then(|linkast| find_anchor(&linkast, anchor))
                     .map_or(start, |anchored_line| get_node_range(&anchored_line));
                 Some(GotoDefinitionResponse::Scalar(Location::new(
-                    linkuri, range,
+                    Repository::client_url_percent_encoding(&linkuri),
+                    range,
                 )))
             } else {
                 Some(GotoDefinitionResponse::Scalar(Location::new(
-                    linkuri, start,
+                    Repository::client_url_percent_encoding(&linkuri),
+                    start,
                 )))
             }
         }
@@ -1545,7 +2399,10 @@ impl LanguageServer for Backend {
                                     Position::new(link_loc.source_line as u32, start_char),
                                     Position::new(link_loc.source_line as u32, end_char),
                                 );
-                                references.push(Location::new(source_uri.clone(), range));
+                                references.push(Location::new(
+                                    Repository::client_url_percent_encoding(source_uri),
+                                    range,
+                                ));
                             }
                         }
                     }
@@ -1673,8 +2530,13 @@ impl LanguageServer for Backend {
                 }
             }
 
-            // If not on a WikiLink, allow renaming the current file
-            // Get the file name from the URI
+            // If not on a WikiLink, allow renaming the current file, but only from
+            // the first line unless configured otherwise, so that plain text isn't
+            // mistaken for something renameable
+            let always_rename_file = self.settings.lock().unwrap().rename.always_rename_file;
+            if position.line != 0 && !always_rename_file {
+                return None;
+            }
             if let Ok(path) = uri.to_file_path() {
                 if let Some(file_stem) = path.file_stem() {
                     if let Some(name) = file_stem.to_str() {
@@ -1774,7 +2636,7 @@ impl LanguageServer for Backend {
 
             document_changes.push(DocumentChangeOperation::Edit(TextDocumentEdit {
                 text_document: OptionalVersionedTextDocumentIdentifier {
-                    uri: uri.clone(),
+                    uri: Repository::client_url_percent_encoding(&uri),
                     version: None,
                 },
                 edits: vec![OneOf::Left(anchor_edit)],
@@ -1800,9 +2662,15 @@ impl LanguageServer for Backend {
                                     source_rope.value().get_line(link_loc.source_line)
                                 {
                                     if let Some(src_line_str) = line.as_str() {
-                                        // Build new link text with updated anchor
+                                        // Keep the note name as written in the link and
+                                        // only replace the anchor
+                                        let (start, end) = link_loc.source_col_range;
+                                        let written_note = src_line_str
+                                            .get(start + 1..end)
+                                            .and_then(|inner| inner.split_once('#'))
+                                            .map_or(current_file_link.as_str(), |(note, _)| note);
                                         let new_link_text =
-                                            format!("[{}#{}]", current_file_link, clean_new_name);
+                                            format_wiki_link(written_note, Some(clean_new_name));
 
                                         // Convert byte offsets to UTF-16
                                         let start_char = utf16_from_byte_idx(
@@ -1834,7 +2702,7 @@ impl LanguageServer for Backend {
                             document_changes.push(DocumentChangeOperation::Edit(
                                 TextDocumentEdit {
                                     text_document: OptionalVersionedTextDocumentIdentifier {
-                                        uri: source_uri.clone(),
+                                        uri: Repository::client_url_percent_encoding(source_uri),
                                         version: None,
                                     },
                                     edits,
@@ -1865,10 +2733,11 @@ impl LanguageServer for Backend {
             });
         }
 
-        if new_name.ends_with(".pn") {
+        let extension = format!(".{}", self.note_extension);
+        if new_name.ends_with(&extension) {
             return Err(tower_lsp::jsonrpc::Error {
                 code: tower_lsp::jsonrpc::ErrorCode::InvalidParams,
-                message: "Note name should not include .pn extension".into(),
+                message: format!("Note name should not include {} extension", extension).into(),
                 data: None,
             });
         }
@@ -1921,26 +2790,29 @@ impl LanguageServer for Backend {
 
             log::info!("Renaming note '{}' to '{}'", old_name, new_name);
 
+            // A note exists if it is on disk or open in the editor; freshly
+            // created buffers have not been saved yet
+            let note_exists = |note_uri: &Url| {
+                repo.document_map.contains_key(note_uri)
+                    || note_uri.to_file_path().is_ok_and(|path| path.exists())
+            };
+
             // Check if new name conflicts
             let root_uri = self.root_uri.lock().unwrap().as_ref().cloned()?;
             if let Some(new_uri) = repo.link_to_uri(new_name, &root_uri) {
-                if let Ok(new_path) = new_uri.to_file_path() {
-                    if new_path.exists() {
-                        log::warn!("Target file already exists: {:?}", new_path);
-                        return None;
-                    }
+                if note_exists(&new_uri) {
+                    log::warn!("Target note already exists: {}", new_uri);
+                    return None;
                 }
             }
 
             // Find all references to the old note
             let old_uri = repo.link_to_uri(&old_name, &root_uri)?;
 
-            // Check if the target file actually exists
-            if let Ok(old_path) = old_uri.to_file_path() {
-                if !old_path.exists() {
-                    log::warn!("Target file does not exist: {:?}", old_path);
-                    return None;
-                }
+            // Check if the target note actually exists
+            if !note_exists(&old_uri) {
+                log::warn!("Target note does not exist: {}", old_uri);
+                return None;
             }
 
             let mut document_changes = Vec::new();
@@ -1962,13 +2834,10 @@ impl LanguageServer for Backend {
                         for link_loc in &edge_data.locations {
                             if let Some(line) = source_rope.value().get_line(link_loc.source_line) {
                                 if let Some(line_str) = line.as_str() {
-                                    // Build new link text preserving anchor
-                                    let new_link_text =
-                                        if let Some(ref anchor_name) = link_loc.target_anchor {
-                                            format!("[{}#{}]", new_name, anchor_name)
-                                        } else {
-                                            format!("[{}]", new_name)
-                                        };
+                                    let new_link_text = format_wiki_link(
+                                        new_name,
+                                        link_loc.target_anchor.as_deref(),
+                                    );
 
                                     // Convert byte offsets to UTF-16
                                     let start_char =
@@ -1995,7 +2864,7 @@ impl LanguageServer for Backend {
                             document_changes.push(DocumentChangeOperation::Edit(
                                 TextDocumentEdit {
                                     text_document: OptionalVersionedTextDocumentIdentifier {
-                                        uri: source_uri.clone(),
+                                        uri: Repository::client_url_percent_encoding(source_uri),
                                         version: None,
                                     },
                                     edits,
@@ -2010,8 +2879,8 @@ impl LanguageServer for Backend {
             let new_uri = repo.link_to_uri(new_name, &root_uri)?;
             document_changes.push(DocumentChangeOperation::Op(ResourceOp::Rename(
                 RenameFile {
-                    old_uri: old_uri.clone(),
-                    new_uri: new_uri.clone(),
+                    old_uri: Repository::client_url_percent_encoding(&old_uri),
+                    new_uri: Repository::client_url_percent_encoding(&new_uri),
                     options: Some(RenameFileOptions {
                         overwrite: Some(false),
                         ignore_if_exists: Some(false),
@@ -2049,6 +2918,287 @@ impl LanguageServer for Backend {
 
         Ok(result)
     }
+
+    async fn document_highlight(
+        &self,
+        params: DocumentHighlightParams,
+    ) -> Result<Option<Vec<DocumentHighlight>>> {
+        let uri = Repository::normalize_url_percent_encoding(
+            &params.text_document_position_params.text_document.uri,
+        );
+        let position = params.text_document_position_params.position;
+
+        let result = || -> Option<Vec<DocumentHighlight>> {
+            let repo_lock = self.repository.lock().unwrap();
+            let repo = repo_lock.as_ref()?;
+            let root_uri = self.root_uri.lock().unwrap().clone()?;
+            let ast = repo.ast_map.get(&uri)?;
+            let rope = repo.document_map.get(&uri)?;
+
+            let line = rope.value().get_line(position.line as usize)?;
+            let line_str = line.as_str()?;
+            let posbyte = utf16_to_byte_idx(line_str, position.character as usize);
+
+            // Self-links point to this note
+            let target_of = |link: &str| {
+                if link.is_empty() {
+                    Some(uri.clone())
+                } else {
+                    repo.link_to_uri(link, &root_uri)
+                }
+            };
+
+            // The note under the cursor, and the anchor in it when the cursor is
+            // on an anchor of this note or on a link to one
+            let (target, anchor) = if let Some((name, _)) =
+                find_anchor_at_position(&ast, position.line as usize, posbyte)
+            {
+                (uri.clone(), Some(name))
+            } else {
+                let route = locate_node_route(&ast, position.line as usize, posbyte)?;
+                let (link, anchor) = route.iter().find_map(|node| match node.kind() {
+                    AstNodeKind::WikiLink { link, anchor } => Some((link.clone(), anchor.clone())),
+                    _ => None,
+                })?;
+                let target = target_of(&link)?;
+                let anchor = anchor.filter(|_| target == uri);
+                (target, anchor)
+            };
+
+            let mut locations = vec![];
+            if let Some(anchor) = &anchor {
+                find_anchor_definitions(&ast, anchor, &mut locations);
+            }
+            let mut wikilinks = vec![];
+            Repository::gather_wikilinks(&ast, &mut wikilinks);
+            locations.extend(
+                wikilinks
+                    .into_iter()
+                    .filter(|(link, link_anchor, _)| {
+                        target_of(link).as_ref() == Some(&target)
+                            && (anchor.is_none() || link_anchor == &anchor)
+                    })
+                    .map(|(_, _, location)| location),
+            );
+
+            let mut highlights: Vec<DocumentHighlight> = locations
+                .iter()
+                .map(|location| DocumentHighlight {
+                    range: get_location_range(location),
+                    kind: Some(DocumentHighlightKind::TEXT),
+                })
+                .collect();
+            highlights.sort_by_key(|h| (h.range.start.line, h.range.start.character));
+            Some(highlights)
+        }();
+
+        Ok(result)
+    }
+
+    async fn selection_range(
+        &self,
+        params: SelectionRangeParams,
+    ) -> Result<Option<Vec<SelectionRange>>> {
+        let uri = Repository::normalize_url_percent_encoding(&params.text_document.uri);
+
+        let result = || -> Option<Vec<SelectionRange>> {
+            let repo_lock = self.repository.lock().unwrap();
+            let repo = repo_lock.as_ref()?;
+            let ast = repo.ast_map.get(&uri)?;
+            let rope = repo.document_map.get(&uri)?;
+            let rope = rope.value();
+
+            let line_text = |row: usize| -> String {
+                if row >= rope.len_lines() {
+                    return String::new();
+                }
+                let line = rope.line(row).to_string();
+                line.trim_end_matches(['\n', '\r']).to_string()
+            };
+
+            Some(
+                params
+                    .positions
+                    .iter()
+                    .map(|&position| {
+                        let line = line_text(position.line as usize);
+                        let posbyte = utf16_to_byte_idx(&line, position.character as usize);
+                        let line_len = |row: usize| {
+                            let text = line_text(row);
+                            utf16_from_byte_idx(&text, text.len()) as u32
+                        };
+                        let ranges = selection_ranges_at(&ast, &line, position, posbyte, &line_len);
+
+                        // Innermost range first, each one the child of the next
+                        let mut selection: Option<SelectionRange> = None;
+                        for range in ranges.into_iter().rev() {
+                            selection = Some(SelectionRange {
+                                range,
+                                parent: selection.map(Box::new),
+                            });
+                        }
+                        selection.unwrap_or(SelectionRange {
+                            range: Range::new(position, position),
+                            parent: None,
+                        })
+                    })
+                    .collect(),
+            )
+        }();
+
+        Ok(result)
+    }
+
+    async fn formatting(&self, params: DocumentFormattingParams) -> Result<Option<Vec<TextEdit>>> {
+        let uri = Repository::normalize_url_percent_encoding(&params.text_document.uri);
+
+        let result = || -> Option<Vec<TextEdit>> {
+            let repo_lock = self.repository.lock().unwrap();
+            let repo = repo_lock.as_ref()?;
+            let text = repo.document_map.get(&uri)?.value().to_string();
+            Some(format_document(&text, params.options.tab_size as usize))
+        }();
+
+        Ok(result)
+    }
+
+    async fn range_formatting(
+        &self,
+        params: DocumentRangeFormattingParams,
+    ) -> Result<Option<Vec<TextEdit>>> {
+        let uri = Repository::normalize_url_percent_encoding(&params.text_document.uri);
+
+        let result = || -> Option<Vec<TextEdit>> {
+            let repo_lock = self.repository.lock().unwrap();
+            let repo = repo_lock.as_ref()?;
+            let text = repo.document_map.get(&uri)?.value().to_string();
+            Some(format_tables(&text, params.range))
+        }();
+
+        Ok(result)
+    }
+
+    async fn document_link(&self, params: DocumentLinkParams) -> Result<Option<Vec<DocumentLink>>> {
+        let uri = Repository::normalize_url_percent_encoding(&params.text_document.uri);
+
+        let result = || -> Option<Vec<DocumentLink>> {
+            let repo_lock = self.repository.lock().unwrap();
+            let repo = repo_lock.as_ref()?;
+            let root_uri = self.root_uri.lock().unwrap().clone()?;
+            let ast = repo.ast_map.get(&uri)?;
+            let mut links = vec![];
+            visit_nodes(ast.value(), &mut |node| {
+                links.extend(document_link_for(repo, &root_uri, &uri, node));
+            });
+            Some(links)
+        }();
+
+        Ok(result)
+    }
+}
+
+/// Nested ranges around `position`, innermost first: the word, the inline
+/// nodes containing it, its line, and the subtrees of the enclosing lines.
+/// `line_len` gives the UTF-16 length of a row.
+fn selection_ranges_at(
+    ast: &AstNode,
+    line: &str,
+    position: Position,
+    posbyte: usize,
+    line_len: &dyn Fn(usize) -> u32,
+) -> Vec<Range> {
+    let mut ranges: Vec<Range> = vec![];
+    let mut push = |range: Range| {
+        let contains_previous = ranges
+            .last()
+            .is_none_or(|inner| range.start <= inner.start && inner.end <= range.end);
+        if contains_previous && ranges.last() != Some(&range) {
+            ranges.push(range);
+        }
+    };
+
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let word_start = line[..posbyte]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| is_word(*c))
+        .last()
+        .map_or(posbyte, |(i, _)| i);
+    let word_end = line[posbyte..]
+        .char_indices()
+        .find(|(_, c)| !is_word(*c))
+        .map_or(line.len(), |(i, _)| posbyte + i);
+    if word_start < word_end {
+        let row = position.line;
+        push(Range::new(
+            Position::new(row, utf16_from_byte_idx(line, word_start) as u32),
+            Position::new(row, utf16_from_byte_idx(line, word_end) as u32),
+        ));
+    }
+
+    let route = locate_node_route(ast, position.line as usize, posbyte).unwrap_or_default();
+    for node in &route {
+        if matches!(node.kind(), AstNodeKind::Dummy) {
+            continue;
+        }
+        let range = get_node_range(node);
+        push(range);
+        let last_row = last_row_of(node);
+        if last_row > node.location().row {
+            push(Range::new(
+                range.start,
+                Position::new(last_row as u32, line_len(last_row)),
+            ));
+        }
+    }
+    ranges
+}
+
+/// Call `f` with `node` and every node below it, in document order
+fn visit_nodes(node: &AstNode, f: &mut impl FnMut(&AstNode)) {
+    f(node);
+    for content in node.value().contents.lock().unwrap().iter() {
+        visit_nodes(content, f);
+    }
+    for child in node.value().children.lock().unwrap().iter() {
+        visit_nodes(child, f);
+    }
+}
+
+/// Clickable link for a WikiLink, URL or local file link, or remote image in
+/// the note `uri`. WikiLinks to missing notes still get a link, since
+/// opening the target creates the note.
+fn document_link_for(
+    repo: &Repository,
+    root_uri: &Url,
+    uri: &Url,
+    node: &AstNode,
+) -> Option<DocumentLink> {
+    let (target, tooltip) = match node.kind() {
+        AstNodeKind::WikiLink { link, .. } if !link.is_empty() => {
+            let target = repo.link_to_uri(link, root_uri)?;
+            let tooltip = repo
+                .link_to_path(link)
+                .is_none()
+                .then(|| format!("Create note '{}'", link));
+            (Repository::client_url_percent_encoding(&target), tooltip)
+        }
+        AstNodeKind::Link { link, .. } => {
+            (Url::parse(link).or_else(|_| uri.join(link)).ok()?, None)
+        }
+        AstNodeKind::Image { src, .. }
+            if src.starts_with("http://") || src.starts_with("https://") =>
+        {
+            (Url::parse(src).ok()?, None)
+        }
+        _ => return None,
+    };
+    Some(DocumentLink {
+        range: get_node_range(node),
+        target: Some(target),
+        tooltip,
+        data: None,
+    })
 }
 
 /// Recursively find the maximum row number in a node's subtree.
@@ -2157,19 +3307,28 @@ mod tests {
     #[test]
     fn test_embed_valid_no_diagnostic() {
         // Valid URL embed — no warnings
-        let (_ast, diags) = parse_text("[@embed https://example.com/video]");
+        let (_ast, diags) = parse_text(
+            "[@embed https://example.com/video]",
+            &DiagnosticTranslator::default(),
+        );
         assert!(diags
             .iter()
             .all(|d| d.severity != Some(DiagnosticSeverity::WARNING)));
 
         // Valid local embed with ./
-        let (_ast, diags) = parse_text("[@embed ./docs/report.pdf]");
+        let (_ast, diags) = parse_text(
+            "[@embed ./docs/report.pdf]",
+            &DiagnosticTranslator::default(),
+        );
         assert!(diags
             .iter()
             .all(|d| d.severity != Some(DiagnosticSeverity::WARNING)));
 
         // Valid title + local path — unquoted is fine now because ./ is unambiguous
-        let (_ast, diags) = parse_text("[@embed My Title ./docs/report.pdf]");
+        let (_ast, diags) = parse_text(
+            "[@embed My Title ./docs/report.pdf]",
+            &DiagnosticTranslator::default(),
+        );
         assert!(diags
             .iter()
             .all(|d| d.severity != Some(DiagnosticSeverity::WARNING)));
@@ -2178,7 +3337,8 @@ mod tests {
     #[test]
     fn test_embed_bare_path_produces_error() {
         // Bare path without ./ → parse error → @embed diagnostic
-        let (_ast, diags) = parse_text("[@embed docs/report.pdf]");
+        let (_ast, diags) =
+            parse_text("[@embed docs/report.pdf]", &DiagnosticTranslator::default());
         assert!(
             diags
                 .iter()
@@ -2190,8 +3350,10 @@ mod tests {
     #[test]
     fn test_img_ambiguous_produces_error() {
         // filename-as-alt before bare path (no ./) → parse error → @img diagnostic
-        let (_ast, diags) =
-            parse_text("[@img 2026-03-04-10-20-35.png assets/2026-03-04-10-20-35.png]");
+        let (_ast, diags) = parse_text(
+            "[@img 2026-03-04-10-20-35.png assets/2026-03-04-10-20-35.png]",
+            &DiagnosticTranslator::default(),
+        );
         assert!(
             diags
                 .iter()
@@ -2203,8 +3365,10 @@ mod tests {
     #[test]
     fn test_img_unquoted_alt_dotslash_path_valid() {
         // unquoted alt before ./ path — now valid and unambiguous
-        let (_ast, diags) =
-            parse_text("[@img 2026-03-04-10-20-35.png ./assets/2026-03-04-10-20-35.png]");
+        let (_ast, diags) = parse_text(
+            "[@img 2026-03-04-10-20-35.png ./assets/2026-03-04-10-20-35.png]",
+            &DiagnosticTranslator::default(),
+        );
         assert!(
             diags
                 .iter()
@@ -2216,8 +3380,10 @@ mod tests {
     #[test]
     fn test_multiple_invalid_commands_on_one_line() {
         // Two bad commands on the same line — both should produce diagnostics
-        let (_ast, diags) =
-            parse_text("see [@embed docs/a.pdf] and [@img assets/b.png] for details");
+        let (_ast, diags) = parse_text(
+            "see [@embed docs/a.pdf] and [@img assets/b.png] for details",
+            &DiagnosticTranslator::default(),
+        );
         let invalid_embed = diags
             .iter()
             .filter(|d| d.code == Some(NumberOrString::String("invalid-embed".into())))
@@ -2233,7 +3399,10 @@ mod tests {
     #[test]
     fn test_multiple_same_invalid_commands_on_one_line() {
         // Two bad @embed on the same line — both should produce diagnostics
-        let (_ast, diags) = parse_text("[@embed docs/a.pdf] and [@embed docs/b.pdf]");
+        let (_ast, diags) = parse_text(
+            "[@embed docs/a.pdf] and [@embed docs/b.pdf]",
+            &DiagnosticTranslator::default(),
+        );
         let count = diags
             .iter()
             .filter(|d| d.code == Some(NumberOrString::String("invalid-embed".into())))
@@ -2245,6 +3414,7 @@ mod tests {
     fn test_task_label_conceal_urls() {
         let (ast, _) = parse_text(
             "buy milk [https://example.com/foo milk title] {@task status=todo due=2026-06-01}",
+            &DiagnosticTranslator::default(),
         );
         let children = ast.value().children.lock().unwrap();
         let line = &children[0];
@@ -2253,14 +3423,17 @@ mod tests {
 
         let (ast2, _) = parse_text(
             "[milk title https://example.com/foo] buy milk {@task status=todo due=2026-06-01}",
+            &DiagnosticTranslator::default(),
         );
         let children2 = ast2.value().children.lock().unwrap();
         let line2 = &children2[0];
         let label2 = task_label(line2);
         assert_eq!(label2, "[milk title🔗] buy milk");
 
-        let (ast3, _) =
-            parse_text("buy milk [https://example.com/foo] {@task status=todo due=2026-06-01}");
+        let (ast3, _) = parse_text(
+            "buy milk [https://example.com/foo] {@task status=todo due=2026-06-01}",
+            &DiagnosticTranslator::default(),
+        );
         let children3 = ast3.value().children.lock().unwrap();
         let line3 = &children3[0];
         let label3 = task_label(line3);
@@ -2269,6 +3442,7 @@ mod tests {
         // Multi-byte character tests
         let (ast4, _) = parse_text(
             "牛乳を買う [https://example.com/foo 牛乳] {@task status=todo due=2026-06-01}",
+            &DiagnosticTranslator::default(),
         );
         let children4 = ast4.value().children.lock().unwrap();
         let line4 = &children4[0];
@@ -2277,6 +3451,7 @@ mod tests {
 
         let (ast5, _) = parse_text(
             "[牛乳 https://example.com/foo] 牛乳を買う {@task status=todo due=2026-06-01}",
+            &DiagnosticTranslator::default(),
         );
         let children5 = ast5.value().children.lock().unwrap();
         let line5 = &children5[0];
@@ -2284,13 +3459,89 @@ mod tests {
         assert_eq!(label5, "[牛乳🔗] 牛乳を買う");
     }
 
+    #[test]
+    fn test_parse_error_range_uses_utf16_columns() {
+        // The stray bracket makes the whole line an error
+        let (_ast, diags) = parse_text("日本語 ] です\n", &DiagnosticTranslator::default());
+        assert_eq!(diags.len(), 1, "{:?}", diags);
+        assert_eq!(
+            diags[0].range,
+            Range::new(Position::new(0, 0), Position::new(0, 8))
+        );
+
+        // An error in a table column after a Japanese column
+        let (_ast, diags) = parse_text(
+            "[@table]\n\t名前\tx [unclosed\n",
+            &DiagnosticTranslator::default(),
+        );
+        assert_eq!(diags.len(), 1, "{:?}", diags);
+        assert_eq!(
+            diags[0].range,
+            Range::new(Position::new(1, 4), Position::new(1, 15))
+        );
+    }
+
+    #[test]
+    fn test_configured_diagnostic_severities() {
+        let settings: PattoSettings = serde_json::from_value(serde_json::json!({
+            "diagnostics": {
+                "invalid-img": "hint",
+                "invalid-embed": "info",
+                "stale-started-at": "off",
+            }
+        }))
+        .unwrap();
+        let translator = DiagnosticTranslator::with_config(settings.diagnostics);
+        let text = "[@img assets/b.png]\na ] b\n\
+            done {@task status=done due=2026-06-01 started_at=2026-06-01T09:00}\n";
+
+        let (_ast, diags) = parse_text(text, &DiagnosticTranslator::default());
+        let codes = |diags: &[Diagnostic]| -> Vec<(String, Option<DiagnosticSeverity>)> {
+            let mut codes: Vec<_> = diags
+                .iter()
+                .map(|d| match &d.code {
+                    Some(NumberOrString::String(code)) => (code.clone(), d.severity),
+                    _ => (String::new(), d.severity),
+                })
+                .collect();
+            codes.sort_by(|a, b| a.0.cmp(&b.0));
+            codes
+        };
+        assert_eq!(
+            codes(&diags),
+            vec![
+                (
+                    "invalid-embed".to_string(),
+                    Some(DiagnosticSeverity::WARNING)
+                ),
+                ("invalid-img".to_string(), Some(DiagnosticSeverity::WARNING)),
+                (
+                    "stale-started-at".to_string(),
+                    Some(DiagnosticSeverity::WARNING)
+                ),
+            ]
+        );
+
+        let (_ast, diags) = parse_text(text, &translator);
+        assert_eq!(
+            codes(&diags),
+            vec![
+                (
+                    "invalid-embed".to_string(),
+                    Some(DiagnosticSeverity::INFORMATION)
+                ),
+                ("invalid-img".to_string(), Some(DiagnosticSeverity::HINT)),
+            ]
+        );
+    }
+
     // ── stale started_at diagnostics ────────────────────────────────────────
 
     #[test]
     fn test_stale_started_at_warn_on_done_task() {
         // Done task with started_at still present → should emit stale-started-at warning.
         let (_ast, diags) = parse_text(
-            "buy milk {@task status=done due=2026-06-01 completed_at=2026-06-01T11:00 started_at=2026-06-01T09:00 time_spent=2h}\n",
+            "buy milk {@task status=done due=2026-06-01 completed_at=2026-06-01T11:00 started_at=2026-06-01T09:00 time_spent=2h}\n", &DiagnosticTranslator::default(),
         );
         let stale: Vec<_> = diags
             .iter()
@@ -2310,6 +3561,7 @@ mod tests {
         // Doing task with started_at is legitimate — must NOT emit the warning.
         let (_ast, diags) = parse_text(
             "buy milk {@task status=doing due=2026-06-01 started_at=2026-06-01T09:00}\n",
+            &DiagnosticTranslator::default(),
         );
         let stale: Vec<_> = diags
             .iter()
@@ -2325,7 +3577,7 @@ mod tests {
     fn test_stale_started_at_no_warn_on_done_task_without_started_at() {
         // Clean done task (no started_at) → no warning.
         let (_ast, diags) = parse_text(
-            "buy milk {@task status=done due=2026-06-01 completed_at=2026-06-01T11:00 time_spent=2h}\n",
+            "buy milk {@task status=done due=2026-06-01 completed_at=2026-06-01T11:00 time_spent=2h}\n", &DiagnosticTranslator::default(),
         );
         let stale: Vec<_> = diags
             .iter()
@@ -2336,4 +3588,17 @@ mod tests {
             "done task without started_at should not produce stale-started-at warning"
         );
     }
+
+    #[test]
+    fn test_relative_file_link() {
+        let base = Path::new("/notes/daily");
+        assert_eq!(
+            relative_file_link(base, Path::new("/notes/daily/img/a.png")),
+            "./img/a.png"
+        );
+        assert_eq!(
+            relative_file_link(base, Path::new("/notes/assets/b.png")),
+            "../assets/b.png"
+        );
+    }
 }
@@ -0,0 +1,199 @@
+//! BibTeX reader for the BibTeX paper provider.
+//!
+//! Parsing is done by the `biblatex` crate, which also expands `@string`
+//! macros and resolves `crossref`s. Only what paper completion needs is kept:
+//! the citation key, title, authors, year and a link (`url`, or the `doi`
+//! resolver).
+
+use std::collections::HashMap;
+
+use biblatex::{Bibliography, ChunksExt};
+
+pub use biblatex::ParseError;
+
+/// A single `@type{key, field = value, ...}` entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BibEntry {
+    pub entry_type: String,
+    pub key: String,
+    /// Field values keyed by lowercase field name, with braces and quotes removed
+    pub fields: HashMap<String, String>,
+    /// Authors as "First Last"
+    authors: Vec<String>,
+}
+
+impl BibEntry {
+    fn new(entry: &biblatex::Entry) -> Self {
+        let fields = entry
+            .fields
+            .iter()
+            .map(|(name, value)| {
+                (
+                    name.to_ascii_lowercase(),
+                    clean_value(&value.format_verbatim()),
+                )
+            })
+            .collect();
+        let authors = entry
+            .author()
+            .unwrap_or_default()
+            .iter()
+            .map(|person| {
+                [
+                    &person.given_name,
+                    &person.prefix,
+                    &person.name,
+                    &person.suffix,
+                ]
+                .into_iter()
+                .map(|part| part.trim())
+                .filter(|part| !part.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ")
+            })
+            .filter(|name| !name.is_empty())
+            .collect();
+        Self {
+            entry_type: entry.entry_type.to_string().to_ascii_lowercase(),
+            key: entry.key.clone(),
+            fields,
+            authors,
+        }
+    }
+
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .get(name)
+            .map(String::as_str)
+            .filter(|v| !v.is_empty())
+    }
+
+    /// Authors as "First Last", whether written "Last, First" or "First Last"
+    pub fn authors(&self) -> Vec<String> {
+        self.authors.clone()
+    }
+
+    /// `year`, or the year of a biblatex `date`
+    pub fn year(&self) -> Option<i32> {
+        let value = self.field("year").or_else(|| self.field("date"))?;
+        value.get(..4)?.parse().ok()
+    }
+
+    /// `url`, or the DOI resolver link
+    pub fn link(&self) -> Option<String> {
+        self.field("url").map(str::to_string).or_else(|| {
+            self.field("doi")
+                .map(|doi| format!("https://doi.org/{}", doi))
+        })
+    }
+}
+
+/// Parse every entry in `input`. A syntax error anywhere fails the whole file.
+pub fn parse_bibtex(input: &str) -> Result<Vec<BibEntry>, ParseError> {
+    let bibliography = Bibliography::parse(input)?;
+    Ok(bibliography.iter().map(BibEntry::new).collect())
+}
+
+/// Collapse the whitespace of a field value
+fn clean_value(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BIB: &str = r#"
+@comment{ not an entry }
+@string{ nips = "NeurIPS" }
+@inproceedings{vaswani2017attention,
+  title     = {Attention Is {All} You Need},
+  author    = {Vaswani, Ashish and Shazeer, Noam and Niki Parmar},
+  booktitle = nips,
+  year      = 2017,
+  url       = "https://arxiv.org/abs/1706.03762",
+}
+@article{he2016,
+  title = "Deep Residual Learning for Image Recognition",
+  author = {He, Kaiming},
+  date = {2016-06},
+  doi = {10.1109/CVPR.2016.90}
+}
+"#;
+
+    #[test]
+    fn parse_entries() {
+        let entries = parse_bibtex(BIB).unwrap();
+        assert_eq!(entries.len(), 2, "{:?}", entries);
+
+        let attention = &entries[0];
+        assert_eq!(attention.entry_type, "inproceedings");
+        assert_eq!(attention.key, "vaswani2017attention");
+        assert_eq!(attention.field("title"), Some("Attention Is All You Need"));
+        assert_eq!(
+            attention.authors(),
+            vec!["Ashish Vaswani", "Noam Shazeer", "Niki Parmar"]
+        );
+        assert_eq!(attention.year(), Some(2017));
+        assert_eq!(
+            attention.link().as_deref(),
+            Some("https://arxiv.org/abs/1706.03762")
+        );
+
+        let resnet = &entries[1];
+        assert_eq!(resnet.authors(), vec!["Kaiming He"]);
+        assert_eq!(resnet.year(), Some(2016));
+        assert_eq!(
+            resnet.link().as_deref(),
+            Some("https://doi.org/10.1109/CVPR.2016.90")
+        );
+    }
+
+    #[test]
+    fn nested_braces() {
+        let entries = parse_bibtex(
+            "@article{k, title = {The {RISC-V} {ISA} {{Manual}}}, \
+             author = {{Barcelona Supercomputing Center} and van der Berg, Jan}}",
+        )
+        .unwrap();
+        assert_eq!(entries[0].field("title"), Some("The RISC-V ISA Manual"));
+        // A braced author is a single name, not split on its spaces
+        assert_eq!(
+            entries[0].authors(),
+            vec!["Barcelona Supercomputing Center", "Jan van der Berg"]
+        );
+    }
+
+    #[test]
+    fn string_macros() {
+        let entries = parse_bibtex(
+            "@string{conf = \"Conference on\"}\n\
+             @string{cvpr = conf # \" Computer Vision\"}\n\
+             @inproceedings{k, title = {T}, booktitle = cvpr # {, 2016}, month = jun}",
+        )
+        .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            entries[0].field("booktitle"),
+            Some("Conference on Computer Vision, 2016")
+        );
+    }
+
+    #[test]
+    fn quoted_values() {
+        let entries = parse_bibtex(
+            "@misc{k, title = \"Braces {Inside} {\\\"O}sterreich\", \
+             note = \"a, b = c\", year = \"2020\"}",
+        )
+        .unwrap();
+        assert_eq!(entries[0].field("title"), Some("Braces Inside Österreich"));
+        assert_eq!(entries[0].field("note"), Some("a, b = c"));
+        assert_eq!(entries[0].year(), Some(2020));
+    }
+
+    #[test]
+    fn syntax_errors_are_reported() {
+        assert!(parse_bibtex("@book{ok, title = {Unclosed}").is_err());
+        assert!(parse_bibtex("@book{ok, booktitle = undefined}").is_err());
+    }
+}
@@ -0,0 +1,357 @@
+//! Whole-document formatting for `textDocument/formatting` and table
+//! alignment for `textDocument/rangeFormatting`.
+//!
+//! Every line outside a block body is rewritten so that its indentation is
+//! made of tabs only and its trailing whitespace is removed.  Leading spaces
+//! count as `tab_size` columns per tab, and a line is never indented more than
+//! one level deeper than the line above it, which is the deepest nesting the
+//! parser accepts.
+//!
+//! The bodies of `[@code]` and `[@math]` blocks are left verbatim and so are
+//! `[@table]` rows, whose trailing tabs separate empty cells.  When the header
+//! of such a block moves to another depth, its body moves by the same number of
+//! tabs so that it still belongs to the block.
+//!
+//! Finally the document is made to end with a single newline.
+//!
+//! Range formatting pads the cells of the tables it overlaps with spaces so
+//! that their columns line up.  Cells stay separated by a tab: every cell of a
+//! column is padded to the same display width, so the following tab stops at
+//! the same column in every row whatever the tab width of the editor.
+
+use std::collections::HashMap;
+
+use str_indices::utf16::from_byte_idx as utf16_from_byte_idx;
+use tower_lsp::lsp_types::{Position, Range, TextEdit};
+use unicode_width::UnicodeWidthStr;
+
+use crate::parser::{self, AstNode, AstNodeKind};
+
+/// Compute the edits formatting `text`, indenting with `tab_size` columns per tab
+pub fn format_document(text: &str, tab_size: usize) -> Vec<TextEdit> {
+    let tab_size = tab_size.max(1);
+    let result = parser::parse_text(text);
+
+    // Row of each block body line mapped to the row of the block header
+    let mut block_rows = HashMap::new();
+    collect_block_rows(&result.ast, &mut block_rows);
+
+    let lines = split_lines(text);
+    let Some(last_row) = lines
+        .iter()
+        .rposition(|(_, content)| !content.trim().is_empty())
+    else {
+        // Nothing but blank lines
+        return if text.is_empty() {
+            vec![]
+        } else {
+            vec![TextEdit {
+                range: Range::new(Position::new(0, 0), end_position(text)),
+                new_text: String::new(),
+            }]
+        };
+    };
+
+    let mut edits = vec![];
+    // Tabs added to (or removed from) each block header, applied to its body
+    let mut header_shift: HashMap<usize, isize> = HashMap::new();
+    let mut previous_depth: Option<usize> = None;
+
+    for (row, &(_, content)) in lines.iter().enumerate().take(last_row + 1) {
+        let formatted = if let Some(header) = block_rows.get(&row) {
+            let shift = header_shift.get(header).copied().unwrap_or(0);
+            shift_indentation(content, shift)
+        } else {
+            let body = content.trim_start_matches([' ', '\t']).trim_end();
+            if body.is_empty() {
+                String::new()
+            } else {
+                let tabs = content.len() - content.trim_start_matches('\t').len();
+                let depth = indentation_width(content, tab_size) / tab_size;
+                let depth = depth.min(previous_depth.map_or(0, |d| d + 1));
+                previous_depth = Some(depth);
+                header_shift.insert(row, depth as isize - tabs as isize);
+                format!("{}{}", "\t".repeat(depth), body)
+            }
+        };
+
+        if formatted != content {
+            edits.push(TextEdit {
+                range: Range::new(
+                    Position::new(row as u32, 0),
+                    Position::new(row as u32, utf16_len(content)),
+                ),
+                new_text: formatted,
+            });
+        }
+    }
+
+    // A single newline after the last non-blank line
+    let (start, content) = lines[last_row];
+    let tail = &text[start + content.len()..];
+    if tail != "\n" && tail != "\r\n" {
+        edits.push(TextEdit {
+            range: Range::new(
+                Position::new(last_row as u32, utf16_len(content)),
+                end_position(text),
+            ),
+            new_text: "\n".to_string(),
+        });
+    }
+
+    edits
+}
+
+/// Compute the edits aligning the columns of the `[@table]` blocks overlapping `range`
+pub fn format_tables(text: &str, range: Range) -> Vec<TextEdit> {
+    let result = parser::parse_text(text);
+    let mut tables = vec![];
+    collect_tables(&result.ast, &mut tables);
+
+    tables
+        .iter()
+        .filter(|table| {
+            let rows = table.value().children.lock().unwrap();
+            let first = table.location().row;
+            let last = rows.last().map_or(first, |row| row.location().row);
+            first <= range.end.line as usize && last >= range.start.line as usize
+        })
+        .flat_map(align_table)
+        .collect()
+}
+
+fn collect_tables(node: &AstNode, tables: &mut Vec<AstNode>) {
+    if matches!(node.kind(), AstNodeKind::Table { .. }) {
+        tables.push(node.clone());
+        return;
+    }
+    for content in node.value().contents.lock().unwrap().iter() {
+        collect_tables(content, tables);
+    }
+    for child in node.value().children.lock().unwrap().iter() {
+        collect_tables(child, tables);
+    }
+}
+
+/// Rewrite every row of `table` with its cells padded to the widest cell of
+/// their column.  The last cell of a row is never padded, and so does not
+/// widen its column either.
+fn align_table(table: &AstNode) -> Vec<TextEdit> {
+    let rows: Vec<(AstNode, Vec<String>)> = table
+        .value()
+        .children
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|row| {
+            let cells = row
+                .value()
+                .contents
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|column| column.extract_str().trim_end().to_string())
+                .collect();
+            (row.clone(), cells)
+        })
+        .collect();
+
+    let mut widths: Vec<usize> = vec![];
+    for (_, cells) in &rows {
+        for (i, cell) in cells.iter().enumerate().take(cells.len().saturating_sub(1)) {
+            if widths.len() <= i {
+                widths.push(0);
+            }
+            widths[i] = widths[i].max(cell.width());
+        }
+    }
+
+    rows.iter()
+        .filter_map(|(row, cells)| {
+            let location = row.location();
+            let line = &location.input;
+            let old_text = &line[location.span.0..];
+            let new_text = cells
+                .iter()
+                .enumerate()
+                .map(|(i, cell)| {
+                    if i + 1 < cells.len() {
+                        format!("{}{}", cell, " ".repeat(widths[i] - cell.width()))
+                    } else {
+                        cell.clone()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\t");
+            (new_text != old_text).then(|| TextEdit {
+                range: Range::new(
+                    Position::new(location.row as u32, utf16_len(&line[..location.span.0])),
+                    Position::new(location.row as u32, utf16_len(line)),
+                ),
+                new_text,
+            })
+        })
+        .collect()
+}
+
+/// Record the rows of `[@code]`, `[@math]` and `[@table]` bodies with the row
+/// of their header
+fn collect_block_rows(node: &AstNode, rows: &mut HashMap<usize, usize>) {
+    match node.kind() {
+        AstNodeKind::Code { inline: false, .. }
+        | AstNodeKind::Math { inline: false }
+        | AstNodeKind::Table { .. } => {
+            let header = node.location().row;
+            for child in node.value().children.lock().unwrap().iter() {
+                rows.insert(child.location().row, header);
+            }
+            return;
+        }
+        _ => {}
+    }
+    for content in node.value().contents.lock().unwrap().iter() {
+        collect_block_rows(content, rows);
+    }
+    for child in node.value().children.lock().unwrap().iter() {
+        collect_block_rows(child, rows);
+    }
+}
+
+/// Byte offset and content, without the line terminator, of every line
+fn split_lines(text: &str) -> Vec<(usize, &str)> {
+    let mut lines = vec![];
+    let mut start = 0;
+    for line in text.split_inclusive('\n') {
+        let content = line.trim_end_matches('\n').trim_end_matches('\r');
+        lines.push((start, content));
+        start += line.len();
+    }
+    lines
+}
+
+/// Width in columns of the leading whitespace of `line`
+fn indentation_width(line: &str, tab_size: usize) -> usize {
+    line.chars()
+        .map_while(|c| match c {
+            '\t' => Some(tab_size),
+            ' ' => Some(1),
+            _ => None,
+        })
+        .sum()
+}
+
+/// Add `shift` leading tabs to a block body line, or remove them when negative
+fn shift_indentation(line: &str, shift: isize) -> String {
+    if shift > 0 && !line.is_empty() {
+        format!("{}{}", "\t".repeat(shift as usize), line)
+    } else if shift < 0 {
+        let tabs = line.len() - line.trim_start_matches('\t').len();
+        line[tabs.min(shift.unsigned_abs())..].to_string()
+    } else {
+        line.to_string()
+    }
+}
+
+fn utf16_len(text: &str) -> u32 {
+    utf16_from_byte_idx(text, text.len()) as u32
+}
+
+fn end_position(text: &str) -> Position {
+    let line = text.matches('\n').count();
+    let last = text.rsplit('\n').next().unwrap_or("");
+    Position::new(line as u32, utf16_len(last))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Apply `edits`, which must not overlap, to `text`
+    fn apply(text: &str, edits: &[TextEdit]) -> String {
+        let lines = split_lines(text);
+        let offset = |position: Position| match lines.get(position.line as usize) {
+            Some(&(start, content)) => {
+                start + str_indices::utf16::to_byte_idx(content, position.character as usize)
+            }
+            None => text.len(),
+        };
+        let mut edits: Vec<&TextEdit> = edits.iter().collect();
+        edits.sort_by_key(|edit| (edit.range.start.line, edit.range.start.character));
+
+        let mut result = text.to_string();
+        for edit in edits.iter().rev() {
+            let range = offset(edit.range.start)..offset(edit.range.end);
+            result.replace_range(range, &edit.new_text);
+        }
+        result
+    }
+
+    #[test]
+    fn spaces_become_tabs_and_whitespace_is_trimmed() {
+        let text = "parent  \n    child\t\n        grandchild\n  \n    sibling\n\n\n";
+        let edits = format_document(text, 4);
+        assert_eq!(
+            apply(text, &edits),
+            "parent\n\tchild\n\t\tgrandchild\n\n\tsibling\n"
+        );
+    }
+
+    #[test]
+    fn depth_is_capped_at_one_level_deeper() {
+        let text = "parent\n\t\t\tchild\n";
+        assert_eq!(apply(text, &format_document(text, 4)), "parent\n\tchild\n");
+    }
+
+    #[test]
+    fn formatted_document_has_no_edits() {
+        let text = "parent\n\tchild\n";
+        assert!(format_document(text, 4).is_empty());
+    }
+
+    #[test]
+    fn block_bodies_are_verbatim() {
+        let text =
+            "[@code python]\n\tdef f():  \n\t    return 1\t\n\n\tpass\n[@table]\n\ta\t\n  after  ";
+        let edits = format_document(text, 4);
+        assert_eq!(
+            apply(text, &edits),
+            "[@code python]\n\tdef f():  \n\t    return 1\t\n\n\tpass\n[@table]\n\ta\t\nafter\n"
+        );
+    }
+
+    #[test]
+    fn block_body_follows_its_header() {
+        let text = "parent\n\t\t[@code]\n\t\t\tx  \n\t\t\t\ty\n";
+        let edits = format_document(text, 4);
+        assert_eq!(apply(text, &edits), "parent\n\t[@code]\n\t\tx  \n\t\t\ty\n");
+    }
+
+    fn whole(text: &str) -> Range {
+        Range::new(Position::new(0, 0), end_position(text))
+    }
+
+    #[test]
+    fn table_columns_are_aligned_by_display_width() {
+        let text = "[@table]\n\tname\tcity\tnote\n\t田中\tTokyo\n\tAlexander\t東京都\tx\n";
+        let edits = format_tables(text, whole(text));
+        assert_eq!(
+            apply(text, &edits),
+            "[@table]\n\tname     \tcity  \tnote\n\t田中     \tTokyo\n\tAlexander\t東京都\tx\n"
+        );
+        assert!(format_tables(&apply(text, &edits), whole(text)).is_empty());
+    }
+
+    #[test]
+    fn ranges_outside_tables_are_left_alone() {
+        let text = "intro\n[@table]\n\ta\tb\n\tlonger\tc\noutro\n";
+        let outro = Range::new(Position::new(4, 0), Position::new(4, 5));
+        assert!(format_tables(text, outro).is_empty());
+
+        let cell = Range::new(Position::new(2, 1), Position::new(2, 1));
+        let edits = format_tables(text, cell);
+        assert_eq!(
+            apply(text, &edits),
+            "intro\n[@table]\n\ta     \tb\n\tlonger\tc\noutro\n"
+        );
+    }
+}
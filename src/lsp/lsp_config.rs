@@ -15,6 +15,17 @@ pub struct PattoLspConfig {
     pub zotero_api_key: Option<String>,
     #[serde(alias = "ZOTERO_ENDPOINT")]
     pub zotero_endpoint: Option<String>,
+    #[serde(default)]
+    pub bibtex: Option<BibtexSection>,
+    /// Paper provider to use when several are configured: "zotero" or "bibtex"
+    #[serde(alias = "paperProvider")]
+    pub paper_provider: Option<String>,
+    /// Template of the reference line inserted by `patto/insertCitation`
+    #[serde(alias = "citationTemplate")]
+    pub citation_template: Option<String>,
+    /// Extension of note files, e.g. "patto" (default "pn")
+    #[serde(alias = "noteExtension")]
+    pub note_extension: Option<String>,
 }
 
 impl PattoLspConfig {
@@ -46,6 +57,24 @@ impl PattoLspConfig {
     }
 }
 
+impl PattoLspConfig {
+    /// Configured note file extension, without a leading dot
+    pub fn note_extension(&self) -> Option<String> {
+        normalize_field(self.note_extension.as_deref())
+            .map(|ext| ext.trim_start_matches('.').to_string())
+            .filter(|ext| !ext.is_empty())
+    }
+
+    /// Path of the configured BibTeX file, with a leading `~/` expanded
+    pub fn bibtex_path(&self) -> Option<PathBuf> {
+        let path = normalize_field(self.bibtex.as_ref()?.path.as_deref())?;
+        match path.strip_prefix("~/") {
+            Some(rest) => Some(PathBuf::from(env::var_os("HOME")?).join(rest)),
+            None => Some(PathBuf::from(path)),
+        }
+    }
+}
+
 fn normalize_field(value: Option<&str>) -> Option<String> {
     value
         .map(str::trim)
@@ -61,6 +90,18 @@ pub struct ZoteroSection {
     pub api_key: Option<String>,
     #[serde(alias = "endpoint", alias = "base_url", alias = "ZOTERO_ENDPOINT")]
     pub endpoint: Option<String>,
+    /// Prefix completed paper links with the citation key
+    #[serde(alias = "insertCitekey")]
+    pub insert_citekey: Option<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct BibtexSection {
+    /// Path to a `.bib` file
+    pub path: Option<String>,
+    /// Prefix completed paper links with the citation key
+    #[serde(alias = "insertCitekey")]
+    pub insert_citekey: Option<bool>,
 }
 
 #[cfg_attr(not(feature = "zotero"), allow(dead_code))]
@@ -1,4 +1,6 @@
 pub mod backend;
+pub mod bibtex;
+pub mod formatting;
 pub mod lsp_config;
 pub mod paper;
 pub mod task_edits;
@@ -1,3 +1,4 @@
+use super::bibtex::parse_bibtex;
 use super::lsp_config::{resolve_cache_file, PattoLspConfig};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
@@ -19,12 +20,55 @@ use fuzzy_matcher::FuzzyMatcher;
 const DEFAULT_LIMIT: usize = 100000;
 const CACHE_REFRESH_INTERVAL_SECS: u64 = 600;
 const CACHE_FILE_NAME: &str = "paper-catalog.json";
+const DEFAULT_CITATION_TEMPLATE: &str = "{authors} ({year}) [{title} {link}]";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PaperReference {
     pub title: String,
     pub key: String,
     pub link: String,
+    /// Author names as displayed, e.g. "Ashish Vaswani"
+    #[serde(default)]
+    pub authors: Vec<String>,
+    #[serde(default)]
+    pub year: Option<i32>,
+    /// Citation key (Zotero's `citationKey` field or Better BibTeX's
+    /// `Citation Key:` line in `extra`)
+    #[serde(default)]
+    pub citekey: Option<String>,
+}
+
+impl PaperReference {
+    /// Short author list for display: "Smith", "Smith and Lee" or "Smith et al."
+    pub fn author_summary(&self) -> Option<String> {
+        let last_name = |name: &str| name.split_whitespace().last().unwrap_or(name).to_string();
+        match self.authors.as_slice() {
+            [] => None,
+            [a] => Some(last_name(a)),
+            [a, b] => Some(format!("{} and {}", last_name(a), last_name(b))),
+            [a, ..] => Some(format!("{} et al.", last_name(a))),
+        }
+    }
+
+    /// Fuzzy score of `query` against the title, authors and year.
+    ///
+    /// Every whitespace-separated term has to match at least one field; the
+    /// score is the sum of each term's best field score.
+    fn match_score(&self, matcher: &SkimMatcherV2, query: &str) -> Option<i64> {
+        let year = self.year.map(|y| y.to_string());
+        let fields = std::iter::once(self.title.as_str())
+            .chain(self.authors.iter().map(String::as_str))
+            .chain(year.as_deref())
+            .chain(self.citekey.as_deref());
+        let fields: Vec<&str> = fields.collect();
+        query.split_whitespace().try_fold(0, |total, term| {
+            fields
+                .iter()
+                .filter_map(|field| matcher.fuzzy_match(field, term))
+                .max()
+                .map(|score| total + score)
+        })
+    }
 }
 
 #[derive(Debug, Error)]
@@ -34,6 +78,18 @@ pub enum PaperProviderError {
     #[cfg(not(feature = "zotero"))]
     #[error("paper integration feature \"{0}\" is disabled at compile time")]
     FeatureDisabled(&'static str),
+    #[error("failed to read bibliography {path:?}: {source}")]
+    Bibliography {
+        #[source]
+        source: io::Error,
+        path: PathBuf,
+    },
+    #[error("failed to parse bibliography {path:?}: {source}")]
+    BibliographySyntax {
+        #[source]
+        source: super::bibtex::ParseError,
+        path: PathBuf,
+    },
     #[cfg(feature = "zotero")]
     #[error("zotero request failed: {0}")]
     Zotero(#[from] zotero_rs::errors::ZoteroError),
@@ -102,15 +158,11 @@ impl PaperCache {
     }
 
     fn search(&self, query: &str, limit: usize) -> Vec<PaperReference> {
-        let matcher = SkimMatcherV2::default();
-        self.entries
-            .read()
-            .unwrap()
-            .iter()
-            .filter(|paper| matcher.fuzzy_match(&paper.title, query).is_some())
-            .take(limit)
-            .cloned()
-            .collect()
+        rank_papers(&self.entries.read().unwrap(), query, limit)
+    }
+
+    fn find(&self, id: &str) -> Option<PaperReference> {
+        find_paper(&self.entries.read().unwrap(), id)
     }
 
     fn update_entries(&self, entries: Vec<PaperReference>, fetched_at: DateTime<Utc>) {
@@ -171,6 +223,57 @@ impl PaperCache {
     }
 }
 
+/// Papers matching `query`, best match first. Papers with equal scores keep
+/// their order (most recently added first for Zotero).
+fn rank_papers(papers: &[PaperReference], query: &str, limit: usize) -> Vec<PaperReference> {
+    let matcher = SkimMatcherV2::default();
+    let mut scored: Vec<(i64, &PaperReference)> = papers
+        .iter()
+        .filter_map(|paper| Some((paper.match_score(&matcher, query)?, paper)))
+        .collect();
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    scored
+        .into_iter()
+        .take(limit)
+        .map(|(_, paper)| paper.clone())
+        .collect()
+}
+
+/// The paper whose citation key or provider key is `id`
+fn find_paper(papers: &[PaperReference], id: &str) -> Option<PaperReference> {
+    papers
+        .iter()
+        .find(|paper| paper.citekey.as_deref() == Some(id) || paper.key == id)
+        .cloned()
+}
+
+/// Fill `{authors}`, `{author_list}`, `{year}`, `{title}`, `{link}` and
+/// `{citekey}` in `template`. Placeholders without a value are left empty,
+/// and the `()` or `[]` and extra spaces they leave behind are dropped.
+pub fn format_citation(template: &str, paper: &PaperReference) -> String {
+    let filled = template
+        .replace("{authors}", &paper.author_summary().unwrap_or_default())
+        .replace("{author_list}", &paper.authors.join(", "))
+        .replace(
+            "{year}",
+            &paper.year.map(|year| year.to_string()).unwrap_or_default(),
+        )
+        .replace("{title}", &paper.title)
+        .replace("{link}", &paper.link)
+        .replace("{citekey}", paper.citekey.as_deref().unwrap_or(&paper.key));
+    filled
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace(" ]", "]")
+        .replace("[ ", "[")
+        .replace("()", "")
+        .replace("[]", "")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct PaperCacheFile {
     fetched_at: DateTime<Utc>,
@@ -181,6 +284,8 @@ struct PaperCacheFile {
 pub struct PaperCatalog {
     provider: Option<Arc<DynPaperProvider>>,
     cache: Arc<PaperCache>,
+    insert_citekey: bool,
+    citation_template: String,
 }
 
 impl Default for PaperCatalog {
@@ -205,18 +310,33 @@ impl PaperCatalog {
         #[allow(unused_mut)]
         let mut provider: Option<Arc<DynPaperProvider>> = None;
 
+        let mut insert_citekey = None;
+
         if let Some(cfg) = config {
-            #[cfg(feature = "zotero")]
-            {
-                if let Some(credentials) = cfg.zotero_credentials() {
-                    provider = Some(Arc::new(ZoteroPaperProvider::new(credentials)?));
+            let prefer_bibtex = cfg.paper_provider.as_deref() == Some("bibtex")
+                || (cfg.paper_provider.is_none() && cfg.zotero_credentials().is_none());
+
+            if !prefer_bibtex {
+                #[cfg(feature = "zotero")]
+                {
+                    if let Some(credentials) = cfg.zotero_credentials() {
+                        provider = Some(Arc::new(ZoteroPaperProvider::new(credentials)?));
+                        insert_citekey = cfg.zotero.as_ref().and_then(|s| s.insert_citekey);
+                    }
+                }
+
+                #[cfg(not(feature = "zotero"))]
+                {
+                    if cfg.zotero_credentials().is_some() {
+                        return Err(PaperProviderError::FeatureDisabled("zotero"));
+                    }
                 }
             }
 
-            #[cfg(not(feature = "zotero"))]
-            {
-                if cfg.zotero_credentials().is_some() {
-                    return Err(PaperProviderError::FeatureDisabled("zotero"));
+            if provider.is_none() {
+                if let Some(path) = cfg.bibtex_path() {
+                    provider = Some(Arc::new(BibtexPaperProvider { path }));
+                    insert_citekey = cfg.bibtex.as_ref().and_then(|s| s.insert_citekey);
                 }
             }
         }
@@ -225,13 +345,28 @@ impl PaperCatalog {
             cache.spawn_refresh_loop(provider.clone());
         }
 
-        Ok(Self { provider, cache })
+        let insert_citekey = insert_citekey.unwrap_or(false);
+        let citation_template = config
+            .and_then(|cfg| cfg.citation_template.clone())
+            .unwrap_or_else(|| DEFAULT_CITATION_TEMPLATE.to_string());
+
+        Ok(Self {
+            provider,
+            cache,
+            insert_citekey,
+            citation_template,
+        })
     }
 
     pub fn is_configured(&self) -> bool {
         self.provider.is_some()
     }
 
+    /// Whether completions should insert the paper's citation key
+    pub fn insert_citekey(&self) -> bool {
+        self.insert_citekey
+    }
+
     pub fn provider_name(&self) -> Option<&'static str> {
         self.provider.as_ref().map(|provider| provider.name())
     }
@@ -259,6 +394,26 @@ impl PaperCatalog {
         }
     }
 
+    /// Look up a paper by citation key or provider key, fetching a snapshot
+    /// if the cache has never been filled
+    pub async fn find(&self, id: &str) -> Result<Option<PaperReference>, PaperProviderError> {
+        let Some(provider) = &self.provider else {
+            return Err(PaperProviderError::NotConfigured);
+        };
+        if let Some(paper) = self.cache.find(id) {
+            return Ok(Some(paper));
+        }
+        if self.cache.last_updated().is_some() {
+            return Ok(None);
+        }
+        Ok(find_paper(&provider.full_snapshot().await?, id))
+    }
+
+    /// Reference line for `paper` using the configured citation template
+    pub fn format_citation(&self, paper: &PaperReference) -> String {
+        format_citation(&self.citation_template, paper)
+    }
+
     pub async fn refresh(&self) -> Result<(), PaperProviderError> {
         if let Some(provider) = &self.provider {
             match self.cache.refresh_once(provider.clone()).await {
@@ -274,6 +429,64 @@ impl PaperCatalog {
     }
 }
 
+/// Papers from a local BibTeX file, re-read on every refresh
+struct BibtexPaperProvider {
+    path: PathBuf,
+}
+
+impl BibtexPaperProvider {
+    async fn read(&self) -> Result<Vec<PaperReference>, PaperProviderError> {
+        let content = tokio::fs::read_to_string(&self.path)
+            .await
+            .map_err(|source| PaperProviderError::Bibliography {
+                source,
+                path: self.path.clone(),
+            })?;
+        let papers = parse_bibtex(&content)
+            .map_err(|source| PaperProviderError::BibliographySyntax {
+                source,
+                path: self.path.clone(),
+            })?
+            .into_iter()
+            .filter_map(|entry| {
+                let title = entry.field("title")?.to_string();
+                Some(PaperReference {
+                    title,
+                    link: entry.link().unwrap_or_default(),
+                    authors: entry.authors(),
+                    year: entry.year(),
+                    citekey: Some(entry.key.clone()),
+                    key: entry.key,
+                })
+            })
+            .collect();
+        Ok(papers)
+    }
+}
+
+#[async_trait]
+impl PaperProvider for BibtexPaperProvider {
+    fn name(&self) -> &'static str {
+        "bibtex"
+    }
+
+    async fn health_check(&self) -> Result<(), PaperProviderError> {
+        self.read().await.map(|_| ())
+    }
+
+    async fn full_snapshot(&self) -> Result<Vec<PaperReference>, PaperProviderError> {
+        self.read().await
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<PaperReference>, PaperProviderError> {
+        Ok(rank_papers(&self.read().await?, query, limit))
+    }
+}
+
 #[cfg(feature = "zotero")]
 struct ZoteroPaperProvider {
     inner: zotero_rs::ZoteroAsync,
@@ -377,6 +590,12 @@ fn extract_references(value: &Value) -> Vec<PaperReference> {
                         title: title.to_string(),
                         key: key.to_string(),
                         link: format!("{}{}", ZOTERO_URL_PREFIX, key),
+                        authors: extract_authors(data),
+                        year: data
+                            .get("date")
+                            .and_then(Value::as_str)
+                            .and_then(extract_year),
+                        citekey: extract_citekey(data),
                     })
                 })
                 .collect()
@@ -384,8 +603,178 @@ fn extract_references(value: &Value) -> Vec<PaperReference> {
         .unwrap_or_default()
 }
 
+/// Author names from Zotero's `creators`, which hold either a single `name`
+/// or a `firstName`/`lastName` pair
+#[cfg(feature = "zotero")]
+fn extract_authors(data: &Value) -> Vec<String> {
+    let Some(creators) = data.get("creators").and_then(Value::as_array) else {
+        return vec![];
+    };
+    creators
+        .iter()
+        .filter(|c| c.get("creatorType").and_then(Value::as_str) == Some("author"))
+        .filter_map(|c| {
+            if let Some(name) = c.get("name").and_then(Value::as_str) {
+                return Some(name.trim().to_string());
+            }
+            let first = c.get("firstName").and_then(Value::as_str).unwrap_or("");
+            let last = c.get("lastName").and_then(Value::as_str)?;
+            Some(
+                format!("{} {}", first.trim(), last.trim())
+                    .trim()
+                    .to_string(),
+            )
+        })
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// First four-digit run in a free-form Zotero date ("2017", "June 12, 2017", "2017-06-12")
+#[cfg_attr(not(feature = "zotero"), allow(dead_code))]
+fn extract_year(date: &str) -> Option<i32> {
+    date.as_bytes()
+        .windows(4)
+        .position(|w| w.iter().all(u8::is_ascii_digit))
+        .and_then(|i| date[i..i + 4].parse().ok())
+}
+
+#[cfg(feature = "zotero")]
+fn extract_citekey(data: &Value) -> Option<String> {
+    let field = data
+        .get("citationKey")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|key| !key.is_empty());
+    let extra = || {
+        data.get("extra")?
+            .as_str()?
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("Citation Key:"))
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+    };
+    field.or_else(extra).map(str::to_string)
+}
+
 #[cfg(not(feature = "zotero"))]
 #[allow(dead_code)]
 fn extract_references(_: &serde_json::Value) -> Vec<PaperReference> {
     vec![]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paper(title: &str, authors: &[&str], year: Option<i32>) -> PaperReference {
+        PaperReference {
+            title: title.to_string(),
+            key: title.to_string(),
+            link: String::new(),
+            authors: authors.iter().map(|a| a.to_string()).collect(),
+            year,
+            citekey: None,
+        }
+    }
+
+    #[test]
+    fn search_matches_authors_and_year() {
+        let cache = PaperCache::new(env::temp_dir().join("patto-paper-search-test.json"));
+        *cache.entries.write().unwrap() = vec![
+            paper("Deep Residual Learning", &["Kaiming He"], Some(2016)),
+            paper("Attention Is All You Need", &["Ashish Vaswani"], Some(2017)),
+            paper("Vaswani's Notes", &[], None),
+        ];
+
+        let titles = |query: &str| {
+            cache
+                .search(query, 10)
+                .into_iter()
+                .map(|p| p.title)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(titles("vaswani 2017"), vec!["Attention Is All You Need"]);
+        assert_eq!(titles("2016"), vec!["Deep Residual Learning"]);
+        assert_eq!(titles("residual he"), vec!["Deep Residual Learning"]);
+        assert_eq!(titles("").len(), 3);
+    }
+
+    #[tokio::test]
+    async fn bibtex_provider_search() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("references.bib");
+        fs::write(
+            &path,
+            "@article{he2016, title = {Deep Residual Learning}, author = {He, Kaiming}, year = 2016}\n\
+             @misc{notitle, author = {Nobody}}\n\
+             @inproceedings{vaswani2017, title = {Attention Is All You Need}, \
+             author = {Vaswani, Ashish}, year = 2017, doi = {10.5555/3295222}}\n",
+        )
+        .unwrap();
+        let provider = BibtexPaperProvider { path };
+
+        assert_eq!(provider.full_snapshot().await.unwrap().len(), 2);
+        let found = provider.search("vaswani 2017", 10).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].citekey.as_deref(), Some("vaswani2017"));
+        assert_eq!(found[0].link, "https://doi.org/10.5555/3295222");
+
+        let missing = BibtexPaperProvider {
+            path: dir.path().join("missing.bib"),
+        };
+        assert!(matches!(
+            missing.health_check().await,
+            Err(PaperProviderError::Bibliography { .. })
+        ));
+
+        let broken = dir.path().join("broken.bib");
+        fs::write(&broken, "@article{unclosed, title = {Oops}\n").unwrap();
+        let broken = BibtexPaperProvider { path: broken };
+        assert!(matches!(
+            broken.full_snapshot().await,
+            Err(PaperProviderError::BibliographySyntax { .. })
+        ));
+    }
+
+    #[test]
+    fn citation_template() {
+        let mut p = paper(
+            "Attention Is All You Need",
+            &["Ashish Vaswani", "Noam Shazeer", "Niki Parmar"],
+            Some(2017),
+        );
+        p.link = "https://arxiv.org/abs/1706.03762".to_string();
+        p.citekey = Some("vaswani2017".to_string());
+        assert_eq!(
+            format_citation(DEFAULT_CITATION_TEMPLATE, &p),
+            "Vaswani et al. (2017) [Attention Is All You Need https://arxiv.org/abs/1706.03762]"
+        );
+        assert_eq!(
+            format_citation("{citekey}: {author_list}. {title}", &p),
+            "vaswani2017: Ashish Vaswani, Noam Shazeer, Niki Parmar. Attention Is All You Need"
+        );
+
+        // Missing values leave no empty brackets behind
+        let bare = paper("Untitled Draft", &[], None);
+        assert_eq!(
+            format_citation(DEFAULT_CITATION_TEMPLATE, &bare),
+            "[Untitled Draft]"
+        );
+    }
+
+    #[test]
+    fn year_and_author_summary() {
+        assert_eq!(extract_year("June 12, 2017"), Some(2017));
+        assert_eq!(extract_year("2017-06-12"), Some(2017));
+        assert_eq!(extract_year("n.d."), None);
+
+        let p = paper(
+            "t",
+            &["Ashish Vaswani", "Noam Shazeer", "Niki Parmar"],
+            None,
+        );
+        assert_eq!(p.author_summary().as_deref(), Some("Vaswani et al."));
+        let p = paper("t", &["Kaiming He", "Xiangyu Zhang"], None);
+        assert_eq!(p.author_summary().as_deref(), Some("He and Zhang"));
+    }
+}
@@ -269,6 +269,80 @@ pub fn generate_edits_for_transition(
     }
 }
 
+// ─── Status toggle ───────────────────────────────────────────────────────────
+
+/// The status `patto/toggleTask` moves a task to: todo → doing → done → todo.
+/// Paused tasks resume as doing.
+pub fn next_toggle_status(status: &TaskStatus) -> TaskStatus {
+    match status {
+        TaskStatus::Todo | TaskStatus::Paused => TaskStatus::Doing,
+        TaskStatus::Doing => TaskStatus::Done,
+        TaskStatus::Done => TaskStatus::Todo,
+    }
+}
+
+/// Generate the edits that advance the task in `snapshot` to its next status.
+///
+/// Shorthand tokens keep their form and only have the leading symbol replaced;
+/// `{@task …}` blocks are rewritten with the new `status=`, dropping a stale
+/// `completed_at` when a done task is reopened.  With `stamp_completion`, a task
+/// becoming done is recorded exactly like an edited one (`completed_at=<now>`
+/// plus any running clock flushed into `time_spent`), which turns a shorthand
+/// token into a long-form block.
+pub fn generate_toggle_edits(
+    snapshot: &TaskSnapshot,
+    now: chrono::NaiveDateTime,
+    stamp_completion: bool,
+) -> Vec<TextEdit> {
+    let status = next_toggle_status(&snapshot.status);
+    let mut new = snapshot.clone();
+    new.status = status.clone();
+
+    if status == TaskStatus::Done && stamp_completion {
+        let transition = TaskTransition::BecameDone {
+            old: snapshot.clone(),
+            new,
+        };
+        return generate_edits_for_transition(&transition, now);
+    }
+
+    if snapshot.is_shorthand {
+        let symbol = match status {
+            TaskStatus::Todo => "!",
+            TaskStatus::Doing | TaskStatus::Paused => "*",
+            TaskStatus::Done => "-",
+        };
+        let line_idx = snapshot.row as u32;
+        let line_text = &snapshot.line_text;
+        let start = snapshot.prop_span.0;
+        return vec![TextEdit {
+            range: Range {
+                start: Position {
+                    line: line_idx,
+                    character: utf16_from_byte_idx(line_text, start) as u32,
+                },
+                end: Position {
+                    line: line_idx,
+                    character: utf16_from_byte_idx(line_text, start + 1) as u32,
+                },
+            },
+            new_text: symbol.to_string(),
+        }];
+    }
+
+    let status_str = match status {
+        TaskStatus::Todo => "todo",
+        TaskStatus::Doing => "doing",
+        TaskStatus::Paused => "paused",
+        TaskStatus::Done => "done",
+    };
+    let mut fields = vec![("status", status_str.to_string())];
+    if snapshot.status == TaskStatus::Done {
+        fields.push(("completed_at", String::new())); // delete
+    }
+    build_edits(snapshot, &fields)
+}
+
 // ─── Internal helpers ─────────────────────────────────────────────────────────
 
 /// Compute elapsed time between `started_at` and `now`.
@@ -280,7 +354,9 @@ fn elapsed_since(
     use crate::parser::Deadline;
     let start = match started_at.as_ref()? {
         Deadline::DateTime(dt) => *dt,
+        Deadline::DateTimeTz(dt) => crate::parser::local_naive(dt),
         Deadline::Date(_) => return None,
+        Deadline::TimeRange { .. } | Deadline::Recurring(_) => return None,
         Deadline::Uninterpretable(_) => return None,
     };
     let secs = (now - start).num_seconds();
@@ -550,6 +626,63 @@ mod tests {
         assert_eq!(transitions.len(), 0);
     }
 
+    // ── Status toggle ─────────────────────────────────────────────────────────
+
+    #[test]
+    fn toggle_cycles_todo_doing_done() {
+        assert_eq!(next_toggle_status(&TaskStatus::Todo), TaskStatus::Doing);
+        assert_eq!(next_toggle_status(&TaskStatus::Doing), TaskStatus::Done);
+        assert_eq!(next_toggle_status(&TaskStatus::Done), TaskStatus::Todo);
+        assert_eq!(next_toggle_status(&TaskStatus::Paused), TaskStatus::Doing);
+    }
+
+    #[test]
+    fn toggle_shorthand_rewrites_symbol() {
+        let now =
+            chrono::NaiveDateTime::parse_from_str("2026-05-19T10:30", "%Y-%m-%dT%H:%M").unwrap();
+        let mut snap = make_snapshot(0, TaskStatus::Todo, None);
+        snap.is_shorthand = true;
+        snap.line_text = "buy milk !2026-05-20".to_string();
+        snap.prop_span = crate::parser::Span(9, 20);
+
+        let edits = generate_toggle_edits(&snap, now, true);
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].new_text, "*");
+        assert_eq!(edits[0].range.start, Position::new(0, 9));
+        assert_eq!(edits[0].range.end, Position::new(0, 10));
+    }
+
+    #[test]
+    fn toggle_longform_to_done_stamps_completion() {
+        let now =
+            chrono::NaiveDateTime::parse_from_str("2026-05-19T10:30", "%Y-%m-%dT%H:%M").unwrap();
+        let snap = make_snapshot(0, TaskStatus::Doing, Some("2026-05-19T09:00"));
+
+        let edits = generate_toggle_edits(&snap, now, true);
+        assert_eq!(edits.len(), 1);
+        let text = &edits[0].new_text;
+        assert!(text.contains("status=done"), "{text}");
+        assert!(text.contains("completed_at=2026-05-19T10:30"), "{text}");
+        assert!(text.contains("time_spent=1h30m"), "{text}");
+
+        let edits = generate_toggle_edits(&snap, now, false);
+        assert_eq!(
+            edits[0].new_text,
+            "{@task status=done started_at=2026-05-19T09:00}"
+        );
+    }
+
+    #[test]
+    fn toggle_reopening_done_task_clears_completion() {
+        let now =
+            chrono::NaiveDateTime::parse_from_str("2026-05-19T10:30", "%Y-%m-%dT%H:%M").unwrap();
+        let mut snap = make_snapshot(0, TaskStatus::Done, None);
+        snap.completed_at = Some(crate::parser::parse_deadline_pub("2026-05-18T17:00"));
+
+        let edits = generate_toggle_edits(&snap, now, true);
+        assert_eq!(edits[0].new_text, "{@task status=todo}");
+    }
+
     // ── Paused status ─────────────────────────────────────────────────────────
 
     #[test]
@@ -12,6 +12,9 @@ pub struct MarkdownRendererOptions {
     pub(crate) task_format: TaskFormat,
     pub(crate) anchor_format: AnchorFormat,
     pub(crate) include_frontmatter: bool,
+    pub(crate) callouts: bool,
+    pub(crate) metadata: bool,
+    pub(crate) title: Option<String>,
 }
 
 impl MarkdownRendererOptions {
@@ -45,6 +48,9 @@ impl MarkdownRendererOptions {
             task_format,
             anchor_format,
             include_frontmatter,
+            callouts: false,
+            metadata: false,
+            title: None,
         }
     }
 
@@ -54,6 +60,24 @@ impl MarkdownRendererOptions {
         self
     }
 
+    /// Render quotes starting with `NOTE:`, `WARNING:`, etc. as GitHub-style alerts
+    pub fn with_callouts(mut self, enabled: bool) -> Self {
+        self.callouts = enabled;
+        self
+    }
+
+    /// Add a title, a task count summary and the earliest open deadline to the frontmatter
+    pub fn with_metadata(mut self, enabled: bool) -> Self {
+        self.metadata = enabled;
+        self
+    }
+
+    /// Title for the frontmatter of a note without headings, such as its file stem
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
     // Accessor methods for renderer
     pub fn wiki_link_format(&self) -> WikiLinkFormat {
         self.wiki_link_format
@@ -74,4 +98,16 @@ impl MarkdownRendererOptions {
     pub fn include_frontmatter(&self) -> bool {
         self.include_frontmatter
     }
+
+    pub fn callouts(&self) -> bool {
+        self.callouts
+    }
+
+    pub fn metadata(&self) -> bool {
+        self.metadata
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
 }
@@ -4,7 +4,9 @@ use pest_derive::Parser;
 use std::cmp;
 use std::cmp::Ordering;
 use std::fmt;
+use std::io;
 use std::ops;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 //use std::time::{Instant};
 use log;
@@ -17,6 +19,9 @@ use pest::iterators::Pair;
 
 use serde::{Deserialize, Serialize};
 
+/// Characters that a backslash makes literal
+pub const ESCAPABLE_CHARS: &str = "[]#*\\";
+
 #[derive(Parser)]
 #[grammar = "patto.pest"]
 pub struct PattoLineParser;
@@ -167,19 +172,138 @@ where
 #[derive(PartialEq, Eq, Debug, Clone, Deserialize, Serialize)]
 pub enum Deadline {
     DateTime(chrono::NaiveDateTime),
+    /// A date and time with an explicit UTC offset, written as `2024-10-10T09:00+09:00`
+    DateTimeTz(chrono::DateTime<chrono::FixedOffset>),
     Date(chrono::NaiveDate),
+    /// A time slot within a day, written as `2024-10-10T09:00..10:30`
+    TimeRange {
+        start: chrono::NaiveDateTime,
+        end: chrono::NaiveDateTime,
+    },
+    /// A repeating deadline such as `weekly:mon`
+    Recurring(Recurrence),
     Uninterpretable(String),
 }
 
+impl Deadline {
+    /// The deadline used for ordering: time ranges sort by their start and
+    /// recurring deadlines by their next occurrence from today.
+    fn effective(&self) -> Deadline {
+        match self {
+            Deadline::DateTimeTz(dt) => Deadline::DateTime(local_naive(dt)),
+            Deadline::TimeRange { start, .. } => Deadline::DateTime(*start),
+            Deadline::Recurring(recurrence) => {
+                Deadline::Date(recurrence.next_occurrence(chrono::Local::now().date_naive()))
+            }
+            other => other.clone(),
+        }
+    }
+
+    /// Tie-breaker between variants that resolve to the same effective deadline
+    fn rank(&self) -> u8 {
+        match self {
+            Deadline::TimeRange { .. } => 1,
+            Deadline::Recurring(_) => 2,
+            _ => 0,
+        }
+    }
+
+    /// The day the deadline falls on, as used for ordering; `None` if uninterpretable
+    pub fn date(&self) -> Option<chrono::NaiveDate> {
+        match self.effective() {
+            Deadline::Date(d) => Some(d),
+            Deadline::DateTime(dt) => Some(dt.date()),
+            _ => None,
+        }
+    }
+
+    /// The deadline as an offset-aware instant; naive date-times are taken
+    /// as local time and dates as local midnight
+    pub fn to_datetime(&self) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+        let naive = match self {
+            Deadline::DateTimeTz(dt) => return Some(*dt),
+            Deadline::DateTime(dt) => *dt,
+            Deadline::Date(d) => d.and_hms_opt(0, 0, 0)?,
+            Deadline::TimeRange { start, .. } => *start,
+            Deadline::Recurring(_) | Deadline::Uninterpretable(_) => return None,
+        };
+        naive
+            .and_local_timezone(chrono::Local)
+            .earliest()
+            .map(|dt| dt.fixed_offset())
+    }
+
+    /// Where the deadline stands relative to `now`, in local time.
+    ///
+    /// Dates are overdue from the day after, times from the minute after and
+    /// time ranges once they have ended; recurring deadlines always have a
+    /// next occurrence, so they are never overdue.
+    pub fn status(&self, now: chrono::NaiveDateTime) -> DeadlineStatus {
+        let today = now.date();
+        let (overdue, day) = match self {
+            Deadline::Date(date) => (*date < today, *date),
+            Deadline::DateTime(datetime) => (*datetime < now, datetime.date()),
+            Deadline::DateTimeTz(datetime) => {
+                let local = local_naive(datetime);
+                (local < now, local.date())
+            }
+            // A slot that has started but not ended yet is due today
+            Deadline::TimeRange { start, end } => (*end < now, start.date().min(today)),
+            Deadline::Recurring(recurrence) => (false, recurrence.next_occurrence(today)),
+            Deadline::Uninterpretable(_) => return DeadlineStatus::None,
+        };
+        if overdue {
+            DeadlineStatus::Overdue
+        } else if day == today {
+            DeadlineStatus::Today
+        } else {
+            DeadlineStatus::Upcoming
+        }
+    }
+}
+
+/// Classification of a [`Deadline`] returned by [`Deadline::status`]
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum DeadlineStatus {
+    /// The deadline has passed
+    Overdue,
+    /// Due later today
+    Today,
+    /// Due after today
+    Upcoming,
+    /// The deadline could not be interpreted
+    #[default]
+    None,
+}
+
+/// An offset-aware date-time as wall-clock time in the local timezone
+pub fn local_naive(dt: &chrono::DateTime<chrono::FixedOffset>) -> chrono::NaiveDateTime {
+    dt.with_timezone(&chrono::Local).naive_local()
+}
+
 impl fmt::Display for Deadline {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Deadline::DateTime(dt) => {
                 write!(f, "{}", dt.format("%Y-%m-%dT%H:%M"))?;
             }
+            Deadline::DateTimeTz(dt) => {
+                write!(f, "{}", dt.format("%Y-%m-%dT%H:%M%:z"))?;
+            }
             Deadline::Date(d) => {
                 write!(f, "{}", d)?;
             }
+            Deadline::TimeRange { start, end } => {
+                write!(
+                    f,
+                    "{}..{}",
+                    start.format("%Y-%m-%dT%H:%M"),
+                    end.format("%H:%M")
+                )?;
+            }
+            Deadline::Recurring(recurrence) => {
+                write!(f, "{}", recurrence)?;
+            }
             Deadline::Uninterpretable(s) => {
                 write!(f, "{}", s)?;
             }
@@ -196,7 +320,7 @@ impl PartialOrd for Deadline {
 
 impl Ord for Deadline {
     fn cmp(&self, other: &Self) -> Ordering {
-        match (self, other) {
+        let ordering = match (&self.effective(), &other.effective()) {
             (Deadline::Date(d1), Deadline::Date(d2)) => d1.cmp(d2),
             (Deadline::DateTime(dt1), Deadline::DateTime(dt2)) => dt1.cmp(dt2),
             (Deadline::Uninterpretable(t1), Deadline::Uninterpretable(t2)) => t1.cmp(t2),
@@ -211,6 +335,89 @@ impl Ord for Deadline {
             (Deadline::Date(_), _) => Ordering::Less,
             (Deadline::DateTime(_), Deadline::Uninterpretable(_)) => Ordering::Less,
             (Deadline::Uninterpretable(_), _) => Ordering::Greater,
+            // `effective` never yields a time range or a recurrence
+            _ => Ordering::Equal,
+        };
+        ordering
+            .then_with(|| self.rank().cmp(&other.rank()))
+            .then_with(|| self.to_string().cmp(&other.to_string()))
+    }
+}
+
+/// Repetition rule of a recurring deadline: `daily`, `weekly:mon` (several days
+/// joined by `/`, e.g. `weekly:mon/thu`) or `monthly:15`
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Serialize)]
+pub enum Recurrence {
+    Daily,
+    Weekly(Vec<chrono::Weekday>),
+    /// Day of the month; months without that day are skipped
+    Monthly(u32),
+}
+
+impl Recurrence {
+    pub fn parse(spec: &str) -> Option<Self> {
+        let (freq, arg) = match spec.split_once(':') {
+            Some((freq, arg)) => (freq, Some(arg)),
+            None => (spec, None),
+        };
+        match (freq, arg) {
+            ("daily", None) => Some(Recurrence::Daily),
+            ("weekly", Some(days)) => {
+                let days = days
+                    .split('/')
+                    .map(|day| day.parse::<chrono::Weekday>().ok())
+                    .collect::<Option<Vec<_>>>()?;
+                Some(Recurrence::Weekly(days))
+            }
+            ("monthly", Some(day)) => match day.parse::<u32>() {
+                Ok(day) if (1..=31).contains(&day) => Some(Recurrence::Monthly(day)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The first occurrence on or after `from`
+    pub fn next_occurrence(&self, from: chrono::NaiveDate) -> chrono::NaiveDate {
+        use chrono::Datelike;
+        match self {
+            Recurrence::Daily => from,
+            Recurrence::Weekly(days) => from
+                .iter_days()
+                .take(7)
+                .find(|date| days.contains(&date.weekday()))
+                .unwrap_or(from),
+            Recurrence::Monthly(day) => {
+                let mut month_start = from.with_day(1).unwrap_or(from);
+                // Every day of the month occurs at least once within a year
+                for _ in 0..13 {
+                    if let Some(date) = month_start.with_day(*day) {
+                        if date >= from {
+                            return date;
+                        }
+                    }
+                    month_start = month_start
+                        .checked_add_months(chrono::Months::new(1))
+                        .unwrap_or(month_start);
+                }
+                from
+            }
+        }
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Recurrence::Daily => write!(f, "daily"),
+            Recurrence::Weekly(days) => {
+                let days = days
+                    .iter()
+                    .map(|day| day.to_string().to_lowercase())
+                    .collect::<Vec<_>>();
+                write!(f, "weekly:{}", days.join("/"))
+            }
+            Recurrence::Monthly(day) => write!(f, "monthly:{}", day),
         }
     }
 }
@@ -223,7 +430,7 @@ pub enum TaskStatus {
     Done,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub enum Property {
     Task {
         status: TaskStatus,
@@ -243,9 +450,57 @@ pub enum Property {
         name: String,
         location: Location,
     },
+    /// Any other property, e.g. `{@tag project=patto priority=high}`
+    Tag {
+        name: String,
+        /// Arguments in source order: `(key, Some(value))` for `key=value`,
+        /// `(arg, None)` for a positional argument.
+        args: Vec<(String, Option<String>)>,
+        location: Location,
+    },
 }
 
-#[derive(Debug, Default, Serialize)]
+impl Property {
+    /// Source form of a generic tag, e.g. `{@tag project=patto}`; `None` for other properties
+    pub fn tag_source(&self) -> Option<String> {
+        let Property::Tag { name, args, .. } = self else {
+            return None;
+        };
+        let mut source = format!("{{@{}", name);
+        for (key, value) in args {
+            match value {
+                Some(value) => source.push_str(&format!(" {}={}", key, value)),
+                None => source.push_str(&format!(" {}", key)),
+            }
+        }
+        source.push('}');
+        Some(source)
+    }
+
+    /// Where the property was written
+    pub fn location(&self) -> &Location {
+        match self {
+            Property::Task { location, .. }
+            | Property::Anchor { location, .. }
+            | Property::Tag { location, .. } => location,
+        }
+    }
+
+    /// Copy of the property written `delta` rows further down
+    fn shifted(&self, delta: isize) -> Property {
+        let mut property = self.clone();
+        match &mut property {
+            Property::Task { location, .. }
+            | Property::Anchor { location, .. }
+            | Property::Tag { location, .. } => {
+                location.row = location.row.saturating_add_signed(delta)
+            }
+        }
+        property
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
 #[serde(tag = "type")]
 pub enum AstNodeKind {
     Line {
@@ -273,6 +528,10 @@ pub enum AstNodeKind {
     Image {
         src: String,
         alt: Option<String>,
+        /// `width=` option, in pixels
+        width: Option<u32>,
+        /// `height=` option, in pixels
+        height: Option<u32>,
     },
     WikiLink {
         link: String,
@@ -299,9 +558,15 @@ pub enum AstNodeKind {
         underline: bool,
         deleted: bool,
     },
+    /// Definition list item (`term:: definition`); the contents are the definition
+    DefinitionItem {
+        term: String,
+    },
 
     Text,
     HorizontalLine,
+    /// `%% comment %%` or a `//` line; kept for editing, dropped when rendering
+    Comment,
     #[default]
     Dummy,
 }
@@ -432,12 +697,26 @@ impl AstNode {
     pub fn horizontal_line(input: &str, row: usize, span: Option<Span>) -> Self {
         Self::new(input, row, span, Some(AstNodeKind::HorizontalLine))
     }
+    pub fn comment(input: &str, row: usize, span: Option<Span>) -> Self {
+        Self::new(input, row, span, Some(AstNodeKind::Comment))
+    }
     pub fn image(
         input: &str,
         row: usize,
         span: Option<Span>,
         src: &str,
         alt: Option<&str>,
+    ) -> Self {
+        Self::image_with_size(input, row, span, src, alt, None, None)
+    }
+    pub fn image_with_size(
+        input: &str,
+        row: usize,
+        span: Option<Span>,
+        src: &str,
+        alt: Option<&str>,
+        width: Option<u32>,
+        height: Option<u32>,
     ) -> Self {
         Self::new(
             input,
@@ -446,6 +725,8 @@ impl AstNode {
             Some(AstNodeKind::Image {
                 src: src.to_string(),
                 alt: alt.map(str::to_string),
+                width,
+                height,
             }),
         )
     }
@@ -470,6 +751,16 @@ impl AstNode {
             }),
         )
     }
+    pub fn definition_item(input: &str, row: usize, span: Option<Span>, term: &str) -> Self {
+        Self::new(
+            input,
+            row,
+            span,
+            Some(AstNodeKind::DefinitionItem {
+                term: term.to_string(),
+            }),
+        )
+    }
     pub fn table(input: &str, row: usize, span: Option<Span>, caption: Option<&str>) -> Self {
         Self::new(
             input,
@@ -526,6 +817,68 @@ impl AstNode {
     pub fn extract_str(&self) -> &str {
         self.location().as_str()
     }
+    /// Whether this is a line holding nothing but a comment; such a line is
+    /// left out of the rendered output together with its nested lines
+    pub fn is_comment_line(&self) -> bool {
+        let AstNodeKind::Line { properties } = self.kind() else {
+            return false;
+        };
+        let contents = self.value().contents.lock().unwrap();
+        properties.is_empty()
+            && contents.len() == 1
+            && matches!(contents[0].kind(), AstNodeKind::Comment)
+    }
+    /// Whether this is a character written with a backslash escape, like `\[`
+    pub fn is_escaped(&self) -> bool {
+        let Location { input, span, .. } = self.location();
+        matches!(self.kind(), AstNodeKind::Text)
+            && span.0 > 0
+            && span.1 == span.0 + 1
+            && input.as_bytes()[span.0 - 1] == b'\\'
+            && ESCAPABLE_CHARS.contains(&input[span.0..span.1])
+    }
+    /// Deep copy of the subtree moved `delta` rows down (up when negative),
+    /// so that blocks after an edit adding or removing lines can be reused.
+    pub fn shifted(&self, delta: isize) -> AstNode {
+        let shift =
+            |properties: &Vec<Property>| properties.iter().map(|p| p.shifted(delta)).collect();
+        let kind = match self.kind() {
+            AstNodeKind::Line { properties } => AstNodeKind::Line {
+                properties: shift(properties),
+            },
+            AstNodeKind::QuoteContent { properties } => AstNodeKind::QuoteContent {
+                properties: shift(properties),
+            },
+            kind => kind.clone(),
+        };
+        let location = Location {
+            row: self.location().row.saturating_add_signed(delta),
+            ..self.location().clone()
+        };
+        let node = AstNode::with_line_id(
+            "",
+            location,
+            Some(kind),
+            *self.value().stable_id.lock().unwrap(),
+        );
+        node.value().contents.lock().unwrap().extend(
+            self.value()
+                .contents
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|c| c.shifted(delta)),
+        );
+        node.value().children.lock().unwrap().extend(
+            self.value()
+                .children
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|c| c.shifted(delta)),
+        );
+        node
+    }
 }
 
 impl Clone for AstNode {
@@ -552,6 +905,26 @@ impl fmt::Display for AstNode {
     }
 }
 
+/// Heading level (1 is the top) of a line whose sole content is a bold decoration
+///
+/// Larger decorations map to shallower headings: `[** x]` and bigger are
+/// level 1 and `[* x]` is level 2. Renderers and exporters share this rule so
+/// that they agree on what a heading is.
+pub fn heading_level(node: &AstNode) -> Option<u8> {
+    if !matches!(node.kind(), AstNodeKind::Line { .. }) {
+        return None;
+    }
+    match node.value().contents.lock().unwrap().as_slice() {
+        [content] => match content.kind() {
+            AstNodeKind::Decoration { fontsize, .. } if *fontsize > 0 => {
+                Some(3u8.saturating_sub((*fontsize).min(3) as u8).max(1))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 /// State for a single quote level in the quote stack
 struct QuoteLevel {
     node: AstNode,
@@ -740,6 +1113,33 @@ pub struct ParserResult {
 }
 
 pub fn parse_text(text: &str) -> ParserResult {
+    parse_text_at_row(text, 0)
+}
+
+/// Read and parse the note at `path`.
+///
+/// A file that is not valid UTF-8 is an [`io::ErrorKind::InvalidData`] error
+/// naming the offending byte offset.
+pub fn parse_file(path: &Path) -> io::Result<ParserResult> {
+    let bytes = std::fs::read(path)?;
+    let text = String::from_utf8(bytes).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "{} is not valid UTF-8 (invalid byte at offset {})",
+                path.display(),
+                e.utf8_error().valid_up_to()
+            ),
+        )
+    })?;
+    Ok(parse_text(&text))
+}
+
+/// Parse `text` as a slice of a larger document whose first line sits at `row_offset`.
+///
+/// Rows recorded in the resulting nodes and errors are shifted by `row_offset`,
+/// so the returned subtrees can be spliced into an existing AST.
+pub fn parse_text_at_row(text: &str, row_offset: usize) -> ParserResult {
     let indent_content_len: Vec<_> = text
         .lines()
         .map(|l| {
@@ -757,8 +1157,9 @@ pub fn parse_text(text: &str) -> ParserResult {
     };
 
     let mut errors: Vec<ParserError> = Vec::new();
-    for (iline, linetext) in text.lines().enumerate() {
-        let (indent, content_len) = indent_content_len[iline];
+    for (index, linetext) in text.lines().enumerate() {
+        let iline = index + row_offset;
+        let (indent, content_len) = indent_content_len[index];
 
         // Handle block exit first
         match &mut block_context {
@@ -772,7 +1173,7 @@ pub fn parse_text(text: &str) -> ParserResult {
                         state.current_min_indent(),
                         content_len,
                         &indent_content_len,
-                        iline,
+                        index,
                     )
                 {
                     state.stack.pop();
@@ -785,7 +1186,7 @@ pub fn parse_text(text: &str) -> ParserResult {
                         state.current_min_indent(),
                         content_len,
                         &indent_content_len,
-                        iline,
+                        index,
                     )
                 {
                     block_context = BlockContext::Line {
@@ -797,7 +1198,7 @@ pub fn parse_text(text: &str) -> ParserResult {
             BlockContext::Code { min_indent, .. }
             | BlockContext::Math { min_indent, .. }
             | BlockContext::Table { min_indent, .. } => {
-                if should_exit_block(indent, *min_indent, content_len, &indent_content_len, iline) {
+                if should_exit_block(indent, *min_indent, content_len, &indent_content_len, index) {
                     block_context = BlockContext::Line {
                         last_nonempty_indent: indent,
                     };
@@ -928,10 +1329,9 @@ pub fn parse_text(text: &str) -> ParserResult {
                 }
 
                 // Regular quote content - parse from `indent` (clean, no tabs in span)
-                match PattoLineParser::parse(Rule::statement_nestable, &linetext[indent..]) {
-                    Ok(mut parsed) => {
-                        let (nodes, props) =
-                            transform_statement(parsed.next().unwrap(), linetext, iline, indent);
+                match parse_nested_line(&linetext[indent..]) {
+                    Ok(parsed) => {
+                        let (nodes, props) = transform_statement(parsed, linetext, iline, indent);
                         let quotecontent = AstNode::quotecontent(
                             linetext,
                             iline,
@@ -981,6 +1381,10 @@ pub fn parse_text(text: &str) -> ParserResult {
             }
 
             BlockContext::Table { node, min_indent } => {
+                // Blank lines inside the block separate nothing and make no row
+                if content_len == 0 {
+                    continue;
+                }
                 let linestart = cmp::min(*min_indent, indent);
                 let columntexts: Vec<&str> = linetext[linestart..].split('\t').collect();
                 let mut span_start = linestart;
@@ -990,16 +1394,23 @@ pub fn parse_text(text: &str) -> ParserResult {
                     let span_end = span_start + column_text.len();
                     let span = Span(span_start, span_end);
 
-                    match PattoLineParser::parse(Rule::statement_nestable, column_text) {
-                        Ok(mut parsed) => {
-                            let inner = parsed.next().unwrap();
+                    match parse_nested_line(column_text) {
+                        Ok(parsed) => {
                             let (nodes, _) =
-                                transform_statement(inner, linetext, iline, span_start);
+                                transform_statement(parsed, linetext, iline, span_start);
                             let column = AstNode::tablecolumn(linetext, iline, Some(span));
                             column.add_contents(nodes);
                             columns.push(column);
                         }
-                        Err(_) => {
+                        Err(e) => {
+                            errors.push(ParserError::ParseError(
+                                Location {
+                                    input: Arc::from(linetext),
+                                    row: iline,
+                                    span: span.clone(),
+                                },
+                                e.into(),
+                            ));
                             let column = AstNode::tablecolumn(linetext, iline, Some(span.clone()));
                             column.add_content(AstNode::text(linetext, iline, Some(span)));
                             columns.push(column);
@@ -1021,6 +1432,95 @@ pub fn parse_text(text: &str) -> ParserResult {
     }
 }
 
+/// Turn bare URLs in the text of `ast` into [`AstNodeKind::Link`] nodes, in place.
+///
+/// Only `http`, `https` and `ftp` URLs are linked. Sentence punctuation right
+/// after a URL (`see https://example.com.`) and a closing parenthesis without
+/// a matching opening one are left out of the link.
+pub fn autolink_urls(ast: &AstNode) {
+    {
+        let mut contents = ast.value().contents.lock().unwrap();
+        if contents
+            .iter()
+            .any(|content| matches!(content.kind(), AstNodeKind::Text))
+        {
+            let linked = contents.drain(..).flat_map(split_bare_urls).collect();
+            *contents = linked;
+        }
+        for content in contents.iter() {
+            autolink_urls(content);
+        }
+    }
+    for child in ast.value().children.lock().unwrap().iter() {
+        autolink_urls(child);
+    }
+}
+
+/// `node` split into text and links if it is text holding bare URLs
+fn split_bare_urls(node: AstNode) -> Vec<AstNode> {
+    if !matches!(node.kind(), AstNodeKind::Text) || node.is_escaped() {
+        return vec![node];
+    }
+    let Location { row, input, span } = node.location();
+    let mut nodes = vec![];
+    let mut start = span.0;
+    for (url_start, url_end) in find_bare_urls(node.extract_str()) {
+        let (url_start, url_end) = (span.0 + url_start, span.0 + url_end);
+        if start < url_start {
+            nodes.push(AstNode::text(input, *row, Some(Span(start, url_start))));
+        }
+        nodes.push(AstNode::link(
+            input,
+            *row,
+            Some(Span(url_start, url_end)),
+            &input[url_start..url_end],
+            None,
+        ));
+        start = url_end;
+    }
+    if nodes.is_empty() {
+        return vec![node];
+    }
+    if start < span.1 {
+        nodes.push(AstNode::text(input, *row, Some(Span(start, span.1))));
+    }
+    nodes
+}
+
+/// Byte ranges of the bare URLs in `text`
+fn find_bare_urls(text: &str) -> Vec<(usize, usize)> {
+    use std::sync::OnceLock;
+    static URL_RE: OnceLock<regex::Regex> = OnceLock::new();
+    let re =
+        URL_RE.get_or_init(|| regex::Regex::new(r#"\b(?:https?|ftp)://[^\s<>"\[\]{}]+"#).unwrap());
+
+    re.find_iter(text)
+        .filter_map(|m| {
+            let mut url = m.as_str();
+            loop {
+                let trimmed = url.trim_end_matches(|c| ".,;:!?'\"。、，．！？".contains(c));
+                let trimmed = match trimmed.strip_suffix(')') {
+                    Some(rest) if rest.matches('(').count() <= rest.matches(')').count() => rest,
+                    _ => trimmed,
+                };
+                if trimmed.len() == url.len() {
+                    break;
+                }
+                url = trimmed;
+            }
+            // A scheme alone is not worth a link
+            (!url.ends_with("://")).then(|| (m.start(), m.start() + url.len()))
+        })
+        .collect()
+}
+
+/// Parse the content of a quote line or a table column, which must be
+/// consumed entirely so that nothing after an unparsable part is dropped
+fn parse_nested_line(text: &str) -> Result<Pair<'_, Rule>, pest::error::Error<Rule>> {
+    let mut parsed = PattoLineParser::parse(Rule::nested_line, text)?;
+    Ok(parsed.next().unwrap().into_inner().next().unwrap())
+}
+
 pub fn parse_text_with_persistent_line_tracking(
     text: &str,
     line_tracker: &mut LineTracker,
@@ -1046,7 +1546,7 @@ pub fn parse_text_with_persistent_line_tracking(
     result
 }
 
-fn apply_line_ids_to_ast(node: &AstNode, line_tracker: &LineTracker, _text: &str) {
+pub(crate) fn apply_line_ids_to_ast(node: &AstNode, line_tracker: &LineTracker, _text: &str) {
     if let AstNodeKind::Line { .. } = node.kind() {
         // Get the line number from the location and assign stable_id
         let row = node.0.location.row;
@@ -1189,6 +1689,7 @@ fn transform_img<'a>(
     match inner.as_rule() {
         Rule::img_alt_path_opts => {
             let mut inner2 = inner.into_inner();
+            // The text between the quotes, escapes kept as written
             let alt_img = inner2
                 .next()
                 .unwrap()
@@ -1196,28 +1697,23 @@ fn transform_img<'a>(
                 .next()
                 .unwrap()
                 .into_inner()
-                .next()
-                .unwrap()
                 .as_str();
             let img_path = inner2.next().unwrap().into_inner().next().unwrap().as_str();
-            // inner2.chunks(2).map(|(k,v)| {
-            //     match k.unwrap().as_str() {
-            //         "width" => {
-            //             match v.parse::<isize>() {
-            //                 Ok(v) =>
-            //     }
-            // }
-            Some(AstNode::image(
+            let (width, height) = transform_img_opts(inner2.next().unwrap());
+            Some(AstNode::image_with_size(
                 line,
                 row,
                 Some(span),
                 img_path,
                 Some(alt_img),
+                width,
+                height,
             ))
         }
         Rule::img_path_alt_opts => {
             let mut inner2 = inner.into_inner();
             let img_path = inner2.next().unwrap().into_inner().next().unwrap().as_str();
+            // The text between the quotes, escapes kept as written
             let alt_img = inner2
                 .next()
                 .unwrap()
@@ -1225,57 +1721,76 @@ fn transform_img<'a>(
                 .next()
                 .unwrap()
                 .into_inner()
-                .next()
-                .unwrap()
                 .as_str();
-            Some(AstNode::image(
+            let (width, height) = transform_img_opts(inner2.next().unwrap());
+            Some(AstNode::image_with_size(
                 line,
                 row,
                 Some(span),
                 img_path,
                 Some(alt_img),
+                width,
+                height,
             ))
         }
         Rule::img_unquoted_alt_path_opts => {
             let mut inner2 = inner.into_inner();
             let alt_img = inner2.next().unwrap().as_str(); // url_title
             let img_path = inner2.next().unwrap().as_str(); // local_file
-            Some(AstNode::image(
+            let (width, height) = transform_img_opts(inner2.next().unwrap());
+            Some(AstNode::image_with_size(
                 line,
                 row,
                 Some(span),
                 img_path,
                 Some(alt_img),
+                width,
+                height,
             ))
         }
         Rule::img_path_unquoted_alt_opts => {
             let mut inner2 = inner.into_inner();
             let img_path = inner2.next().unwrap().into_inner().next().unwrap().as_str();
-            let alt_img = inner2.next().unwrap().as_str(); // url_title
-            Some(AstNode::image(
+            let alt_img = inner2.next().unwrap().as_str(); // img_title
+            let (width, height) = transform_img_opts(inner2.next().unwrap());
+            Some(AstNode::image_with_size(
                 line,
                 row,
                 Some(span),
                 img_path,
                 Some(alt_img),
+                width,
+                height,
             ))
         }
         Rule::img_unquoted_alt_url_opts => {
             let mut inner2 = inner.into_inner();
             let alt_img = inner2.next().unwrap().as_str(); // url_title
             let img_path = inner2.next().unwrap().as_str(); // URL
-            Some(AstNode::image(
+            let (width, height) = transform_img_opts(inner2.next().unwrap());
+            Some(AstNode::image_with_size(
                 line,
                 row,
                 Some(span),
                 img_path,
                 Some(alt_img),
+                width,
+                height,
             ))
         }
         Rule::img_path_opts => {
             let mut inner2 = inner.into_inner();
             let img_path = inner2.next().unwrap().into_inner().next().unwrap().as_str();
-            Some(AstNode::image(line, row, Some(span), img_path, None))
+            let (width, height) = transform_img_opts(inner2.next().unwrap());
+            Some(AstNode::image_with_size(
+                line,
+                row,
+                Some(span),
+                img_path,
+                None,
+                width,
+                height,
+            ))
         }
         _ => {
             unreachable!();
@@ -1283,6 +1798,23 @@ fn transform_img<'a>(
     }
 }
 
+/// assuming pair is img_opts; options with an unparsable value are ignored
+fn transform_img_opts(pair: Pair<Rule>) -> (Option<u32>, Option<u32>) {
+    let mut width = None;
+    let mut height = None;
+    for opt in pair.into_inner() {
+        let mut kv = opt.into_inner();
+        let key = kv.next().unwrap().as_str();
+        let value = kv.next().unwrap().as_str().parse::<u32>().ok();
+        match key {
+            "width" => width = value.or(width),
+            "height" => height = value.or(height),
+            _ => {}
+        }
+    }
+    (width, height)
+}
+
 /// assuming pair is expr_wiki_link
 fn transform_wiki_link<'a>(
     pair: Pair<'a, Rule>,
@@ -1589,15 +2121,43 @@ pub fn parse_deadline_pub(value: &str) -> Deadline {
 
 /// Helper to parse deadline strings
 fn parse_deadline(value: &str) -> Deadline {
-    if let Ok(datetime) = chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M") {
+    if let Some(datetime) = parse_naive_datetime(value) {
         Deadline::DateTime(datetime)
     } else if let Ok(date) = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d") {
         Deadline::Date(date)
+    } else if let Ok(datetime) = chrono::DateTime::parse_from_str(value, "%Y-%m-%dT%H:%M%:z") {
+        Deadline::DateTimeTz(datetime)
+    } else if let Some(range) = parse_time_range(value) {
+        range
+    } else if let Some(recurrence) = Recurrence::parse(value) {
+        Deadline::Recurring(recurrence)
     } else {
         Deadline::Uninterpretable(value.to_string())
     }
 }
 
+/// Parse `2024-10-10T09:00` or `2024-10-10 09:00`
+fn parse_naive_datetime(value: &str) -> Option<chrono::NaiveDateTime> {
+    chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M")
+        .or_else(|_| chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M"))
+        .ok()
+}
+
+/// Parse `2024-10-10T09:00..10:30` into a same-day time range. Ranges need
+/// their own separator: `T09:00-10:30` is a time with a UTC offset. Only the
+/// form with a space, `2024-10-10 09:00-10:30`, may use a hyphen.
+fn parse_time_range(value: &str) -> Option<Deadline> {
+    let (start, end) = match value.split_once("..") {
+        Some(range) => range,
+        None if value.contains(' ') => value.rsplit_once('-')?,
+        None => return None,
+    };
+    let start = parse_naive_datetime(start)?;
+    let end = chrono::NaiveTime::parse_from_str(end, "%H:%M").ok()?;
+    let end = start.date().and_time(end);
+    (end >= start).then_some(Deadline::TimeRange { start, end })
+}
+
 fn transform_property(
     pair: Pair<Rule>,
     input: &str,
@@ -1761,9 +2321,25 @@ fn transform_property(
                         location,
                     })
                 }
-                _ => {
-                    log::warn!("Unknown property: {}", property_name);
-                    None
+                name => {
+                    // Any other property is a generic tag: {@tag project=patto priority=high}
+                    let args = inner
+                        .filter_map(|arg| match arg.as_rule() {
+                            Rule::property_keyword_pair => {
+                                let mut pair_inner = arg.into_inner();
+                                let key = pair_inner.next().unwrap().as_str().to_string();
+                                let value = pair_inner.next().unwrap().as_str().to_string();
+                                Some((key, Some(value)))
+                            }
+                            Rule::property_positional_arg => Some((arg.as_str().to_string(), None)),
+                            _ => None,
+                        })
+                        .collect();
+                    Some(Property::Tag {
+                        name: name.to_string(),
+                        args,
+                        location,
+                    })
                 }
             }
         }
@@ -1851,6 +2427,19 @@ fn transform_statement<'a>(
                 node.add_contents(inner_nodes);
                 nodes.push(node);
             }
+            Rule::expr_definition => {
+                let s = Some(Into::<Span>::into(inner.as_span()) + indent);
+                let mut inner2 = inner.into_inner();
+                let term = inner2.next().unwrap().as_str().trim();
+                let node = AstNode::definition_item(line, row, s, term);
+                if let Some(body) = inner2.next() {
+                    // properties written inside the definition belong to the line
+                    let (inner_nodes, inner_props) = transform_statement(body, line, row, indent);
+                    node.add_contents(inner_nodes);
+                    props.extend(inner_props);
+                }
+                nodes.push(node);
+            }
             Rule::expr_wiki_link => {
                 if let Some(node) = transform_wiki_link(inner, line, row, indent) {
                     nodes.push(node);
@@ -1927,6 +2516,18 @@ fn transform_statement<'a>(
                     Some(Into::<Span>::into(inner.as_span()) + indent),
                 ));
             }
+            Rule::expr_escaped => {
+                // The backslash is dropped; the character is plain text
+                let Span(start, end) = Into::<Span>::into(inner.as_span()) + indent;
+                nodes.push(AstNode::text(line, row, Some(Span(start + 1, end))));
+            }
+            Rule::expr_comment | Rule::expr_line_comment => {
+                nodes.push(AstNode::comment(
+                    line,
+                    row,
+                    Some(Into::<Span>::into(inner.as_span()) + indent),
+                ));
+            }
             Rule::expr_hr => {
                 nodes.push(AstNode::horizontal_line(
                     line,
@@ -2043,6 +2644,9 @@ mod tests {
                 Property::Anchor { name, .. } => {
                     assert_eq!(name, "anchor1");
                 }
+                Property::Tag { name, .. } => {
+                    panic!("unexpected tag: {}", name);
+                }
             }
         }
     }
@@ -2151,6 +2755,53 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_parse_generic_tag() -> Result<(), Box<dyn std::error::Error>> {
+        let input = "Plan release {@tag project=patto urgent priority=high} #plan";
+        let mut parsed = PattoLineParser::parse(Rule::statement, input)?;
+        let (nodes, props) = transform_statement(parsed.next().unwrap(), input, 0, 0);
+
+        assert_eq!(nodes.len(), 1, "Should have one text node");
+        assert_eq!(props.len(), 2, "Should have a tag and an anchor");
+        let Property::Tag {
+            ref name,
+            ref args,
+            ref location,
+        } = props[0]
+        else {
+            panic!("Expected tag property");
+        };
+        assert_eq!(name, "tag");
+        assert_eq!(
+            args,
+            &vec![
+                ("project".to_string(), Some("patto".to_string())),
+                ("urgent".to_string(), None),
+                ("priority".to_string(), Some("high".to_string())),
+            ]
+        );
+        assert_eq!(
+            &input[location.span.0..location.span.1],
+            "{@tag project=patto urgent priority=high}"
+        );
+        assert_eq!(
+            props[0].tag_source().as_deref(),
+            Some("{@tag project=patto urgent priority=high}")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_unknown_property_is_tag() -> Result<(), Box<dyn std::error::Error>> {
+        let input = "Read later {@status reading}";
+        let mut parsed = PattoLineParser::parse(Rule::statement, input)?;
+        let (_nodes, props) = transform_statement(parsed.next().unwrap(), input, 0, 0);
+
+        assert_eq!(props.len(), 1);
+        assert_eq!(props[0].tag_source().as_deref(), Some("{@status reading}"));
+        Ok(())
+    }
+
     #[test]
     fn test_parse_math() {
         let input = "[@math  ]";
@@ -2219,6 +2870,126 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_comments() {
+        let input = "before %% aside %% after #anchor";
+        let mut parsed = PattoLineParser::parse(Rule::statement, input).unwrap();
+        let (nodes, props) = transform_statement(parsed.next().unwrap(), input, 0, 0);
+        assert_eq!(props.len(), 1);
+        let kinds: Vec<_> = nodes
+            .iter()
+            .map(|n| (matches!(n.kind(), AstNodeKind::Comment), n.extract_str()))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![(false, "before "), (true, "%% aside %%"), (false, " after")]
+        );
+
+        // Properties inside a comment line are commented out too
+        let result = parse_text("// later {@task status=todo due=2024-01-01}\n");
+        assert!(result.parse_errors.is_empty());
+        let line = result.ast.value().children.lock().unwrap()[0].clone();
+        assert!(line.is_comment_line());
+        assert!(matches!(line.kind(), AstNodeKind::Line { properties } if properties.is_empty()));
+
+        // An unclosed marker is plain text
+        let result = parse_text("50%% done\n");
+        let line = result.ast.value().children.lock().unwrap()[0].clone();
+        assert!(!line.is_comment_line());
+        let contents = line.value().contents.lock().unwrap();
+        assert_eq!(contents.len(), 1);
+        assert!(matches!(contents[0].kind(), AstNodeKind::Text));
+    }
+
+    #[test]
+    fn test_parse_escaped_characters() {
+        let input = "see \\[not a link\\] or \\#tag, a\\*b and C:\\\\dir";
+        let mut parsed = PattoLineParser::parse(Rule::statement, input).unwrap();
+        let (nodes, props) = transform_statement(parsed.next().unwrap(), input, 0, 0);
+        assert!(props.is_empty());
+        assert!(nodes.iter().all(|n| matches!(n.kind(), AstNodeKind::Text)));
+        let text: String = nodes.iter().map(|n| n.extract_str()).collect();
+        assert_eq!(text, "see [not a link] or #tag, a*b and C:\\dir");
+
+        let escaped: Vec<_> = nodes
+            .iter()
+            .filter(|n| n.is_escaped())
+            .map(|n| (n.extract_str(), n.location().span.clone()))
+            .collect();
+        assert_eq!(
+            escaped,
+            vec![
+                ("[", Span(5, 6)),
+                ("]", Span(17, 18)),
+                ("#", Span(23, 24)),
+                ("*", Span(31, 32)),
+                ("\\", Span(41, 42)),
+            ]
+        );
+
+        // A lone backslash stays as it is
+        let input = "C:\\path";
+        let mut parsed = PattoLineParser::parse(Rule::statement, input).unwrap();
+        let (nodes, _) = transform_statement(parsed.next().unwrap(), input, 0, 0);
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes[0].extract_str(), "C:\\path");
+    }
+
+    #[test]
+    fn test_parse_table_rows() {
+        let input = "[@table]\n\tname\t[note]\n\n\tbob\t[note#top]\n";
+        let result = parse_text(input);
+        assert!(result.parse_errors.is_empty(), "{:?}", result.parse_errors);
+
+        let line = result.ast.value().children.lock().unwrap()[0].clone();
+        let table = line.value().contents.lock().unwrap()[0].clone();
+        assert!(matches!(table.kind(), AstNodeKind::Table { .. }));
+        // The empty line inside the block makes no row
+        let rows = table.value().children.lock().unwrap().clone();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].location().row, 1);
+        assert_eq!(rows[1].location().row, 3);
+
+        let columns = rows[1].value().contents.lock().unwrap().clone();
+        assert_eq!(columns.len(), 2);
+        assert!(matches!(columns[0].kind(), AstNodeKind::TableColumn));
+        assert_eq!(columns[0].location().span, Span(1, 4));
+        assert_eq!(columns[1].location().span, Span(5, 15));
+        assert_eq!(columns[1].extract_str(), "[note#top]");
+        let cell = columns[1].value().contents.lock().unwrap()[0].clone();
+        assert!(matches!(cell.kind(), AstNodeKind::WikiLink { .. }));
+        assert_eq!(cell.location().span, Span(5, 15));
+    }
+
+    #[test]
+    fn test_parse_error_in_nested_line_keeps_text() {
+        let input = "[@table]\n\tok\tx [unclosed\n[@quote]\n\ta ] b\n";
+        let result = parse_text(input);
+
+        let rows: Vec<_> = result
+            .parse_errors
+            .iter()
+            .map(|e| {
+                let location = e.location();
+                (location.row, location.span.clone())
+            })
+            .collect();
+        assert_eq!(rows, vec![(1, Span(4, 15)), (3, Span(1, 6))]);
+
+        let lines = result.ast.value().children.lock().unwrap().clone();
+        let table = lines[0].value().contents.lock().unwrap()[0].clone();
+        let row = table.value().children.lock().unwrap()[0].clone();
+        let column = row.value().contents.lock().unwrap()[1].clone();
+        let text = column.value().contents.lock().unwrap()[0].clone();
+        assert!(matches!(text.kind(), AstNodeKind::Text));
+        assert_eq!(text.extract_str(), "x [unclosed");
+
+        let quote = lines[1].value().contents.lock().unwrap()[0].clone();
+        let content = quote.value().children.lock().unwrap()[0].clone();
+        let text = content.value().contents.lock().unwrap()[0].clone();
+        assert_eq!(text.extract_str(), "a ] b");
+    }
+
     #[test]
     fn test_parse_math_inline() -> Result<(), Box<dyn std::error::Error>> {
         let input = "[$ math = a * b * c$]";
@@ -2480,7 +3251,7 @@ mod tests {
                 Ok(mut parsed) => {
                     let node = transform_img(parsed.next().unwrap(), input, 0, 0)
                         .ok_or("transform_img failed")?;
-                    if let AstNodeKind::Image { src, alt } = node.kind() {
+                    if let AstNodeKind::Image { src, alt, .. } = node.kind() {
                         assert_eq!(src, exp_src, "src mismatch for: {input}");
                         assert_eq!(
                             *alt,
@@ -2514,6 +3285,84 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_img_size_options() -> Result<(), Box<dyn std::error::Error>> {
+        for (input, exp_src, exp_alt, exp_width, exp_height) in [
+            (
+                r#"[@img ./cat.png "alt" width=300]"#,
+                "./cat.png",
+                Some("alt"),
+                Some(300),
+                None,
+            ),
+            (
+                r#"[@img "alt" https://example.com/cat.png width=300 height=200]"#,
+                "https://example.com/cat.png",
+                Some("alt"),
+                Some(300),
+                Some(200),
+            ),
+            (
+                "[@img ./cat.png height=120 width=80]",
+                "./cat.png",
+                None,
+                Some(80),
+                Some(120),
+            ),
+            (
+                "[@img https://example.com/cat.png A nice cat width=300]",
+                "https://example.com/cat.png",
+                Some("A nice cat"),
+                Some(300),
+                None,
+            ),
+            (
+                "[@img A nice cat ./cat.png width=300]",
+                "./cat.png",
+                Some("A nice cat"),
+                Some(300),
+                None,
+            ),
+            // without options
+            ("[@img ./cat.png]", "./cat.png", None, None, None),
+            (
+                "[@img ./cat.png A cat]",
+                "./cat.png",
+                Some("A cat"),
+                None,
+                None,
+            ),
+            // an unknown key stays part of the unquoted alt
+            (
+                "[@img ./cat.png size=3]",
+                "./cat.png",
+                Some("size=3"),
+                None,
+                None,
+            ),
+        ] {
+            let mut parsed = PattoLineParser::parse(Rule::expr_img, input)
+                .map_err(|e| format!("Parse failed for '{input}': {e}"))?;
+            let node =
+                transform_img(parsed.next().unwrap(), input, 0, 0).ok_or("transform_img failed")?;
+            if let AstNodeKind::Image {
+                src,
+                alt,
+                width,
+                height,
+            } = node.kind()
+            {
+                assert_eq!(src, exp_src, "src mismatch for: {input}");
+                assert_eq!(alt.as_deref(), exp_alt, "alt mismatch for: {input}");
+                assert_eq!(*width, exp_width, "width mismatch for: {input}");
+                assert_eq!(*height, exp_height, "height mismatch for: {input}");
+            } else {
+                panic!("Expected Image, got {:?} for: {input}", node.kind());
+            }
+        }
+        Ok(())
+    }
+
     #[test]
     fn test_parse_unknown_command() {
         let input = "[@unknown rust]";
@@ -2583,6 +3432,51 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_wiki_link_in_decoration() -> Result<(), Box<dyn std::error::Error>> {
+        let input = "[* see [other note]] and [y]";
+        let mut parsed = PattoLineParser::parse(Rule::statement, input)?;
+        let (nodes, _props) = transform_statement(parsed.next().unwrap(), input, 0, 0);
+        assert_eq!(nodes.len(), 3);
+        let AstNodeKind::Decoration { fontsize, .. } = nodes[0].kind() else {
+            panic!("decoration is not parsed: {:?}", nodes[0].kind());
+        };
+        assert_eq!(*fontsize, 1, "bold must be set");
+        assert_eq!(nodes[0].extract_str(), "[* see [other note]]");
+        let contents = nodes[0].value().contents.lock().unwrap();
+        assert_eq!(contents.len(), 2);
+        assert!(matches!(
+            contents[1].kind(),
+            AstNodeKind::WikiLink { link, anchor: None } if link == "other note"
+        ));
+        assert!(matches!(
+            nodes[2].kind(),
+            AstNodeKind::WikiLink { link, .. } if link == "y"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_wiki_link_in_nested_decoration() -> Result<(), Box<dyn std::error::Error>> {
+        let input = "[* a [/ b [c#sec]] d]";
+        let mut parsed = PattoLineParser::parse(Rule::statement, input)?;
+        let (nodes, _props) = transform_statement(parsed.next().unwrap(), input, 0, 0);
+        assert_eq!(nodes.len(), 1);
+        let outer = nodes[0].value().contents.lock().unwrap();
+        assert_eq!(outer.len(), 3);
+        assert!(matches!(
+            outer[1].kind(),
+            AstNodeKind::Decoration { italic: true, .. }
+        ));
+        assert_eq!(outer[2].extract_str(), " d");
+        let inner = outer[1].value().contents.lock().unwrap();
+        assert!(matches!(
+            inner[1].kind(),
+            AstNodeKind::WikiLink { link, anchor: Some(anchor) } if link == "c" && anchor == "sec"
+        ));
+        Ok(())
+    }
+
     #[test]
     fn test_parse_wiki_link_anchored() {
         let input = "[test wiki_page#anchored]";
@@ -2665,7 +3559,7 @@ mod tests {
                 Ok(mut parsed) => {
                     let node = transform_img(parsed.next().unwrap(), input, 0, 0)
                         .ok_or("transform_img failed")?;
-                    if let AstNodeKind::Image { src, alt } = &node.kind() {
+                    if let AstNodeKind::Image { src, alt, .. } = &node.kind() {
                         assert_eq!(src, g_path);
                         assert_eq!(*alt, g_alt);
                     } else {
@@ -2828,6 +3722,60 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_parse_definition_item() -> Result<(), Box<dyn std::error::Error>> {
+        let input = "Deadline:: see [release plan] #deadline";
+        let mut parsed = PattoLineParser::parse(Rule::statement, input)?;
+        let (nodes, props) = transform_statement(parsed.next().unwrap(), input, 0, 0);
+        assert_eq!(nodes.len(), 1);
+        let AstNodeKind::DefinitionItem { term } = nodes[0].kind() else {
+            panic!("definition item is not parsed: {:?}", nodes[0].kind());
+        };
+        assert_eq!(term, "Deadline");
+        let contents = nodes[0].value().contents.lock().unwrap();
+        assert_eq!(contents.len(), 2);
+        assert_eq!(contents[0].extract_str(), "see ");
+        assert!(matches!(
+            contents[1].kind(),
+            AstNodeKind::WikiLink { link, .. } if link == "release plan"
+        ));
+        assert!(matches!(&props[..], [Property::Anchor { name, .. }] if name == "deadline"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_definition_item_empty_and_indented() {
+        let result = parse_text("glossary\n\tterm with spaces::\n");
+        assert!(result.parse_errors.is_empty());
+        let root_children = result.ast.value().children.lock().unwrap();
+        let children = root_children[0].value().children.lock().unwrap();
+        let contents = children[0].value().contents.lock().unwrap();
+        let AstNodeKind::DefinitionItem { term } = contents[0].kind() else {
+            panic!("definition item is not parsed: {:?}", contents[0].kind());
+        };
+        assert_eq!(term, "term with spaces");
+        assert!(contents[0].value().contents.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_definition_marker_not_at_line_prefix() -> Result<(), Box<dyn std::error::Error>> {
+        for input in [
+            "task [due:: 2024-12-31]",
+            "std::vec::Vec is a vector",
+            "[* bold]:: not a term",
+        ] {
+            let mut parsed = PattoLineParser::parse(Rule::statement, input)?;
+            let (nodes, _props) = transform_statement(parsed.next().unwrap(), input, 0, 0);
+            assert!(
+                nodes
+                    .iter()
+                    .all(|n| !matches!(n.kind(), AstNodeKind::DefinitionItem { .. })),
+                "{input:?} must not be a definition item"
+            );
+        }
+        Ok(())
+    }
+
     #[test]
     fn test_parse_horizontal_line() -> Result<(), Box<dyn std::error::Error>> {
         let input = "-----";
@@ -2873,6 +3821,49 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_task_location_covers_expression() -> Result<(), Box<dyn std::error::Error>> {
+        let input = "Write {@task status=todo due=2024-12-31} !2024-10-10 -2024-10-11T20:00";
+        let mut parsed = PattoLineParser::parse(Rule::statement, input)?;
+        let (_nodes, props) = transform_statement(parsed.next().unwrap(), input, 0, 0);
+
+        let sources: Vec<&str> = props
+            .iter()
+            .map(|prop| match prop {
+                Property::Task { location, .. } => &input[location.span.0..location.span.1],
+                _ => panic!("Expected task property"),
+            })
+            .collect();
+        assert_eq!(
+            sources,
+            vec![
+                "{@task status=todo due=2024-12-31}",
+                "!2024-10-10",
+                "-2024-10-11T20:00"
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_task_location_in_indented_line() {
+        let input = "parent\n\tchild {@task status=doing}";
+        let result = parse_text(input);
+        let children = result.ast.value().children.lock().unwrap();
+        let child = children[0].value().children.lock().unwrap()[0].clone();
+        let AstNodeKind::Line { properties } = child.kind() else {
+            panic!("Expected line");
+        };
+        let Property::Task { location, .. } = &properties[0] else {
+            panic!("Expected task property");
+        };
+        assert_eq!(location.row, 1);
+        assert_eq!(
+            &location.input[location.span.0..location.span.1],
+            "{@task status=doing}"
+        );
+    }
+
     #[test]
     #[allow(deprecated)]
     fn test_deadline_sorting_order() -> Result<(), Box<dyn std::error::Error>> {
@@ -2904,6 +3895,226 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_parse_deadline_with_offset() {
+        let due = parse_deadline("2024-10-10T09:00+09:00");
+        let expected = chrono::DateTime::parse_from_rfc3339("2024-10-10T09:00:00+09:00").unwrap();
+        assert_eq!(due, Deadline::DateTimeTz(expected));
+        assert_eq!(due.to_string(), "2024-10-10T09:00+09:00");
+        assert_eq!(due.to_datetime(), Some(expected));
+
+        // A negative offset is not mistaken for the end of a time range
+        let due = parse_deadline("2024-10-10T03:00-05:00");
+        let expected = chrono::DateTime::parse_from_rfc3339("2024-10-10T03:00:00-05:00").unwrap();
+        assert_eq!(due, Deadline::DateTimeTz(expected));
+        assert_eq!(due.to_string(), "2024-10-10T03:00-05:00");
+
+        // Naive date-times are still parsed as such and read as local time
+        let naive = parse_deadline("2024-10-10T09:00");
+        assert!(matches!(naive, Deadline::DateTime(_)));
+        assert_eq!(
+            naive.to_datetime().map(|dt| local_naive(&dt)),
+            chrono::NaiveDate::from_ymd_opt(2024, 10, 10)
+                .unwrap()
+                .and_hms_opt(9, 0, 0)
+        );
+    }
+
+    #[test]
+    fn test_deadline_ordering_across_offsets() {
+        let tokyo = parse_deadline("2024-10-10T09:00+09:00");
+        let utc = parse_deadline("2024-10-10T09:00+00:00");
+        // 09:00 in Tokyo is midnight UTC, so it comes first
+        assert!(tokyo < utc);
+
+        let local = Deadline::DateTime(local_naive(
+            &chrono::DateTime::parse_from_rfc3339("2024-10-10T02:00:00+00:00").unwrap(),
+        ));
+        let mut values = vec![utc.clone(), local.clone(), tokyo.clone()];
+        values.sort();
+        assert_eq!(values, vec![tokyo, local, utc]);
+    }
+
+    #[test]
+    fn test_parse_task_due_with_offset() {
+        let result = parse_text("release {@task status=todo due=2024-10-10T09:00+09:00}");
+        let line = result.ast.value().children.lock().unwrap()[0].clone();
+        let AstNodeKind::Line { properties } = line.kind() else {
+            panic!("Expected line");
+        };
+        assert!(matches!(
+            properties.as_slice(),
+            [Property::Task {
+                due: Deadline::DateTimeTz(_),
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn test_deadline_status() {
+        let now = chrono::NaiveDate::from_ymd_opt(2024, 10, 10)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        assert_eq!(
+            parse_deadline("2024-10-10").status(now),
+            DeadlineStatus::Today
+        );
+        assert_eq!(
+            parse_deadline("2024-10-09").status(now),
+            DeadlineStatus::Overdue
+        );
+        assert_eq!(
+            parse_deadline("2024-10-11").status(now),
+            DeadlineStatus::Upcoming
+        );
+        assert_eq!(
+            parse_deadline("2024-10-10T18:00").status(now),
+            DeadlineStatus::Today
+        );
+        assert_eq!(
+            parse_deadline("2024-10-10T09:00").status(now),
+            DeadlineStatus::Overdue
+        );
+        assert_eq!(
+            parse_deadline("2024-10-10T11:00..13:00").status(now),
+            DeadlineStatus::Today
+        );
+        assert_eq!(parse_deadline("daily").status(now), DeadlineStatus::Today);
+        assert_eq!(parse_deadline("someday").status(now), DeadlineStatus::None);
+    }
+
+    #[test]
+    fn test_parse_deadline_time_range_and_recurrence() {
+        let start = chrono::NaiveDate::from_ymd_opt(2024, 10, 10)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap();
+        let end = start.date().and_hms_opt(10, 30, 0).unwrap();
+        let range = parse_deadline("2024-10-10T09:00..10:30");
+        assert_eq!(range, Deadline::TimeRange { start, end });
+        assert_eq!(range.to_string(), "2024-10-10T09:00..10:30");
+
+        // The form with a space may separate the times with a hyphen
+        assert_eq!(
+            parse_deadline("2024-10-10 09:00-10:30"),
+            Deadline::TimeRange { start, end }
+        );
+        assert_eq!(
+            parse_deadline("2024-10-10 09:00..10:30"),
+            Deadline::TimeRange { start, end }
+        );
+        assert_eq!(
+            parse_deadline("2024-10-10 09:00"),
+            Deadline::DateTime(start)
+        );
+
+        // A range may not end before it starts
+        assert!(matches!(
+            parse_deadline("2024-10-10T09:00..08:00"),
+            Deadline::Uninterpretable(_)
+        ));
+        assert!(matches!(
+            parse_deadline("2024-10-10T09:00..08:0"),
+            Deadline::Uninterpretable(_)
+        ));
+
+        let weekly = parse_deadline("weekly:mon/thu");
+        assert_eq!(
+            weekly,
+            Deadline::Recurring(Recurrence::Weekly(vec![
+                chrono::Weekday::Mon,
+                chrono::Weekday::Thu
+            ]))
+        );
+        assert_eq!(weekly.to_string(), "weekly:mon/thu");
+        assert_eq!(
+            parse_deadline("daily"),
+            Deadline::Recurring(Recurrence::Daily)
+        );
+        assert_eq!(
+            parse_deadline("monthly:15"),
+            Deadline::Recurring(Recurrence::Monthly(15))
+        );
+        assert!(matches!(
+            parse_deadline("weekly:someday"),
+            Deadline::Uninterpretable(_)
+        ));
+        assert!(matches!(
+            parse_deadline("monthly:32"),
+            Deadline::Uninterpretable(_)
+        ));
+    }
+
+    #[test]
+    fn test_recurrence_next_occurrence() {
+        // 2024-10-10 is a Thursday
+        let from = chrono::NaiveDate::from_ymd_opt(2024, 10, 10).unwrap();
+        let date = |m, d| chrono::NaiveDate::from_ymd_opt(2024, m, d).unwrap();
+
+        assert_eq!(Recurrence::Daily.next_occurrence(from), from);
+        assert_eq!(
+            Recurrence::Weekly(vec![chrono::Weekday::Thu]).next_occurrence(from),
+            from
+        );
+        assert_eq!(
+            Recurrence::Weekly(vec![chrono::Weekday::Mon, chrono::Weekday::Sat])
+                .next_occurrence(from),
+            date(10, 12)
+        );
+        assert_eq!(Recurrence::Monthly(15).next_occurrence(from), date(10, 15));
+        assert_eq!(Recurrence::Monthly(5).next_occurrence(from), date(11, 5));
+        // November has no 31st
+        assert_eq!(
+            Recurrence::Monthly(31).next_occurrence(date(11, 1)),
+            date(12, 31)
+        );
+    }
+
+    #[test]
+    fn test_recurring_deadline_sorts_by_next_occurrence() {
+        let today = chrono::Local::now().date_naive();
+        let daily = Deadline::Recurring(Recurrence::Daily);
+        let tomorrow = Deadline::Date(today.succ_opt().unwrap());
+        let yesterday = Deadline::Date(today.pred_opt().unwrap());
+        let today_date = Deadline::Date(today);
+
+        let mut values = vec![tomorrow.clone(), daily.clone(), yesterday.clone()];
+        values.sort();
+        assert_eq!(values, vec![yesterday, daily.clone(), tomorrow]);
+
+        // On the same day, a plain date comes before the recurrence
+        assert!(today_date < daily);
+    }
+
+    #[test]
+    fn test_parse_task_with_time_range() -> Result<(), Box<dyn std::error::Error>> {
+        let input = "standup {@task status=todo due=weekly:mon scheduled=2024-10-10T09:00..09:15}";
+        let mut parsed = PattoLineParser::parse(Rule::statement, input)?;
+        let (_nodes, props) = transform_statement(parsed.next().unwrap(), input, 0, 0);
+
+        let Property::Task { due, scheduled, .. } = &props[0] else {
+            panic!("Expected task property");
+        };
+        assert_eq!(
+            due,
+            &Deadline::Recurring(Recurrence::Weekly(vec![chrono::Weekday::Mon]))
+        );
+        assert!(matches!(scheduled, Some(Deadline::TimeRange { .. })));
+
+        let input = "standup {@task due=2024-10-10 09:00-10:30 status=doing} #daily";
+        let mut parsed = PattoLineParser::parse(Rule::statement, input)?;
+        let (_nodes, props) = transform_statement(parsed.next().unwrap(), input, 0, 0);
+        let Property::Task { due, status, .. } = &props[0] else {
+            panic!("Expected task property");
+        };
+        assert_eq!(due.to_string(), "2024-10-10T09:00..10:30");
+        assert_eq!(*status, TaskStatus::Doing);
+        assert!(matches!(&props[1], Property::Anchor { name, .. } if name == "daily"));
+        Ok(())
+    }
+
     // #[test]
     // fn test_parse_error() {
     //     let err = PattoLineParser::parse(Rule::expr_command, "[@  ] #anchor").unwrap_err();
@@ -2957,4 +4168,19 @@ mod tab_indentation_tests {
             "Should have at least one top-level line"
         );
     }
+
+    #[test]
+    fn test_heading_level() {
+        let result = parse_text("[*** top]\n[** big]\n[* small]\n[/ italic]\n[* mixed] text\n");
+        let levels: Vec<Option<u8>> = result
+            .ast
+            .value()
+            .children
+            .lock()
+            .unwrap()
+            .iter()
+            .map(heading_level)
+            .collect();
+        assert_eq!(levels, vec![Some(1), Some(1), Some(2), None, None]);
+    }
 }
@@ -1,30 +1,148 @@
+use std::collections::{HashMap, HashSet};
 use std::io;
 use std::io::Write;
 
-use crate::parser::{AstNode, AstNodeKind};
-use crate::parser::{Property, TaskStatus};
-use crate::utils::{get_gyazo_img_src, get_twitter_embed, get_youtube_id};
+use crate::parser::{heading_level, AstNode, AstNodeKind};
+use crate::parser::{Deadline, Property, TaskStatus};
+use crate::utils::{get_gyazo_img_src, get_twitter_embed, get_youtube_id, slugify, unique_slug};
 use html_escape::encode_text;
 
+/// Renders a parsed note into an output format.
+///
+/// The trait is object safe, so the output format can be chosen at runtime
+/// through a `Box<dyn Renderer>`.
 pub trait Renderer {
+    /// Render a whole document, including any document-level header
     fn format(&self, ast: &AstNode, output: &mut dyn Write) -> io::Result<()>;
+
+    /// Render `node` with its contents and children, which need not be the
+    /// root of a document: a single line renders as a fragment at the top
+    /// level, without the document-level header of [`Renderer::format`]
+    fn format_node(&self, node: &AstNode, output: &mut dyn Write) -> io::Result<()>;
+
+    /// Render a whole document as [`Renderer::format`] does, into a string
+    fn render_to_string(&self, ast: &AstNode) -> String {
+        let mut output = Vec::new();
+        self.format(ast, &mut output)
+            .expect("writing to a Vec<u8> cannot fail");
+        String::from_utf8(output).expect("renderers emit UTF-8")
+    }
 }
 
 #[derive(Debug, Default)]
 pub struct HtmlRendererOptions {
-    // maybe deleted in the future
+    /// Highlight code blocks with syntect (requires the `html-highlight` feature)
+    pub highlight_code: bool,
+    /// Prepend a table of contents built from heading lines
+    pub toc: bool,
+    /// Pre-render math to MathML (requires the `html-mathml` feature)
+    pub mathml: bool,
+    /// Emit `data-line-id` attributes from the stable ids assigned by
+    /// [`crate::parser::parse_text_with_persistent_line_tracking`]
+    pub line_ids: bool,
+    /// Join runs of adjacent top-level text lines into one `<p>` each,
+    /// separated by spaces; blank lines, nested lines and lines with block
+    /// commands, properties or headings are left as they are
+    pub paragraphs: bool,
+    /// Tells whether the note of a WikiLink exists and where its page is.
+    /// When set, links render as `class="wikilink"` with that href, or as
+    /// `class="wikilink broken"` without one
+    pub link_resolver: Option<LinkResolver>,
+}
+
+/// What a [`LinkResolver`] knows about the target note of a WikiLink
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkStatus {
+    /// The note exists; `href` points at its page, without the anchor
+    Exists { href: String },
+    /// No note by that name
+    Broken,
+}
+
+/// Resolves WikiLink targets (e.g. `projects/plan`) for [`HtmlRenderer`]
+pub struct LinkResolver(Box<dyn Fn(&str) -> LinkStatus + Send + Sync>);
+
+impl LinkResolver {
+    pub fn new(resolve: impl Fn(&str) -> LinkStatus + Send + Sync + 'static) -> Self {
+        Self(Box::new(resolve))
+    }
+
+    pub fn resolve(&self, link: &str) -> LinkStatus {
+        (self.0)(link)
+    }
+}
+
+impl std::fmt::Debug for LinkResolver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("LinkResolver")
+    }
+}
+
+impl HtmlRendererOptions {
+    /// Enable or disable the table of contents
+    pub fn with_toc(mut self, enabled: bool) -> Self {
+        self.toc = enabled;
+        self
+    }
+
+    /// Enable or disable `data-line-id` attributes on line elements
+    pub fn with_line_ids(mut self, enabled: bool) -> Self {
+        self.line_ids = enabled;
+        self
+    }
+
+    /// Enable or disable joining adjacent text lines into paragraphs
+    pub fn with_paragraphs(mut self, enabled: bool) -> Self {
+        self.paragraphs = enabled;
+        self
+    }
+
+    /// Link WikiLinks to the pages `resolver` finds instead of `.pn` files
+    pub fn with_link_resolver(
+        mut self,
+        resolver: impl Fn(&str) -> LinkStatus + Send + Sync + 'static,
+    ) -> Self {
+        self.link_resolver = Some(LinkResolver::new(resolver));
+        self
+    }
 }
 
 pub struct HtmlRenderer {
-    #[allow(dead_code)]
     options: HtmlRendererOptions,
 }
 
 impl Renderer for HtmlRenderer {
     fn format(&self, ast: &AstNode, output: &mut dyn Write) -> io::Result<()> {
-        self._format_impl(ast, output)?;
+        let mut heading_ids = HashMap::new();
+        if self.options.toc {
+            let headings = collect_headings(ast);
+            if !headings.is_empty() {
+                write!(output, "<nav class=\"patto-toc\"><ul>")?;
+                for heading in &headings {
+                    write!(
+                        output,
+                        "<li class=\"patto-toc-level-{}\"><a href=\"#{}\">{}</a></li>",
+                        heading.level,
+                        heading.id,
+                        encode_text(&heading.text)
+                    )?;
+                }
+                write!(output, "</ul></nav>")?;
+            }
+            // Headings with an explicit anchor already carry that id
+            heading_ids = headings
+                .into_iter()
+                .filter(|heading| !heading.has_anchor)
+                .map(|heading| (heading.row, heading.id))
+                .collect();
+        }
+        self._format_impl(ast, output, &heading_ids)?;
         Ok(())
     }
+
+    fn format_node(&self, node: &AstNode, output: &mut dyn Write) -> io::Result<()> {
+        self._format_impl(node, output, &HashMap::new())
+    }
 }
 
 impl HtmlRenderer {
@@ -33,6 +151,9 @@ impl HtmlRenderer {
     }
 
     fn get_stable_id_attr(&self, ast: &AstNode) -> String {
+        if !self.options.line_ids {
+            return String::new();
+        }
         if let Some(stable_id) = *ast.value().stable_id.lock().unwrap() {
             format!(" data-line-id=\"{}\"", stable_id)
         } else {
@@ -40,16 +161,84 @@ impl HtmlRenderer {
         }
     }
 
-    fn _format_impl(&self, ast: &AstNode, output: &mut dyn Write) -> io::Result<()> {
+    /// Highlight the lines of a code block, if enabled and `lang` is known to syntect
+    #[cfg(feature = "html-highlight")]
+    fn highlight_code_block(&self, lang: &str, ast: &AstNode) -> Option<String> {
+        if !self.options.highlight_code {
+            return None;
+        }
+        let children = ast.value().children.lock().unwrap();
+        let lines: Vec<&str> = children.iter().map(|child| child.extract_str()).collect();
+        crate::syntax_highlight::highlight_code_html(lang, &lines, None)
+    }
+
+    #[cfg(not(feature = "html-highlight"))]
+    fn highlight_code_block(&self, _lang: &str, _ast: &AstNode) -> Option<String> {
+        None
+    }
+
+    #[cfg(feature = "html-mathml")]
+    fn render_mathml(&self, latex: &str, inline: bool) -> Option<String> {
+        if !self.options.mathml {
+            return None;
+        }
+        let display = if inline {
+            latex2mathml::DisplayStyle::Inline
+        } else {
+            latex2mathml::DisplayStyle::Block
+        };
+        latex2mathml::latex_to_mathml(latex, display).ok()
+    }
+
+    #[cfg(not(feature = "html-mathml"))]
+    fn render_mathml(&self, _latex: &str, _inline: bool) -> Option<String> {
+        None
+    }
+
+    fn _format_impl(
+        &self,
+        ast: &AstNode,
+        output: &mut dyn Write,
+        heading_ids: &HashMap<usize, String>,
+    ) -> io::Result<()> {
         match &ast.kind() {
             AstNodeKind::Dummy => {
                 write!(output, "<ul class=\"patto-document\">")?;
                 let children = ast.value().children.lock().unwrap();
-                for child in children.iter() {
+                let children: Vec<&AstNode> = children
+                    .iter()
+                    .filter(|child| !child.is_comment_line())
+                    .collect();
+                let mut rest = children.as_slice();
+                while let [child, ..] = rest {
                     let id_attr = self.get_stable_id_attr(child);
-                    write!(output, "<li class=\"patto-line\"{}>", id_attr)?;
-                    self._format_impl(child, output)?;
-                    write!(output, "</li>")?;
+                    let paragraph_len = if self.options.paragraphs {
+                        rest.iter().take_while(|line| is_prose_line(line)).count()
+                    } else {
+                        0
+                    };
+                    if paragraph_len == 0 {
+                        write!(output, "<li class=\"patto-line\"{}>", id_attr)?;
+                        self._format_impl(child, output, heading_ids)?;
+                        write!(output, "</li>")?;
+                        rest = &rest[1..];
+                        continue;
+                    }
+                    write!(
+                        output,
+                        "<li class=\"patto-line\"{}><p class=\"patto-paragraph\">",
+                        id_attr
+                    )?;
+                    for (i, line) in rest[..paragraph_len].iter().enumerate() {
+                        if i > 0 {
+                            write!(output, " ")?;
+                        }
+                        for content in line.value().contents.lock().unwrap().iter() {
+                            self._format_impl(content, output, heading_ids)?;
+                        }
+                    }
+                    write!(output, "</p></li>")?;
+                    rest = &rest[paragraph_len..];
                 }
                 write!(output, "</ul>")?;
             }
@@ -63,7 +252,10 @@ impl HtmlRenderer {
                 }
                 let isdone = matches!(task_status, Some(TaskStatus::Done));
 
-                write!(output, "<div class=\"patto-task-row\">")?;
+                match heading_ids.get(&ast.location().row).filter(|_| !is_quote) {
+                    Some(id) => write!(output, "<div class=\"patto-task-row\" id=\"{}\">", id)?,
+                    None => write!(output, "<div class=\"patto-task-row\">")?,
+                }
                 if let Some(status) = task_status {
                     let (icon, cls) = match status {
                         TaskStatus::Done => ("✓", "patto-task-icon-done"),
@@ -86,7 +278,7 @@ impl HtmlRenderer {
                 )?;
                 let contents = ast.value().contents.lock().unwrap();
                 for content in contents.iter() {
-                    self._format_impl(content, output)?;
+                    self._format_impl(content, output, heading_ids)?;
                 }
                 write!(output, "</div>")?;
 
@@ -109,6 +301,15 @@ impl HtmlRenderer {
                                 )?;
                             }
                         }
+                        Property::Tag { .. } => {
+                            if let Some(source) = property.tag_source() {
+                                write!(
+                                    output,
+                                    "<span class=\"patto-tag\">{}</span>",
+                                    encode_text(&source)
+                                )?;
+                            }
+                        }
                     }
                 }
                 write!(output, "</div>")?; // close patto-task-row
@@ -116,10 +317,10 @@ impl HtmlRenderer {
                 let children = ast.value().children.lock().unwrap();
                 if !children.is_empty() {
                     write!(output, "<ul class=\"patto-children\">")?;
-                    for child in children.iter() {
+                    for child in children.iter().filter(|child| !child.is_comment_line()) {
                         let id_attr = self.get_stable_id_attr(child);
                         write!(output, "<li class=\"patto-item\"{}>", id_attr)?;
-                        self._format_impl(child, output)?;
+                        self._format_impl(child, output, heading_ids)?;
                         write!(output, "</li>")?;
                     }
                     write!(output, "</ul>")?;
@@ -129,25 +330,46 @@ impl HtmlRenderer {
                 write!(output, "<blockquote class=\"patto-quote\">")?;
                 let children = ast.value().children.lock().unwrap();
                 for child in children.iter() {
-                    self.render_quote_content_html(child, output, 0)?;
+                    self.render_quote_content_html(child, output, 0, heading_ids)?;
                 }
                 write!(output, "</blockquote>")?;
             }
             AstNodeKind::Math { inline } => {
                 if *inline {
-                    write!(output, "<span class=\"patto-math-inline\">\\(")?;
+                    write!(output, "<span class=\"patto-math-inline\">")?;
                     let contents = ast.value().contents.lock().unwrap();
-                    write!(output, "{}", contents[0].extract_str())?;
-                    write!(output, "\\)</span>")?;
+                    let latex = contents[0].extract_str();
+                    if let Some(mathml) = self.render_mathml(latex, true) {
+                        write!(output, "{}", mathml)?;
+                    } else {
+                        write!(output, "\\({}\\)", latex)?;
+                    }
+                    write!(output, "</span>")?;
                 } else {
                     write!(output, "<div class=\"patto-math-block\">")?;
-                    // see https://github.com/mathjax/MathJax/issues/2312
-                    write!(output, "\\[\\displaylines{{")?;
                     let children = ast.value().children.lock().unwrap();
-                    for child in children.iter() {
-                        write!(output, "{}", child.extract_str())?;
+                    // Each line is its own display equation, as with \displaylines below
+                    let mathml: Option<Vec<String>> = children
+                        .iter()
+                        .map(|child| child.extract_str())
+                        .filter(|line| !line.trim().is_empty())
+                        .map(|line| self.render_mathml(line, false))
+                        .collect();
+                    match mathml {
+                        Some(equations) if !equations.is_empty() => {
+                            for equation in equations {
+                                write!(output, "{}", equation)?;
+                            }
+                        }
+                        _ => {
+                            // see https://github.com/mathjax/MathJax/issues/2312
+                            write!(output, "\\[\\displaylines{{")?;
+                            for child in children.iter() {
+                                write!(output, "{}", child.extract_str())?;
+                            }
+                            write!(output, "}}\\]")?;
+                        }
                     }
-                    write!(output, "}}\\]")?;
                     write!(output, "</div>")?;
                 }
             }
@@ -165,11 +387,19 @@ impl HtmlRenderer {
                             writeln!(output, "{}", child.extract_str())?;
                         }
                         write!(output, "</pre>")?;
+                    } else if let Some(highlighted) = self.highlight_code_block(lang, ast) {
+                        // `nohighlight` keeps highlight.js from re-highlighting the block
+                        write!(
+                            output,
+                            "<pre class=\"patto-code-block patto-highlighted\"><code class=\"language-{} nohighlight\">{}</code></pre>",
+                            html_escape::encode_double_quoted_attribute(lang),
+                            highlighted
+                        )?;
                     } else {
                         write!(
                             output,
                             "<pre class=\"hljs patto-code-block\"><code class=\"language-{}\">",
-                            lang
+                            html_escape::encode_double_quoted_attribute(lang)
                         )?;
                         let children = ast.value().children.lock().unwrap();
                         for child in children.iter() {
@@ -179,28 +409,69 @@ impl HtmlRenderer {
                     }
                 }
             }
-            AstNodeKind::Image { src, alt } => {
+            AstNodeKind::Image {
+                src,
+                alt,
+                width,
+                height,
+            } => {
                 let mut src_exported = src.clone();
                 if let Some(src) = get_gyazo_img_src(src) {
                     src_exported = src.clone();
                 }
+                let mut size = String::new();
+                if let Some(width) = width {
+                    size.push_str(&format!(" width=\"{}\"", width));
+                }
+                if let Some(height) = height {
+                    size.push_str(&format!(" height=\"{}\"", height));
+                }
                 write!(output, "<figure class=\"patto-figure\">")?;
                 if let Some(alt) = alt {
                     write!(
                         output,
-                        "<img class=\"patto-image\" alt=\"{}\" src=\"{}\"/>",
-                        alt, src_exported
+                        "<img class=\"patto-image\" alt=\"{}\" src=\"{}\"{}/>",
+                        alt, src_exported, size
                     )?;
                     write!(output, "<figcaption>{}</figcaption>", encode_text(alt))?;
                 } else {
                     write!(
                         output,
-                        "<img class=\"patto-image\" src=\"{}\"/>",
-                        src_exported
+                        "<img class=\"patto-image\" src=\"{}\"{}/>",
+                        src_exported, size
                     )?;
                 }
                 write!(output, "</figure>")?;
             }
+            AstNodeKind::WikiLink { link, anchor }
+                if !link.is_empty() && self.options.link_resolver.is_some() =>
+            {
+                let text = match anchor {
+                    Some(anchor) => format!("{}#{}", link, anchor),
+                    None => link.clone(),
+                };
+                let resolver = self.options.link_resolver.as_ref().unwrap();
+                match resolver.resolve(link) {
+                    LinkStatus::Exists { href } => {
+                        let fragment = anchor
+                            .as_ref()
+                            .map(|a| format!("#{}", a))
+                            .unwrap_or_default();
+                        write!(
+                            output,
+                            "<a class=\"wikilink\" href=\"{}{}\">{}</a>",
+                            html_escape::encode_double_quoted_attribute(&href),
+                            fragment,
+                            encode_text(&text)
+                        )?;
+                    }
+                    LinkStatus::Broken => write!(
+                        output,
+                        "<span class=\"wikilink broken\">{}</span>",
+                        encode_text(&text)
+                    )?,
+                }
+            }
             AstNodeKind::WikiLink { link, anchor } => {
                 if let Some(anchor) = anchor {
                     // TODO eliminate the logic that self-link if link is empty
@@ -330,16 +601,29 @@ impl HtmlRenderer {
                 )?;
                 let contents = ast.value().contents.lock().unwrap();
                 for content in contents.iter() {
-                    self._format_impl(content, output)?;
+                    self._format_impl(content, output, heading_ids)?;
                 }
                 write!(output, "</span>")?;
             }
+            AstNodeKind::DefinitionItem { term } => {
+                write!(
+                    output,
+                    "<dl class=\"patto-dl\"><dt>{}</dt><dd>",
+                    encode_text(term)
+                )?;
+                let contents = ast.value().contents.lock().unwrap();
+                for content in contents.iter() {
+                    self._format_impl(content, output, heading_ids)?;
+                }
+                write!(output, "</dd></dl>")?;
+            }
             AstNodeKind::Text | AstNodeKind::CodeContent | AstNodeKind::MathContent => {
                 write!(output, "{}", ast.extract_str())?;
             }
             AstNodeKind::HorizontalLine => {
                 write!(output, "<hr class=\"patto-hr\"/>")?;
             }
+            AstNodeKind::Comment => {}
             AstNodeKind::Table { caption } => {
                 write!(output, "<div class=\"patto-table-wrapper\">")?;
                 if let Some(caption) = caption {
@@ -353,7 +637,7 @@ impl HtmlRenderer {
                 write!(output, "<tbody>")?;
                 let children = ast.value().children.lock().unwrap();
                 for child in children.iter() {
-                    self._format_impl(child, output)?;
+                    self._format_impl(child, output, heading_ids)?;
                 }
                 write!(output, "</tbody></table></div>")?;
             }
@@ -361,7 +645,7 @@ impl HtmlRenderer {
                 write!(output, "<tr>")?;
                 let contents = ast.value().contents.lock().unwrap();
                 for content in contents.iter() {
-                    self._format_impl(content, output)?;
+                    self._format_impl(content, output, heading_ids)?;
                 }
                 write!(output, "</tr>")?;
             }
@@ -369,7 +653,7 @@ impl HtmlRenderer {
                 write!(output, "<td>")?;
                 let contents = ast.value().contents.lock().unwrap();
                 for content in contents.iter() {
-                    self._format_impl(content, output)?;
+                    self._format_impl(content, output, heading_ids)?;
                 }
                 write!(output, "</td>")?;
             }
@@ -383,6 +667,7 @@ impl HtmlRenderer {
         quote_content: &AstNode,
         output: &mut dyn Write,
         indent_level: usize,
+        heading_ids: &HashMap<usize, String>,
     ) -> io::Result<()> {
         // Check if this contains a nested Quote block
         let contents = quote_content.value().contents.lock().unwrap();
@@ -393,9 +678,9 @@ impl HtmlRenderer {
             // Render the nested quote as a nested blockquote
             for content in contents.iter() {
                 if let AstNodeKind::Quote = content.kind() {
-                    self._format_impl(content, output)?;
+                    self._format_impl(content, output, heading_ids)?;
                 } else {
-                    self._format_impl(content, output)?;
+                    self._format_impl(content, output, heading_ids)?;
                 }
             }
             drop(contents);
@@ -412,7 +697,7 @@ impl HtmlRenderer {
             }
 
             for content in contents.iter() {
-                self._format_impl(content, output)?;
+                self._format_impl(content, output, heading_ids)?;
             }
             drop(contents);
 
@@ -423,17 +708,17 @@ impl HtmlRenderer {
         let children = quote_content.value().children.lock().unwrap();
         for child in children.iter() {
             if let AstNodeKind::QuoteContent { .. } = child.kind() {
-                self.render_quote_content_html(child, output, indent_level + 1)?;
+                self.render_quote_content_html(child, output, indent_level + 1, heading_ids)?;
             } else if indent_level > 0 {
                 write!(
                     output,
                     "<div style=\"margin-left: {}em\">",
                     indent_level * 2
                 )?;
-                self._format_impl(child, output)?;
+                self._format_impl(child, output, heading_ids)?;
                 write!(output, "</div>")?;
             } else {
-                self._format_impl(child, output)?;
+                self._format_impl(child, output, heading_ids)?;
             }
         }
 
@@ -454,13 +739,19 @@ impl Renderer for MarkdownRenderer {
             writeln!(output, "---")?;
             writeln!(output, "patto_source: true")?;
             writeln!(output, "flavor: {}", self.options.flavor)?;
+            if self.options.metadata() {
+                self.write_metadata(ast, output)?;
+            }
             writeln!(output, "---")?;
             writeln!(output)?;
         }
 
+        self.format_node(ast, output)
+    }
+
+    fn format_node(&self, node: &AstNode, output: &mut dyn Write) -> io::Result<()> {
         let depth: usize = 0;
-        self._format_impl(ast, output, depth, false)?;
-        Ok(())
+        self._format_impl(node, output, depth, false)
     }
 }
 
@@ -469,6 +760,59 @@ impl MarkdownRenderer {
         Self { options }
     }
 
+    /// Write the frontmatter fields derived from the note: its title, a count of
+    /// its tasks by status and the earliest deadline of the open ones
+    fn write_metadata(&self, ast: &AstNode, output: &mut dyn Write) -> io::Result<()> {
+        let title = collect_headings(ast)
+            .into_iter()
+            .next()
+            .map(|heading| heading.text)
+            .or_else(|| self.options.title().map(str::to_string));
+        if let Some(title) = title {
+            writeln!(output, "title: {}", yaml_quote(&title))?;
+        }
+
+        fn gather_tasks(node: &AstNode, tasks: &mut Vec<(TaskStatus, Deadline)>) {
+            if let AstNodeKind::Line { properties } | AstNodeKind::QuoteContent { properties } =
+                node.kind()
+            {
+                for property in properties {
+                    if let Property::Task { status, due, .. } = property {
+                        tasks.push((status.clone(), due.clone()));
+                    }
+                }
+            }
+            for content in node.value().contents.lock().unwrap().iter() {
+                gather_tasks(content, tasks);
+            }
+            for child in node.value().children.lock().unwrap().iter() {
+                gather_tasks(child, tasks);
+            }
+        }
+        let mut tasks = Vec::new();
+        gather_tasks(ast, &mut tasks);
+
+        let count = |wanted: TaskStatus| tasks.iter().filter(|(s, _)| *s == wanted).count();
+        writeln!(output, "tasks:")?;
+        writeln!(output, "  total: {}", tasks.len())?;
+        writeln!(output, "  todo: {}", count(TaskStatus::Todo))?;
+        writeln!(output, "  doing: {}", count(TaskStatus::Doing))?;
+        writeln!(output, "  paused: {}", count(TaskStatus::Paused))?;
+        writeln!(output, "  done: {}", count(TaskStatus::Done))?;
+
+        let next_due = tasks
+            .iter()
+            .filter(|(status, due)| {
+                !matches!(status, TaskStatus::Done) && !matches!(due, Deadline::Uninterpretable(_))
+            })
+            .map(|(_, due)| due)
+            .min();
+        if let Some(due) = next_due {
+            writeln!(output, "next_due: {}", yaml_quote(&due.to_string()))?;
+        }
+        Ok(())
+    }
+
     /// Format a range of lines from the AST to markdown
     /// start_line and end_line are 0-indexed, inclusive
     pub fn format_range(
@@ -552,6 +896,9 @@ impl MarkdownRenderer {
                 }
             }
             AstNodeKind::Line { properties } | AstNodeKind::QuoteContent { properties } => {
+                if ast.is_comment_line() {
+                    return Ok(());
+                }
                 let has_children = !ast.value().children.lock().unwrap().is_empty();
                 let is_quote_content = matches!(ast.kind(), AstNodeKind::QuoteContent { .. });
 
@@ -686,6 +1033,11 @@ impl MarkdownRenderer {
                     }
                 }
 
+                // Append tags as comments so they survive the export
+                for source in properties.iter().filter_map(Property::tag_source) {
+                    write!(output, " <!-- {} -->", source)?;
+                }
+
                 // Block containers handle their own newlines
                 if !is_block_container {
                     writeln!(output)?;
@@ -736,11 +1088,25 @@ impl MarkdownRenderer {
                     writeln!(output, "```")?;
                 }
             }
-            AstNodeKind::Image { src, alt } => {
-                if let Some(alt) = alt {
-                    write!(output, "![{}]({})", alt, src)?;
-                } else {
-                    write!(output, "![]({})", src)?;
+            AstNodeKind::Image { src, alt, .. } => {
+                let is_local = !src.contains("://");
+                match self.options.wiki_link_format() {
+                    // Obsidian embeds local files by their vault path
+                    WikiLinkFormat::WikiStyle if is_local => {
+                        let path = src.strip_prefix("./").unwrap_or(src);
+                        if let Some(alt) = alt {
+                            write!(output, "![[{}|{}]]", path, alt)?;
+                        } else {
+                            write!(output, "![[{}]]", path)?;
+                        }
+                    }
+                    _ => {
+                        if let Some(alt) = alt {
+                            write!(output, "![{}]({})", alt, src)?;
+                        } else {
+                            write!(output, "![]({})", src)?;
+                        }
+                    }
                 }
             }
             AstNodeKind::WikiLink { link, anchor } => {
@@ -846,12 +1212,19 @@ impl MarkdownRenderer {
                     write!(output, "***")?;
                 }
             }
+            AstNodeKind::DefinitionItem { term } => {
+                write!(output, "**{}**: ", term)?;
+                for content in ast.value().contents.lock().unwrap().iter() {
+                    self._format_impl(content, output, depth, in_quote)?;
+                }
+            }
             AstNodeKind::Text | AstNodeKind::CodeContent | AstNodeKind::MathContent => {
                 write!(output, "{}", ast.extract_str())?;
             }
             AstNodeKind::HorizontalLine => {
                 write!(output, "---")?;
             }
+            AstNodeKind::Comment => {}
             AstNodeKind::Table { caption } => {
                 // Caption as emphasized text
                 if let Some(caption) = caption {
@@ -902,10 +1275,22 @@ impl MarkdownRenderer {
         inner_depth: usize,
     ) -> io::Result<()> {
         let children = quote.value().children.lock().unwrap();
-        for child in children.iter() {
+        let callout = if self.options.callouts() && inner_depth == 0 {
+            children.first().and_then(detect_callout)
+        } else {
+            None
+        };
+        if let Some((label, _)) = callout {
+            for _ in 0..depth {
+                write!(output, "  ")?;
+            }
+            writeln!(output, "> [!{}]", label)?;
+        }
+        for (i, child) in children.iter().enumerate() {
             match child.kind() {
                 AstNodeKind::QuoteContent { .. } => {
-                    self.render_quote_content(child, output, depth, inner_depth)?;
+                    let strip_prefix = callout.filter(|_| i == 0).map(|(_, len)| len);
+                    self.render_quote_content(child, output, depth, inner_depth, strip_prefix)?;
                 }
                 _ => {
                     // Other children (shouldn't happen normally but handle gracefully)
@@ -921,26 +1306,39 @@ impl MarkdownRenderer {
     }
 
     /// Render a QuoteContent node with proper visual indentation
+    /// `strip_prefix` drops that many leading bytes of the first text (a callout keyword)
     fn render_quote_content(
         &self,
         quote_content: &AstNode,
         output: &mut dyn Write,
         depth: usize,
         inner_depth: usize,
+        strip_prefix: Option<usize>,
     ) -> io::Result<()> {
-        // Output the "> " prefix with outer depth indentation
-        for _ in 0..depth {
-            write!(output, "  ")?;
-        }
-        write!(output, "> ")?;
+        let contents = quote_content.value().contents.lock().unwrap();
+
+        // A callout keyword alone on its line leaves nothing to render
+        let stripped_text = strip_prefix.and_then(|len| {
+            contents
+                .first()
+                .map(|first| first.extract_str().get(len..).unwrap_or_default())
+        });
+        let skip_line = contents.len() == 1 && stripped_text.is_some_and(str::is_empty);
+
+        if !skip_line {
+            // Output the "> " prefix with outer depth indentation
+            for _ in 0..depth {
+                write!(output, "  ")?;
+            }
+            write!(output, "> ")?;
 
-        // Add visual indentation for inner depth (spaces after ">")
-        for _ in 0..inner_depth {
-            write!(output, "    ")?; // 4 spaces per indent level
+            // Add visual indentation for inner depth (spaces after ">")
+            for _ in 0..inner_depth {
+                write!(output, "    ")?; // 4 spaces per indent level
+            }
         }
 
         // Check if this is a nested Quote block
-        let contents = quote_content.value().contents.lock().unwrap();
         let has_nested_quote =
             contents.len() == 1 && matches!(contents[0].kind(), AstNodeKind::Quote);
 
@@ -957,10 +1355,13 @@ impl MarkdownRenderer {
                     self._format_impl(content, output, depth, true)?;
                 }
             }
-        } else {
+        } else if !skip_line {
             // Regular content
-            for content in contents.iter() {
-                self._format_impl(content, output, depth, true)?;
+            for (i, content) in contents.iter().enumerate() {
+                match stripped_text.filter(|_| i == 0) {
+                    Some(text) => write!(output, "{}", text)?,
+                    None => self._format_impl(content, output, depth, true)?,
+                }
             }
             drop(contents);
 
@@ -982,7 +1383,7 @@ impl MarkdownRenderer {
         let children = quote_content.value().children.lock().unwrap();
         for child in children.iter() {
             if let AstNodeKind::QuoteContent { .. } = child.kind() {
-                self.render_quote_content(child, output, depth, inner_depth + 1)?;
+                self.render_quote_content(child, output, depth, inner_depth + 1, None)?;
             } else {
                 // Other children
                 for _ in 0..depth {
@@ -1061,6 +1462,29 @@ impl MarkdownRenderer {
     }
 }
 
+/// Keywords recognized at the start of a quote, named after GitHub alert types
+const CALLOUT_KEYWORDS: [&str; 5] = ["NOTE", "TIP", "IMPORTANT", "WARNING", "CAUTION"];
+
+/// Detect a callout keyword (e.g. `NOTE:`) at the start of a quote's first line.
+/// Returns the alert type and the byte length of the keyword prefix to strip.
+fn detect_callout(quote_content: &AstNode) -> Option<(&'static str, usize)> {
+    if !matches!(quote_content.kind(), AstNodeKind::QuoteContent { .. }) {
+        return None;
+    }
+    let contents = quote_content.value().contents.lock().unwrap();
+    let first = contents.first()?;
+    if !matches!(first.kind(), AstNodeKind::Text) {
+        return None;
+    }
+    let text = first.extract_str();
+    let (keyword, rest) = text.split_once(':')?;
+    let label = CALLOUT_KEYWORDS
+        .into_iter()
+        .find(|k| k.eq_ignore_ascii_case(keyword.trim()))?;
+    let rest_start = text.len() - rest.trim_start().len();
+    Some((label, rest_start))
+}
+
 /// Renderer that outputs patto format (for round-trip conversion)
 #[derive(Debug, Default)]
 pub struct PattoRenderer {
@@ -1077,6 +1501,84 @@ impl PattoRenderer {
         Self { base_indent }
     }
 
+    /// Write the lines of a code or math block one level deeper than its header
+    fn write_block_body(
+        &self,
+        ast: &AstNode,
+        output: &mut dyn Write,
+        depth: usize,
+    ) -> io::Result<()> {
+        for child in ast.value().children.lock().unwrap().iter() {
+            let text = child.extract_str();
+            if !text.is_empty() {
+                for _ in 0..=depth {
+                    write!(output, "\t")?;
+                }
+            }
+            writeln!(output, "{}", text)?;
+        }
+        Ok(())
+    }
+
+    /// Write the contents and properties of a line, without its indentation
+    ///
+    /// Properties parsed from the same line are put back between the contents
+    /// where they were found, so that parsing the output gives the same tree;
+    /// any other property is appended.
+    fn write_line_body(
+        &self,
+        ast: &AstNode,
+        properties: &[Property],
+        line: &mut Vec<u8>,
+        depth: usize,
+    ) -> io::Result<()> {
+        let input = ast.location().input.clone();
+        let position = |location: &crate::parser::Location| {
+            if !input.is_empty() && location.input == input {
+                location.span.0
+            } else {
+                usize::MAX
+            }
+        };
+
+        let contents = ast.value().contents.lock().unwrap();
+        let mut pieces: Vec<(usize, Option<&AstNode>, Option<&Property>)> = contents
+            .iter()
+            .map(|content| (position(content.location()), Some(content), None))
+            .chain(
+                properties
+                    .iter()
+                    .map(|property| (position(property.location()), None, Some(property))),
+            )
+            .collect();
+        pieces.sort_by_key(|(position, _, _)| *position);
+
+        for (_, content, property) in pieces {
+            if let Some(content) = content {
+                self._format_impl(content, line, depth)?;
+                continue;
+            }
+            let Some(property) = property else { continue };
+            // Properties are separated from whatever precedes them on the line
+            if line.last().is_some_and(|c| !c.is_ascii_whitespace()) {
+                write!(line, " ")?;
+            }
+            match property {
+                Property::Anchor { name, .. } => write!(line, "#{}", name)?,
+                Property::Task { .. } => match task_shorthand(property) {
+                    Some(shorthand) => write!(line, "{}", shorthand)?,
+                    None => write_task_property(line, property)?,
+                },
+                Property::Tag { .. } => {
+                    if let Some(source) = property.tag_source() {
+                        write!(line, "{}", source)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn _format_impl(&self, ast: &AstNode, output: &mut dyn Write, depth: usize) -> io::Result<()> {
         match ast.kind() {
             AstNodeKind::Dummy => {
@@ -1087,129 +1589,29 @@ impl PattoRenderer {
                     self._format_impl(child, output, self.base_indent)?;
                 }
             }
-            AstNodeKind::Line { properties } => {
-                // Indentation
-                for _ in 0..depth {
-                    write!(output, "\t")?;
-                }
-
-                // Check for task property
-                let mut task_prop: Option<(
-                    &TaskStatus,
-                    &crate::parser::Deadline,
-                    Option<&crate::parser::Deadline>,
-                    Option<&crate::parser::Deadline>,
-                )> = None;
-                for property in properties {
-                    if let Property::Task {
-                        status,
-                        due,
-                        scheduled,
-                        completed_at,
-                        ..
-                    } = property
-                    {
-                        task_prop = Some((status, due, scheduled.as_ref(), completed_at.as_ref()));
-                        break;
-                    }
-                }
-
-                // Render contents
-                for content in ast.value().contents.lock().unwrap().iter() {
-                    self._format_impl(content, output, 0)?;
-                }
-
-                // Add task property if present
-                if let Some((status, due, scheduled, completed_at)) = task_prop {
-                    let status_str = match status {
-                        TaskStatus::Todo => "todo",
-                        TaskStatus::Doing => "doing",
-                        TaskStatus::Paused => "paused",
-                        TaskStatus::Done => "done",
-                    };
-                    let due_str = due.to_string();
-                    write!(output, " {{@task status={}", status_str)?;
-                    if !due_str.is_empty() {
-                        write!(output, " due={}", due_str)?;
-                    }
-                    if let Some(s) = scheduled {
-                        write!(output, " scheduled={}", s)?;
-                    }
-                    if let Some(c) = completed_at {
-                        write!(output, " completed_at={}", c)?;
-                    }
-                    write!(output, "}}")?;
-                }
-
-                writeln!(output)?;
-
-                // Children
-                for child in ast.value().children.lock().unwrap().iter() {
-                    self._format_impl(child, output, depth + 1)?;
-                }
-            }
-            AstNodeKind::QuoteContent { properties } => {
+            AstNodeKind::Line { properties } | AstNodeKind::QuoteContent { properties } => {
                 // Indentation based on structural depth
                 for _ in 0..depth {
                     write!(output, "\t")?;
                 }
 
-                // Check for task property
-                let mut task_prop: Option<(
-                    &TaskStatus,
-                    &crate::parser::Deadline,
-                    Option<&crate::parser::Deadline>,
-                    Option<&crate::parser::Deadline>,
-                )> = None;
-                for property in properties {
-                    if let Property::Task {
-                        status,
-                        due,
-                        scheduled,
-                        completed_at,
-                        ..
-                    } = property
-                    {
-                        task_prop = Some((status, due, scheduled.as_ref(), completed_at.as_ref()));
-                        break;
-                    }
-                }
-
-                // Render contents (clean text, no embedded tabs)
-                for content in ast.value().contents.lock().unwrap().iter() {
-                    self._format_impl(content, output, 0)?;
-                }
-
-                // Add task property if present
-                if let Some((status, due, scheduled, completed_at)) = task_prop {
-                    let status_str = match status {
-                        TaskStatus::Todo => "todo",
-                        TaskStatus::Doing => "doing",
-                        TaskStatus::Paused => "paused",
-                        TaskStatus::Done => "done",
-                    };
-                    let due_str = due.to_string();
-                    write!(output, " {{@task status={}", status_str)?;
-                    if !due_str.is_empty() {
-                        write!(output, " due={}", due_str)?;
-                    }
-                    if let Some(s) = scheduled {
-                        write!(output, " scheduled={}", s)?;
-                    }
-                    if let Some(c) = completed_at {
-                        write!(output, " completed_at={}", c)?;
-                    }
-                    write!(output, "}}")?;
+                let mut line = Vec::new();
+                self.write_line_body(ast, properties, &mut line, depth)?;
+                output.write_all(&line)?;
+                // Blocks end with the newline of their last line
+                if line.last() != Some(&b'\n') {
+                    writeln!(output)?;
                 }
 
-                writeln!(output)?;
-
-                // Recursively render nested children at depth+1
+                // Children
                 for child in ast.value().children.lock().unwrap().iter() {
                     self._format_impl(child, output, depth + 1)?;
                 }
             }
             AstNodeKind::Text => {
+                if ast.is_escaped() {
+                    write!(output, "\\")?;
+                }
                 write!(output, "{}", ast.extract_str())?;
             }
             AstNodeKind::Decoration {
@@ -1219,10 +1621,7 @@ impl PattoRenderer {
                 deleted,
             } => {
                 // Determine decoration markers
-                let mut markers = String::new();
-                if *fontsize > 0 {
-                    markers.push('*');
-                }
+                let mut markers = "*".repeat((*fontsize).max(0) as usize);
                 if *italic {
                     markers.push('/');
                 }
@@ -1243,6 +1642,16 @@ impl PattoRenderer {
                     write!(output, "]")?;
                 }
             }
+            AstNodeKind::DefinitionItem { term } => {
+                write!(output, "{}::", term)?;
+                let contents = ast.value().contents.lock().unwrap();
+                if !contents.is_empty() {
+                    write!(output, " ")?;
+                }
+                for content in contents.iter() {
+                    self._format_impl(content, output, 0)?;
+                }
+            }
             AstNodeKind::Code { lang, inline } => {
                 if *inline {
                     write!(output, "[` ")?;
@@ -1256,11 +1665,7 @@ impl PattoRenderer {
                     } else {
                         writeln!(output, "[@code {}]", lang)?;
                     }
-                    for child in ast.value().children.lock().unwrap().iter() {
-                        write!(output, "\t")?;
-                        write!(output, "{}", child.extract_str())?;
-                        writeln!(output)?;
-                    }
+                    self.write_block_body(ast, output, depth)?;
                 }
             }
             AstNodeKind::CodeContent | AstNodeKind::MathContent => {
@@ -1275,11 +1680,7 @@ impl PattoRenderer {
                     write!(output, " $]")?;
                 } else {
                     writeln!(output, "[@math]")?;
-                    for child in ast.value().children.lock().unwrap().iter() {
-                        write!(output, "\t")?;
-                        write!(output, "{}", child.extract_str())?;
-                        writeln!(output)?;
-                    }
+                    self.write_block_body(ast, output, depth)?;
                 }
             }
             AstNodeKind::Quote => {
@@ -1300,7 +1701,9 @@ impl PattoRenderer {
                 }
             }
             AstNodeKind::TableRow => {
-                write!(output, "\t")?;
+                for _ in 0..=depth {
+                    write!(output, "\t")?;
+                }
                 let contents = ast.value().contents.lock().unwrap();
                 for (i, cell) in contents.iter().enumerate() {
                     if i > 0 {
@@ -1342,23 +1745,842 @@ impl PattoRenderer {
                     write!(output, "[@embed {}]", link)?;
                 }
             }
-            AstNodeKind::Image { src, alt } => {
+            AstNodeKind::Image {
+                src,
+                alt,
+                width,
+                height,
+            } => {
+                write!(output, "[@img {}", src)?;
                 if let Some(a) = alt {
-                    write!(output, "[@img {} \"{}\"]", src, a)?;
-                } else {
-                    write!(output, "[@img {}]", src)?;
+                    write!(output, " \"{}\"", a)?;
                 }
+                if let Some(width) = width {
+                    write!(output, " width={}", width)?;
+                }
+                if let Some(height) = height {
+                    write!(output, " height={}", height)?;
+                }
+                write!(output, "]")?;
             }
             AstNodeKind::HorizontalLine => {
-                writeln!(output, "---")?;
+                // Keep the dashes as written: a line needs five of them
+                writeln!(output, "{}", ast.extract_str())?;
+            }
+            AstNodeKind::Comment => {
+                write!(output, "{}", ast.extract_str())?;
             }
         }
         Ok(())
     }
 }
 
+/// The `!2024-12-31` form of a task written that way in the source
+fn task_shorthand(property: &Property) -> Option<String> {
+    let Property::Task {
+        status,
+        due,
+        scheduled: None,
+        completed_at: None,
+        started_at: None,
+        time_spent: None,
+        location,
+        ..
+    } = property
+    else {
+        return None;
+    };
+    let symbol = match status {
+        TaskStatus::Todo => "!",
+        TaskStatus::Doing => "*",
+        TaskStatus::Done => "-",
+        TaskStatus::Paused => return None,
+    };
+    let source = location.input.get(location.span.0..location.span.1)?;
+    let due = due.to_string();
+    (source.starts_with(symbol) && !due.is_empty()).then(|| format!("{}{}", symbol, due))
+}
+
+fn write_task_property(output: &mut dyn Write, property: &Property) -> io::Result<()> {
+    let Property::Task {
+        status,
+        due,
+        scheduled,
+        completed_at,
+        started_at,
+        time_spent,
+        ..
+    } = property
+    else {
+        return Ok(());
+    };
+    let status_str = match status {
+        TaskStatus::Todo => "todo",
+        TaskStatus::Doing => "doing",
+        TaskStatus::Paused => "paused",
+        TaskStatus::Done => "done",
+    };
+    write!(output, "{{@task status={}", status_str)?;
+    let due_str = due.to_string();
+    if !due_str.is_empty() {
+        write!(output, " due={}", due_str)?;
+    }
+    if let Some(s) = scheduled {
+        write!(output, " scheduled={}", s)?;
+    }
+    if let Some(c) = completed_at {
+        write!(output, " completed_at={}", c)?;
+    }
+    if let Some(s) = started_at {
+        write!(output, " started_at={}", s)?;
+    }
+    if let Some(t) = time_spent {
+        write!(output, " time_spent={}", t)?;
+    }
+    write!(output, "}}")
+}
+
 impl Renderer for PattoRenderer {
     fn format(&self, ast: &AstNode, output: &mut dyn Write) -> io::Result<()> {
-        self._format_impl(ast, output, 0)
+        self.format_node(ast, output)
+    }
+
+    fn format_node(&self, node: &AstNode, output: &mut dyn Write) -> io::Result<()> {
+        self._format_impl(node, output, self.base_indent)
+    }
+}
+
+/// Renderer that outputs Emacs Org-mode
+///
+/// Lines become plain list items, tasks become `TODO`/`DONE` headlines with
+/// planning lines, and top-level `[* heading]` lines become headlines.
+#[derive(Debug, Default)]
+pub struct OrgRenderer {}
+
+impl Renderer for OrgRenderer {
+    fn format(&self, ast: &AstNode, output: &mut dyn Write) -> io::Result<()> {
+        self._format_impl(ast, output, 0, &generated_heading_ids(ast))
+    }
+
+    fn format_node(&self, node: &AstNode, output: &mut dyn Write) -> io::Result<()> {
+        self._format_impl(node, output, 0, &HashMap::new())
+    }
+}
+
+impl OrgRenderer {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    /// Format a range of lines from the AST to Org-mode
+    /// start_line and end_line are 0-indexed, inclusive
+    pub fn format_range(
+        &self,
+        ast: &AstNode,
+        output: &mut dyn Write,
+        start_line: usize,
+        end_line: usize,
+    ) -> io::Result<()> {
+        let heading_ids = generated_heading_ids(ast);
+        self._format_range_impl(ast, output, 0, start_line, end_line, &heading_ids)
+    }
+
+    fn _format_range_impl(
+        &self,
+        ast: &AstNode,
+        output: &mut dyn Write,
+        depth: usize,
+        start_line: usize,
+        end_line: usize,
+        heading_ids: &HashMap<usize, String>,
+    ) -> io::Result<()> {
+        match &ast.kind() {
+            AstNodeKind::Dummy => {
+                let children = ast.value().children.lock().unwrap();
+                for child in children.iter() {
+                    if child.location().row <= end_line {
+                        self._format_range_impl(
+                            child,
+                            output,
+                            depth,
+                            start_line,
+                            end_line,
+                            heading_ids,
+                        )?;
+                    }
+                }
+            }
+            AstNodeKind::Line { .. } => {
+                let row = ast.location().row;
+                if row >= start_line && row <= end_line {
+                    self._format_impl(ast, output, depth, heading_ids)?;
+                } else if row < start_line {
+                    // This line is before range, but its children may be in it
+                    let children = ast.value().children.lock().unwrap();
+                    for child in children.iter() {
+                        if child.location().row <= end_line {
+                            self._format_range_impl(
+                                child,
+                                output,
+                                depth,
+                                start_line,
+                                end_line,
+                                heading_ids,
+                            )?;
+                        }
+                    }
+                }
+            }
+            _ => {
+                self._format_impl(ast, output, depth, heading_ids)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn _format_impl(
+        &self,
+        ast: &AstNode,
+        output: &mut dyn Write,
+        depth: usize,
+        heading_ids: &HashMap<usize, String>,
+    ) -> io::Result<()> {
+        match &ast.kind() {
+            AstNodeKind::Dummy => {
+                let children = ast.value().children.lock().unwrap();
+                for child in children.iter() {
+                    self._format_impl(child, output, depth, heading_ids)?;
+                }
+            }
+            AstNodeKind::Line { properties } => {
+                if ast.is_comment_line() {
+                    return Ok(());
+                }
+                let has_children = !ast.value().children.lock().unwrap().is_empty();
+                let heading = heading_level(ast);
+                let contents = ast.value().contents.lock().unwrap();
+                let is_block_container = contents.len() == 1
+                    && matches!(
+                        contents[0].kind(),
+                        AstNodeKind::Quote
+                            | AstNodeKind::Code { inline: false, .. }
+                            | AstNodeKind::Math { inline: false }
+                            | AstNodeKind::Table { .. }
+                    );
+                if contents.is_empty() && properties.is_empty() && !has_children {
+                    writeln!(output)?;
+                    return Ok(());
+                }
+
+                let task = properties.iter().find_map(|property| match property {
+                    Property::Task {
+                        status,
+                        due,
+                        scheduled,
+                        completed_at,
+                        ..
+                    } => Some((status, due, scheduled, completed_at)),
+                    _ => None,
+                });
+
+                if let Some((status, due, scheduled, completed_at)) = task {
+                    // Tasks become headlines so that Org agenda picks them up
+                    let keyword = if matches!(status, TaskStatus::Done) {
+                        "DONE"
+                    } else {
+                        "TODO"
+                    };
+                    write!(output, "{} {} ", "*".repeat(depth + 1), keyword)?;
+                    for content in contents.iter() {
+                        self._format_impl(content, output, depth, heading_ids)?;
+                    }
+                    self.write_anchors(properties, output)?;
+                    writeln!(output)?;
+
+                    // A planning keyword takes a single timestamp; deadlines that
+                    // need several become plain active timestamps instead
+                    let mut planning = Vec::new();
+                    let mut timestamps = Vec::new();
+                    if matches!(status, TaskStatus::Done) {
+                        for c in completed_at.iter() {
+                            planning.extend(
+                                org_timestamps(c, false)
+                                    .into_iter()
+                                    .map(|c| format!("CLOSED: {}", c)),
+                            );
+                        }
+                    } else {
+                        for (keyword, deadline) in std::iter::once(("DEADLINE", due))
+                            .chain(scheduled.iter().map(|s| ("SCHEDULED", s)))
+                        {
+                            match org_timestamps(deadline, true).as_slice() {
+                                [stamp] => planning.push(format!("{}: {}", keyword, stamp)),
+                                stamps => timestamps.extend_from_slice(stamps),
+                            }
+                        }
+                    }
+                    if !planning.is_empty() {
+                        writeln!(output, "{}", planning.join(" "))?;
+                    }
+                    if !timestamps.is_empty() {
+                        writeln!(output, "{}", timestamps.join(" "))?;
+                    }
+                } else if let Some(level) = heading.filter(|_| depth == 0) {
+                    write!(output, "{} ", "*".repeat(level as usize))?;
+                    for content in contents[0].value().contents.lock().unwrap().iter() {
+                        self._format_impl(content, output, depth, heading_ids)?;
+                    }
+                    self.write_anchors(properties, output)?;
+                    // Make the id the HTML table of contents uses a link target too
+                    if let Some(id) = heading_ids.get(&ast.location().row) {
+                        write!(output, " <<{}>>", id)?;
+                    }
+                    writeln!(output)?;
+                } else {
+                    if !is_block_container {
+                        write!(output, "{}", "  ".repeat(depth))?;
+                        if depth > 0 || has_children {
+                            write!(output, "- ")?;
+                        }
+                    }
+                    for content in contents.iter() {
+                        self._format_impl(content, output, depth, heading_ids)?;
+                    }
+                    self.write_anchors(properties, output)?;
+                    // Block containers handle their own newlines
+                    if !is_block_container {
+                        writeln!(output)?;
+                    }
+                }
+                drop(contents);
+
+                let children = ast.value().children.lock().unwrap();
+                for child in children.iter() {
+                    self._format_impl(child, output, depth + 1, heading_ids)?;
+                }
+            }
+            AstNodeKind::Quote => {
+                writeln!(output, "#+begin_quote")?;
+                let children = ast.value().children.lock().unwrap();
+                for child in children.iter() {
+                    self.render_quote_content(child, output, 0, heading_ids)?;
+                }
+                writeln!(output, "#+end_quote")?;
+            }
+            AstNodeKind::QuoteContent { .. } => {
+                self.render_quote_content(ast, output, 0, heading_ids)?;
+            }
+            AstNodeKind::Math { inline } => {
+                if *inline {
+                    write!(output, "\\(")?;
+                    let contents = ast.value().contents.lock().unwrap();
+                    if !contents.is_empty() {
+                        write!(output, "{}", contents[0].extract_str())?;
+                    }
+                    write!(output, "\\)")?;
+                } else {
+                    writeln!(output, "\\[")?;
+                    let children = ast.value().children.lock().unwrap();
+                    for child in children.iter() {
+                        writeln!(output, "{}", child.extract_str())?;
+                    }
+                    writeln!(output, "\\]")?;
+                }
+            }
+            AstNodeKind::Code { lang, inline } => {
+                if *inline {
+                    write!(output, "~")?;
+                    let contents = ast.value().contents.lock().unwrap();
+                    if !contents.is_empty() {
+                        write!(output, "{}", contents[0].extract_str())?;
+                    }
+                    write!(output, "~")?;
+                } else {
+                    let block = if lang.is_empty() {
+                        writeln!(output, "#+begin_example")?;
+                        "example"
+                    } else {
+                        writeln!(output, "#+begin_src {}", lang)?;
+                        "src"
+                    };
+                    let children = ast.value().children.lock().unwrap();
+                    for child in children.iter() {
+                        writeln!(output, "{}", child.extract_str())?;
+                    }
+                    writeln!(output, "#+end_{}", block)?;
+                }
+            }
+            AstNodeKind::Image { src, .. } => {
+                write!(output, "[[{}]]", src)?;
+            }
+            AstNodeKind::WikiLink { link, anchor } => {
+                if let Some(anchor) = anchor {
+                    if link.is_empty() {
+                        // Self-link to a dedicated target
+                        write!(output, "[[{}][#{}]]", anchor, anchor)?;
+                    } else {
+                        write!(
+                            output,
+                            "[[file:{}.org::{}][{}#{}]]",
+                            link, anchor, link, anchor
+                        )?;
+                    }
+                } else {
+                    write!(output, "[[file:{}.org][{}]]", link, link)?;
+                }
+            }
+            AstNodeKind::Link { link, title } | AstNodeKind::Embed { link, title } => {
+                if let Some(title) = title {
+                    write!(output, "[[{}][{}]]", link, title)?;
+                } else {
+                    write!(output, "[[{}]]", link)?;
+                }
+            }
+            AstNodeKind::Decoration {
+                fontsize,
+                italic,
+                underline,
+                deleted,
+            } => {
+                let mut markers = Vec::new();
+                if *fontsize > 0 {
+                    markers.push('*');
+                }
+                if *italic {
+                    markers.push('/');
+                }
+                if *underline {
+                    markers.push('_');
+                }
+                if *deleted {
+                    markers.push('+');
+                }
+                for marker in markers.iter() {
+                    write!(output, "{}", marker)?;
+                }
+                for content in ast.value().contents.lock().unwrap().iter() {
+                    self._format_impl(content, output, depth, heading_ids)?;
+                }
+                for marker in markers.iter().rev() {
+                    write!(output, "{}", marker)?;
+                }
+            }
+            AstNodeKind::DefinitionItem { term } => {
+                write!(output, "{} :: ", term)?;
+                for content in ast.value().contents.lock().unwrap().iter() {
+                    self._format_impl(content, output, depth, heading_ids)?;
+                }
+            }
+            AstNodeKind::Text | AstNodeKind::CodeContent | AstNodeKind::MathContent => {
+                write!(output, "{}", ast.extract_str())?;
+            }
+            AstNodeKind::HorizontalLine => {
+                write!(output, "-----")?;
+            }
+            AstNodeKind::Comment => {}
+            AstNodeKind::Table { caption } => {
+                if let Some(caption) = caption {
+                    writeln!(output, "#+caption: {}", caption)?;
+                }
+
+                let children = ast.value().children.lock().unwrap();
+                for (i, child) in children.iter().enumerate() {
+                    self._format_impl(child, output, depth, heading_ids)?;
+
+                    // Add header separator after first row
+                    if i == 0 && children.len() > 1 {
+                        let col_count = child.value().contents.lock().unwrap().len();
+                        writeln!(output, "|{}|", vec!["---"; col_count].join("+"))?;
+                    }
+                }
+            }
+            AstNodeKind::TableRow => {
+                write!(output, "|")?;
+                let contents = ast.value().contents.lock().unwrap();
+                for content in contents.iter() {
+                    write!(output, " ")?;
+                    self._format_impl(content, output, depth, heading_ids)?;
+                    write!(output, " |")?;
+                }
+                writeln!(output)?;
+            }
+            AstNodeKind::TableColumn => {
+                for content in ast.value().contents.lock().unwrap().iter() {
+                    self._format_impl(content, output, depth, heading_ids)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Render a QuoteContent node inside `#+begin_quote`, indenting nested lines
+    fn render_quote_content(
+        &self,
+        quote_content: &AstNode,
+        output: &mut dyn Write,
+        inner_depth: usize,
+        heading_ids: &HashMap<usize, String>,
+    ) -> io::Result<()> {
+        let contents = quote_content.value().contents.lock().unwrap();
+        let has_nested_quote =
+            contents.len() == 1 && matches!(contents[0].kind(), AstNodeKind::Quote);
+        if has_nested_quote {
+            self._format_impl(&contents[0], output, 0, heading_ids)?;
+        } else {
+            write!(output, "{}", "  ".repeat(inner_depth))?;
+            for content in contents.iter() {
+                self._format_impl(content, output, 0, heading_ids)?;
+            }
+            if let AstNodeKind::QuoteContent { properties } = quote_content.kind() {
+                self.write_anchors(properties, output)?;
+            }
+            writeln!(output)?;
+        }
+        drop(contents);
+
+        let children = quote_content.value().children.lock().unwrap();
+        for child in children.iter() {
+            self.render_quote_content(child, output, inner_depth + 1, heading_ids)?;
+        }
+        Ok(())
+    }
+
+    /// Append anchors as Org dedicated targets (`<<name>>`) and tags as verbatim text
+    fn write_anchors(&self, properties: &[Property], output: &mut dyn Write) -> io::Result<()> {
+        for property in properties {
+            if let Property::Anchor { name, .. } = property {
+                write!(output, " <<{}>>", name)?;
+            }
+        }
+        for source in properties.iter().filter_map(Property::tag_source) {
+            write!(output, " ={}=", source)?;
+        }
+        Ok(())
+    }
+}
+
+/// A YAML double-quoted scalar holding `text`
+fn yaml_quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// A heading line collected for the table of contents
+pub(crate) struct TocHeading {
+    pub(crate) row: usize,
+    pub(crate) level: u8,
+    pub(crate) id: String,
+    pub(crate) text: String,
+    pub(crate) has_anchor: bool,
+}
+
+/// Ids generated for headings without an anchor, by row
+/// Whether `line` is plain text that can share a paragraph with its neighbors:
+/// a non-blank line without properties, nested lines or block commands that
+/// is not a heading
+fn is_prose_line(line: &AstNode) -> bool {
+    let AstNodeKind::Line { properties } = line.kind() else {
+        return false;
+    };
+    if !properties.is_empty()
+        || !line.value().children.lock().unwrap().is_empty()
+        || heading_level(line).is_some()
+    {
+        return false;
+    }
+    let contents = line.value().contents.lock().unwrap();
+    !contents.is_empty()
+        && contents.iter().all(|content| {
+            matches!(
+                content.kind(),
+                AstNodeKind::Text
+                    | AstNodeKind::WikiLink { .. }
+                    | AstNodeKind::Link { .. }
+                    | AstNodeKind::Decoration { .. }
+                    | AstNodeKind::Comment
+                    | AstNodeKind::Code { inline: true, .. }
+                    | AstNodeKind::Math { inline: true }
+            )
+        })
+}
+
+fn generated_heading_ids(ast: &AstNode) -> HashMap<usize, String> {
+    collect_headings(ast)
+        .into_iter()
+        .filter(|heading| !heading.has_anchor)
+        .map(|heading| (heading.row, heading.id))
+        .collect()
+}
+
+/// Collect top-level heading lines in document order, assigning each a unique id.
+/// A heading's own anchor is used as its id; other headings get slugs of their text.
+/// The generated ids double as anchors, see [`crate::repository::find_anchor`].
+pub(crate) fn collect_headings(ast: &AstNode) -> Vec<TocHeading> {
+    fn gather_lines(node: &AstNode, depth: usize, lines: &mut Vec<(AstNode, usize)>) {
+        for child in node.value().children.lock().unwrap().iter() {
+            if let AstNodeKind::Line { .. } = child.kind() {
+                lines.push((child.clone(), depth));
+                gather_lines(child, depth + 1, lines);
+            }
+        }
+    }
+    let mut lines = Vec::new();
+    gather_lines(ast, 0, &mut lines);
+
+    // Reserve every anchor so generated slugs never collide with them
+    let mut used: HashSet<String> = lines
+        .iter()
+        .flat_map(|(line, _)| match line.kind() {
+            AstNodeKind::Line { properties } => properties
+                .iter()
+                .filter_map(|property| match property {
+                    Property::Anchor { name, .. } => Some(name.clone()),
+                    _ => None,
+                })
+                .collect(),
+            _ => vec![],
+        })
+        .collect();
+
+    let mut headings = Vec::new();
+    for (line, depth) in lines {
+        let AstNodeKind::Line { properties } = line.kind() else {
+            continue;
+        };
+        let Some(level) = heading_level(&line).filter(|_| depth == 0) else {
+            continue;
+        };
+        let contents = line.value().contents.lock().unwrap();
+        let text = plain_text(&contents[0]);
+        let anchor = properties.iter().find_map(|property| match property {
+            Property::Anchor { name, .. } => Some(name.clone()),
+            _ => None,
+        });
+        let has_anchor = anchor.is_some();
+        let id = anchor.unwrap_or_else(|| {
+            let slug = slugify(&text);
+            let base = if slug.is_empty() { "heading" } else { &slug };
+            unique_slug(base, &mut used)
+        });
+        headings.push(TocHeading {
+            row: line.location().row,
+            level,
+            id,
+            text,
+            has_anchor,
+        });
+    }
+    headings
+}
+
+/// Concatenate the visible text of a node's contents
+pub(crate) fn plain_text(node: &AstNode) -> String {
+    match node.kind() {
+        AstNodeKind::Text | AstNodeKind::CodeContent | AstNodeKind::MathContent => {
+            node.extract_str().to_string()
+        }
+        AstNodeKind::WikiLink { link, anchor } => match anchor {
+            Some(anchor) => format!("{}#{}", link, anchor),
+            None => link.clone(),
+        },
+        AstNodeKind::Link { link, title } => title.clone().unwrap_or_else(|| link.clone()),
+        _ => node
+            .value()
+            .contents
+            .lock()
+            .unwrap()
+            .iter()
+            .map(plain_text)
+            .collect(),
+    }
+}
+
+/// Format a deadline as Org timestamps; active timestamps use `<>`, inactive `[]`.
+/// A weekly recurrence on several weekdays yields one repeating timestamp per day.
+fn org_timestamps(deadline: &crate::parser::Deadline, active: bool) -> Vec<String> {
+    let (open, close) = if active { ('<', '>') } else { ('[', ']') };
+    let stamp = match deadline {
+        crate::parser::Deadline::Date(d) => d.format("%Y-%m-%d %a").to_string(),
+        crate::parser::Deadline::DateTime(dt) => dt.format("%Y-%m-%d %a %H:%M").to_string(),
+        // Org timestamps carry no offset; show the local wall-clock time
+        crate::parser::Deadline::DateTimeTz(dt) => crate::parser::local_naive(dt)
+            .format("%Y-%m-%d %a %H:%M")
+            .to_string(),
+        crate::parser::Deadline::TimeRange { start, end } => format!(
+            "{}-{}",
+            start.format("%Y-%m-%d %a %H:%M"),
+            end.format("%H:%M")
+        ),
+        crate::parser::Deadline::Recurring(recurrence) => {
+            // Org repeaters restart from the next occurrence
+            let today = chrono::Local::now().date_naive();
+            let (repeater, recurrences) = match recurrence {
+                crate::parser::Recurrence::Daily => ("+1d", vec![recurrence.clone()]),
+                crate::parser::Recurrence::Weekly(days) => (
+                    "+1w",
+                    days.iter()
+                        .map(|day| crate::parser::Recurrence::Weekly(vec![*day]))
+                        .collect(),
+                ),
+                crate::parser::Recurrence::Monthly(_) => ("+1m", vec![recurrence.clone()]),
+            };
+            let mut dates: Vec<_> = recurrences
+                .iter()
+                .map(|recurrence| recurrence.next_occurrence(today))
+                .collect();
+            dates.sort();
+            dates.dedup();
+            return dates
+                .into_iter()
+                .map(|date| {
+                    format!(
+                        "{}{} {}{}",
+                        open,
+                        date.format("%Y-%m-%d %a"),
+                        repeater,
+                        close
+                    )
+                })
+                .collect();
+        }
+        crate::parser::Deadline::Uninterpretable(_) => return Vec::new(),
+    };
+    vec![format!("{}{}{}", open, stamp, close)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_text;
+
+    /// Location-free outline of a tree, for comparing the structure of two parses
+    fn shape(node: &AstNode, out: &mut String, depth: usize) {
+        let indent = "  ".repeat(depth);
+        let properties = match node.kind() {
+            AstNodeKind::Line { properties } | AstNodeKind::QuoteContent { properties } => {
+                Some(properties)
+            }
+            _ => None,
+        };
+        match properties {
+            Some(properties) => {
+                let kind = if matches!(node.kind(), AstNodeKind::Line { .. }) {
+                    "Line"
+                } else {
+                    "QuoteContent"
+                };
+                out.push_str(&format!("{}{}", indent, kind));
+                for property in properties {
+                    match property {
+                        Property::Task {
+                            status,
+                            due,
+                            scheduled,
+                            completed_at,
+                            started_at,
+                            time_spent,
+                            ..
+                        } => out.push_str(&format!(
+                            " task({:?} {} {:?} {:?} {:?} {:?})",
+                            status, due, scheduled, completed_at, started_at, time_spent
+                        )),
+                        Property::Anchor { name, .. } => out.push_str(&format!(" #{}", name)),
+                        Property::Tag { .. } => {
+                            out.push_str(&format!(" {}", property.tag_source().unwrap()))
+                        }
+                    }
+                }
+                out.push('\n');
+            }
+            None => {
+                out.push_str(&format!("{}{:?}", indent, node.kind()));
+                if matches!(
+                    node.kind(),
+                    AstNodeKind::Text | AstNodeKind::CodeContent | AstNodeKind::MathContent
+                ) {
+                    out.push_str(&format!(" {:?}", node.extract_str()));
+                }
+                out.push('\n');
+            }
+        }
+        for content in node.value().contents.lock().unwrap().iter() {
+            shape(content, out, depth + 1);
+        }
+        for child in node.value().children.lock().unwrap().iter() {
+            out.push_str(&format!("{}child:\n", indent));
+            shape(child, out, depth + 1);
+        }
+    }
+
+    fn render_patto(ast: &AstNode) -> String {
+        let mut output = Vec::new();
+        PattoRenderer::new().format(ast, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    const CORPUS: &[&str] = &[
+        "plain line\n\tchild\n\t\tgrandchild\nsibling\n",
+        "fix the bug {@task status=todo due=2024-12-31} #bug {@tag project=patto}\n",
+        "reviewed #review {@task status=done due=2024-01-02 completed_at=2024-01-03}\n",
+        "tracked {@task status=doing due=2024-05-01T10:00 started_at=2024-05-01T09:00 time_spent=1h30m}\n",
+        "shorthand !2024-06-01\n",
+        "{@tag priority=high} first #a #b\n",
+        "see [` let x = 1; `] and [`tight`] and [$ x^2 $]\n",
+        "[* bold] [/ italic] [** big [_ under] text] [*/-_ all] [- gone]\n",
+        "[@img ./cat.png \"a \\\"quoted\\\" cat\"] [@img https://example.com/a.png]\n",
+        "[@img \"alt text\" ./dog.png] [@img ./dog.png plain alt]\n",
+        "[note] [note#anchor] [#self] [title https://example.com] [https://example.com]\n",
+        "[@embed https://www.youtube.com/watch?v=abc] [./file.txt]\n",
+        "term:: definition with [link]\n",
+        "a \\[not a link\\] \\* and \\\\ C:\\path\n",
+        "\\#not-an-anchor [* \\]]\n",
+        "text %% aside %% more #a\n// commented out\n\tnested\n",
+        "-----\n",
+        "parent\n\t[@code rust]\n\t\tfn main() {\n\t\t    println!(\"hi\");\n\t\t}\n\tafter code\n",
+        "parent\n\t[@math]\n\t\tx = y\n\t[@table caption=\"cap\"]\n\t\ta\tb\n\t\t[link]\tc\n",
+        "[@quote]\n\tquoted line\n\t\tnested in quote\n\tsecond\nafter quote\n",
+        "parent\n\t[@quote]\n\t\tindented quote\n",
+    ];
+
+    #[test]
+    fn patto_round_trip_is_structural_identity() {
+        for source in CORPUS {
+            let first = parse_text(source);
+            assert!(
+                first.parse_errors.is_empty(),
+                "{:?}: {:?}",
+                source,
+                first.parse_errors
+            );
+            let rendered = render_patto(&first.ast);
+            let second = parse_text(&rendered);
+            assert!(
+                second.parse_errors.is_empty(),
+                "{:?} rendered as {:?}: {:?}",
+                source,
+                rendered,
+                second.parse_errors
+            );
+
+            let (mut expected, mut actual) = (String::new(), String::new());
+            shape(&first.ast, &mut expected, 0);
+            shape(&second.ast, &mut actual, 0);
+            assert_eq!(expected, actual, "{:?} rendered as {:?}", source, rendered);
+
+            // Rendering canonical output again changes nothing
+            assert_eq!(rendered, render_patto(&second.ast), "{:?}", source);
+        }
     }
 }
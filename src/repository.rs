@@ -1,18 +1,27 @@
-use std::collections::{HashMap, HashSet};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use dashmap::DashMap;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
 use gdsl::sync_digraph::Graph;
 use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher};
 use serde::{Deserialize, Serialize};
+use str_indices::utf16::to_byte_idx as utf16_to_byte_idx;
 use tokio::sync::{broadcast, mpsc};
 use tokio::time::sleep;
-use tower_lsp::lsp_types::Url;
+use tower_lsp::lsp_types::{Position, Range, Url};
 use urlencoding::encode;
 
+use crate::ignore::{IgnoreRules, IGNORE_FILENAME};
+use crate::index_cache::{content_hash, modified_time, CachedLink, CachedNote, IndexCache};
+use crate::line_tracker::LineTracker;
 use crate::parser::{self, AstNode, AstNodeKind, Deadline, Location, Property, TaskStatus};
+use crate::renderer::{collect_headings, plain_text};
+use crate::stats::NoteStats;
 
 // ---------------------------------------------------------------------------
 // Workspace config (.patto.toml in notes directory)
@@ -20,12 +29,18 @@ use crate::parser::{self, AstNode, AstNodeKind, Deadline, Location, Property, Ta
 
 pub const WORKSPACE_CONFIG_FILENAME: &str = ".patto.toml";
 
+/// Extension of note files, without the dot, unless configured otherwise
+pub const DEFAULT_NOTE_EXTENSION: &str = "pn";
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct PattoWorkspaceConfig {
     #[serde(default)]
     pub version: u32,
     #[serde(default)]
     pub pinned_files: Vec<String>,
+    /// Extra `.gitignore`-style patterns of paths that are never scanned
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<String>,
 }
 
 pub fn load_workspace_config(dir: &Path) -> PattoWorkspaceConfig {
@@ -43,8 +58,43 @@ pub fn save_workspace_config(dir: &Path, config: &PattoWorkspaceConfig) -> anyho
     Ok(())
 }
 
+/// Optional loosening of how WikiLinks are matched to note files.
+///
+/// A link whose note does not exist under its exact name resolves to a note
+/// with the same [`key`](Self::key) instead, e.g. `[My Note]` to `my_note.pn`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkNormalization {
+    /// Match links to note names regardless of case
+    #[serde(default)]
+    pub ignore_case: bool,
+    /// Treat spaces and underscores as the same character
+    #[serde(default)]
+    pub ignore_separators: bool,
+}
+
+impl LinkNormalization {
+    pub fn is_enabled(&self) -> bool {
+        self.ignore_case || self.ignore_separators
+    }
+
+    /// Key a link or note name is matched by; names with equal keys refer to the same note
+    pub fn key(&self, name: &str) -> String {
+        let key = if self.ignore_separators {
+            name.replace(' ', "_")
+        } else {
+            name.to_string()
+        };
+        if self.ignore_case {
+            key.to_lowercase()
+        } else {
+            key
+        }
+    }
+}
+
 /// Location information for a WikiLink
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct LinkLocation {
     /// Source line number (0-indexed)
     pub source_line: usize,
@@ -61,6 +111,53 @@ pub struct LinkEdge {
     pub locations: Vec<LinkLocation>,
 }
 
+/// Narrows [`Repository::aggregate_tasks`]; the default keeps every non-Done task
+#[derive(Debug, Clone, Default)]
+pub struct TaskFilter {
+    /// Keep only tasks with this status
+    pub status: Option<TaskStatus>,
+    /// Inclusive lower bound on the due date
+    pub from: Option<chrono::NaiveDate>,
+    /// Inclusive upper bound on the due date
+    pub to: Option<chrono::NaiveDate>,
+    /// Keep Done tasks as well
+    pub include_done: bool,
+}
+
+impl TaskFilter {
+    /// Whether a task with `status` and `due` passes; tasks without a
+    /// dated deadline are dropped once either bound is set
+    pub fn matches(&self, status: &TaskStatus, due: &Deadline) -> bool {
+        let status_ok = match &self.status {
+            Some(wanted) => wanted == status,
+            None => self.include_done || !matches!(status, TaskStatus::Done),
+        };
+        if !status_ok {
+            return false;
+        }
+        if self.from.is_none() && self.to.is_none() {
+            return true;
+        }
+        due.date().is_some_and(|date| {
+            self.from.is_none_or(|f| date >= f) && self.to.is_none_or(|t| date <= t)
+        })
+    }
+}
+
+/// Two-hop connections grouped by intermediary note: `(intermediary, [(note, snippet)])`
+pub type TwoHopNotes = Vec<(Url, Vec<(Url, Option<String>)>)>;
+
+/// The notes around a note in the document graph
+#[derive(Debug, Clone, Default)]
+pub struct Neighborhood {
+    /// Notes the note links to, with the number of links to each
+    pub outgoing: Vec<(Url, usize)>,
+    /// Notes linking to the note, with the number of links from each
+    pub incoming: Vec<(Url, usize)>,
+    /// Notes sharing a link target with the note, as in [`Repository::two_hop_notes`]
+    pub two_hop: TwoHopNotes,
+}
+
 /// Link location data for preview (serializable)
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct LinkLocationData {
@@ -92,6 +189,47 @@ pub struct FileMetadata {
     pub link_count: u32,
 }
 
+/// A line matching a full-text search query
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SearchHit {
+    pub uri: Url,
+    /// Line number (0-indexed)
+    pub line: usize,
+    /// The matched line, without its trailing newline
+    pub text: String,
+    /// Fuzzy match score; higher is better
+    pub score: i64,
+}
+
+/// A copy of the document graph taken by [`Repository::graph_snapshot`]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct GraphSnapshot {
+    /// Every note in the graph, including notes without links, sorted
+    pub nodes: Vec<Url>,
+    /// Links between notes, sorted by source and then by target
+    pub edges: Vec<GraphSnapshotEdge>,
+}
+
+/// All links from one note to another in a [`GraphSnapshot`]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct GraphSnapshotEdge {
+    pub source: Url,
+    pub target: Url,
+    /// Number of links, i.e. the length of `locations`
+    pub count: usize,
+    pub locations: Vec<LinkLocation>,
+}
+
+/// Output format of [`Repository::export_graph`]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphFormat {
+    /// GraphViz DOT, e.g. for `dot -Tsvg`
+    Dot,
+    /// `{ "nodes": [{ "id" }], "edges": [{ "source", "target", "count" }] }`
+    Json,
+}
+
 /// Messages for repository change notifications
 #[derive(Clone, Debug)]
 pub enum RepositoryMessage {
@@ -101,11 +239,63 @@ pub enum RepositoryMessage {
     BackLinksChanged(PathBuf, Vec<BackLinkData>),
     TwoHopLinksChanged(PathBuf, Vec<(String, Vec<String>)>),
     WorkspaceConfigChanged(PattoWorkspaceConfig),
-    ScanStarted { total_files: usize },
-    ScanProgress { scanned: usize, total: usize },
-    ScanCompleted { total_files: usize },
+    ScanStarted {
+        total_files: usize,
+    },
+    ScanProgress {
+        scanned: usize,
+        total: usize,
+        /// Milliseconds since the scan started
+        elapsed_ms: u64,
+    },
+    ScanCompleted {
+        total_files: usize,
+        /// Files and directories skipped by the ignore rules; an ignored
+        /// directory counts once, its contents are not visited
+        ignored: usize,
+    },
+}
+
+/// Snapshot of the workspace scan for consumers that poll
+/// [`Repository::scan_state`] instead of subscribing to scan messages
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ScanState {
+    pub scanned: usize,
+    pub total: usize,
+    /// Milliseconds since the scan started
+    pub elapsed_ms: u64,
+    pub completed: bool,
 }
 
+impl ScanState {
+    /// Share of the notes scanned so far, from 0 to 100
+    pub fn percentage(&self) -> u32 {
+        if self.completed {
+            return 100;
+        }
+        (self.scanned * 100).checked_div(self.total).unwrap_or(0) as u32
+    }
+
+    /// Estimated milliseconds until the scan completes, extrapolated from the
+    /// pace so far. `None` before the first note is scanned.
+    pub fn eta_ms(&self) -> Option<u64> {
+        if self.completed {
+            return Some(0);
+        }
+        if self.scanned == 0 {
+            return None;
+        }
+        let remaining = self.total.saturating_sub(self.scanned) as u64;
+        Some(self.elapsed_ms * remaining / self.scanned as u64)
+    }
+}
+
+/// Quiet period after which a burst of watcher events on a file is processed
+pub const DEFAULT_WATCH_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// File extensions offered by `[@img` path completion
+pub const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp", "svg"];
+
 /// Repository manages the collection of notes and their relationships
 #[derive(Clone)]
 pub struct Repository {
@@ -127,15 +317,65 @@ pub struct Repository {
     /// Document content cache
     pub document_map: Arc<DashMap<Url, ropey::Rope>>,
 
+    /// Line trackers keeping stable line ids across incremental edits
+    line_trackers: Arc<DashMap<Url, LineTracker>>,
+
     /// Workspace-level config (.patto.toml in notes directory)
     pub workspace_config: Arc<Mutex<PattoWorkspaceConfig>>,
+
+    /// Progress of the initial workspace scan
+    scan_state: Arc<Mutex<ScanState>>,
+
+    /// Paths below `root_dir` that are never scanned or watched
+    ignore_rules: Arc<Mutex<IgnoreRules>>,
+
+    /// Image files in the workspace, sorted; refreshed on every workspace scan
+    pub image_files: Arc<Mutex<Vec<PathBuf>>>,
+
+    /// Extension of note files, without the dot
+    pub note_extension: String,
+
+    /// How WikiLinks are matched to note files
+    link_normalization: Arc<Mutex<LinkNormalization>>,
+
+    /// Names of all known notes, keyed by their [`LinkNormalization::key`]
+    link_index: Arc<Mutex<HashMap<String, BTreeSet<String>>>>,
+
+    /// Notes whose links were restored from the index cache and which have not
+    /// been parsed yet, with their cache entries
+    cached_notes: Arc<Mutex<HashMap<Url, CachedNote>>>,
+
+    /// Where the index cache is kept; `None` unless enabled with
+    /// [`Repository::with_index_cache`]
+    index_cache_path: Option<PathBuf>,
 }
 
 impl Repository {
     /// Create a new repository and build initial document graph
     pub fn new(root_dir: PathBuf) -> Self {
+        Self::with_note_extension(root_dir, DEFAULT_NOTE_EXTENSION)
+    }
+
+    /// Create a new repository whose notes are `*.{note_extension}` files
+    pub fn with_note_extension(root_dir: PathBuf, note_extension: &str) -> Self {
+        Self::build(root_dir, note_extension, None)
+    }
+
+    /// Create a new repository that keeps an index of its notes at
+    /// `index_cache_path`, so that the next start can skip parsing the notes
+    /// left unchanged. Notes restored from the index are parsed on first use.
+    pub fn with_index_cache(
+        root_dir: PathBuf,
+        note_extension: &str,
+        index_cache_path: PathBuf,
+    ) -> Self {
+        Self::build(root_dir, note_extension, Some(index_cache_path))
+    }
+
+    fn build(root_dir: PathBuf, note_extension: &str, index_cache_path: Option<PathBuf>) -> Self {
         let (tx, _) = broadcast::channel(100);
         let workspace_config = load_workspace_config(&root_dir);
+        let ignore_rules = IgnoreRules::load(&root_dir, &workspace_config.ignore);
 
         let repo = Self {
             root_dir,
@@ -144,7 +384,16 @@ impl Repository {
             link_graph: Arc::new(Mutex::new(HashMap::new())),
             ast_map: Arc::new(DashMap::new()),
             document_map: Arc::new(DashMap::new()),
+            line_trackers: Arc::new(DashMap::new()),
+            scan_state: Arc::new(Mutex::new(ScanState::default())),
+            ignore_rules: Arc::new(Mutex::new(ignore_rules)),
             workspace_config: Arc::new(Mutex::new(workspace_config)),
+            image_files: Arc::new(Mutex::new(Vec::new())),
+            note_extension: note_extension.trim_start_matches('.').to_string(),
+            link_normalization: Arc::new(Mutex::new(LinkNormalization::default())),
+            link_index: Arc::new(Mutex::new(HashMap::new())),
+            cached_notes: Arc::new(Mutex::new(HashMap::new())),
+            index_cache_path,
         };
 
         // Spawn background task for initial scanning to avoid blocking
@@ -156,6 +405,23 @@ impl Repository {
         repo
     }
 
+    /// File name of the note `link`, e.g. `link.pn`
+    pub fn note_file_name(&self, link: &str) -> String {
+        format!("{}.{}", link, self.note_extension)
+    }
+
+    /// Whether `path` has the note file extension
+    pub fn is_note_path(&self, path: &Path) -> bool {
+        path.extension().and_then(|s| s.to_str()) == Some(self.note_extension.as_str())
+    }
+
+    /// `name` without a trailing note file extension
+    pub fn strip_note_extension<'a>(&self, name: &'a str) -> &'a str {
+        name.strip_suffix(self.note_extension.as_str())
+            .and_then(|stem| stem.strip_suffix('.'))
+            .unwrap_or(name)
+    }
+
     /// Subscribe to repository change notifications
     pub fn subscribe(&self) -> broadcast::Receiver<RepositoryMessage> {
         self.tx.subscribe()
@@ -205,10 +471,127 @@ impl Repository {
         }
     }
 
+    /// Current link normalization rules
+    pub fn link_normalization(&self) -> LinkNormalization {
+        *self.link_normalization.lock().unwrap()
+    }
+
+    /// Change how links are matched to notes and re-resolve the links of every
+    /// indexed note
+    pub fn set_link_normalization(&self, normalization: LinkNormalization) {
+        {
+            let mut current = self.link_normalization.lock().unwrap();
+            if *current == normalization {
+                return;
+            }
+            *current = normalization;
+        }
+        self.parse_cached_notes();
+        {
+            let mut index = self.link_index.lock().unwrap();
+            let names: Vec<String> = index.drain().flat_map(|(_, names)| names).collect();
+            for name in names {
+                index
+                    .entry(normalization.key(&name))
+                    .or_default()
+                    .insert(name);
+            }
+        }
+        let documents: Vec<(Url, ropey::Rope, AstNode)> = self
+            .ast_map
+            .iter()
+            .filter_map(|entry| {
+                let rope = self.document_map.get(entry.key())?.value().clone();
+                Some((entry.key().clone(), rope, entry.value().clone()))
+            })
+            .collect();
+        for (uri, rope, ast) in documents {
+            self.update_document(&uri, rope, ast);
+        }
+    }
+
+    /// Other notes whose names have the same normalized key as the note at `uri`.
+    /// Links to any of them are ambiguous; they resolve to the first name in order.
+    pub fn link_collisions(&self, uri: &Url) -> Vec<String> {
+        let normalization = self.link_normalization();
+        if !normalization.is_enabled() {
+            return vec![];
+        }
+        let Some(name) = uri
+            .to_file_path()
+            .ok()
+            .and_then(|path| self.note_name(&path))
+        else {
+            return vec![];
+        };
+        self.link_index
+            .lock()
+            .unwrap()
+            .get(&normalization.key(&name))
+            .map(|names| names.iter().filter(|n| **n != name).cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Name a note is linked by: its path relative to `root_dir` without the extension
+    pub fn note_name(&self, path: &Path) -> Option<String> {
+        if !self.is_note_path(path) {
+            return None;
+        }
+        let rel_path = path.strip_prefix(&self.root_dir).ok()?.with_extension("");
+        let components: Vec<_> = rel_path
+            .components()
+            .map(|c| c.as_os_str().to_str())
+            .collect::<Option<_>>()?;
+        Some(components.join("/"))
+    }
+
+    fn index_note(&self, path: &Path) {
+        if let Some(name) = self.note_name(path) {
+            let key = self.link_normalization().key(&name);
+            self.link_index
+                .lock()
+                .unwrap()
+                .entry(key)
+                .or_default()
+                .insert(name);
+        }
+    }
+
+    fn unindex_note(&self, path: &Path) {
+        if let Some(name) = self.note_name(path) {
+            let key = self.link_normalization().key(&name);
+            let mut index = self.link_index.lock().unwrap();
+            if let Some(names) = index.get_mut(&key) {
+                names.remove(&name);
+                if names.is_empty() {
+                    index.remove(&key);
+                }
+            }
+        }
+    }
+
+    /// Name of the note `link` refers to: `link` itself, unless link normalization
+    /// is enabled and only a differently spelled note matches it
+    fn resolve_link<'a>(&self, link: &'a str) -> Cow<'a, str> {
+        let normalization = self.link_normalization();
+        if !normalization.is_enabled() {
+            return Cow::Borrowed(link);
+        }
+        let index = self.link_index.lock().unwrap();
+        match index.get(&normalization.key(link)) {
+            Some(names) if !names.contains(link) => names
+                .first()
+                .map_or(Cow::Borrowed(link), |name| Cow::Owned(name.clone())),
+            _ => Cow::Borrowed(link),
+        }
+    }
+
     /// Convert link name to file path
     pub fn link_to_path(&self, link: &str) -> Option<PathBuf> {
         if !link.is_empty() {
-            let file_path = self.root_dir.join(format!("{}.pn", link));
+            let file_path = self
+                .root_dir
+                .join(self.note_file_name(&self.resolve_link(link)));
             if file_path.exists() {
                 Some(file_path)
             } else {
@@ -244,9 +627,9 @@ impl Repository {
             let mut linkuri = root_uri.clone();
             linkuri.set_path(
                 format!(
-                    "{}{}.pn",
+                    "{}{}",
                     ensure_trailing_slash(root_uri.path()),
-                    encode(link)
+                    self.note_file_name(&encode(&self.resolve_link(link)))
                 )
                 .as_str(),
             );
@@ -259,12 +642,21 @@ impl Repository {
 
     /// Normalize URL percent encoding
     pub fn normalize_url_percent_encoding(url: &Url) -> Url {
+        Self::recase_percent_encoding(url, str::to_uppercase)
+    }
+
+    /// Percent-encode `url` the way neovim does, with lowercase hex digits.
+    /// Every URI sent to the client goes through this, so that files the
+    /// editor opened and files the server points it to are the same buffer.
+    pub fn client_url_percent_encoding(url: &Url) -> Url {
+        Self::recase_percent_encoding(url, str::to_lowercase)
+    }
+
+    fn recase_percent_encoding(url: &Url, recase: fn(&str) -> String) -> Url {
         let re = regex::Regex::new(r"%[0-9a-fA-F]{2}").unwrap();
-        let normalized = re.replace_all(url.as_str(), |caps: &regex::Captures| {
-            caps[0].to_uppercase()
-        });
+        let recased = re.replace_all(url.as_str(), |caps: &regex::Captures| recase(&caps[0]));
 
-        Url::parse(&normalized).unwrap_or(url.clone())
+        Url::parse(&recased).unwrap_or(url.clone())
     }
 
     //// Count links in a patto file using the parser
@@ -289,7 +681,7 @@ impl Repository {
 
                 if path.is_dir() {
                     self.collect_patto_files_with_metadata(&path, files, metadata);
-                } else if path.extension().and_then(|s| s.to_str()) == Some("pn") {
+                } else if self.is_note_path(&path) {
                     if let Ok(rel_path) = path.strip_prefix(&self.root_dir) {
                         let rel_path_str = rel_path.to_string_lossy().to_string();
                         files.push(rel_path_str.clone());
@@ -374,7 +766,9 @@ impl Repository {
                                     .map(|loc| LinkLocationData {
                                         line: loc.source_line,
                                         col_range: loc.source_col_range,
-                                        context: None,
+                                        context: self
+                                            .line_text(source_uri, loc.source_line)
+                                            .map(|text| text.trim().to_string()),
                                         target_anchor: loc.target_anchor.clone(),
                                     })
                                     .collect();
@@ -395,6 +789,233 @@ impl Repository {
         result
     }
 
+    /// Wiki links whose anchor is not defined in the target note.
+    ///
+    /// Returns the source note, the location of the link and the missing
+    /// anchor, ordered by note and position. Self-links are not part of the
+    /// document graph, and links to notes that have not been indexed yet are
+    /// skipped rather than reported.
+    pub fn find_broken_anchors(&self) -> Vec<(Url, Location, String)> {
+        self.parse_cached_notes();
+        let mut broken = Vec::new();
+        if let Ok(graph) = self.document_graph.lock() {
+            for (source_uri, source_node) in graph.iter() {
+                for edge in source_node.iter_out() {
+                    let Some(target_ast) = self.ast_map.get(edge.target().key()) else {
+                        continue;
+                    };
+                    for loc in edge.value().locations.iter() {
+                        let Some(anchor) = &loc.target_anchor else {
+                            continue;
+                        };
+                        if find_anchor(target_ast.value(), anchor).is_some() {
+                            continue;
+                        }
+                        let location = Location {
+                            row: loc.source_line,
+                            input: Arc::from(
+                                self.line_text(source_uri, loc.source_line)
+                                    .unwrap_or_default(),
+                            ),
+                            span: parser::Span(loc.source_col_range.0, loc.source_col_range.1),
+                        };
+                        broken.push((source_uri.clone(), location, anchor.clone()));
+                    }
+                }
+            }
+        }
+        broken.sort_by(|a, b| {
+            (a.0.as_str(), a.1.row, a.1.span.0).cmp(&(b.0.as_str(), b.1.row, b.1.span.0))
+        });
+        broken
+    }
+
+    /// A copy of the document graph with the locations of every link, for
+    /// tools and tests that should not hold the graph lock. No ASTs are cloned.
+    pub fn graph_snapshot(&self) -> GraphSnapshot {
+        let mut snapshot = GraphSnapshot::default();
+        if let Ok(graph) = self.document_graph.lock() {
+            for (source_uri, source_node) in graph.iter() {
+                snapshot.nodes.push(source_uri.clone());
+                for edge in source_node.iter_out() {
+                    let locations = edge.value().locations.clone();
+                    snapshot.edges.push(GraphSnapshotEdge {
+                        source: source_uri.clone(),
+                        target: edge.target().key().clone(),
+                        count: locations.len(),
+                        locations,
+                    });
+                }
+            }
+        }
+        snapshot.nodes.sort();
+        snapshot
+            .edges
+            .sort_by(|a, b| (&a.source, &a.target).cmp(&(&b.source, &b.target)));
+        snapshot
+    }
+
+    /// Export the document graph for visualization.
+    ///
+    /// Nodes are note paths relative to `root_dir`, including notes without any
+    /// links, and each edge carries the number of links from its source to its
+    /// target. Nodes and edges are sorted so the output is stable.
+    pub fn export_graph(&self, format: GraphFormat) -> String {
+        let snapshot = self.graph_snapshot();
+        let mut nodes: Vec<String> = snapshot
+            .nodes
+            .iter()
+            .map(|uri| self.relative_note_path(uri))
+            .collect();
+        let mut edges: Vec<(String, String, usize)> = snapshot
+            .edges
+            .iter()
+            .map(|edge| {
+                (
+                    self.relative_note_path(&edge.source),
+                    self.relative_note_path(&edge.target),
+                    edge.count,
+                )
+            })
+            .collect();
+        nodes.sort();
+        edges.sort();
+
+        match format {
+            GraphFormat::Dot => {
+                fn quote(id: &str) -> String {
+                    format!("\"{}\"", id.replace('\\', "\\\\").replace('"', "\\\""))
+                }
+                let mut out = String::from("digraph patto {\n");
+                for node in &nodes {
+                    out.push_str(&format!("  {};\n", quote(node)));
+                }
+                for (source, target, count) in &edges {
+                    out.push_str(&format!(
+                        "  {} -> {} [label=\"{}\", weight={}];\n",
+                        quote(source),
+                        quote(target),
+                        count,
+                        count
+                    ));
+                }
+                out.push_str("}\n");
+                out
+            }
+            GraphFormat::Json => serde_json::json!({
+                "nodes": nodes
+                    .iter()
+                    .map(|id| serde_json::json!({ "id": id }))
+                    .collect::<Vec<_>>(),
+                "edges": edges
+                    .iter()
+                    .map(|(source, target, count)| {
+                        serde_json::json!({ "source": source, "target": target, "count": count })
+                    })
+                    .collect::<Vec<_>>(),
+            })
+            .to_string(),
+        }
+    }
+
+    /// Notes that link to no other note and that no other note links to,
+    /// sorted by URI. A note whose only links point at itself is an orphan;
+    /// see [`Self::orphan_notes_with`] to count self-links as links.
+    pub fn orphan_notes(&self) -> Vec<Url> {
+        self.orphan_notes_with(false)
+    }
+
+    /// Like [`Self::orphan_notes`], but notes linking to themselves are not
+    /// orphans when `count_self_links` is set.
+    ///
+    /// The graph follows the editor buffers, so open notes are judged by their
+    /// unsaved content.
+    pub fn orphan_notes_with(&self, count_self_links: bool) -> Vec<Url> {
+        self.parse_cached_notes();
+        let Ok(graph) = self.document_graph.lock() else {
+            return Vec::new();
+        };
+        let root_uri = Url::from_directory_path(&self.root_dir).ok();
+
+        let mut orphans: Vec<Url> = self
+            .ast_map
+            .iter()
+            .filter(|entry| {
+                let uri = entry.key();
+                let linked = graph.get(uri).is_some_and(|node| {
+                    node.iter_out().next().is_some() || node.iter_in().next().is_some()
+                });
+                if linked {
+                    return false;
+                }
+                if !count_self_links {
+                    return true;
+                }
+                // Self-links are not part of the graph, so look at the AST
+                let mut wikilinks = vec![];
+                Self::gather_wikilinks(entry.value(), &mut wikilinks);
+                !wikilinks.iter().any(|(link, _, _)| {
+                    link.is_empty()
+                        || root_uri
+                            .as_ref()
+                            .and_then(|root_uri| self.link_to_uri(link, root_uri))
+                            .is_some_and(|link_uri| &link_uri == uri)
+                })
+            })
+            .map(|entry| entry.key().clone())
+            .collect();
+        orphans.sort();
+        orphans
+    }
+
+    /// Path of a note relative to `root_dir`, or the URI itself if it is not a
+    /// file below the root
+    fn relative_note_path(&self, uri: &Url) -> String {
+        let Ok(path) = uri.to_file_path() else {
+            return uri.to_string();
+        };
+        path.strip_prefix(&self.root_dir)
+            .unwrap_or(&path)
+            .display()
+            .to_string()
+    }
+
+    /// Word, line and task counts of a cached note, or `None` if the note has
+    /// not been indexed
+    pub fn note_stats(&self, uri: &Url) -> Option<NoteStats> {
+        self.parse_cached_note(uri);
+        let ast = self.ast_map.get(uri)?.value().clone();
+        let rope = self.document_map.get(uri)?;
+        // A trailing newline does not start another line
+        let mut lines = rope.len_lines();
+        if rope.len_chars() == 0 || rope.char(rope.len_chars() - 1) == '\n' {
+            lines -= 1;
+        }
+        Some(NoteStats::from_ast(&ast, lines))
+    }
+
+    /// Text of a cached document line (0-indexed), without its trailing newline
+    pub fn line_text(&self, uri: &Url, line: usize) -> Option<String> {
+        let Some(rope) = self.document_map.get(uri) else {
+            // Notes restored from the cache are only on disk; this may run under
+            // the graph lock, so read the file instead of parsing it
+            if !self.cached_notes.lock().unwrap().contains_key(uri) {
+                return None;
+            }
+            let path = uri.to_file_path().ok()?;
+            let text = std::fs::read_to_string(path).ok()?;
+            return text
+                .lines()
+                .nth(line)
+                .map(|text| text.trim_end_matches('\r').to_string());
+        };
+        if line >= rope.len_lines() {
+            return None;
+        }
+        let text = rope.line(line).to_string();
+        Some(text.trim_end_matches(['\n', '\r']).to_string())
+    }
+
     /// Helper method to get backlink count (for metadata)
     pub fn count_back_links(&self, file_path: &Path) -> usize {
         let back_links = self.calculate_back_links(file_path);
@@ -424,63 +1045,140 @@ impl Repository {
         };
         let uri = Self::normalize_url_percent_encoding(&uri);
 
-        let mut two_hop_links: Vec<(String, Vec<String>)> = Vec::new();
+        let link_name = |uri: &Url| {
+            uri.to_file_path()
+                .ok()
+                .and_then(|path| self.path_to_link(&path))
+        };
+        let mut two_hop_links: Vec<(String, Vec<String>)> = self
+            .two_hop_notes(&uri)
+            .into_iter()
+            .filter_map(|(bridge, notes)| {
+                // The bridge is the file that connects us to the others
+                let bridge_link_name = link_name(&bridge)?;
+                let connected_files: Vec<String> = notes
+                    .iter()
+                    .filter_map(|(note, _)| link_name(note))
+                    .collect();
+                (!connected_files.is_empty()).then_some((bridge_link_name, connected_files))
+            })
+            .collect();
+
+        // Sort by number of connections (descending), then by bridge name
+        two_hop_links.sort_by(|(a, a_files), (b, b_files)| {
+            b_files.len().cmp(&a_files.len()).then_with(|| a.cmp(b))
+        });
+        two_hop_links
+    }
+
+    /// Notes sharing a link target with `uri`, grouped by that intermediary note.
+    ///
+    /// Returns `(intermediary, notes)` pairs ordered by the number of notes
+    /// (descending) and then by intermediary URI. Each note comes with the
+    /// trimmed text of its first line linking to the intermediary and is listed
+    /// once, ordered by URI.
+    pub fn two_hop_notes(&self, uri: &Url) -> TwoHopNotes {
+        let uri = Self::normalize_url_percent_encoding(uri);
+        let mut grouped: BTreeMap<Url, BTreeMap<Url, Option<String>>> = BTreeMap::new();
 
         if let Ok(graph) = self.document_graph.lock() {
             if let Some(node) = graph.get(&uri) {
                 // For each file this file links to (direct links)
                 for edge in node.iter_out() {
                     let target_uri = edge.target().key();
-                    let target_node = edge.target();
-
-                    // Find other files that also link to this same target (two-hop connections)
-                    let mut connected_files = Vec::new();
 
-                    for incoming_edge in target_node.iter_in() {
+                    // Find other files that also link to this same target
+                    for incoming_edge in edge.target().iter_in() {
                         let source_uri = incoming_edge.source().key();
                         // Skip self and the direct target
-                        if source_uri != &uri && source_uri != target_uri {
-                            if let Ok(source_path) = source_uri.to_file_path() {
-                                if let Some(link_name) = self.path_to_link(&source_path) {
-                                    connected_files.push(link_name);
-                                }
-                            }
-                        }
-                    }
-
-                    if !connected_files.is_empty() {
-                        // Get the bridge link name (the file that connects us to others)
-                        if let Ok(target_path) = target_uri.to_file_path() {
-                            if let Some(bridge_link_name) = self.path_to_link(&target_path) {
-                                connected_files.sort();
-                                two_hop_links.push((bridge_link_name, connected_files));
-                            }
+                        if source_uri == &uri || source_uri == target_uri {
+                            continue;
                         }
+                        let snippet = incoming_edge
+                            .value()
+                            .locations
+                            .iter()
+                            .map(|loc| loc.source_line)
+                            .min()
+                            .and_then(|line| self.line_text(source_uri, line))
+                            .map(|text| text.trim().to_string());
+                        grouped
+                            .entry(target_uri.clone())
+                            .or_default()
+                            .insert(source_uri.clone(), snippet);
                     }
                 }
             }
         }
 
-        // Sort by number of connections (descending)
-        two_hop_links.sort_by_key(|(_, connections)| -(connections.len() as i32));
-        two_hop_links
+        let mut two_hop_notes: TwoHopNotes = grouped
+            .into_iter()
+            .map(|(via, notes)| (via, notes.into_iter().collect()))
+            .collect();
+        // Stable, so ties keep the URI order of the map
+        two_hop_notes.sort_by_key(|(_, notes)| std::cmp::Reverse(notes.len()));
+        two_hop_notes
+    }
+
+    /// Outgoing links, backlinks and two-hop notes of `uri`, or `None` if the
+    /// note is not in the document graph. Direct neighbors are ordered by URI.
+    pub fn neighborhood(&self, uri: &Url) -> Option<Neighborhood> {
+        let uri = Self::normalize_url_percent_encoding(uri);
+        let (mut outgoing, mut incoming) = {
+            let graph = self.document_graph.lock().ok()?;
+            let node = graph.get(&uri)?;
+            let outgoing: Vec<(Url, usize)> = node
+                .iter_out()
+                .map(|edge| (edge.target().key().clone(), edge.value().locations.len()))
+                .collect();
+            let incoming: Vec<(Url, usize)> = node
+                .iter_in()
+                .map(|edge| (edge.source().key().clone(), edge.value().locations.len()))
+                .collect();
+            (outgoing, incoming)
+        };
+        outgoing.sort();
+        incoming.sort();
+        Some(Neighborhood {
+            outgoing,
+            incoming,
+            two_hop: self.two_hop_notes(&uri),
+        })
     }
 
     /// Build initial document graph by scanning all files
     async fn build_initial_graph(&self) {
         // Collect all files first to know total count
-        let files = self.collect_pn_files(&self.root_dir);
+        let (files, ignored) = self.collect_note_files(&self.root_dir);
         let total = files.len();
+        let cache = self.load_cache();
+        // Index every note up front so links to notes scanned later resolve
+        for file_path in &files {
+            self.index_note(file_path);
+        }
 
         // Send start message
+        let started = Instant::now();
+        *self.scan_state.lock().unwrap() = ScanState {
+            total,
+            ..ScanState::default()
+        };
         let _ = self
             .tx
             .send(RepositoryMessage::ScanStarted { total_files: total });
 
         // Process files with progress updates
         for (idx, file_path) in files.iter().enumerate() {
-            if let Ok(content) = std::fs::read_to_string(file_path) {
-                self.add_file_to_graph(file_path, &content);
+            let cached = self
+                .note_name(file_path)
+                .and_then(|name| cache.notes.get(&name));
+            self.load_note(file_path, cached);
+
+            let elapsed_ms = started.elapsed().as_millis() as u64;
+            {
+                let mut state = self.scan_state.lock().unwrap();
+                state.scanned = idx + 1;
+                state.elapsed_ms = elapsed_ms;
             }
 
             tokio::task::yield_now().await;
@@ -489,42 +1187,240 @@ impl Repository {
                 let _ = self.tx.send(RepositoryMessage::ScanProgress {
                     scanned: idx + 1,
                     total,
+                    elapsed_ms,
                 });
             }
         }
 
+        self.refresh_image_files();
+        {
+            let mut state = self.scan_state.lock().unwrap();
+            state.elapsed_ms = started.elapsed().as_millis() as u64;
+            state.completed = true;
+        }
+
         // Send completion message
-        let _ = self
-            .tx
-            .send(RepositoryMessage::ScanCompleted { total_files: total });
+        let _ = self.tx.send(RepositoryMessage::ScanCompleted {
+            total_files: total,
+            ignored,
+        });
+
+        // Refresh the cache for the next start; restored notes keep their entries
+        if total > 0 && self.index_cache_path.is_some() {
+            if let Err(e) = self.save_cache() {
+                log::warn!("Failed to save the index cache: {}", e);
+            }
+        }
+    }
+
+    /// Read the index saved by [`Self::save_cache`]; empty if there is none or
+    /// the index cache is not enabled
+    pub fn load_cache(&self) -> IndexCache {
+        self.index_cache_path
+            .as_deref()
+            .map(IndexCache::load)
+            .unwrap_or_default()
+    }
+
+    /// Save the links of every note whose cached content matches the file on
+    /// disk, so that the next scan can skip parsing the notes left unchanged.
+    /// Notes with unsaved editor changes are left out. Does nothing unless the
+    /// index cache is enabled.
+    pub fn save_cache(&self) -> anyhow::Result<()> {
+        let Some(index_cache_path) = &self.index_cache_path else {
+            return Ok(());
+        };
+        let mut cache = IndexCache::default();
+        for (uri, cached) in self.cached_notes.lock().unwrap().iter() {
+            if let Some(name) = uri.to_file_path().ok().and_then(|p| self.note_name(&p)) {
+                cache.notes.insert(name, cached.clone());
+            }
+        }
+        for entry in self.ast_map.iter() {
+            let Ok(path) = entry.key().to_file_path() else {
+                continue;
+            };
+            let Some(name) = self.note_name(&path) else {
+                continue;
+            };
+            // Read the time first so that a concurrent write invalidates the entry
+            let Some(modified) = modified_time(&path) else {
+                continue;
+            };
+            let Ok(content) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            let matches_disk = self
+                .document_map
+                .get(entry.key())
+                .is_some_and(|rope| *rope == content.as_str());
+            if !matches_disk {
+                continue;
+            }
+            cache.notes.insert(
+                name,
+                CachedNote {
+                    modified,
+                    hash: content_hash(&content),
+                    links: cached_links(entry.value()),
+                },
+            );
+        }
+        cache.save(index_cache_path)
+    }
+
+    /// Add the note at `path` to the graph, from its `cached` entry if the note
+    /// is unchanged and by parsing it otherwise. A note is unchanged if its
+    /// modification time is, or else its content hash.
+    fn load_note(&self, path: &Path, cached: Option<&CachedNote>) {
+        let Ok(uri) = Url::from_file_path(path) else {
+            return;
+        };
+        let uri = Self::normalize_url_percent_encoding(&uri);
+        let modified = modified_time(path);
+        if let Some(cached) = cached.filter(|cached| Some(cached.modified) == modified) {
+            self.restore_note(&uri, cached.clone());
+            return;
+        }
+        let Ok(content) = std::fs::read_to_string(path) else {
+            return;
+        };
+        match cached {
+            Some(cached) if cached.hash == content_hash(&content) => {
+                let mut cached = cached.clone();
+                cached.modified = modified.unwrap_or(cached.modified);
+                self.restore_note(&uri, cached);
+            }
+            _ => self.add_file_to_graph(path, &content),
+        }
+    }
+
+    /// Rebuild the outgoing links of an unchanged note without parsing it
+    fn restore_note(&self, uri: &Url, cached: CachedNote) {
+        self.connect_links(uri, &parser::parse_text("").ast, &cached.links);
+        self.cached_notes
+            .lock()
+            .unwrap()
+            .insert(uri.clone(), cached);
+    }
+
+    /// Parse a note restored from the cache, unless an editor has opened it since
+    fn parse_cached_note(&self, uri: &Url) {
+        if self.cached_notes.lock().unwrap().remove(uri).is_none() {
+            return;
+        }
+        let Ok(path) = uri.to_file_path() else {
+            return;
+        };
+        if let Ok(content) = std::fs::read_to_string(&path) {
+            if !self.document_map.contains_key(uri) {
+                self.add_file_to_graph(&path, &content);
+            }
+        }
     }
 
-    /// Collect all .pn files in directory tree
-    fn collect_pn_files(&self, dir: &Path) -> Vec<PathBuf> {
+    /// The AST of the note `uri`, parsing it first if it was restored from the cache
+    pub fn ast(&self, uri: &Url) -> Option<AstNode> {
+        self.parse_cached_note(uri);
+        self.ast_map.get(uri).map(|entry| entry.value().clone())
+    }
+
+    /// Parse every note restored from the cache that has not been parsed yet.
+    ///
+    /// Queries over all ASTs call this first; it is cheap once every note has
+    /// been parsed.
+    pub fn parse_cached_notes(&self) {
+        let uris: Vec<Url> = self.cached_notes.lock().unwrap().keys().cloned().collect();
+        for uri in uris {
+            self.parse_cached_note(&uri);
+        }
+    }
+
+    /// Latest progress of the initial workspace scan
+    pub fn scan_state(&self) -> ScanState {
+        *self.scan_state.lock().unwrap()
+    }
+
+    /// Rebuild the cached list of image files below `root_dir`
+    pub fn refresh_image_files(&self) {
+        let mut files = Vec::new();
+        let is_image = |ext: &str| IMAGE_EXTENSIONS.iter().any(|e| ext.eq_ignore_ascii_case(e));
+        self.collect_files_recursive(&self.root_dir, &is_image, &mut files, &mut 0);
+        files.sort();
+        *self.image_files.lock().unwrap() = files;
+    }
+
+    /// Collect all note files in directory tree, and the number of ignored paths
+    fn collect_note_files(&self, dir: &Path) -> (Vec<PathBuf>, usize) {
         let mut files = Vec::new();
-        self.collect_pn_files_recursive(dir, &mut files);
-        files
+        let mut ignored = 0;
+        self.collect_files_recursive(
+            dir,
+            &|ext| ext == self.note_extension,
+            &mut files,
+            &mut ignored,
+        );
+        (files, ignored)
     }
 
-    /// Helper to recursively collect .pn files
-    #[allow(clippy::only_used_in_recursion)]
-    fn collect_pn_files_recursive(&self, dir: &Path, files: &mut Vec<PathBuf>) {
+    /// Helper to recursively collect files whose extension satisfies `matches`,
+    /// skipping and counting ignored paths
+    fn collect_files_recursive(
+        &self,
+        dir: &Path,
+        matches: &dyn Fn(&str) -> bool,
+        files: &mut Vec<PathBuf>,
+        ignored: &mut usize,
+    ) {
         if let Ok(entries) = std::fs::read_dir(dir) {
             for entry in entries.flatten() {
                 let path = entry.path();
-                if path.is_dir() {
-                    self.collect_pn_files_recursive(&path, files);
-                } else if path.extension().and_then(|s| s.to_str()) == Some("pn") {
+                let is_dir = path.is_dir();
+                if let Ok(rel_path) = path.strip_prefix(&self.root_dir) {
+                    if self
+                        .ignore_rules
+                        .lock()
+                        .unwrap()
+                        .is_ignored(rel_path, is_dir)
+                    {
+                        *ignored += 1;
+                        continue;
+                    }
+                }
+                if is_dir {
+                    self.collect_files_recursive(&path, matches, files, ignored);
+                } else if path
+                    .extension()
+                    .and_then(|s| s.to_str())
+                    .is_some_and(matches)
+                {
                     files.push(path);
                 }
             }
         }
     }
 
+    /// Whether `path` is excluded from scanning and watching by the ignore
+    /// rules, either itself or through one of its parent directories
+    pub fn is_ignored_path(&self, path: &Path) -> bool {
+        let Ok(rel_path) = path.strip_prefix(&self.root_dir) else {
+            return false;
+        };
+        self.ignore_rules
+            .lock()
+            .unwrap()
+            .is_excluded(rel_path, path.is_dir())
+    }
+
+    /// Re-read the ignore patterns from `.patto.toml` and `.pattoignore`.
+    /// Notes that are already indexed stay until the next scan.
+    pub fn reload_ignore_rules(&self) {
+        let configured = self.workspace_config.lock().unwrap().ignore.clone();
+        *self.ignore_rules.lock().unwrap() = IgnoreRules::load(&self.root_dir, &configured);
+    }
+
     /// Add a file to the document graph
     pub fn add_file_to_graph(&self, file_path: &Path, content: &str) {
-        use gdsl::sync_digraph::Node as GraphNode;
-
         // Parse the file content
         let result = parser::parse_text(content);
         let rope = ropey::Rope::from_str(content);
@@ -532,86 +1428,233 @@ impl Repository {
         // Create URI for the file
         if let Ok(uri) = Url::from_file_path(file_path) {
             let uri = Self::normalize_url_percent_encoding(&uri);
+            self.update_document(&uri, rope, result.ast);
+        }
+    }
 
-            // Store in document and AST maps
-            self.document_map.insert(uri.clone(), rope);
-            self.ast_map.insert(uri.clone(), result.ast.clone());
-
-            // Extract wikilinks WITH locations
-            let mut wikilinks = vec![];
-            Self::gather_wikilinks(&result.ast, &mut wikilinks);
-
-            // Get root URI for link resolution
-            if let Ok(root_uri) = Url::from_directory_path(&self.root_dir) {
-                // Group links by target URI
-                let mut links_by_target: HashMap<Url, Vec<LinkLocation>> = HashMap::new();
-
-                for (link, anchor, location) in &wikilinks {
-                    if let Some(link_uri) = self.link_to_uri(link, &root_uri) {
-                        let link_loc = LinkLocation {
-                            source_line: location.row,
-                            source_col_range: (location.span.0, location.span.1),
-                            target_anchor: anchor.clone(),
-                        };
-                        links_by_target.entry(link_uri).or_default().push(link_loc);
-                    }
+    /// Apply an editor change to a cached document and refresh its AST.
+    ///
+    /// Only the top-level blocks overlapping the edited lines are reparsed and
+    /// spliced into the cached AST, so untouched subtrees keep their nodes and
+    /// stable line ids; the blocks after an edit that adds or removes lines are
+    /// moved by as many rows. Falls back to a full reparse when the document has
+    /// not been cached yet.
+    pub fn apply_edit(&self, uri: &Url, range: Range, new_text: &str) {
+        let uri = Self::normalize_url_percent_encoding(uri);
+        let Some(old_text) = self.document_map.get(&uri).map(|rope| rope.to_string()) else {
+            if let Ok(file_path) = uri.to_file_path() {
+                self.add_file_to_graph(&file_path, new_text);
+            }
+            return;
+        };
+
+        let start = position_to_byte(&old_text, range.start);
+        let end = position_to_byte(&old_text, range.end).max(start);
+        let text = [&old_text[..start], new_text, &old_text[end..]].concat();
+
+        let ast = self
+            .reparse_edited_blocks(&uri, &old_text, &text, range)
+            .unwrap_or_else(|| parser::parse_text(&text).ast);
+        self.assign_line_ids(&uri, &old_text, &text, &ast);
+        self.update_document(&uri, ropey::Rope::from_str(&text), ast);
+    }
+
+    /// Reparse the top-level blocks touched by `range` and splice them into the cached AST.
+    ///
+    /// A non-empty line without indentation always starts a new top-level block,
+    /// regardless of what precedes it, so the reparsed segment is widened until it
+    /// is bounded by such lines. `range` is in `old_text` rows. Returns `None` when
+    /// a full reparse is required.
+    fn reparse_edited_blocks(
+        &self,
+        uri: &Url,
+        old_text: &str,
+        text: &str,
+        range: Range,
+    ) -> Option<AstNode> {
+        let old_lines: Vec<&str> = old_text.lines().collect();
+        let lines: Vec<&str> = text.lines().collect();
+        if lines.is_empty() || old_lines.is_empty() {
+            return None;
+        }
+        // Rows after the edit move by the number of lines it added
+        let delta = lines.len() as isize - old_lines.len() as isize;
+
+        let old_ast = self.ast_map.get(uri)?.value().clone();
+        let blocks = old_ast.value().children.lock().unwrap().clone();
+        if blocks.is_empty() {
+            return None;
+        }
+
+        let row_of = |i: usize| blocks[i].location().row;
+        let block_at = |row: usize| {
+            blocks
+                .iter()
+                .rposition(|block| block.location().row <= row)
+                .unwrap_or(0)
+        };
+        let is_boundary = |line: &str| !line.is_empty() && !line.starts_with('\t');
+
+        let last_row = old_lines.len() - 1;
+        let mut first = block_at((range.start.line as usize).min(last_row));
+        let mut last = block_at((range.end.line as usize).min(last_row));
+
+        // Empty lines before the segment look ahead to its first line, so that
+        // line must be a boundary both before and after the edit.
+        while first > 0
+            && !(is_boundary(old_lines[row_of(first)])
+                && lines
+                    .get(row_of(first))
+                    .is_some_and(|line| is_boundary(line)))
+        {
+            first -= 1;
+        }
+        // Lines after the edit are unchanged, only moved
+        while last + 1 < blocks.len() && !is_boundary(old_lines[row_of(last + 1)]) {
+            last += 1;
+        }
+
+        let start_row = row_of(first);
+        let end_row = if last + 1 < blocks.len() {
+            row_of(last + 1).checked_add_signed(delta)?
+        } else {
+            lines.len()
+        };
+        if end_row < start_row {
+            return None;
+        }
+
+        // Include the following boundary line so trailing empty lines see the
+        // same lookahead as in a full parse, then drop its node.
+        let lookahead = end_row < lines.len();
+        let segment_end = if lookahead { end_row + 1 } else { end_row };
+        let segment = lines[start_row..segment_end].join("\n");
+        let reparsed = parser::parse_text_at_row(&segment, start_row).ast;
+        let mut new_blocks = reparsed.value().children.lock().unwrap().clone();
+        if lookahead {
+            new_blocks.pop();
+        }
+
+        let root = AstNode::new(text, 0, None, Some(AstNodeKind::Dummy));
+        root.value().children.lock().unwrap().extend(
+            blocks[..first]
+                .iter()
+                .cloned()
+                .chain(new_blocks)
+                .chain(blocks[last + 1..].iter().map(|block| match delta {
+                    0 => block.clone(),
+                    _ => block.shifted(delta),
+                })),
+        );
+        Some(root)
+    }
+
+    /// Assign stable line ids to `ast`, seeding the tracker with `old_text` on first use.
+    fn assign_line_ids(&self, uri: &Url, old_text: &str, text: &str, ast: &AstNode) {
+        let Ok(mut tracker) = self
+            .line_trackers
+            .entry(uri.clone())
+            .or_try_insert_with(|| {
+                let mut tracker = LineTracker::new()?;
+                tracker.process_file_content(old_text)?;
+                anyhow::Ok(tracker)
+            })
+        else {
+            return;
+        };
+        if tracker.process_file_content(text).is_ok() {
+            parser::apply_line_ids_to_ast(ast, &tracker, text);
+        }
+    }
+
+    /// Store a parsed document in the caches and refresh its outgoing links.
+    fn update_document(&self, uri: &Url, rope: ropey::Rope, ast: AstNode) {
+        // Store in document and AST maps
+        self.document_map.insert(uri.clone(), rope);
+        self.ast_map.insert(uri.clone(), ast.clone());
+        self.cached_notes.lock().unwrap().remove(uri);
+        if let Ok(path) = uri.to_file_path() {
+            self.index_note(&path);
+        }
+
+        self.connect_links(uri, &ast, &cached_links(&ast));
+    }
+
+    /// Point the outgoing edges of `uri` at the targets of `links`; `ast` is
+    /// stored with the graph node if the note has none yet.
+    fn connect_links(&self, uri: &Url, ast: &AstNode, links: &[CachedLink]) {
+        use gdsl::sync_digraph::Node as GraphNode;
+
+        // Get root URI for link resolution
+        if let Ok(root_uri) = Url::from_directory_path(&self.root_dir) {
+            // Group links by target URI
+            let mut links_by_target: HashMap<Url, Vec<LinkLocation>> = HashMap::new();
+
+            for link in links {
+                if let Some(link_uri) = self.link_to_uri(&link.link, &root_uri) {
+                    let link_loc = LinkLocation {
+                        source_line: link.line,
+                        source_col_range: link.col_range,
+                        target_anchor: link.anchor.clone(),
+                    };
+                    links_by_target.entry(link_uri).or_default().push(link_loc);
                 }
+            }
+
+            // Update document graph
+            if let Ok(mut graph) = self.document_graph.lock() {
+                // Get or create node for this file
+                let node = graph.get(uri).unwrap_or_else(|| {
+                    let n = GraphNode::new(uri.clone(), ast.clone());
+                    graph.insert(n.clone());
+                    n
+                });
 
-                // Update document graph
-                if let Ok(mut graph) = self.document_graph.lock() {
-                    // Get or create node for this file
-                    let node = graph.get(&uri).unwrap_or_else(|| {
-                        let n = GraphNode::new(uri.clone(), result.ast.clone());
+                // Update edges with location data
+                for (link_uri, locations) in &links_by_target {
+                    // Skip self-links: connecting a node to itself causes a
+                    // re-entrancy deadlock in gdsl's RwLock (connect() acquires
+                    // write on self then write on other, which are the same lock).
+                    if link_uri == uri {
+                        continue;
+                    }
+
+                    // Create or get target node
+                    let target_node = graph.get(link_uri).unwrap_or_else(|| {
+                        // Try to get AST from cache, or create placeholder
+                        let target_ast = self
+                            .ast_map
+                            .get(link_uri)
+                            .map(|entry| entry.value().clone())
+                            .unwrap_or_else(|| {
+                                // Create a placeholder AST
+                                parser::parse_text("").ast
+                            });
+                        let n = GraphNode::new(link_uri.clone(), target_ast);
                         graph.insert(n.clone());
                         n
                     });
 
-                    // Update edges with location data
-                    for (link_uri, locations) in &links_by_target {
-                        // Skip self-links: connecting a node to itself causes a
-                        // re-entrancy deadlock in gdsl's RwLock (connect() acquires
-                        // write on self then write on other, which are the same lock).
-                        if link_uri == &uri {
-                            continue;
-                        }
-
-                        // Create or get target node
-                        let target_node = graph.get(link_uri).unwrap_or_else(|| {
-                            // Try to get AST from cache, or create placeholder
-                            let target_ast = self
-                                .ast_map
-                                .get(link_uri)
-                                .map(|entry| entry.value().clone())
-                                .unwrap_or_else(|| {
-                                    // Create a placeholder AST
-                                    parser::parse_text("").ast
-                                });
-                            let n = GraphNode::new(link_uri.clone(), target_ast);
-                            graph.insert(n.clone());
-                            n
-                        });
-
-                        // Disconnect old edge and create new one with updated data
-                        let _ = node.disconnect(link_uri);
-                        node.connect(
-                            &target_node,
-                            LinkEdge {
-                                locations: locations.clone(),
-                            },
-                        );
-                    }
+                    // Disconnect old edge and create new one with updated data
+                    let _ = node.disconnect(link_uri);
+                    node.connect(
+                        &target_node,
+                        LinkEdge {
+                            locations: locations.clone(),
+                        },
+                    );
+                }
 
-                    // Remove connections that no longer exist
-                    let current_targets: HashSet<_> = links_by_target.keys().collect();
-                    let edges_to_remove: Vec<_> = node
-                        .iter_out()
-                        .filter(|edge| !current_targets.contains(&edge.target().key()))
-                        .map(|edge| edge.target().key().clone())
-                        .collect();
+                // Remove connections that no longer exist
+                let current_targets: HashSet<_> = links_by_target.keys().collect();
+                let edges_to_remove: Vec<_> = node
+                    .iter_out()
+                    .filter(|edge| !current_targets.contains(&edge.target().key()))
+                    .map(|edge| edge.target().key().clone())
+                    .collect();
 
-                    for target_uri in edges_to_remove {
-                        let _ = node.disconnect(&target_uri);
-                    }
+                for target_uri in edges_to_remove {
+                    let _ = node.disconnect(&target_uri);
                 }
             }
         }
@@ -623,8 +1666,11 @@ impl Repository {
             let uri = Self::normalize_url_percent_encoding(&uri);
 
             // Remove from maps
+            self.unindex_note(file_path);
             self.document_map.remove(&uri);
             self.ast_map.remove(&uri);
+            self.line_trackers.remove(&uri);
+            self.cached_notes.lock().unwrap().remove(&uri);
 
             // Remove from graph
             if let Ok(mut graph) = self.document_graph.lock() {
@@ -696,19 +1742,57 @@ impl Repository {
             .send(RepositoryMessage::TwoHopLinksChanged(path, two_hop_links));
     }
 
-    /// Collect all non-Done tasks across the entire workspace, sorted by deadline.
+    /// Bring the graph in line with a note file once its watcher events have
+    /// settled, and broadcast whether it was added, changed or removed
+    async fn sync_note_from_disk(&self, path: PathBuf) {
+        let Ok(rel_path) = path.strip_prefix(&self.root_dir).map(Path::to_path_buf) else {
+            return;
+        };
+        let known = Url::from_file_path(&path)
+            .map(|uri| {
+                let uri = Self::normalize_url_percent_encoding(&uri);
+                self.document_map.contains_key(&uri)
+                    || self.cached_notes.lock().unwrap().contains_key(&uri)
+            })
+            .unwrap_or(false);
+
+        match tokio::fs::read_to_string(&path).await {
+            Ok(content) if known => self.handle_live_file_change(path, content).await,
+            Ok(content) => {
+                self.add_file_to_graph(&path, &content);
+                let Ok(metadata) = self.collect_file_metadata(&path) else {
+                    return;
+                };
+                let _ = self
+                    .tx
+                    .send(RepositoryMessage::FileAdded(rel_path, metadata));
+            }
+            Err(_) if !path.exists() => {
+                self.remove_file_from_graph(&path);
+                let _ = self.tx.send(RepositoryMessage::FileRemoved(rel_path));
+            }
+            Err(_) => {}
+        }
+    }
+
+    /// Collect tasks passing `filter` across the entire workspace, sorted by deadline.
     ///
-    /// Returns `(uri, ast_node, deadline)` tuples where `ast_node` is the task line.
-    pub fn aggregate_tasks(&self) -> Vec<(Url, AstNode, Deadline)> {
-        let mut tasks: Vec<(Url, AstNode, Deadline)> = Vec::new();
+    /// Returns `(uri, ast_node, deadline, heading)` tuples where `ast_node` is the task
+    /// line and `heading` the text of the section heading above it.
+    pub fn aggregate_tasks(
+        &self,
+        filter: &TaskFilter,
+    ) -> Vec<(Url, AstNode, Deadline, Option<String>)> {
+        self.parse_cached_notes();
+        let mut tasks: Vec<(Url, AstNode, Deadline, Option<String>)> = Vec::new();
         self.ast_map.iter().for_each(|entry| {
             let mut tasklines = Vec::new();
-            gather_tasks(entry.value(), &mut tasklines);
-            for (node, due) in tasklines {
-                tasks.push((entry.key().clone(), node, due));
+            gather_tasks(entry.value(), None, filter, &mut tasklines);
+            for (node, due, heading) in tasklines {
+                tasks.push((entry.key().clone(), node, due, heading));
             }
         });
-        tasks.sort_by_key(|(_, _, due)| due.clone());
+        tasks.sort_by_key(|(_, _, due, _)| due.clone());
         tasks
     }
 
@@ -719,6 +1803,7 @@ impl Repository {
         from: Option<chrono::NaiveDate>,
         to: Option<chrono::NaiveDate>,
     ) -> Vec<(Url, AstNode, chrono::NaiveDate)> {
+        self.parse_cached_notes();
         let mut tasks: Vec<(Url, AstNode, chrono::NaiveDate)> = Vec::new();
         self.ast_map.iter().for_each(|entry| {
             let mut completed = Vec::new();
@@ -734,8 +1819,78 @@ impl Repository {
         tasks
     }
 
+    /// Collect lines whose tags satisfy every `(key, value)` term of `filter`,
+    /// sorted by path and line number.
+    ///
+    /// A term with a value matches a `key=value` argument of any tag on the line;
+    /// a term without one matches a tag name, an argument key, or a positional argument.
+    pub fn query_by_tag(&self, filter: &[(String, Option<String>)]) -> Vec<(Url, AstNode)> {
+        self.parse_cached_notes();
+        let mut lines: Vec<(Url, AstNode)> = Vec::new();
+        self.ast_map.iter().for_each(|entry| {
+            let mut tagged = Vec::new();
+            gather_tagged_lines(entry.value(), filter, &mut tagged);
+            for node in tagged {
+                lines.push((entry.key().clone(), node));
+            }
+        });
+        lines.sort_by(|(a_uri, a_node), (b_uri, b_node)| {
+            a_uri
+                .path()
+                .cmp(b_uri.path())
+                .then_with(|| a_node.location().row.cmp(&b_node.location().row))
+        });
+        lines
+    }
+
+    /// Fuzzy-search every line of the cached documents.
+    ///
+    /// Returns at most `limit` hits sorted by score (highest first), with ties
+    /// broken by path and line number.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchHit> {
+        if query.trim().is_empty() || limit == 0 {
+            return vec![];
+        }
+        self.parse_cached_notes();
+        let matcher = SkimMatcherV2::default();
+        let mut hits: Vec<SearchHit> = Vec::new();
+        self.document_map.iter().for_each(|entry| {
+            for (line, text) in entry.value().lines().enumerate() {
+                let text = text.to_string();
+                let text = text.trim_end_matches(['\n', '\r']);
+                if let Some(score) = matcher.fuzzy_match(text, query) {
+                    hits.push(SearchHit {
+                        uri: entry.key().clone(),
+                        line,
+                        text: text.to_string(),
+                        score,
+                    });
+                }
+            }
+        });
+        hits.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| a.uri.path().cmp(b.uri.path()))
+                .then_with(|| a.line.cmp(&b.line))
+        });
+        hits.truncate(limit);
+        hits
+    }
+
     /// Start filesystem watcher for the repository
     pub async fn start_watcher(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.start_watcher_with_debounce(DEFAULT_WATCH_DEBOUNCE)
+            .await
+    }
+
+    /// Watch `root_dir` for note changes. Events on a file are coalesced until
+    /// it has been quiet for `debounce`, so editors that save through temp
+    /// files and renames cause a single reparse and broadcast.
+    pub async fn start_watcher_with_debounce(
+        &self,
+        debounce: Duration,
+    ) -> Result<(), Box<dyn std::error::Error>> {
         let (tx, mut rx) = mpsc::channel(100);
         let watch_dir = self.root_dir.clone();
         let dir_display = watch_dir.display().to_string();
@@ -761,7 +1916,6 @@ impl Repository {
 
         let pending_changes: Arc<Mutex<HashMap<PathBuf, Instant>>> =
             Arc::new(Mutex::new(HashMap::new()));
-        let debounce_duration = Duration::from_millis(10);
 
         let repo_tx = self.tx.clone();
         let root_dir = self.root_dir.clone();
@@ -774,10 +1928,14 @@ impl Repository {
                     continue;
                 }
                 for path in event.paths {
-                    let is_pn = path.extension().and_then(|s| s.to_str()) == Some("pn");
-                    let is_workspace_config = path.file_name().and_then(|n| n.to_str())
-                        == Some(WORKSPACE_CONFIG_FILENAME);
+                    let is_pn = repository.is_note_path(&path);
+                    let file_name = path.file_name().and_then(|n| n.to_str());
+                    let is_workspace_config = file_name == Some(WORKSPACE_CONFIG_FILENAME);
 
+                    if file_name == Some(IGNORE_FILENAME) {
+                        repository.reload_ignore_rules();
+                        continue;
+                    }
                     if !is_pn && !is_workspace_config {
                         continue;
                     }
@@ -787,114 +1945,47 @@ impl Repository {
                         if event.kind.is_modify() || event.kind.is_create() {
                             let new_cfg = load_workspace_config(&root_dir);
                             *repository.workspace_config.lock().unwrap() = new_cfg.clone();
+                            repository.reload_ignore_rules();
                             let _ =
                                 repo_tx.send(RepositoryMessage::WorkspaceConfigChanged(new_cfg));
                         }
                         continue;
                     }
+                    if repository.is_ignored_path(&path) {
+                        continue;
+                    }
 
-                    if event.kind.is_create() {
-                        let Ok(rel_path) = path.strip_prefix(&root_dir) else {
-                            continue;
-                        };
-                        // Read file content and add to graph
-                        let Ok(content) = std::fs::read_to_string(&path) else {
-                            continue;
-                        };
-                        repository.add_file_to_graph(&path, &content);
+                    {
+                        let mut changes = pending_changes.lock().unwrap();
+                        changes.insert(path.clone(), Instant::now());
+                    }
 
-                        let Ok(file_metadata) = std::fs::metadata(&path) else {
-                            continue;
-                        };
-                        let modified = file_metadata
-                            .modified()
-                            .unwrap_or(SystemTime::UNIX_EPOCH)
-                            .duration_since(UNIX_EPOCH)
-                            .unwrap_or_default()
-                            .as_secs();
-
-                        let created = file_metadata
-                            .created()
-                            .unwrap_or(SystemTime::UNIX_EPOCH)
-                            .duration_since(UNIX_EPOCH)
-                            .unwrap_or_default()
-                            .as_secs();
-
-                        //let link_count = repository.count_links_in_file(&path).unwrap_or(0);
-                        let link_count = repository.calculate_back_links(&path).len();
-
-                        let metadata = FileMetadata {
-                            modified,
-                            created,
-                            link_count: link_count.try_into().unwrap(),
+                    let path_clone = path.clone();
+                    let pending_changes_clone = Arc::clone(&pending_changes);
+                    let repository_clone = repository.clone();
+
+                    tokio::spawn(async move {
+                        sleep(debounce).await;
+
+                        // Only the task of the last event in a burst processes the file
+                        let should_process = {
+                            let mut changes = pending_changes_clone.lock().unwrap();
+                            if let Some(&last_change) = changes.get(&path_clone) {
+                                let is_latest =
+                                    Instant::now().duration_since(last_change) >= debounce;
+                                if is_latest {
+                                    changes.remove(&path_clone);
+                                }
+                                is_latest
+                            } else {
+                                false
+                            }
                         };
 
-                        let _ = repo_tx.send(RepositoryMessage::FileAdded(
-                            rel_path.to_path_buf(),
-                            metadata,
-                        ));
-                    } else if event.kind.is_remove() {
-                        let Ok(rel_path) = path.strip_prefix(&root_dir) else {
-                            continue;
-                        };
-                        // Remove from graph
-                        repository.remove_file_from_graph(&path);
-                        let _ =
-                            repo_tx.send(RepositoryMessage::FileRemoved(rel_path.to_path_buf()));
-                    } else if event.kind.is_modify() && path.is_file() {
-                        {
-                            let mut changes = pending_changes.lock().unwrap();
-                            changes.insert(path.clone(), Instant::now());
+                        if should_process {
+                            repository_clone.sync_note_from_disk(path_clone).await;
                         }
-
-                        let path_clone = path.clone();
-                        let pending_changes_clone = Arc::clone(&pending_changes);
-                        let repository_clone = repository.clone();
-
-                        tokio::spawn(async move {
-                            sleep(debounce_duration).await;
-
-                            let should_process = {
-                                let mut changes = pending_changes_clone.lock().unwrap();
-                                if let Some(&last_change) = changes.get(&path_clone) {
-                                    let is_latest = Instant::now().duration_since(last_change)
-                                        >= debounce_duration;
-                                    if is_latest {
-                                        changes.remove(&path_clone);
-                                        true
-                                    } else {
-                                        false
-                                    }
-                                } else {
-                                    false
-                                }
-                            };
-
-                            if should_process {
-                                let Ok(content) = tokio::fs::read_to_string(&path_clone).await
-                                else {
-                                    return;
-                                };
-
-                                let _start = Instant::now();
-                                repository_clone
-                                    .handle_live_file_change(path_clone.clone(), content)
-                                    .await;
-
-                                /*
-                                if let Ok(rel_path) =
-                                    path_clone.strip_prefix(&repository_clone.root_dir)
-                                {
-                                    eprintln!(
-                                        "File {} processed in {} ms",
-                                        rel_path.display(),
-                                        start.elapsed().as_millis()
-                                    );
-                                }
-                                */
-                            }
-                        });
-                    }
+                    });
                 }
             }
         });
@@ -903,20 +1994,161 @@ impl Repository {
     }
 }
 
-/// Recursively collect non-Done task lines from an AST node.
-pub fn gather_tasks(parent: &AstNode, tasklines: &mut Vec<(AstNode, Deadline)>) {
+/// Convert an LSP position (UTF-16 column) into a byte offset within `text`.
+fn position_to_byte(text: &str, position: Position) -> usize {
+    let line_start: usize = text
+        .split_inclusive('\n')
+        .take(position.line as usize)
+        .map(str::len)
+        .sum();
+    let line = text[line_start..].split('\n').next().unwrap_or_default();
+    line_start + utf16_to_byte_idx(line, position.character as usize)
+}
+
+/// Find the line that `anchor` points at in a note's AST: the line defining it
+/// with `#anchor` or `{@anchor}`, or else a top-level heading whose generated id
+/// is `anchor` (the id the HTML table of contents links to).
+pub fn find_anchor(ast: &AstNode, anchor: &str) -> Option<AstNode> {
+    find_explicit_anchor(ast, anchor).or_else(|| {
+        let heading = collect_headings(ast)
+            .into_iter()
+            .find(|heading| !heading.has_anchor && heading.id == anchor)?;
+        ast.value()
+            .children
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|line| line.location().row == heading.row)
+            .cloned()
+    })
+}
+
+/// Every anchor a note can be linked to with its row, in document order:
+/// explicit anchors plus the generated ids of headings without one.
+pub fn gather_anchor_targets(ast: &AstNode) -> Vec<(String, usize)> {
+    fn gather_explicit(parent: &AstNode, anchors: &mut Vec<(String, usize)>) {
+        if let AstNodeKind::Line { ref properties } = &parent.kind() {
+            for prop in properties {
+                if let Property::Anchor { name, location } = prop {
+                    anchors.push((name.to_string(), location.row));
+                }
+            }
+        }
+        for child in parent.value().children.lock().unwrap().iter() {
+            gather_explicit(child, anchors);
+        }
+    }
+
+    let mut anchors = vec![];
+    gather_explicit(ast, &mut anchors);
+    anchors.extend(
+        collect_headings(ast)
+            .into_iter()
+            .filter(|heading| !heading.has_anchor)
+            .map(|heading| (heading.id, heading.row)),
+    );
+    anchors.sort_by_key(|(_, row)| *row);
+    anchors
+}
+
+fn find_explicit_anchor(parent: &AstNode, anchor: &str) -> Option<AstNode> {
+    if let AstNodeKind::Line { ref properties } = &parent.kind() {
+        for prop in properties {
+            if let Property::Anchor { name, .. } = prop {
+                if name == anchor {
+                    return Some(parent.clone());
+                }
+            }
+        }
+    }
+
+    parent
+        .value()
+        .children
+        .lock()
+        .unwrap()
+        .iter()
+        .find_map(|child| find_explicit_anchor(child, anchor))
+}
+
+/// The wiki links of a note in the form kept by the index cache
+fn cached_links(ast: &AstNode) -> Vec<CachedLink> {
+    let mut wikilinks = vec![];
+    Repository::gather_wikilinks(ast, &mut wikilinks);
+    wikilinks
+        .into_iter()
+        .map(|(link, anchor, location)| CachedLink {
+            link,
+            anchor,
+            line: location.row,
+            col_range: (location.span.0, location.span.1),
+        })
+        .collect()
+}
+
+/// Recursively collect task lines passing `filter` from an AST node.
+///
+/// Each task comes with its context `heading`: the nearest heading among its
+/// ancestors and their preceding siblings, i.e. the section it belongs to.
+pub fn gather_tasks(
+    parent: &AstNode,
+    heading: Option<&str>,
+    filter: &TaskFilter,
+    tasklines: &mut Vec<(AstNode, Deadline, Option<String>)>,
+) {
     if let AstNodeKind::Line { ref properties } = &parent.kind() {
         for prop in properties {
             if let Property::Task { status, due, .. } = prop {
-                if !matches!(status, TaskStatus::Done) {
-                    tasklines.push((parent.clone(), due.clone()));
+                if filter.matches(status, due) {
+                    tasklines.push((parent.clone(), due.clone(), heading.map(str::to_string)));
                     break;
                 }
             }
         }
     }
+    let mut current = heading_text(parent).or_else(|| heading.map(str::to_string));
     for child in parent.value().children.lock().unwrap().iter() {
-        gather_tasks(child, tasklines);
+        gather_tasks(child, current.as_deref(), filter, tasklines);
+        if let Some(text) = heading_text(child) {
+            current = Some(text);
+        }
+    }
+}
+
+/// Visible text of a heading line
+fn heading_text(node: &AstNode) -> Option<String> {
+    parser::heading_level(node)?;
+    let contents = node.value().contents.lock().unwrap();
+    contents.first().map(plain_text)
+}
+
+/// Recursively collect lines whose tags satisfy every term of `filter`.
+pub fn gather_tagged_lines(
+    parent: &AstNode,
+    filter: &[(String, Option<String>)],
+    lines: &mut Vec<AstNode>,
+) {
+    if let AstNodeKind::Line { ref properties } = &parent.kind() {
+        let matches_term = |(key, value): &(String, Option<String>)| {
+            properties.iter().any(|prop| {
+                let Property::Tag { name, args, .. } = prop else {
+                    return false;
+                };
+                match value {
+                    Some(value) => args
+                        .iter()
+                        .any(|(k, v)| k == key && v.as_deref() == Some(value.as_str())),
+                    None => name == key || args.iter().any(|(k, _)| k == key),
+                }
+            })
+        };
+        let has_tag = properties.iter().any(|p| matches!(p, Property::Tag { .. }));
+        if has_tag && filter.iter().all(matches_term) {
+            lines.push(parent.clone());
+        }
+    }
+    for child in parent.value().children.lock().unwrap().iter() {
+        gather_tagged_lines(child, filter, lines);
     }
 }
 
@@ -934,7 +2166,9 @@ pub fn gather_completed_tasks(parent: &AstNode, tasklines: &mut Vec<(AstNode, ch
                     let date = match completed_at {
                         Deadline::Date(d) => Some(*d),
                         Deadline::DateTime(dt) => Some(dt.date()),
-                        Deadline::Uninterpretable(_) => None,
+                        Deadline::DateTimeTz(dt) => Some(crate::parser::local_naive(dt).date()),
+                        Deadline::TimeRange { start, .. } => Some(start.date()),
+                        Deadline::Recurring(_) | Deadline::Uninterpretable(_) => None,
                     };
                     if let Some(date) = date {
                         tasklines.push((parent.clone(), date));
@@ -948,3 +2182,56 @@ pub fn gather_completed_tasks(parent: &AstNode, tasklines: &mut Vec<(AstNode, ch
         gather_completed_tasks(child, tasklines);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn link_normalization_keys() {
+        let off = LinkNormalization::default();
+        assert!(!off.is_enabled());
+        assert_eq!(off.key("My Note"), "My Note");
+
+        let case = LinkNormalization {
+            ignore_case: true,
+            ..Default::default()
+        };
+        assert_eq!(case.key("My Note"), "my note");
+        assert_eq!(case.key("日記 ÄB"), "日記 äb");
+
+        let separators = LinkNormalization {
+            ignore_separators: true,
+            ..Default::default()
+        };
+        assert_eq!(separators.key("My Note"), separators.key("My_Note"));
+        assert_ne!(separators.key("My Note"), separators.key("my_note"));
+
+        let both = LinkNormalization {
+            ignore_case: true,
+            ignore_separators: true,
+        };
+        assert_eq!(both.key("My Note"), "my_note");
+        assert_eq!(both.key("MY_NOTE"), "my_note");
+    }
+
+    #[test]
+    fn scan_state_eta() {
+        let mut state = ScanState {
+            total: 40,
+            ..Default::default()
+        };
+        assert_eq!(state.percentage(), 0);
+        assert_eq!(state.eta_ms(), None);
+
+        state.scanned = 10;
+        state.elapsed_ms = 500;
+        assert_eq!(state.percentage(), 25);
+        assert_eq!(state.eta_ms(), Some(1500));
+
+        state.completed = true;
+        assert_eq!(state.percentage(), 100);
+        assert_eq!(state.eta_ms(), Some(0));
+        assert_eq!(ScanState::default().percentage(), 0);
+    }
+}
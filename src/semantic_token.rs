@@ -1,7 +1,7 @@
 use str_indices::utf16::from_byte_idx as utf16_from_byte_idx;
-use tower_lsp::lsp_types::{SemanticToken, SemanticTokenType};
+use tower_lsp::lsp_types::{SemanticToken, SemanticTokenModifier, SemanticTokenType};
 
-use crate::parser::{AstNode, AstNodeKind, Property};
+use crate::parser::{AstNode, AstNodeKind, DeadlineStatus, Location, Property, Span, TaskStatus};
 
 pub const LEGEND_TYPE: &[SemanticTokenType] = &[
     SemanticTokenType::FUNCTION,
@@ -16,6 +16,15 @@ pub const LEGEND_TYPE: &[SemanticTokenType] = &[
     SemanticTokenType::METHOD,
     SemanticTokenType::ENUM,
     SemanticTokenType::MODIFIER,
+    SemanticTokenType::new("deadline"),
+];
+
+pub const LEGEND_MODIFIER: &[SemanticTokenModifier] = &[
+    SemanticTokenModifier::new("taskTodo"),
+    SemanticTokenModifier::new("taskDoing"),
+    SemanticTokenModifier::new("taskPaused"),
+    SemanticTokenModifier::new("taskDone"),
+    SemanticTokenModifier::new("overdue"),
 ];
 
 // Token type indices (must match LEGEND_TYPE order)
@@ -34,6 +43,14 @@ const TOKEN_TYPE_TYPE: u32 = 8;
 const TOKEN_TYPE_METHOD: u32 = 9;
 const TOKEN_TYPE_ENUM: u32 = 10;
 const TOKEN_TYPE_MODIFIER: u32 = 11;
+const TOKEN_TYPE_DEADLINE: u32 = 12;
+
+// Token modifier bits (must match LEGEND_MODIFIER order)
+const TOKEN_MODIFIER_TASK_TODO: u32 = 1 << 0;
+const TOKEN_MODIFIER_TASK_DOING: u32 = 1 << 1;
+const TOKEN_MODIFIER_TASK_PAUSED: u32 = 1 << 2;
+const TOKEN_MODIFIER_TASK_DONE: u32 = 1 << 3;
+const TOKEN_MODIFIER_OVERDUE: u32 = 1 << 4;
 
 #[derive(Debug, Clone)]
 struct ImCompleteSemanticToken {
@@ -41,38 +58,131 @@ struct ImCompleteSemanticToken {
     start: u32,
     length: u32,
     token_type: u32,
+    token_modifiers: u32,
+}
+
+impl ImCompleteSemanticToken {
+    /// Token covering `location`
+    fn new(location: &Location, token_type: u32, token_modifiers: u32) -> Self {
+        let Span(from, to) = &location.span;
+        Self::with_range(location, *from, *to, token_type, token_modifiers)
+    }
+
+    /// Token covering the bytes `from..to` of the line of `location`.
+    ///
+    /// Spans are byte offsets into the whole source line, leading tabs
+    /// included, while LSP columns are UTF-16 code units from the start of the
+    /// line: a tab is one unit and a CJK character one or two.
+    fn with_range(
+        location: &Location,
+        from: usize,
+        to: usize,
+        token_type: u32,
+        token_modifiers: u32,
+    ) -> Self {
+        let line_text: &str = location.input.as_ref();
+        let start = utf16_from_byte_idx(line_text, from) as u32;
+        Self {
+            line: location.row as u32,
+            start,
+            length: utf16_from_byte_idx(line_text, to) as u32 - start,
+            token_type,
+            token_modifiers,
+        }
+    }
+}
+
+/// Byte range of the deadline within a task's source, `due=...` in the long
+/// form or the date after the status symbol in the abbreviated one
+fn deadline_span(source: &str) -> Option<(usize, usize)> {
+    let value_end = |from: usize| {
+        source[from..]
+            .find(|c: char| c.is_whitespace() || c == '}')
+            .map_or(source.len(), |i| from + i)
+    };
+    if source.starts_with("{@") {
+        let start = source
+            .match_indices("due=")
+            .find(|(i, _)| source[..*i].ends_with(char::is_whitespace))?
+            .0
+            + "due=".len();
+        let mut end = value_end(start);
+        // `due=2024-10-10 09:00` keeps its time after the space
+        let rest = &source[end..];
+        if rest.starts_with(' ') && rest[1..].get(2..3) == Some(":") {
+            end = value_end(end + 1);
+        }
+        (end > start).then_some((start, end))
+    } else {
+        // Skip the status symbol, whatever its length
+        let start = source.find(|c: char| c.is_ascii_digit())?;
+        Some((start, source.len()))
+    }
 }
 
 fn properties_to_tokens(properties: &Vec<Property>, tokens: &mut Vec<ImCompleteSemanticToken>) {
     for prop in properties {
         match prop {
-            Property::Task { location, .. } => {
-                // Highlight @task as COMMENT
+            Property::Task {
+                status,
+                due,
+                location,
+                ..
+            } => {
+                // Highlight @task as COMMENT and its deadline as DEADLINE, both
+                // marked with the task status so that editors can dim done tasks
                 let line_text: &str = location.input.as_ref();
-                let start = utf16_from_byte_idx(line_text, location.span.0) as u32;
-                let length = (utf16_from_byte_idx(line_text, location.span.1)
-                    - utf16_from_byte_idx(line_text, location.span.0))
-                    as u32;
-                tokens.push(ImCompleteSemanticToken {
-                    line: location.row as u32,
-                    start,
-                    length,
-                    token_type: TOKEN_TYPE_COMMENT,
-                });
+                let token_modifiers = match status {
+                    TaskStatus::Todo => TOKEN_MODIFIER_TASK_TODO,
+                    TaskStatus::Doing => TOKEN_MODIFIER_TASK_DOING,
+                    TaskStatus::Paused => TOKEN_MODIFIER_TASK_PAUSED,
+                    TaskStatus::Done => TOKEN_MODIFIER_TASK_DONE,
+                };
+                let (start, end) = (location.span.0, location.span.1);
+                let mut push = |from: usize, to: usize, token_type: u32, token_modifiers: u32| {
+                    if to > from {
+                        tokens.push(ImCompleteSemanticToken::with_range(
+                            location,
+                            from,
+                            to,
+                            token_type,
+                            token_modifiers,
+                        ));
+                    }
+                };
+                match deadline_span(&line_text[start..end]) {
+                    Some((due_start, due_end)) => {
+                        let (due_start, due_end) = (start + due_start, start + due_end);
+                        let overdue = *status != TaskStatus::Done
+                            && due.status(chrono::Local::now().naive_local())
+                                == DeadlineStatus::Overdue;
+                        let due_modifiers = if overdue {
+                            token_modifiers | TOKEN_MODIFIER_OVERDUE
+                        } else {
+                            token_modifiers
+                        };
+                        push(start, due_start, TOKEN_TYPE_COMMENT, token_modifiers);
+                        push(due_start, due_end, TOKEN_TYPE_DEADLINE, due_modifiers);
+                        push(due_end, end, TOKEN_TYPE_COMMENT, token_modifiers);
+                    }
+                    None => push(start, end, TOKEN_TYPE_COMMENT, token_modifiers),
+                }
+            }
+            Property::Tag { location, .. } => {
+                // Highlight other properties as COMMENT
+                tokens.push(ImCompleteSemanticToken::new(
+                    location,
+                    TOKEN_TYPE_COMMENT,
+                    0,
+                ));
             }
             Property::Anchor { location, .. } => {
                 // Highlight anchor as KEYWORD
-                let line_text: &str = location.input.as_ref();
-                let start = utf16_from_byte_idx(line_text, location.span.0) as u32;
-                let length = (utf16_from_byte_idx(line_text, location.span.1)
-                    - utf16_from_byte_idx(line_text, location.span.0))
-                    as u32;
-                tokens.push(ImCompleteSemanticToken {
-                    line: location.row as u32,
-                    start,
-                    length,
-                    token_type: TOKEN_TYPE_KEYWORD,
-                });
+                tokens.push(ImCompleteSemanticToken::new(
+                    location,
+                    TOKEN_TYPE_KEYWORD,
+                    0,
+                ));
             }
         }
     }
@@ -85,7 +195,6 @@ fn collect_semantic_tokens(
 ) {
     let location = node.location();
     let row = location.row as u32;
-    let span = &location.span;
 
     let mut b_process: bool = true;
     if let Some((start_line, end_line)) = line_range {
@@ -94,29 +203,20 @@ fn collect_semantic_tokens(
         }
     }
     if b_process {
-        let line_text: &str = location.input.as_ref();
         match node.kind() {
             AstNodeKind::WikiLink { .. } => {
-                let start = utf16_from_byte_idx(line_text, span.0) as u32;
-                let length = (utf16_from_byte_idx(line_text, span.1)
-                    - utf16_from_byte_idx(line_text, span.0)) as u32;
-                tokens.push(ImCompleteSemanticToken {
-                    line: row,
-                    start,
-                    length,
-                    token_type: TOKEN_TYPE_OPERATOR,
-                });
+                tokens.push(ImCompleteSemanticToken::new(
+                    location,
+                    TOKEN_TYPE_OPERATOR,
+                    0,
+                ));
             }
             AstNodeKind::Link { .. } => {
-                let start = utf16_from_byte_idx(line_text, span.0) as u32;
-                let length = (utf16_from_byte_idx(line_text, span.1)
-                    - utf16_from_byte_idx(line_text, span.0)) as u32;
-                tokens.push(ImCompleteSemanticToken {
-                    line: row,
-                    start,
-                    length,
-                    token_type: TOKEN_TYPE_FUNCTION,
-                });
+                tokens.push(ImCompleteSemanticToken::new(
+                    location,
+                    TOKEN_TYPE_FUNCTION,
+                    0,
+                ));
             }
             AstNodeKind::Code {
                 lang: _lang,
@@ -127,15 +227,7 @@ fn collect_semantic_tokens(
                 } else {
                     TOKEN_TYPE_COMMENT
                 };
-                let start = utf16_from_byte_idx(line_text, span.0) as u32;
-                let length = (utf16_from_byte_idx(line_text, span.1)
-                    - utf16_from_byte_idx(line_text, span.0)) as u32;
-                tokens.push(ImCompleteSemanticToken {
-                    line: row,
-                    start,
-                    length,
-                    token_type,
-                });
+                tokens.push(ImCompleteSemanticToken::new(location, token_type, 0));
             }
             AstNodeKind::Math { inline } => {
                 let token_type = if *inline {
@@ -143,71 +235,32 @@ fn collect_semantic_tokens(
                 } else {
                     TOKEN_TYPE_COMMENT
                 };
-                let start = utf16_from_byte_idx(line_text, span.0) as u32;
-                let length = (utf16_from_byte_idx(line_text, span.1)
-                    - utf16_from_byte_idx(line_text, span.0)) as u32;
-                tokens.push(ImCompleteSemanticToken {
-                    line: row,
-                    start,
-                    length,
-                    token_type,
-                });
+                tokens.push(ImCompleteSemanticToken::new(location, token_type, 0));
             }
             AstNodeKind::Image { .. } => {
-                let start = utf16_from_byte_idx(line_text, span.0) as u32;
-                let length = (utf16_from_byte_idx(line_text, span.1)
-                    - utf16_from_byte_idx(line_text, span.0)) as u32;
-                tokens.push(ImCompleteSemanticToken {
-                    line: row,
-                    start,
-                    length,
-                    token_type: TOKEN_TYPE_TYPE,
-                });
+                tokens.push(ImCompleteSemanticToken::new(location, TOKEN_TYPE_TYPE, 0));
             }
             AstNodeKind::Quote => {
-                let start = utf16_from_byte_idx(line_text, span.0) as u32;
-                let length = (utf16_from_byte_idx(line_text, span.1)
-                    - utf16_from_byte_idx(line_text, span.0)) as u32;
-                tokens.push(ImCompleteSemanticToken {
-                    line: row,
-                    start,
-                    length,
-                    token_type: TOKEN_TYPE_COMMENT,
-                });
+                tokens.push(ImCompleteSemanticToken::new(
+                    location,
+                    TOKEN_TYPE_COMMENT,
+                    0,
+                ));
             }
             AstNodeKind::MathContent => {
                 // NOTE: allow editor to render text as tex for now. should we call tree-sitter?
-                // let start = utf16_from_byte_idx(line_text, span.0) as u32;
-                // let length = (utf16_from_byte_idx(line_text, span.1) - utf16_from_byte_idx(line_text, span.0)) as u32;
-                // tokens.push(ImCompleteSemanticToken {
-                //     line: row,
-                //     start,
-                //     length,
-                //     token_type: TOKEN_TYPE_ENUM,
-                // });
+                // tokens.push(ImCompleteSemanticToken::new(location, TOKEN_TYPE_ENUM, 0));
             }
             AstNodeKind::CodeContent => {
-                let start = utf16_from_byte_idx(line_text, span.0) as u32;
-                let length = (utf16_from_byte_idx(line_text, span.1)
-                    - utf16_from_byte_idx(line_text, span.0)) as u32;
-                tokens.push(ImCompleteSemanticToken {
-                    line: row,
-                    start,
-                    length,
-                    token_type: TOKEN_TYPE_STRING,
-                });
+                tokens.push(ImCompleteSemanticToken::new(location, TOKEN_TYPE_STRING, 0));
             }
             AstNodeKind::Table { .. } => {
                 // Highlight @table command as PROPERTY
-                let start = utf16_from_byte_idx(line_text, span.0) as u32;
-                let length = (utf16_from_byte_idx(line_text, span.1)
-                    - utf16_from_byte_idx(line_text, span.0)) as u32;
-                tokens.push(ImCompleteSemanticToken {
-                    line: row,
-                    start,
-                    length,
-                    token_type: TOKEN_TYPE_PROPERTY,
-                });
+                tokens.push(ImCompleteSemanticToken::new(
+                    location,
+                    TOKEN_TYPE_PROPERTY,
+                    0,
+                ));
             }
             AstNodeKind::Decoration {
                 fontsize: _,
@@ -218,46 +271,37 @@ fn collect_semantic_tokens(
                 // Highlight decoration based on type
                 // Deleted text should be highlighted as COMMENT (indicates removed/deprecated)
                 // Other decorations (bold, italic, underline) as MODIFIER
-                let start = utf16_from_byte_idx(line_text, span.0) as u32;
-                let length = (utf16_from_byte_idx(line_text, span.1)
-                    - utf16_from_byte_idx(line_text, span.0)) as u32;
                 let token_type = if *deleted {
                     TOKEN_TYPE_COMMENT
                 } else {
                     TOKEN_TYPE_MODIFIER
                 };
-                tokens.push(ImCompleteSemanticToken {
-                    line: row,
-                    start,
-                    length,
-                    token_type,
-                });
+                tokens.push(ImCompleteSemanticToken::new(location, token_type, 0));
             }
             AstNodeKind::HorizontalLine => {
                 // Highlight horizontal line as COMMENT (visual separator)
-                let start = utf16_from_byte_idx(line_text, span.0) as u32;
-                let length = (utf16_from_byte_idx(line_text, span.1)
-                    - utf16_from_byte_idx(line_text, span.0)) as u32;
-                tokens.push(ImCompleteSemanticToken {
-                    line: row,
-                    start,
-                    length,
-                    token_type: TOKEN_TYPE_COMMENT,
-                });
+                tokens.push(ImCompleteSemanticToken::new(
+                    location,
+                    TOKEN_TYPE_COMMENT,
+                    0,
+                ));
+            }
+            AstNodeKind::Comment => {
+                tokens.push(ImCompleteSemanticToken::new(
+                    location,
+                    TOKEN_TYPE_COMMENT,
+                    0,
+                ));
             }
             AstNodeKind::Line { properties } => {
                 properties_to_tokens(properties, tokens);
             }
             AstNodeKind::QuoteContent { properties } => {
-                let start = utf16_from_byte_idx(line_text, span.0) as u32;
-                let length = (utf16_from_byte_idx(line_text, span.1)
-                    - utf16_from_byte_idx(line_text, span.0)) as u32;
-                tokens.push(ImCompleteSemanticToken {
-                    line: row,
-                    start,
-                    length,
-                    token_type: TOKEN_TYPE_COMMENT,
-                });
+                tokens.push(ImCompleteSemanticToken::new(
+                    location,
+                    TOKEN_TYPE_COMMENT,
+                    0,
+                ));
                 properties_to_tokens(properties, tokens);
             }
             _ => {}
@@ -265,7 +309,12 @@ fn collect_semantic_tokens(
     }
 
     // Recursively process children and contents
-    for child in node.value().children.lock().unwrap().iter() {
+    let children = node.value().children.lock().unwrap();
+    let children = match line_range {
+        Some((start_line, end_line)) => children_in_range(&children, start_line, end_line),
+        None => &children[..],
+    };
+    for child in children {
         collect_semantic_tokens(child, tokens, line_range);
     }
     for content in node.value().contents.lock().unwrap().iter() {
@@ -273,6 +322,20 @@ fn collect_semantic_tokens(
     }
 }
 
+/// The children whose subtrees may have lines in `start_line..=end_line`.
+///
+/// Children are ordered by row and each subtree ends before the next sibling
+/// starts, so a child is skipped if its next sibling starts at or before
+/// `start_line`, and everything from the first child below `end_line` on.
+fn children_in_range(children: &[AstNode], start_line: u32, end_line: u32) -> &[AstNode] {
+    let row = |child: &AstNode| child.location().row as u32;
+    let first = children
+        .partition_point(|child| row(child) <= start_line)
+        .saturating_sub(1);
+    let end = first + children[first..].partition_point(|child| row(child) <= end_line);
+    &children[first..end]
+}
+
 fn build_semantic_tokens(tokens: Vec<ImCompleteSemanticToken>) -> Vec<SemanticToken> {
     let mut sorted_tokens = tokens;
     sorted_tokens.sort_by(|a, b| {
@@ -300,7 +363,7 @@ fn build_semantic_tokens(tokens: Vec<ImCompleteSemanticToken>) -> Vec<SemanticTo
             delta_start,
             length: token.length,
             token_type: token.token_type,
-            token_modifiers_bitset: 0,
+            token_modifiers_bitset: token.token_modifiers,
         });
 
         prev_line = token.line;
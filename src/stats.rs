@@ -0,0 +1,169 @@
+//! Per-note statistics: word, line and task counts and an estimated reading time.
+
+use serde::{Deserialize, Serialize};
+
+use crate::parser::{AstNode, AstNodeKind, Property, TaskStatus};
+
+/// Reading speed for space-separated words, in words per minute.
+const WORDS_PER_MINUTE: f64 = 200.0;
+/// Reading speed for CJK text, in characters per minute.
+const CJK_CHARS_PER_MINUTE: f64 = 500.0;
+
+/// Number of tasks in each status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct TaskCounts {
+    pub todo: usize,
+    pub doing: usize,
+    pub paused: usize,
+    pub done: usize,
+}
+
+/// Statistics of a single note.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct NoteStats {
+    /// Words outside code and math blocks; every CJK character counts as a word.
+    pub words: usize,
+    pub lines: usize,
+    pub tasks: TaskCounts,
+    /// Estimated reading time, rounded up to whole minutes.
+    pub reading_time_minutes: usize,
+}
+
+impl NoteStats {
+    /// Compute the statistics of a parsed note with `lines` lines.
+    pub fn from_ast(ast: &AstNode, lines: usize) -> Self {
+        let mut counter = Counter::default();
+        counter.visit(ast);
+
+        let minutes = counter.latin_words as f64 / WORDS_PER_MINUTE
+            + counter.cjk_chars as f64 / CJK_CHARS_PER_MINUTE;
+        Self {
+            words: counter.latin_words + counter.cjk_chars,
+            lines,
+            tasks: counter.tasks,
+            reading_time_minutes: minutes.ceil() as usize,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Counter {
+    latin_words: usize,
+    cjk_chars: usize,
+    tasks: TaskCounts,
+}
+
+impl Counter {
+    fn visit(&mut self, node: &AstNode) {
+        match node.kind() {
+            AstNodeKind::Code { inline: false, .. } | AstNodeKind::Math { inline: false } => {
+                return;
+            }
+            AstNodeKind::Line { properties } => {
+                for prop in properties {
+                    if let Property::Task { status, .. } = prop {
+                        let count = match status {
+                            TaskStatus::Todo => &mut self.tasks.todo,
+                            TaskStatus::Doing => &mut self.tasks.doing,
+                            TaskStatus::Paused => &mut self.tasks.paused,
+                            TaskStatus::Done => &mut self.tasks.done,
+                        };
+                        *count += 1;
+                    }
+                }
+            }
+            AstNodeKind::Text => self.count(node.extract_str()),
+            AstNodeKind::WikiLink { link, anchor } => {
+                self.count(link);
+                if let Some(anchor) = anchor {
+                    self.count(anchor);
+                }
+            }
+            AstNodeKind::Link { link, title } | AstNodeKind::Embed { link, title } => {
+                self.count(title.as_deref().unwrap_or(link));
+            }
+            AstNodeKind::DefinitionItem { term } => self.count(term),
+            _ => {}
+        }
+        for content in node.value().contents.lock().unwrap().iter() {
+            self.visit(content);
+        }
+        for child in node.value().children.lock().unwrap().iter() {
+            self.visit(child);
+        }
+    }
+
+    fn count(&mut self, text: &str) {
+        let (words, cjk) = count_words(text);
+        self.latin_words += words;
+        self.cjk_chars += cjk;
+    }
+}
+
+/// Count the whitespace-separated words and the CJK characters in `text`.
+///
+/// CJK text is not separated by spaces, so each CJK character is counted on
+/// its own and also ends the word before it. Runs without any alphanumeric
+/// character, such as `-` or `→`, are not words.
+pub fn count_words(text: &str) -> (usize, usize) {
+    let mut words = 0;
+    let mut cjk = 0;
+    let mut in_word = false;
+    for c in text.chars() {
+        if is_cjk(c) {
+            cjk += 1;
+            in_word = false;
+        } else if c.is_alphanumeric() {
+            if !in_word {
+                words += 1;
+                in_word = true;
+            }
+        } else if c.is_whitespace() {
+            in_word = false;
+        }
+    }
+    (words, cjk)
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}'     // Hiragana, Katakana
+        | '\u{3400}'..='\u{4DBF}'   // CJK Extension A
+        | '\u{4E00}'..='\u{9FFF}'   // CJK Unified Ideographs
+        | '\u{AC00}'..='\u{D7AF}'   // Hangul Syllables
+        | '\u{F900}'..='\u{FAFF}'   // CJK Compatibility Ideographs
+        | '\u{FF66}'..='\u{FF9F}'   // Halfwidth Katakana
+        | '\u{20000}'..='\u{2FFFF}' // CJK Extensions B-F
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_text;
+
+    #[test]
+    fn count_mixed_english_and_japanese() {
+        assert_eq!(count_words("Hello, world!"), (2, 0));
+        assert_eq!(count_words("patto は便利なノートです"), (1, 9));
+        assert_eq!(count_words("Rustで書く -> fast"), (2, 3));
+    }
+
+    #[test]
+    fn note_stats_skip_blocks_and_count_tasks() {
+        let text = "日本語 and English\n[@code rust]\n\tlet ignored = 1;\n[@math]\n\tE = mc^2\nwrite docs {@task status=todo}\nship it {@task status=done}\n";
+        let ast = parse_text(text).ast;
+        let stats = NoteStats::from_ast(&ast, text.lines().count());
+        assert_eq!(stats.words, 3 + 2 + 2 + 2);
+        assert_eq!(stats.lines, 7);
+        assert_eq!(
+            stats.tasks,
+            TaskCounts {
+                todo: 1,
+                done: 1,
+                ..Default::default()
+            }
+        );
+        assert_eq!(stats.reading_time_minutes, 1);
+    }
+}
@@ -1,14 +1,20 @@
-//! Syntax highlighting for code blocks in the TUI preview using `syntect`.
+//! Syntax highlighting for code blocks in the TUI preview and HTML export using `syntect`.
 //!
 //! Lazily initialises a [`SyntaxSet`] and [`ThemeSet`] on first use (both are
 //! embedded at compile time via syntect's `default-syntaxes` / `default-themes`
 //! features).
 
+#[cfg(feature = "preview-tui")]
 use ratatui::style::{Color, Style};
+#[cfg(feature = "preview-tui")]
 use ratatui::text::Span;
 use std::sync::OnceLock;
 use syntect::easy::HighlightLines;
-use syntect::highlighting::{Color as SyntectColor, ThemeSet};
+#[cfg(feature = "preview-tui")]
+use syntect::highlighting::Color as SyntectColor;
+use syntect::highlighting::ThemeSet;
+#[cfg(feature = "html-highlight")]
+use syntect::html::{styled_line_to_highlighted_html, IncludeBackground};
 use syntect::parsing::SyntaxSet;
 use syntect::util::LinesWithEndings;
 
@@ -22,9 +28,15 @@ fn engine() -> &'static (SyntaxSet, ThemeSet) {
     })
 }
 
+#[cfg(feature = "preview-tui")]
 const DEFAULT_THEME: &str = "base16-ocean.dark";
 
+/// Theme for HTML export, readable on the light page background.
+#[cfg(feature = "html-highlight")]
+const DEFAULT_HTML_THEME: &str = "InspiredGitHub";
+
 /// Convert a syntect foreground color to a ratatui [`Color`].
+#[cfg(feature = "preview-tui")]
 fn to_ratatui_color(c: SyntectColor) -> Color {
     Color::Rgb(c.r, c.g, c.b)
 }
@@ -34,6 +46,7 @@ fn to_ratatui_color(c: SyntectColor) -> Color {
 ///
 /// Returns one `Vec<Span<'static>>` per input line.  On unknown / empty `lang`,
 /// or any highlighting error, each line is returned as a single unstyled span.
+#[cfg(feature = "preview-tui")]
 pub fn highlight_code(lang: &str, lines: &[&str], theme: Option<&str>) -> Vec<Vec<Span<'static>>> {
     let (ss, ts) = engine();
 
@@ -98,7 +111,38 @@ pub fn highlight_code(lang: &str, lines: &[&str], theme: Option<&str>) -> Vec<Ve
     result
 }
 
+/// Highlight `lines` of code for `lang` as HTML `<span style="...">` runs, one
+/// line per `\n`, using the given `theme` name (falls back to
+/// [`DEFAULT_HTML_THEME`] when `None`).
+///
+/// Returns `None` on unknown / empty `lang` or any highlighting error so the
+/// caller can emit the code unhighlighted.
+#[cfg(feature = "html-highlight")]
+pub fn highlight_code_html(lang: &str, lines: &[&str], theme: Option<&str>) -> Option<String> {
+    if lang.is_empty() {
+        return None;
+    }
+    let (ss, ts) = engine();
+    let syntax = ss.find_syntax_by_token(lang)?;
+    let theme = ts
+        .themes
+        .get(theme.unwrap_or(DEFAULT_HTML_THEME))
+        .or_else(|| ts.themes.get(DEFAULT_HTML_THEME))?;
+
+    let mut highlighter = HighlightLines::new(syntax, theme);
+    let mut joined = lines.join("\n");
+    joined.push('\n');
+
+    let mut html = String::new();
+    for raw_line in LinesWithEndings::from(&joined) {
+        let regions = highlighter.highlight_line(raw_line, ss).ok()?;
+        html.push_str(&styled_line_to_highlighted_html(&regions, IncludeBackground::No).ok()?);
+    }
+    Some(html)
+}
+
 /// Plain (unstyled) fallback — one span per line.
+#[cfg(feature = "preview-tui")]
 fn plain_fallback(lines: &[&str]) -> Vec<Vec<Span<'static>>> {
     lines
         .iter()
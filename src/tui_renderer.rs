@@ -1,7 +1,8 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 use crate::parser::{AstNode, AstNodeKind, Property, TaskStatus};
 use crate::utils::get_gyazo_img_src;
@@ -71,27 +72,151 @@ pub struct RenderedDoc {
     pub focusables: Vec<FocusableItem>,
     /// Map from anchor name to element index.
     pub anchors: HashMap<String, usize>,
+    /// Foldable lines: source row of a line with children → last source row
+    /// of its subtree.
+    pub folds: BTreeMap<usize, usize>,
 }
 
 impl RenderedDoc {}
 
+/// Colors for the themable parts of a rendered document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocColors {
+    /// Wiki links (`[note]`, `[#anchor]`).
+    pub wiki_link: Color,
+    /// URL links and embeds.
+    pub url: Color,
+    /// Headings (`[* ...]` and larger). `None` keeps the surrounding color.
+    pub heading: Option<Color>,
+    /// Icon of completed tasks.
+    pub task_done: Color,
+}
+
+impl Default for DocColors {
+    fn default() -> Self {
+        Self {
+            wiki_link: Color::Cyan,
+            url: Color::Blue,
+            heading: None,
+            task_done: Color::Green,
+        }
+    }
+}
+
+/// Tab width used by [`render_ast`] and [`render_ast_with_colors`].
+pub const DEFAULT_TAB_WIDTH: usize = 4;
+
 /// Render an AST root node into a flat list of DocElements.
 pub fn render_ast(ast: &AstNode, syntax_theme: Option<&str>) -> RenderedDoc {
+    render_ast_with_colors(ast, syntax_theme, &DocColors::default())
+}
+
+/// Like [`render_ast`], but with the given document colors.
+pub fn render_ast_with_colors(
+    ast: &AstNode,
+    syntax_theme: Option<&str>,
+    colors: &DocColors,
+) -> RenderedDoc {
+    render_ast_with_tab_width(ast, syntax_theme, colors, DEFAULT_TAB_WIDTH)
+}
+
+/// Like [`render_ast_with_colors`], but expanding tabs to stops every
+/// `tab_width` columns instead of [`DEFAULT_TAB_WIDTH`].
+///
+/// Tabs in code blocks are expanded relative to the start of the code; any
+/// other tab is expanded relative to the start of its row. The rendered
+/// elements never contain a tab, so their display width is exact.
+pub fn render_ast_with_tab_width(
+    ast: &AstNode,
+    syntax_theme: Option<&str>,
+    colors: &DocColors,
+    tab_width: usize,
+) -> RenderedDoc {
+    render_ast_folded(ast, syntax_theme, colors, tab_width, &HashSet::new())
+}
+
+/// Like [`render_ast_with_tab_width`], but with the children of the lines at
+/// the source rows in `collapsed` replaced by a single "… N lines" marker.
+///
+/// [`RenderedDoc::folds`] lists every foldable line, collapsed or not.
+pub fn render_ast_folded(
+    ast: &AstNode,
+    syntax_theme: Option<&str>,
+    colors: &DocColors,
+    tab_width: usize,
+    collapsed: &HashSet<usize>,
+) -> RenderedDoc {
     let mut elements = Vec::new();
     let mut focusables = Vec::new();
     let mut anchors = HashMap::new();
+    let settings = RenderSettings {
+        syntax_theme,
+        tab_width,
+        collapsed,
+    };
     render_node(
         ast,
         &mut elements,
         &mut focusables,
         &mut anchors,
         0,
-        syntax_theme,
+        &settings,
+        colors,
     );
+    for element in elements.iter_mut() {
+        if let DocElement::TextLine(line, _) = element {
+            expand_tabs_in_line(line, tab_width);
+        }
+    }
+    let mut folds = BTreeMap::new();
+    collect_folds(ast, &mut folds);
     RenderedDoc {
         elements,
         focusables,
         anchors,
+        folds,
+    }
+}
+
+/// Settings shared by the whole rendering pass.
+struct RenderSettings<'a> {
+    syntax_theme: Option<&'a str>,
+    tab_width: usize,
+    /// Source rows of the lines whose children are folded away.
+    collapsed: &'a HashSet<usize>,
+}
+
+/// Replace each tab in `s` with spaces up to the next multiple of
+/// `tab_width`, counting display columns from `start_col`.
+fn expand_tabs(s: &str, start_col: usize, tab_width: usize) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut col = start_col;
+    for ch in s.chars() {
+        if ch == '\t' {
+            let n = if tab_width == 0 {
+                0
+            } else {
+                tab_width - col % tab_width
+            };
+            out.extend(std::iter::repeat_n(' ', n));
+            col += n;
+        } else {
+            out.push(ch);
+            col += UnicodeWidthChar::width(ch).unwrap_or(0);
+        }
+    }
+    out
+}
+
+/// Expand the tabs of every span in `line`, with stops counted from the
+/// start of the line.
+fn expand_tabs_in_line(line: &mut Line<'static>, tab_width: usize) {
+    let mut col = 0;
+    for span in line.spans.iter_mut() {
+        if span.content.contains('\t') {
+            span.content = expand_tabs(&span.content, col, tab_width).into();
+        }
+        col += span.content.width();
     }
 }
 
@@ -142,22 +267,106 @@ fn flush_image_row(
     buf.clear();
 }
 
+/// Render the children of the line `ast` one level deeper than `indent`, or
+/// a "… N lines" marker in their place when the line is collapsed.
+fn render_children(
+    ast: &AstNode,
+    elements: &mut Vec<DocElement>,
+    focusables: &mut Vec<FocusableItem>,
+    anchors: &mut HashMap<String, usize>,
+    indent: usize,
+    settings: &RenderSettings<'_>,
+    colors: &DocColors,
+) {
+    let row = ast.location().row;
+    if settings.collapsed.contains(&row) {
+        let Some(first_row) = ast
+            .value()
+            .children
+            .lock()
+            .unwrap()
+            .first()
+            .map(|c| c.location().row)
+        else {
+            return;
+        };
+        let hidden = subtree_last_row(ast) - row;
+        elements.push(DocElement::TextLine(
+            Line::from(vec![
+                Span::raw("  ".repeat(indent + 1)),
+                Span::styled(
+                    format!("… {} lines", hidden),
+                    Style::default()
+                        .fg(Color::DarkGray)
+                        .add_modifier(Modifier::DIM),
+                ),
+            ]),
+            first_row,
+        ));
+        return;
+    }
+    let children = ast.value().children.lock().unwrap();
+    for child in children.iter() {
+        render_node(
+            child,
+            elements,
+            focusables,
+            anchors,
+            indent + 1,
+            settings,
+            colors,
+        );
+    }
+}
+
+/// Last source row covered by `ast`, its block contents and its children.
+fn subtree_last_row(ast: &AstNode) -> usize {
+    let mut last = ast.location().row;
+    for content in ast.value().contents.lock().unwrap().iter() {
+        if !content.value().children.lock().unwrap().is_empty() {
+            last = last.max(subtree_last_row(content));
+        }
+    }
+    if let Some(child) = ast.value().children.lock().unwrap().last() {
+        last = last.max(subtree_last_row(child));
+    }
+    last
+}
+
+/// Record every line with children in `folds`, keyed by its source row.
+fn collect_folds(ast: &AstNode, folds: &mut BTreeMap<usize, usize>) {
+    let has_children = !ast.value().children.lock().unwrap().is_empty();
+    if has_children && matches!(ast.kind(), AstNodeKind::Line { .. }) {
+        folds.insert(ast.location().row, subtree_last_row(ast));
+    }
+    let children = ast.value().children.lock().unwrap();
+    for child in children.iter() {
+        collect_folds(child, folds);
+    }
+}
+
 fn render_node(
     ast: &AstNode,
     elements: &mut Vec<DocElement>,
     focusables: &mut Vec<FocusableItem>,
     anchors: &mut HashMap<String, usize>,
     indent: usize,
-    syntax_theme: Option<&str>,
+    settings: &RenderSettings<'_>,
+    colors: &DocColors,
 ) {
     match ast.kind() {
         AstNodeKind::Dummy => {
             let children = ast.value().children.lock().unwrap();
             for child in children.iter() {
-                render_node(child, elements, focusables, anchors, indent, syntax_theme);
+                render_node(
+                    child, elements, focusables, anchors, indent, settings, colors,
+                );
             }
         }
         AstNodeKind::Line { properties } | AstNodeKind::QuoteContent { properties } => {
+            if ast.is_comment_line() {
+                return;
+            }
             let is_quote = matches!(ast.kind(), AstNodeKind::QuoteContent { .. });
 
             // Check if this line is a block container (only content is a block element)
@@ -181,20 +390,11 @@ fn render_node(
                     focusables,
                     anchors,
                     indent,
-                    syntax_theme,
+                    settings,
+                    colors,
                 );
                 // Still render children (nested lines after the block)
-                let children = ast.value().children.lock().unwrap();
-                for child in children.iter() {
-                    render_node(
-                        child,
-                        elements,
-                        focusables,
-                        anchors,
-                        indent + 1,
-                        syntax_theme,
-                    );
-                }
+                render_children(ast, elements, focusables, anchors, indent, settings, colors);
                 return;
             }
             drop(contents);
@@ -230,7 +430,7 @@ fn render_node(
             // Task icon / bullet
             if let Some(status) = task_status {
                 let (icon, color) = match status {
-                    TaskStatus::Done => ("✓ ", Color::Green),
+                    TaskStatus::Done => ("✓ ", colors.task_done),
                     TaskStatus::Doing => ("◑ ", Color::Yellow),
                     TaskStatus::Paused => ("⏸ ", Color::Cyan),
                     _ => ("○ ", Color::White),
@@ -267,8 +467,14 @@ fn render_node(
 
             let contents = ast.value().contents.lock().unwrap();
             for content in contents.iter() {
-                let result =
-                    render_inline(content, &mut spans, base_style, focusables, elements.len());
+                let result = render_inline(
+                    content,
+                    &mut spans,
+                    base_style,
+                    focusables,
+                    elements.len(),
+                    colors,
+                );
                 match result {
                     InlineResult::ImageBlock { src, alt } => {
                         // If spans have real text, flush them before starting an image group
@@ -312,22 +518,15 @@ fn render_node(
             elements.push(DocElement::TextLine(Line::from(spans), ast.location().row));
 
             // Children (nested lines)
-            let children = ast.value().children.lock().unwrap();
-            for child in children.iter() {
-                render_node(
-                    child,
-                    elements,
-                    focusables,
-                    anchors,
-                    indent + 1,
-                    syntax_theme,
-                );
-            }
+            drop(contents);
+            render_children(ast, elements, focusables, anchors, indent, settings, colors);
         }
         AstNodeKind::Quote => {
             let children = ast.value().children.lock().unwrap();
             for child in children.iter() {
-                render_node(child, elements, focusables, anchors, indent, syntax_theme);
+                render_node(
+                    child, elements, focusables, anchors, indent, settings, colors,
+                );
             }
         }
         AstNodeKind::Math { inline } => {
@@ -374,12 +573,12 @@ fn render_node(
                 let children = ast.value().children.lock().unwrap();
                 let raw_lines: Vec<String> = children
                     .iter()
-                    .map(|c| c.extract_str().replace('\t', "    "))
+                    .map(|c| expand_tabs(c.extract_str(), 0, settings.tab_width))
                     .collect();
                 drop(children);
                 let raw_refs: Vec<&str> = raw_lines.iter().map(|s| s.as_str()).collect();
                 let highlighted =
-                    crate::syntax_highlight::highlight_code(lang, &raw_refs, syntax_theme);
+                    crate::syntax_highlight::highlight_code(lang, &raw_refs, settings.syntax_theme);
                 for (line_spans, _raw) in highlighted.into_iter().zip(raw_lines.iter()) {
                     let mut spans = vec![Span::raw(prefix.clone())];
                     if line_spans.is_empty() {
@@ -392,7 +591,7 @@ fn render_node(
                 }
             }
         }
-        AstNodeKind::Image { src, alt } => {
+        AstNodeKind::Image { src, alt, .. } => {
             let mut src_resolved = src.clone();
             if let Some(gyazo_src) = get_gyazo_img_src(src) {
                 src_resolved = gyazo_src;
@@ -424,7 +623,9 @@ fn render_node(
         | AstNodeKind::Link { .. }
         | AstNodeKind::Embed { .. }
         | AstNodeKind::Decoration { .. }
+        | AstNodeKind::DefinitionItem { .. }
         | AstNodeKind::Text
+        | AstNodeKind::Comment
         | AstNodeKind::MathContent
         | AstNodeKind::CodeContent => {
             // These are inline — rendered by render_inline when inside a Line
@@ -449,9 +650,32 @@ fn render_node(
                 ));
             }
             let children = ast.value().children.lock().unwrap();
+            let widths = table_column_widths(&children, colors);
+            if widths.is_empty() {
+                return;
+            }
+            elements.push(DocElement::TextLine(
+                table_border(indent, &widths, '┌', '┬', '┐'),
+                ast.location().row,
+            ));
             for child in children.iter() {
-                render_table_row(child, elements, focusables, indent, child.location().row);
+                render_table_row(
+                    child,
+                    elements,
+                    focusables,
+                    indent,
+                    &widths,
+                    child.location().row,
+                    colors,
+                );
             }
+            let last_row = children
+                .last()
+                .map_or(ast.location().row, |c| c.location().row);
+            elements.push(DocElement::TextLine(
+                table_border(indent, &widths, '└', '┴', '┘'),
+                last_row,
+            ));
         }
         AstNodeKind::TableRow | AstNodeKind::TableColumn => {
             // Handled inside Table rendering
@@ -459,25 +683,88 @@ fn render_node(
     }
 }
 
+/// Display width of the widest cell in each column, in terminal columns.
+fn table_column_widths(rows: &[AstNode], colors: &DocColors) -> Vec<usize> {
+    let mut widths: Vec<usize> = Vec::new();
+    for row in rows {
+        let contents = row.value().contents.lock().unwrap();
+        for (i, col) in contents.iter().enumerate() {
+            // Render into scratch buffers only to measure the cell
+            let mut spans = Vec::new();
+            let mut focusables = Vec::new();
+            for c in col.value().contents.lock().unwrap().iter() {
+                render_inline(c, &mut spans, Style::default(), &mut focusables, 0, colors);
+            }
+            let width = spans.iter().map(|s| s.content.width()).sum();
+            if i < widths.len() {
+                widths[i] = widths[i].max(width);
+            } else {
+                widths.push(width);
+            }
+        }
+    }
+    widths
+}
+
+/// A horizontal table border such as `┌───┬───┐` for the given column widths.
+fn table_border(
+    indent: usize,
+    widths: &[usize],
+    left: char,
+    middle: char,
+    right: char,
+) -> Line<'static> {
+    let mut border = String::new();
+    border.push(left);
+    for (i, width) in widths.iter().enumerate() {
+        if i > 0 {
+            border.push(middle);
+        }
+        border.push_str(&"─".repeat(width + 2));
+    }
+    border.push(right);
+    Line::from(vec![
+        Span::raw("  ".repeat(indent)),
+        Span::styled(border, Style::default().fg(Color::DarkGray)),
+    ])
+}
+
 fn render_table_row(
     ast: &AstNode,
     elements: &mut Vec<DocElement>,
     focusables: &mut Vec<FocusableItem>,
     indent: usize,
+    widths: &[usize],
     source_row: usize,
+    colors: &DocColors,
 ) {
     let mut spans: Vec<Span<'static>> = Vec::new();
     spans.push(Span::raw("  ".repeat(indent)));
     spans.push(Span::styled("│ ", Style::default().fg(Color::DarkGray)));
 
     let contents = ast.value().contents.lock().unwrap();
-    for (i, col) in contents.iter().enumerate() {
+    for (i, width) in widths.iter().enumerate() {
         if i > 0 {
             spans.push(Span::styled(" │ ", Style::default().fg(Color::DarkGray)));
         }
-        let col_contents = col.value().contents.lock().unwrap();
-        for c in col_contents.iter() {
-            render_inline(c, &mut spans, Style::default(), focusables, elements.len());
+        let cell_start = spans.len();
+        if let Some(col) = contents.get(i) {
+            let col_contents = col.value().contents.lock().unwrap();
+            for c in col_contents.iter() {
+                render_inline(
+                    c,
+                    &mut spans,
+                    Style::default(),
+                    focusables,
+                    elements.len(),
+                    colors,
+                );
+            }
+        }
+        // Pad short cells (and missing ones of ragged rows) to the column width
+        let cell_width: usize = spans[cell_start..].iter().map(|s| s.content.width()).sum();
+        if cell_width < *width {
+            spans.push(Span::raw(" ".repeat(width - cell_width)));
         }
     }
     spans.push(Span::styled(" │", Style::default().fg(Color::DarkGray)));
@@ -495,6 +782,7 @@ fn render_inline(
     base_style: Style,
     focusables: &mut Vec<FocusableItem>,
     current_elem_idx: usize,
+    colors: &DocColors,
 ) -> InlineResult {
     match ast.kind() {
         AstNodeKind::Text => {
@@ -516,7 +804,7 @@ fn render_inline(
             spans.push(Span::styled(
                 text,
                 base_style
-                    .fg(Color::Cyan)
+                    .fg(colors.wiki_link)
                     .add_modifier(Modifier::UNDERLINED),
             ));
             // Self-link: empty link name with anchor -> jump within current doc
@@ -551,9 +839,7 @@ fn render_inline(
             let char_end = char_start + display.chars().count();
             spans.push(Span::styled(
                 display.to_string(),
-                base_style
-                    .fg(Color::Blue)
-                    .add_modifier(Modifier::UNDERLINED),
+                base_style.fg(colors.url).add_modifier(Modifier::UNDERLINED),
             ));
             focusables.push(FocusableItem {
                 elem_idx: current_elem_idx,
@@ -574,12 +860,10 @@ fn render_inline(
             let char_end = char_start + text.chars().count();
             let style = if is_pdf {
                 base_style
-                    .fg(Color::Cyan)
+                    .fg(colors.wiki_link)
                     .add_modifier(Modifier::UNDERLINED)
             } else {
-                base_style
-                    .fg(Color::Blue)
-                    .add_modifier(Modifier::UNDERLINED)
+                base_style.fg(colors.url).add_modifier(Modifier::UNDERLINED)
             };
             spans.push(Span::styled(text, style));
             focusables.push(FocusableItem {
@@ -616,6 +900,9 @@ fn render_inline(
             let mut style = base_style;
             if *fontsize > 0 {
                 style = style.add_modifier(Modifier::BOLD);
+                if let Some(heading) = colors.heading {
+                    style = style.fg(heading);
+                }
             }
             if *italic {
                 style = style.add_modifier(Modifier::ITALIC);
@@ -628,13 +915,34 @@ fn render_inline(
             }
             let contents = ast.value().contents.lock().unwrap();
             for content in contents.iter() {
-                let result = render_inline(content, spans, style, focusables, current_elem_idx);
+                let result =
+                    render_inline(content, spans, style, focusables, current_elem_idx, colors);
+                if matches!(result, InlineResult::ImageBlock { .. }) {
+                    return result;
+                }
+            }
+        }
+        AstNodeKind::DefinitionItem { term } => {
+            spans.push(Span::styled(
+                format!("{}: ", term),
+                base_style.add_modifier(Modifier::BOLD),
+            ));
+            let contents = ast.value().contents.lock().unwrap();
+            for content in contents.iter() {
+                let result = render_inline(
+                    content,
+                    spans,
+                    base_style,
+                    focusables,
+                    current_elem_idx,
+                    colors,
+                );
                 if matches!(result, InlineResult::ImageBlock { .. }) {
                     return result;
                 }
             }
         }
-        AstNodeKind::Image { src, alt } => {
+        AstNodeKind::Image { src, alt, .. } => {
             let mut src_resolved = src.clone();
             if let Some(gyazo_src) = get_gyazo_img_src(src) {
                 src_resolved = gyazo_src;
@@ -653,9 +961,11 @@ fn render_inline(
                     base_style.fg(Color::DarkGray),
                     focusables,
                     current_elem_idx,
+                    colors,
                 );
             }
         }
+        AstNodeKind::Comment => {}
         _ => {
             // Fallback: raw text
             let text = ast.extract_str();
@@ -666,3 +976,332 @@ fn render_inline(
     }
     InlineResult::Inline
 }
+
+/// Convert a rendered document into a standalone HTML page whose inline
+/// styles reproduce the terminal preview.
+///
+/// Unlike [`crate::renderer::HtmlRenderer`], which emits semantic HTML, this
+/// captures the look of the preview: every span becomes a
+/// `<span style="...">` with the colors and modifiers of its ratatui style.
+pub fn render_to_html(doc: &RenderedDoc) -> String {
+    let mut html = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n</head>\n\
+         <body style=\"margin:0;background:#000000;\">\n\
+         <pre style=\"margin:0;padding:1em;color:#e5e5e5;background:#000000;\
+         font-family:monospace;\">",
+    );
+    for element in &doc.elements {
+        match element {
+            DocElement::TextLine(line, _) => {
+                for span in &line.spans {
+                    let text = html_escape::encode_text(&span.content);
+                    let css = style_to_css(span.style);
+                    if css.is_empty() {
+                        html.push_str(&text);
+                    } else {
+                        html.push_str(&format!("<span style=\"{}\">{}</span>", css, text));
+                    }
+                }
+            }
+            DocElement::Image { src, alt, indent } => {
+                html.push_str(&"  ".repeat(*indent));
+                html.push_str(&image_tag(src, alt.as_deref()));
+            }
+            DocElement::ImageRow(images, indent) => {
+                html.push_str(&"  ".repeat(*indent));
+                for (src, alt) in images {
+                    html.push_str(&image_tag(src, alt.as_deref()));
+                }
+            }
+            DocElement::Math { content, indent } => {
+                let prefix = "  ".repeat(*indent);
+                let body = content
+                    .lines()
+                    .map(|l| format!("{}{}", prefix, html_escape::encode_text(l)))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                html.push_str(&format!("<span style=\"color:#cd00cd;\">{}</span>", body));
+            }
+            DocElement::Spacer => {}
+        }
+        html.push('\n');
+    }
+    html.push_str("</pre>\n</body>\n</html>\n");
+    html
+}
+
+fn image_tag(src: &str, alt: Option<&str>) -> String {
+    format!(
+        "<img src=\"{}\" alt=\"{}\" style=\"max-height:20em;vertical-align:top;\">",
+        html_escape::encode_double_quoted_attribute(src),
+        html_escape::encode_double_quoted_attribute(alt.unwrap_or_default())
+    )
+}
+
+/// CSS declarations equivalent to a ratatui `Style`.
+fn style_to_css(style: Style) -> String {
+    let mut css = String::new();
+    if let Some(color) = style.fg.and_then(color_to_css) {
+        css.push_str(&format!("color:{};", color));
+    }
+    if let Some(color) = style.bg.and_then(color_to_css) {
+        css.push_str(&format!("background-color:{};", color));
+    }
+    let modifier = style.add_modifier;
+    if modifier.contains(Modifier::BOLD) {
+        css.push_str("font-weight:bold;");
+    }
+    if modifier.contains(Modifier::DIM) {
+        css.push_str("opacity:0.6;");
+    }
+    if modifier.contains(Modifier::ITALIC) {
+        css.push_str("font-style:italic;");
+    }
+    let underlined = modifier.contains(Modifier::UNDERLINED);
+    let crossed_out = modifier.contains(Modifier::CROSSED_OUT);
+    match (underlined, crossed_out) {
+        (true, true) => css.push_str("text-decoration:underline line-through;"),
+        (true, false) => css.push_str("text-decoration:underline;"),
+        (false, true) => css.push_str("text-decoration:line-through;"),
+        (false, false) => {}
+    }
+    css
+}
+
+/// CSS color for a terminal color, using the xterm palette.
+/// `Color::Reset` has no CSS equivalent and inherits the surrounding color.
+fn color_to_css(color: Color) -> Option<String> {
+    const ANSI: [(u8, u8, u8); 16] = [
+        (0x00, 0x00, 0x00),
+        (0xcd, 0x00, 0x00),
+        (0x00, 0xcd, 0x00),
+        (0xcd, 0xcd, 0x00),
+        (0x00, 0x00, 0xee),
+        (0xcd, 0x00, 0xcd),
+        (0x00, 0xcd, 0xcd),
+        (0xe5, 0xe5, 0xe5),
+        (0x7f, 0x7f, 0x7f),
+        (0xff, 0x00, 0x00),
+        (0x00, 0xff, 0x00),
+        (0xff, 0xff, 0x00),
+        (0x5c, 0x5c, 0xff),
+        (0xff, 0x00, 0xff),
+        (0x00, 0xff, 0xff),
+        (0xff, 0xff, 0xff),
+    ];
+    let (r, g, b) = match color {
+        Color::Reset => return None,
+        Color::Black => ANSI[0],
+        Color::Red => ANSI[1],
+        Color::Green => ANSI[2],
+        Color::Yellow => ANSI[3],
+        Color::Blue => ANSI[4],
+        Color::Magenta => ANSI[5],
+        Color::Cyan => ANSI[6],
+        Color::Gray => ANSI[7],
+        Color::DarkGray => ANSI[8],
+        Color::LightRed => ANSI[9],
+        Color::LightGreen => ANSI[10],
+        Color::LightYellow => ANSI[11],
+        Color::LightBlue => ANSI[12],
+        Color::LightMagenta => ANSI[13],
+        Color::LightCyan => ANSI[14],
+        Color::White => ANSI[15],
+        Color::Rgb(r, g, b) => (r, g, b),
+        Color::Indexed(i) if i < 16 => ANSI[i as usize],
+        Color::Indexed(i) if i < 232 => {
+            // 6x6x6 color cube
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            let i = i - 16;
+            (level(i / 36), level((i / 6) % 6), level(i % 6))
+        }
+        Color::Indexed(i) => {
+            // Grayscale ramp
+            let v = 8 + (i - 232) * 10;
+            (v, v, v)
+        }
+    };
+    Some(format!("#{:02x}{:02x}{:02x}", r, g, b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_to_css() {
+        assert_eq!(color_to_css(Color::Reset), None);
+        assert_eq!(color_to_css(Color::Red).as_deref(), Some("#cd0000"));
+        assert_eq!(color_to_css(Color::White).as_deref(), Some("#ffffff"));
+        assert_eq!(
+            color_to_css(Color::Rgb(0x12, 0xab, 0xef)).as_deref(),
+            Some("#12abef")
+        );
+        // The 16 base colors, the 6x6x6 cube and the grayscale ramp
+        assert_eq!(color_to_css(Color::Indexed(9)).as_deref(), Some("#ff0000"));
+        assert_eq!(color_to_css(Color::Indexed(16)).as_deref(), Some("#000000"));
+        assert_eq!(
+            color_to_css(Color::Indexed(196)).as_deref(),
+            Some("#ff0000")
+        );
+        assert_eq!(
+            color_to_css(Color::Indexed(231)).as_deref(),
+            Some("#ffffff")
+        );
+        assert_eq!(
+            color_to_css(Color::Indexed(232)).as_deref(),
+            Some("#080808")
+        );
+        assert_eq!(
+            color_to_css(Color::Indexed(255)).as_deref(),
+            Some("#eeeeee")
+        );
+    }
+
+    #[test]
+    fn test_style_to_css() {
+        assert_eq!(style_to_css(Style::default()), "");
+        let style = Style::default()
+            .fg(Color::Cyan)
+            .bg(Color::Black)
+            .add_modifier(Modifier::BOLD | Modifier::ITALIC | Modifier::UNDERLINED);
+        assert_eq!(
+            style_to_css(style),
+            "color:#00cdcd;background-color:#000000;font-weight:bold;\
+             font-style:italic;text-decoration:underline;"
+        );
+        let style = Style::default().add_modifier(Modifier::UNDERLINED | Modifier::CROSSED_OUT);
+        assert_eq!(
+            style_to_css(style),
+            "text-decoration:underline line-through;"
+        );
+    }
+
+    #[test]
+    fn test_render_to_html_styles_and_escapes_spans() {
+        let doc = RenderedDoc {
+            elements: vec![
+                DocElement::TextLine(
+                    Line::from(vec![
+                        Span::raw("a < b & c"),
+                        Span::styled("<b>", Style::default().add_modifier(Modifier::BOLD)),
+                    ]),
+                    0,
+                ),
+                DocElement::Image {
+                    src: "x\".png".to_string(),
+                    alt: Some("<alt>".to_string()),
+                    indent: 0,
+                },
+            ],
+            focusables: Vec::new(),
+            anchors: HashMap::new(),
+            folds: BTreeMap::new(),
+        };
+
+        let html = render_to_html(&doc);
+        assert!(
+            html.contains("a &lt; b &amp; c<span style=\"font-weight:bold;\">&lt;b&gt;</span>\n")
+        );
+        assert!(html.contains("src=\"x&quot;.png\" alt=\"&lt;alt&gt;\""));
+        assert!(!html.contains("<b>"));
+    }
+
+    #[test]
+    fn test_expand_tabs_to_next_stop() {
+        assert_eq!(expand_tabs("\tx", 0, 4), "    x");
+        assert_eq!(expand_tabs("ab\tx", 0, 4), "ab  x");
+        assert_eq!(expand_tabs("abcd\tx", 0, 4), "abcd    x");
+        // Stops are counted from `start_col`, not from the start of `s`
+        assert_eq!(expand_tabs("\tx", 1, 4), "   x");
+        assert_eq!(expand_tabs("a\tb\tc", 3, 4), "a    b   c");
+        assert_eq!(expand_tabs("a\tb", 0, 0), "ab");
+    }
+
+    #[test]
+    fn test_expand_tabs_after_wide_characters() {
+        // Each CJK character takes two columns
+        assert_eq!(expand_tabs("日\tx", 0, 4), "日  x");
+        assert_eq!(expand_tabs("日本\tx", 0, 4), "日本    x");
+        assert_eq!(expand_tabs("a日\tx", 0, 4), "a日 x");
+    }
+
+    #[test]
+    fn test_expand_tabs_in_line_spans_share_stops() {
+        let mut line = Line::from(vec![Span::raw("日本語"), Span::raw("\tx")]);
+        expand_tabs_in_line(&mut line, 4);
+        assert_eq!(line.spans[1].content, "  x");
+        assert_eq!(line.width(), 9);
+    }
+
+    /// Text of each rendered line, with placeholders for other elements
+    fn rendered_lines(doc: &RenderedDoc) -> Vec<String> {
+        doc.elements
+            .iter()
+            .map(|element| match element {
+                DocElement::TextLine(line, _) => line.to_string(),
+                other => format!("{:?}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_table_columns_align_with_wide_text() {
+        let ast = crate::parser::parse_text("[@table]\n\tname\t値\n\t日本語\tx\n\ta\n").ast;
+        let doc = render_ast(&ast, None);
+
+        assert_eq!(
+            rendered_lines(&doc),
+            vec![
+                "┌────────┬────┐",
+                "│ name   │ 値 │",
+                "│ 日本語 │ x  │",
+                "│ a      │    │",
+                "└────────┴────┘",
+            ]
+        );
+        // Every row is as wide as the borders
+        assert!(doc.elements.iter().all(|element| match element {
+            DocElement::TextLine(line, _) => line.width() == 15,
+            _ => false,
+        }));
+    }
+
+    #[test]
+    fn test_folded_line_hides_its_children() {
+        let text = "a\n\tb\n\t\tc\n\td\ne\n\tf\n";
+        let ast = crate::parser::parse_text(text).ast;
+        let render = |collapsed: &[usize]| {
+            render_ast_folded(
+                &ast,
+                None,
+                &DocColors::default(),
+                DEFAULT_TAB_WIDTH,
+                &collapsed.iter().copied().collect(),
+            )
+        };
+
+        let doc = render(&[]);
+        assert_eq!(
+            doc.folds,
+            BTreeMap::from([(0, 3), (1, 2), (4, 5)]),
+            "folds are listed whether collapsed or not"
+        );
+
+        // Collapsing `a` hides `b`, `c` and `d`, nested ones included
+        let doc = render(&[0]);
+        assert_eq!(rendered_lines(&doc), vec!["a", "  … 3 lines", "e", "  • f"]);
+        assert_eq!(doc.folds, render(&[]).folds);
+        let DocElement::TextLine(_, row) = &doc.elements[1] else {
+            panic!("Expected the fold marker");
+        };
+        assert_eq!(*row, 1, "the marker stands at the first hidden row");
+
+        // A nested fold only hides its own subtree
+        let doc = render(&[1]);
+        assert_eq!(
+            rendered_lines(&doc),
+            vec!["a", "  • b", "    … 1 lines", "  • d", "e", "  • f"]
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use reqwest;
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use url::Url;
 
 pub(crate) fn get_youtube_id(value: &str) -> Option<String> {
@@ -66,3 +66,68 @@ pub(crate) fn get_gyazo_img_src(url: &str) -> Option<String> {
         _ => None,
     }
 }
+
+/// Lowercase `text`, keep letters and digits (CJK included), and join words with `-`
+///
+/// Punctuation is dropped, so `Getting Started!` becomes `getting-started` and
+/// `はじめに、概要` becomes `はじめに概要`.
+pub fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if (c.is_whitespace() || c == '-' || c == '_')
+            && !slug.is_empty()
+            && !slug.ends_with('-')
+        {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+/// `base`, or `base-1`, `base-2`, ... if taken; the result is added to `used`
+pub fn unique_slug(base: &str, used: &mut HashSet<String>) -> String {
+    let mut slug = base.to_string();
+    let mut suffix = 1;
+    while used.contains(&slug) {
+        slug = format!("{}-{}", base, suffix);
+        suffix += 1;
+    }
+    used.insert(slug.clone());
+    slug
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slugify_ascii() {
+        assert_eq!(slugify("Getting Started!"), "getting-started");
+        assert_eq!(slugify("C++ & Rust_2024"), "c-rust-2024");
+        assert_eq!(slugify("  --Trim me--  "), "trim-me");
+    }
+
+    #[test]
+    fn slugify_collapses_spaces() {
+        assert_eq!(slugify("a   b\tc"), "a-b-c");
+        assert_eq!(slugify("   "), "");
+    }
+
+    #[test]
+    fn slugify_japanese() {
+        assert_eq!(slugify("はじめに"), "はじめに");
+        assert_eq!(slugify("第1章　概要、背景"), "第1章-概要背景");
+        assert_eq!(slugify("Rust入門 ガイド"), "rust入門-ガイド");
+    }
+
+    #[test]
+    fn unique_slug_suffixes() {
+        let mut used = HashSet::new();
+        assert_eq!(unique_slug("intro", &mut used), "intro");
+        assert_eq!(unique_slug("intro", &mut used), "intro-1");
+        assert_eq!(unique_slug("intro", &mut used), "intro-2");
+        assert!(used.contains("intro-1"));
+    }
+}
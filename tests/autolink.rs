@@ -0,0 +1,101 @@
+//! Tests for turning bare URLs in text into links
+
+use patto::markdown::{MarkdownFlavor, MarkdownRendererOptions};
+use patto::parser::{self, AstNode, AstNodeKind};
+use patto::renderer::{HtmlRenderer, HtmlRendererOptions, MarkdownRenderer, Renderer};
+
+/// Parse `text`, autolink it and return the contents of its first line
+fn autolinked_contents(text: &str) -> Vec<AstNode> {
+    let ast = parser::parse_text(text).ast;
+    parser::autolink_urls(&ast);
+    let line = ast.value().children.lock().unwrap()[0].clone();
+    let contents = line.value().contents.lock().unwrap().clone();
+    contents
+}
+
+fn links(contents: &[AstNode]) -> Vec<String> {
+    contents
+        .iter()
+        .filter_map(|node| match node.kind() {
+            AstNodeKind::Link { link, title: None } => Some(link.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_bare_url_becomes_link() {
+    let contents = autolinked_contents("see https://example.com/a?b=1 for details\n");
+
+    assert_eq!(links(&contents), vec!["https://example.com/a?b=1"]);
+    let texts: Vec<_> = contents.iter().map(|node| node.extract_str()).collect();
+    assert_eq!(
+        texts,
+        vec!["see ", "https://example.com/a?b=1", " for details"]
+    );
+}
+
+#[test]
+fn test_trailing_punctuation_is_not_linked() {
+    let contents =
+        autolinked_contents("see https://x.com. or (https://y.com/a_(b)), ok? https://z.com。\n");
+
+    assert_eq!(
+        links(&contents),
+        vec!["https://x.com", "https://y.com/a_(b)", "https://z.com"]
+    );
+    assert_eq!(contents.last().unwrap().extract_str(), "。");
+}
+
+#[test]
+fn test_autolink_leaves_other_nodes_alone() {
+    let contents =
+        autolinked_contents("[title https://a.com] `https://b.com` [* https://c.com] plain\n");
+
+    // The bracketed link keeps its title and code is untouched
+    assert!(matches!(
+        contents[0].kind(),
+        AstNodeKind::Link { title: Some(_), .. }
+    ));
+    assert!(contents.iter().all(
+        |node| !matches!(node.kind(), AstNodeKind::Link { link, .. } if link == "https://b.com")
+    ));
+    // Decorated text is linked too
+    let decorated = contents
+        .iter()
+        .find(|node| matches!(node.kind(), AstNodeKind::Decoration { .. }))
+        .unwrap();
+    let decorated_contents = decorated.value().contents.lock().unwrap().clone();
+    assert_eq!(links(&decorated_contents), vec!["https://c.com"]);
+}
+
+#[test]
+fn test_autolink_is_opt_in() {
+    let ast = parser::parse_text("see https://example.com\n").ast;
+    let mut html = Vec::new();
+    HtmlRenderer::new(HtmlRendererOptions::default())
+        .format(&ast, &mut html)
+        .unwrap();
+    assert!(!String::from_utf8(html).unwrap().contains("<a "));
+
+    parser::autolink_urls(&ast);
+    let mut html = Vec::new();
+    HtmlRenderer::new(HtmlRendererOptions::default())
+        .format(&ast, &mut html)
+        .unwrap();
+    let html = String::from_utf8(html).unwrap();
+    assert!(
+        html.contains(
+            r#"<a class="patto-link" href="https://example.com">https://example.com</a>"#
+        ),
+        "{}",
+        html
+    );
+
+    let mut markdown = Vec::new();
+    MarkdownRenderer::new(MarkdownRendererOptions::new(MarkdownFlavor::Standard))
+        .format(&ast, &mut markdown)
+        .unwrap();
+    let markdown = String::from_utf8(markdown).unwrap();
+    assert!(markdown.contains("https://example.com"), "{}", markdown);
+}
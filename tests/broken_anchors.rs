@@ -0,0 +1,65 @@
+//! Tests for `Repository::find_broken_anchors`
+
+use patto::repository::Repository;
+use tempfile::TempDir;
+use tower_lsp::lsp_types::Url;
+
+/// Write `files` into a temporary workspace and index them in order
+fn workspace(files: &[(&str, &str)]) -> (TempDir, Repository) {
+    let dir = TempDir::new().unwrap();
+    let repo = Repository::new(dir.path().to_path_buf());
+    for (name, content) in files {
+        std::fs::write(dir.path().join(name), content).unwrap();
+    }
+    for (name, content) in files {
+        repo.add_file_to_graph(&dir.path().join(name), content);
+    }
+    (dir, repo)
+}
+
+#[tokio::test]
+async fn test_reports_missing_anchor() {
+    let (dir, repo) = workspace(&[
+        (
+            "source.pn",
+            "intro\nsee [target#present] and [target#missing]\n",
+        ),
+        ("target.pn", "heading #present\n"),
+    ]);
+
+    let broken = repo.find_broken_anchors();
+    assert_eq!(broken.len(), 1, "{:?}", broken);
+    let (uri, location, anchor) = &broken[0];
+    assert_eq!(
+        uri,
+        &Url::from_file_path(dir.path().join("source.pn")).unwrap()
+    );
+    assert_eq!(anchor, "missing");
+    assert_eq!(location.row, 1);
+    assert_eq!(
+        &location.input[location.span.0..location.span.1],
+        "[target#missing]"
+    );
+}
+
+#[tokio::test]
+async fn test_target_indexed_after_source() {
+    let (_dir, repo) = workspace(&[
+        ("source.pn", "[target#present]\n"),
+        ("target.pn", "heading #present\n"),
+    ]);
+    assert!(repo.find_broken_anchors().is_empty());
+}
+
+#[tokio::test]
+async fn test_skips_self_links_and_unindexed_targets() {
+    let dir = TempDir::new().unwrap();
+    let repo = Repository::new(dir.path().to_path_buf());
+    let source = "[#nowhere] [unindexed#anchor] [source#nowhere]\n";
+    std::fs::write(dir.path().join("source.pn"), source).unwrap();
+    // Exists on disk but has not been scanned yet
+    std::fs::write(dir.path().join("unindexed.pn"), "no anchors\n").unwrap();
+    repo.add_file_to_graph(&dir.path().join("source.pn"), source);
+
+    assert!(repo.find_broken_anchors().is_empty());
+}
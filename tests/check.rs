@@ -0,0 +1,45 @@
+//! Tests for the vault-wide linting behind `patto check`
+
+use patto::check::check;
+use patto::repository::Repository;
+use tempfile::TempDir;
+
+/// Write `files` into a temporary workspace and index them in order
+fn workspace(files: &[(&str, &str)]) -> (TempDir, Repository) {
+    let dir = TempDir::new().unwrap();
+    let repo = Repository::new(dir.path().to_path_buf());
+    for (name, content) in files {
+        std::fs::write(dir.path().join(name), content).unwrap();
+    }
+    for (name, content) in files {
+        repo.add_file_to_graph(&dir.path().join(name), content);
+    }
+    (dir, repo)
+}
+
+#[tokio::test]
+async fn test_check_reports_problems_in_order() {
+    let (dir, repo) = workspace(&[
+        ("a.pn", "top #a\nsee [b#x] and [missing]\nagain #a\n"),
+        ("b.pn", "b #y\n"),
+    ]);
+
+    let problems: Vec<_> = check(&repo, dir.path())
+        .into_iter()
+        .map(|p| (p.file, p.line, p.column, p.kind))
+        .collect();
+    assert_eq!(
+        problems,
+        vec![
+            ("a.pn".to_string(), 2, 5, "broken-anchor"),
+            ("a.pn".to_string(), 2, 15, "dangling-link"),
+            ("a.pn".to_string(), 3, 7, "duplicate-anchor"),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_check_clean_vault() {
+    let (dir, repo) = workspace(&[("a.pn", "see [b#y]\n"), ("b.pn", "b #y\n")]);
+    assert!(check(&repo, dir.path()).is_empty());
+}
@@ -0,0 +1,80 @@
+//! Tests for `%% inline %%` and `//` line comments
+
+use patto::markdown::{MarkdownFlavor, MarkdownRendererOptions};
+use patto::parser::{self, AstNode, AstNodeKind};
+use patto::renderer::{
+    HtmlRenderer, HtmlRendererOptions, MarkdownRenderer, PattoRenderer, Renderer,
+};
+use patto::semantic_token::get_semantic_tokens;
+
+const SOURCE: &str = "visible %% private note %% text\n// hidden line\n\thidden child\nlast line\n";
+
+fn parse(text: &str) -> AstNode {
+    let result = parser::parse_text(text);
+    assert!(
+        result.parse_errors.is_empty(),
+        "Parse errors: {:?}",
+        result.parse_errors
+    );
+    result.ast
+}
+
+fn render(renderer: &dyn Renderer, text: &str) -> String {
+    let mut output = Vec::new();
+    renderer.format(&parse(text), &mut output).unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn test_html_drops_comments() {
+    let output = render(&HtmlRenderer::new(HtmlRendererOptions::default()), SOURCE);
+    assert!(output.contains("visible  text"));
+    assert!(output.contains("last line"));
+    assert!(!output.contains("private note"));
+    assert!(!output.contains("%%"));
+    // A comment line is left out together with its nested lines
+    assert!(!output.contains("hidden"));
+    assert_eq!(output.matches("<li").count(), 2);
+}
+
+#[test]
+fn test_markdown_drops_comments() {
+    let output = render(
+        &MarkdownRenderer::new(MarkdownRendererOptions::new(MarkdownFlavor::Standard)),
+        SOURCE,
+    );
+    assert!(output.contains("visible  text"));
+    assert!(output.contains("last line"));
+    assert!(!output.contains("private note"));
+    assert!(!output.contains("hidden"));
+}
+
+#[test]
+fn test_patto_keeps_comments() {
+    assert_eq!(render(&PattoRenderer::new(), SOURCE), SOURCE);
+}
+
+#[test]
+fn test_comments_are_verbatim_in_code_blocks() {
+    let text = "[@code rust]\n\t// not a comment %% nor this %%\n";
+    let ast = parse(text);
+    let line = ast.value().children.lock().unwrap()[0].clone();
+    let code = line.value().contents.lock().unwrap()[0].clone();
+    let content = code.value().children.lock().unwrap()[0].clone();
+    assert!(matches!(content.kind(), AstNodeKind::CodeContent));
+    assert_eq!(content.extract_str(), "// not a comment %% nor this %%");
+
+    let output = render(&HtmlRenderer::new(HtmlRendererOptions::default()), text);
+    assert!(output.contains("// not a comment %% nor this %%"));
+}
+
+#[test]
+fn test_comments_are_highlighted() {
+    let tokens = get_semantic_tokens(&parse("a %% b %% c\n// d\n"));
+    // (delta_line, delta_start, length, token_type); type 3 is COMMENT
+    let tokens: Vec<_> = tokens
+        .iter()
+        .map(|t| (t.delta_line, t.delta_start, t.length, t.token_type))
+        .collect();
+    assert_eq!(tokens, vec![(0, 2, 7, 3), (1, 0, 4, 3)]);
+}
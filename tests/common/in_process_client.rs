@@ -15,7 +15,13 @@ pub struct InProcessLspClient {
 impl InProcessLspClient {
     /// Create a new in-process LSP client
     pub async fn new(workspace: &TestWorkspace) -> Self {
+        Self::with_note_extension(workspace, patto::repository::DEFAULT_NOTE_EXTENSION).await
+    }
+
+    /// Create a new in-process LSP client for notes with another file extension
+    pub async fn with_note_extension(workspace: &TestWorkspace, note_extension: &str) -> Self {
         let workspace_root = workspace.root_uri();
+        let note_extension = note_extension.to_string();
 
         // Create the LspService
         let (service, socket) = LspService::build(|client| Backend {
@@ -25,6 +31,7 @@ impl InProcessLspClient {
             paper_catalog: PaperCatalog::default(),
             settings: Arc::new(Mutex::new(PattoSettings::default())),
             last_valid_task_snapshots: Arc::new(dashmap::DashMap::new()),
+            note_extension: note_extension.clone(),
         })
         .finish();
 
@@ -255,12 +262,93 @@ impl InProcessLspClient {
         self.backend.folding_range(params).await.ok().flatten()
     }
 
+    /// Get the occurrences of the symbol under the cursor
+    pub async fn document_highlight(
+        &mut self,
+        uri: Url,
+        line: u32,
+        character: u32,
+    ) -> Option<Vec<DocumentHighlight>> {
+        let params = DocumentHighlightParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position: Position { line, character },
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        self.backend.document_highlight(params).await.ok().flatten()
+    }
+
+    /// Get the smart-expand selection ranges at `positions`
+    pub async fn selection_range(
+        &mut self,
+        uri: Url,
+        positions: Vec<Position>,
+    ) -> Option<Vec<SelectionRange>> {
+        let params = SelectionRangeParams {
+            text_document: TextDocumentIdentifier { uri },
+            positions,
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        self.backend.selection_range(params).await.ok().flatten()
+    }
+
+    /// Get the edits formatting a document
+    pub async fn formatting(&mut self, uri: Url, tab_size: u32) -> Option<Vec<TextEdit>> {
+        let params = DocumentFormattingParams {
+            text_document: TextDocumentIdentifier { uri },
+            options: FormattingOptions {
+                tab_size,
+                insert_spaces: false,
+                ..Default::default()
+            },
+            work_done_progress_params: Default::default(),
+        };
+        self.backend.formatting(params).await.ok().flatten()
+    }
+
+    /// Get the edits aligning the tables within `range`
+    pub async fn range_formatting(&mut self, uri: Url, range: Range) -> Option<Vec<TextEdit>> {
+        let params = DocumentRangeFormattingParams {
+            text_document: TextDocumentIdentifier { uri },
+            range,
+            options: FormattingOptions {
+                tab_size: 4,
+                insert_spaces: false,
+                ..Default::default()
+            },
+            work_done_progress_params: Default::default(),
+        };
+        self.backend.range_formatting(params).await.ok().flatten()
+    }
+
+    /// Get clickable links of a document
+    pub async fn document_link(&mut self, uri: Url) -> Option<Vec<DocumentLink>> {
+        let params = DocumentLinkParams {
+            text_document: TextDocumentIdentifier { uri },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        self.backend.document_link(params).await.ok().flatten()
+    }
+
     /// Aggregate tasks (Patto-specific)
     pub async fn aggregate_tasks(&mut self) -> Option<Option<serde_json::Value>> {
         self.execute_command("experimental/aggregate_tasks", vec![])
             .await
     }
 
+    /// Aggregate tasks narrowed by a filter object (Patto-specific)
+    pub async fn aggregate_tasks_filtered(
+        &mut self,
+        filter: serde_json::Value,
+    ) -> Option<Option<serde_json::Value>> {
+        self.execute_command("experimental/aggregate_tasks", vec![filter])
+            .await
+    }
+
     /// Get two-hop links (Patto-specific)
     pub async fn two_hop_links(&mut self, uri: Url) -> Option<Option<serde_json::Value>> {
         self.execute_command(
@@ -270,6 +358,21 @@ impl InProcessLspClient {
         .await
     }
 
+    /// Get two-hop links with the linking line of each note (Patto-specific)
+    pub async fn two_hop_links_with_snippets(
+        &mut self,
+        uri: Url,
+    ) -> Option<Option<serde_json::Value>> {
+        self.execute_command(
+            "experimental/retrieve_two_hop_notes",
+            vec![
+                serde_json::json!(uri.to_string()),
+                serde_json::json!({ "withSnippets": true }),
+            ],
+        )
+        .await
+    }
+
     /// Review completed tasks (Patto-specific)
     pub async fn tasks_review(
         &mut self,
@@ -305,6 +408,31 @@ impl InProcessLspClient {
         self.backend.did_change(params).await;
     }
 
+    /// Notify the server of edits, each replacing `range` with its text
+    pub async fn did_change_ranges(
+        &mut self,
+        uri: Url,
+        version: i32,
+        changes: Vec<(tower_lsp::lsp_types::Range, &str)>,
+    ) {
+        use tower_lsp::lsp_types::{
+            DidChangeTextDocumentParams, TextDocumentContentChangeEvent,
+            VersionedTextDocumentIdentifier,
+        };
+        let params = DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier { uri, version },
+            content_changes: changes
+                .into_iter()
+                .map(|(range, text)| TextDocumentContentChangeEvent {
+                    range: Some(range),
+                    range_length: None,
+                    text: text.to_string(),
+                })
+                .collect(),
+        };
+        self.backend.did_change(params).await;
+    }
+
     /// Get the AST for a document from the repository's ast_map
     pub fn get_ast(&self, uri: &Url) -> Option<patto::parser::AstNode> {
         let normalized = patto::repository::Repository::normalize_url_percent_encoding(uri);
@@ -347,6 +475,10 @@ impl InProcessLspClient {
             if let Ok(p) = serde_json::from_value::<DidSaveTextDocumentParams>(params) {
                 self.backend.did_save(p).await;
             }
+        } else if method == "workspace/didChangeConfiguration" {
+            if let Ok(p) = serde_json::from_value::<DidChangeConfigurationParams>(params) {
+                self.backend.did_change_configuration(p).await;
+            }
         }
     }
 
@@ -0,0 +1,67 @@
+//! Tests for English and Japanese diagnostic messages
+
+use patto::diagnostic_translator::{DiagnosticTranslator, FriendlyDiagnostic, Locale};
+use patto::parser;
+
+fn translate(text: &str, locale: Locale) -> FriendlyDiagnostic {
+    let errors = parser::parse_text(text).parse_errors;
+    assert_eq!(errors.len(), 1, "Parse errors: {:?}", errors);
+    DiagnosticTranslator::with_locale(locale)
+        .translate(&errors[0])
+        .unwrap()
+}
+
+#[test]
+fn test_indentation_error_in_english_and_japanese() {
+    let en = translate("親\n\t\t孫\n", Locale::En);
+    let ja = translate("親\n\t\t孫\n", Locale::Ja);
+
+    assert!(
+        en.message.starts_with("Inconsistent indentation"),
+        "{}",
+        en.message
+    );
+    assert!(en.message.contains("Examples:\n  Heading"));
+    assert!(
+        ja.message.starts_with("インデントが揃っていません"),
+        "{}",
+        ja.message
+    );
+    assert!(ja.message.contains("例:\n  Heading"));
+
+    // Codes and severities don't depend on the language
+    assert_eq!(en.code.as_deref(), Some("invalid-indentation"));
+    assert_eq!(ja.code, en.code);
+    assert_eq!(ja.severity, en.severity);
+    assert_eq!(
+        en.code_description_uri.as_deref(),
+        Some("https://github.com/ompugao/patto/wiki/Diagnostic-Errors/invalid-indentation")
+    );
+    assert_eq!(
+        ja.code_description_uri.as_deref(),
+        Some("https://github.com/ompugao/patto/wiki/Diagnostic-Errors-ja/invalid-indentation")
+    );
+}
+
+#[test]
+fn test_property_error_in_japanese() {
+    let en = translate("買い物 {@task status=}\n", Locale::En);
+    let ja = translate("買い物 {@task status=}\n", Locale::Ja);
+
+    assert_eq!(en.code.as_deref(), Some("invalid-property"));
+    assert_eq!(ja.code, en.code);
+    assert!(en.message.is_ascii(), "{}", en.message);
+    assert!(
+        ja.message.starts_with("プロパティの構文が正しくありません"),
+        "{}",
+        ja.message
+    );
+}
+
+#[test]
+fn test_locale_from_tag() {
+    assert_eq!(Locale::from_tag("ja"), Locale::Ja);
+    assert_eq!(Locale::from_tag("ja-JP"), Locale::Ja);
+    assert_eq!(Locale::from_tag("en-US"), Locale::En);
+    assert_eq!(Locale::from_tag("fr"), Locale::En);
+}
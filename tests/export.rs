@@ -0,0 +1,173 @@
+//! Tests for exporting notes to files
+
+use std::path::Path;
+
+use patto::export::{self, ExportFormat, SiteOptions, SiteSummary};
+use patto::markdown::MarkdownFlavor;
+use patto::parser;
+use patto::repository::{Repository, RepositoryMessage};
+use tempfile::TempDir;
+use tokio::sync::broadcast::error::RecvError;
+
+#[test]
+fn test_export_path_keeps_relative_structure() {
+    let path = export::export_path(
+        Path::new("/notes"),
+        Path::new("/site"),
+        Path::new("/notes/projects/plan.pn"),
+        ExportFormat::Html,
+    );
+    assert_eq!(path.as_deref(), Some(Path::new("/site/projects/plan.html")));
+
+    let path = export::export_path(
+        Path::new("/notes"),
+        Path::new("/out"),
+        Path::new("/notes/plan.pn"),
+        ExportFormat::Markdown,
+    );
+    assert_eq!(path.as_deref(), Some(Path::new("/out/plan.md")));
+
+    // Notes outside the root have nowhere to go
+    let path = export::export_path(
+        Path::new("/notes"),
+        Path::new("/site"),
+        Path::new("/elsewhere/plan.pn"),
+        ExportFormat::Html,
+    );
+    assert_eq!(path, None);
+}
+
+#[test]
+fn test_export_and_remove_note() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("notes");
+    let out = dir.path().join("site");
+    let note = root.join("projects/plan.pn");
+    let ast = parser::parse_text("Plan\n\tstep [* one]\n").ast;
+
+    let target = export::export_note(
+        &ast,
+        &root,
+        &out,
+        &note,
+        ExportFormat::Html,
+        MarkdownFlavor::Standard,
+    )
+    .unwrap();
+    assert_eq!(target, out.join("projects/plan.html"));
+    let html = std::fs::read_to_string(&target).unwrap();
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<title>plan</title>"));
+    assert!(html.contains("font-weight: bold;\">one</span>"), "{}", html);
+
+    let removed = export::remove_exported_note(&root, &out, &note, ExportFormat::Html).unwrap();
+    assert_eq!(removed, Some(target.clone()));
+    assert!(!target.exists());
+    // Removing again is not an error
+    let removed = export::remove_exported_note(&root, &out, &note, ExportFormat::Html).unwrap();
+    assert_eq!(removed, None);
+}
+
+#[test]
+fn test_export_markdown() {
+    let dir = TempDir::new().unwrap();
+    let note = dir.path().join("plan.pn");
+    let ast = parser::parse_text("Plan [other]\n").ast;
+
+    let target = export::export_note(
+        &ast,
+        dir.path(),
+        &dir.path().join("out"),
+        &note,
+        ExportFormat::Markdown,
+        MarkdownFlavor::Standard,
+    )
+    .unwrap();
+    let markdown = std::fs::read_to_string(target).unwrap();
+    assert!(markdown.contains("Plan [other](other.md)"), "{}", markdown);
+}
+
+/// A repository over `files` once its initial scan is done
+async fn scanned_repository(root: &Path, files: &[(&str, &str)]) -> Repository {
+    for (name, content) in files {
+        let path = root.join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+    let repository = Repository::new(root.to_path_buf());
+    let mut rx = repository.subscribe();
+    loop {
+        match rx.recv().await {
+            Ok(RepositoryMessage::ScanCompleted { .. }) | Err(RecvError::Closed) => break,
+            _ => continue,
+        }
+    }
+    repository
+}
+
+#[tokio::test]
+async fn test_build_site() {
+    let dir = TempDir::new().unwrap();
+    let root = std::fs::canonicalize(dir.path()).unwrap();
+    let notes = root.join("notes");
+    let site = root.join("site");
+    let repository = scanned_repository(
+        &notes,
+        &[
+            ("home.pn", "See [projects/plan] and [missing]\n"),
+            (
+                "projects/plan.pn",
+                "Plan [home#top] [my note]\n[@img ./assets/chart.png]\n",
+            ),
+            ("my note.pn", "#top\n"),
+            ("projects/assets/chart.png", "png"),
+        ],
+    )
+    .await;
+
+    let summary = export::build_site(
+        &repository,
+        &site,
+        SiteOptions {
+            css: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        summary,
+        SiteSummary {
+            pages: 3,
+            images: 1,
+            broken_links: 1,
+        }
+    );
+
+    let home = std::fs::read_to_string(site.join("home.html")).unwrap();
+    assert!(home.contains("<style>"));
+    assert!(home.contains(r#"<a class="wikilink" href="projects/plan.html">projects/plan</a>"#));
+    assert!(home.contains(r#"<span class="wikilink broken">missing</span>"#));
+
+    // Links from a subdirectory lead back up to the site root
+    let plan = std::fs::read_to_string(site.join("projects/plan.html")).unwrap();
+    assert!(plan.contains(r#"href="../home.html#top""#), "{}", plan);
+    assert!(plan.contains(r#"href="../my%20note.html""#), "{}", plan);
+    assert!(plan.contains(r#"src="./assets/chart.png""#));
+    assert!(site.join("projects/assets/chart.png").is_file());
+
+    let index = std::fs::read_to_string(site.join("index.html")).unwrap();
+    assert!(index.contains(r#"<a class="wikilink" href="home.html">home</a>"#));
+    assert!(index.contains(r#"href="my%20note.html">my note</a>"#));
+    assert!(index.contains(r#"href="projects/plan.html">projects/plan</a>"#));
+}
+
+#[tokio::test]
+async fn test_build_site_without_css() {
+    let dir = TempDir::new().unwrap();
+    let root = std::fs::canonicalize(dir.path()).unwrap();
+    let repository = scanned_repository(&root.join("notes"), &[("a.pn", "a\n")]).await;
+
+    export::build_site(&repository, &root.join("site"), SiteOptions::default()).unwrap();
+    let page = std::fs::read_to_string(root.join("site/a.html")).unwrap();
+    assert!(!page.contains("<style>"));
+}
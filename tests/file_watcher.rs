@@ -0,0 +1,121 @@
+//! Tests for `Repository::start_watcher_with_debounce`
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use patto::repository::{Repository, RepositoryMessage};
+use tempfile::TempDir;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use tokio::time::timeout;
+
+/// Start watching a workspace holding `files` once its initial scan is done
+async fn watched_workspace(
+    files: &[(&str, &str)],
+    debounce: Duration,
+) -> (TempDir, PathBuf, Repository, Receiver<RepositoryMessage>) {
+    let dir = TempDir::new().unwrap();
+    let root = std::fs::canonicalize(dir.path()).unwrap();
+    for (name, content) in files {
+        std::fs::write(root.join(name), content).unwrap();
+    }
+
+    let repo = Repository::new(root.clone());
+    let mut rx = repo.subscribe();
+    loop {
+        match rx.recv().await {
+            Ok(RepositoryMessage::ScanCompleted { .. }) | Err(RecvError::Closed) => break,
+            _ => continue,
+        }
+    }
+
+    repo.start_watcher_with_debounce(debounce).await.unwrap();
+    // Give the watcher thread time to register the directory
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    (dir, root, repo, rx)
+}
+
+/// Run `test` on a multi-threaded runtime. The watcher parks a blocking
+/// thread for the lifetime of the process, so the runtime is shut down
+/// without waiting for it.
+fn run<F: std::future::Future<Output = ()>>(test: F) {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    runtime.block_on(test);
+    runtime.shutdown_background();
+}
+
+/// Drain the messages broadcast until nothing arrives for `quiet`
+async fn collect_messages(
+    rx: &mut Receiver<RepositoryMessage>,
+    quiet: Duration,
+) -> Vec<RepositoryMessage> {
+    let mut messages = vec![];
+    while let Ok(Ok(message)) = timeout(quiet, rx.recv()).await {
+        messages.push(message);
+    }
+    messages
+}
+
+#[test]
+fn test_rapid_writes_are_coalesced() {
+    run(rapid_writes_are_coalesced());
+}
+
+async fn rapid_writes_are_coalesced() {
+    let (_dir, root, _repo, mut rx) = watched_workspace(
+        &[("note.pn", "first version\n")],
+        Duration::from_millis(200),
+    )
+    .await;
+
+    std::fs::write(root.join("note.pn"), "second version\n").unwrap();
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    std::fs::write(root.join("note.pn"), "final version\n").unwrap();
+
+    let messages = collect_messages(&mut rx, Duration::from_millis(1000)).await;
+    let changes: Vec<&String> = messages
+        .iter()
+        .filter_map(|message| match message {
+            RepositoryMessage::FileChanged(path, _, content) if path.ends_with("note.pn") => {
+                Some(content)
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(changes, vec!["final version\n"], "{:?}", messages);
+}
+
+#[test]
+fn test_new_file_is_added_once() {
+    run(new_file_is_added_once());
+}
+
+async fn new_file_is_added_once() {
+    let (_dir, root, repo, mut rx) =
+        watched_workspace(&[("existing.pn", "[new]\n")], Duration::from_millis(100)).await;
+
+    std::fs::write(root.join("new.pn"), "draft\n").unwrap();
+    std::fs::write(root.join("new.pn"), "draft\nmore\n").unwrap();
+
+    let messages = collect_messages(&mut rx, Duration::from_millis(800)).await;
+    let added = messages
+        .iter()
+        .filter(|message| matches!(message, RepositoryMessage::FileAdded(path, _) if path.ends_with("new.pn")))
+        .count();
+    assert_eq!(added, 1, "{:?}", messages);
+    assert!(
+        !messages
+            .iter()
+            .any(|message| matches!(message, RepositoryMessage::FileChanged(..))),
+        "{:?}",
+        messages
+    );
+
+    let uri = tower_lsp::lsp_types::Url::from_file_path(root.join("new.pn")).unwrap();
+    assert_eq!(
+        repo.document_map.get(&uri).map(|text| text.to_string()),
+        Some("draft\nmore\n".to_string())
+    );
+}
@@ -0,0 +1,78 @@
+//! Tests for `Repository::graph_snapshot`
+
+use patto::repository::{GraphSnapshotEdge, LinkLocation, Repository, RepositoryMessage};
+use tempfile::TempDir;
+use tokio::sync::broadcast::error::RecvError;
+use tower_lsp::lsp_types::Url;
+
+#[tokio::test]
+async fn test_graph_snapshot_after_scan() {
+    let dir = TempDir::new().unwrap();
+    let root = std::fs::canonicalize(dir.path()).unwrap();
+    std::fs::write(root.join("a.pn"), "[b] and [b#sec]\nthen [c]\n").unwrap();
+    std::fs::write(root.join("b.pn"), "back to [a]\n").unwrap();
+    std::fs::write(root.join("c.pn"), "leaf\n").unwrap();
+
+    let repository = Repository::new(root.clone());
+    let mut rx = repository.subscribe();
+    loop {
+        match rx.recv().await {
+            Ok(RepositoryMessage::ScanCompleted { .. }) | Err(RecvError::Closed) => break,
+            _ => continue,
+        }
+    }
+    let uri = |name: &str| Url::from_file_path(root.join(name)).unwrap();
+
+    let snapshot = repository.graph_snapshot();
+    assert_eq!(snapshot.nodes, vec![uri("a.pn"), uri("b.pn"), uri("c.pn")]);
+    assert_eq!(
+        snapshot.edges,
+        vec![
+            GraphSnapshotEdge {
+                source: uri("a.pn"),
+                target: uri("b.pn"),
+                count: 2,
+                locations: vec![
+                    LinkLocation {
+                        source_line: 0,
+                        source_col_range: (0, 3),
+                        target_anchor: None,
+                    },
+                    LinkLocation {
+                        source_line: 0,
+                        source_col_range: (8, 15),
+                        target_anchor: Some("sec".to_string()),
+                    },
+                ],
+            },
+            GraphSnapshotEdge {
+                source: uri("a.pn"),
+                target: uri("c.pn"),
+                count: 1,
+                locations: vec![LinkLocation {
+                    source_line: 1,
+                    source_col_range: (5, 8),
+                    target_anchor: None,
+                }],
+            },
+            GraphSnapshotEdge {
+                source: uri("b.pn"),
+                target: uri("a.pn"),
+                count: 1,
+                locations: vec![LinkLocation {
+                    source_line: 0,
+                    source_col_range: (8, 11),
+                    target_anchor: None,
+                }],
+            },
+        ]
+    );
+
+    // The snapshot is detached from the repository and serializable
+    repository.add_file_to_graph(&root.join("c.pn"), "now [a]\n");
+    assert_eq!(snapshot.edges.len(), 3);
+    assert_eq!(repository.graph_snapshot().edges.len(), 4);
+    let json = serde_json::to_value(&snapshot).unwrap();
+    assert_eq!(json["edges"][0]["count"], 2);
+    assert_eq!(json["edges"][0]["locations"][1]["target_anchor"], "sec");
+}
@@ -0,0 +1,27 @@
+//! Tests for code blocks in the HTML renderer
+
+use patto::parser;
+use patto::renderer::{HtmlRenderer, HtmlRendererOptions, Renderer};
+
+fn render_html(patto_text: &str) -> String {
+    let result = parser::parse_text(patto_text);
+    let renderer = HtmlRenderer::new(HtmlRendererOptions::default());
+    let mut output = Vec::new();
+    renderer.format(&result.ast, &mut output).unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn test_language_class() {
+    let output = render_html("[@code rust]\n\tfn main() {}");
+
+    assert!(output.contains("<code class=\"language-rust\">"));
+}
+
+#[test]
+fn test_language_cannot_break_out_of_class_attribute() {
+    let output = render_html("[@code \"x\\\" onclick=\\\"alert(1)\"]\n\tx");
+
+    assert!(!output.contains("\" onclick"), "{}", output);
+    assert!(output.contains("&quot;"), "{}", output);
+}
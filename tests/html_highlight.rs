@@ -0,0 +1,42 @@
+//! Tests for syntect highlighting of code blocks in the HTML renderer
+#![cfg(feature = "html-highlight")]
+
+use patto::parser;
+use patto::renderer::{HtmlRenderer, HtmlRendererOptions, Renderer};
+
+fn render_html(patto_text: &str, highlight_code: bool) -> String {
+    let result = parser::parse_text(patto_text);
+    let renderer = HtmlRenderer::new(HtmlRendererOptions {
+        highlight_code,
+        ..Default::default()
+    });
+    let mut output = Vec::new();
+    renderer.format(&result.ast, &mut output).unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn test_rust_block_highlighted() {
+    let output = render_html("[@code rust]\n\tfn main() {}", true);
+
+    assert!(output.contains("patto-highlighted"));
+    assert!(output.contains("<span style=\""));
+    assert!(output.contains("main</span>"));
+}
+
+#[test]
+fn test_unknown_language_falls_back() {
+    let output = render_html("[@code no-such-lang]\n\tx < y", true);
+
+    assert!(!output.contains("patto-highlighted"));
+    assert!(output.contains("<pre class=\"hljs patto-code-block\">"));
+    assert!(output.contains("x &lt; y"));
+}
+
+#[test]
+fn test_highlighting_disabled() {
+    let output = render_html("[@code rust]\n\tfn main() {}", false);
+
+    assert!(!output.contains("<span style="));
+    assert!(output.contains("fn main() {}"));
+}
@@ -0,0 +1,43 @@
+//! Tests for image captions: the alt text of `[@img]` doubles as a
+//! `<figcaption>` in HTML and stays plain alt text in markdown
+
+use patto::markdown::{MarkdownFlavor, MarkdownRendererOptions};
+use patto::parser;
+use patto::renderer::{HtmlRenderer, HtmlRendererOptions, MarkdownRenderer, Renderer};
+
+fn render_html(input: &str) -> String {
+    let result = parser::parse_text(input);
+    HtmlRenderer::new(HtmlRendererOptions::default()).render_to_string(&result.ast)
+}
+
+#[test]
+fn test_image_with_alt_renders_figcaption() {
+    let html = render_html("[@img ./cat.png \"A cat\"]\n");
+    assert!(
+        html.contains(
+            "<figure class=\"patto-figure\"><img class=\"patto-image\" alt=\"A cat\" src=\"./cat.png\"/><figcaption>A cat</figcaption></figure>"
+        ),
+        "{}",
+        html
+    );
+}
+
+#[test]
+fn test_image_without_alt_has_no_caption() {
+    let html = render_html("[@img ./cat.png]\n");
+    assert!(
+        html.contains("<figure class=\"patto-figure\"><img class=\"patto-image\" src=\"./cat.png\"/></figure>"),
+        "{}",
+        html
+    );
+    assert!(!html.contains("<figcaption>"), "{}", html);
+}
+
+#[test]
+fn test_markdown_keeps_plain_image() {
+    let result = parser::parse_text("[@img ./cat.png \"A cat\"]\n");
+    let markdown = MarkdownRenderer::new(MarkdownRendererOptions::new(MarkdownFlavor::Standard))
+        .render_to_string(&result.ast);
+    assert!(markdown.contains("![A cat](./cat.png)"), "{}", markdown);
+    assert!(!markdown.contains("figure"), "{}", markdown);
+}
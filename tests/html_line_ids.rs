@@ -0,0 +1,46 @@
+//! Tests for stable line ids in rendered HTML
+
+use patto::line_tracker::LineTracker;
+use patto::parser;
+use patto::renderer::{HtmlRenderer, HtmlRendererOptions, Renderer};
+
+/// Helper to render patto text parsed with line tracking
+fn render_tracked(patto_text: &str, options: HtmlRendererOptions) -> String {
+    let mut tracker = LineTracker::new().unwrap();
+    let result = parser::parse_text_with_persistent_line_tracking(patto_text, &mut tracker);
+    let mut output = Vec::new();
+    HtmlRenderer::new(options)
+        .format(&result.ast, &mut output)
+        .unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn test_line_ids_on_lines() {
+    let output = render_tracked(
+        "first\nsecond\n\tnested\n",
+        HtmlRendererOptions::default().with_line_ids(true),
+    );
+
+    assert!(output.contains("<li class=\"patto-line\" data-line-id=\"1\">"));
+    assert!(output.contains("<li class=\"patto-line\" data-line-id=\"2\">"));
+    assert!(output.contains("<li class=\"patto-item\" data-line-id=\"3\">"));
+}
+
+#[test]
+fn test_line_ids_are_opt_in() {
+    let output = render_tracked("first\nsecond\n", HtmlRendererOptions::default());
+
+    assert!(!output.contains("data-line-id"));
+}
+
+#[test]
+fn test_line_ids_need_tracking() {
+    let result = parser::parse_text("first\n");
+    let mut output = Vec::new();
+    HtmlRenderer::new(HtmlRendererOptions::default().with_line_ids(true))
+        .format(&result.ast, &mut output)
+        .unwrap();
+
+    assert!(!String::from_utf8(output).unwrap().contains("data-line-id"));
+}
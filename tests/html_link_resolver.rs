@@ -0,0 +1,54 @@
+//! Tests for resolving WikiLinks to existing and missing notes in HTML
+
+use patto::parser;
+use patto::renderer::{HtmlRenderer, HtmlRendererOptions, LinkStatus, Renderer};
+
+/// Render `text` with a resolver that only knows the note `plan`
+fn render(text: &str) -> String {
+    let options = HtmlRendererOptions::default().with_link_resolver(|link| match link {
+        "plan" => LinkStatus::Exists {
+            href: "pages/plan.html".to_string(),
+        },
+        _ => LinkStatus::Broken,
+    });
+    let mut output = Vec::new();
+    HtmlRenderer::new(options)
+        .format(&parser::parse_text(text).ast, &mut output)
+        .unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn test_existing_note_links_to_resolved_href() {
+    let output = render("see [plan] and [plan#goals]\n");
+
+    assert!(
+        output.contains(r#"<a class="wikilink" href="pages/plan.html">plan</a>"#),
+        "{}",
+        output
+    );
+    assert!(output.contains(r#"<a class="wikilink" href="pages/plan.html#goals">plan#goals</a>"#));
+}
+
+#[test]
+fn test_missing_note_renders_broken_without_href() {
+    let output = render("see [nowhere]\n");
+
+    assert!(
+        output.contains(r#"<span class="wikilink broken">nowhere</span>"#),
+        "{}",
+        output
+    );
+    assert!(!output.contains("href=\"nowhere"));
+}
+
+#[test]
+fn test_links_without_resolver_point_at_notes() {
+    let mut output = Vec::new();
+    HtmlRenderer::new(HtmlRendererOptions::default())
+        .format(&parser::parse_text("[plan]\n").ast, &mut output)
+        .unwrap();
+    let output = String::from_utf8(output).unwrap();
+
+    assert!(output.contains(r#"<a class="patto-wikilink" href="plan.pn">plan</a>"#));
+}
@@ -0,0 +1,50 @@
+//! Tests for MathML pre-rendering of math in the HTML renderer
+#![cfg(feature = "html-mathml")]
+
+use patto::parser;
+use patto::renderer::{HtmlRenderer, HtmlRendererOptions, Renderer};
+
+fn render_html(patto_text: &str, mathml: bool) -> String {
+    let result = parser::parse_text(patto_text);
+    let renderer = HtmlRenderer::new(HtmlRendererOptions {
+        mathml,
+        ..Default::default()
+    });
+    let mut output = Vec::new();
+    renderer.format(&result.ast, &mut output).unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn test_inline_math_to_mathml() {
+    let output = render_html("Euler [$ e^{i\\pi} $]", true);
+
+    assert!(output.contains("<span class=\"patto-math-inline\"><math"));
+    assert!(output.contains("display=\"inline\""));
+    assert!(!output.contains("\\("));
+}
+
+#[test]
+fn test_block_math_to_mathml() {
+    let output = render_html("[@math]\n\ta^2 + b^2 = c^2\n\tx = 1", true);
+
+    assert!(output.contains("<div class=\"patto-math-block\"><math"));
+    assert_eq!(output.matches("display=\"block\"").count(), 2);
+    assert!(!output.contains("\\displaylines"));
+}
+
+#[test]
+fn test_invalid_math_falls_back() {
+    let output = render_html("Broken [$ \\frac{a $]", true);
+
+    assert!(!output.contains("<math"));
+    assert!(output.contains("\\(\\frac{a \\)"));
+}
+
+#[test]
+fn test_mathml_disabled() {
+    let output = render_html("[$ x $]", false);
+
+    assert!(output.contains("\\(x \\)"));
+    assert!(!output.contains("<math"));
+}
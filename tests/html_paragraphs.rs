@@ -0,0 +1,65 @@
+//! Tests for joining adjacent text lines into paragraphs in HTML
+
+use patto::parser;
+use patto::renderer::{HtmlRenderer, HtmlRendererOptions, Renderer};
+
+fn render(text: &str, paragraphs: bool) -> String {
+    let ast = parser::parse_text(text).ast;
+    let mut output = Vec::new();
+    HtmlRenderer::new(HtmlRendererOptions::default().with_paragraphs(paragraphs))
+        .format(&ast, &mut output)
+        .unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn test_adjacent_lines_join_into_paragraph() {
+    let output = render("first line\nsecond [link] line\n\nnext paragraph\n", true);
+
+    assert!(
+        output.contains(
+            r#"<p class="patto-paragraph">first line second <a class="patto-wikilink" href="link.pn">link</a> line</p>"#
+        ),
+        "{}",
+        output
+    );
+    // The blank line breaks the paragraph
+    assert!(output.contains(r#"<p class="patto-paragraph">next paragraph</p>"#));
+    assert_eq!(output.matches("<p ").count(), 2);
+}
+
+#[test]
+fn test_paragraphs_are_opt_in() {
+    let output = render("first line\nsecond line\n", false);
+
+    assert!(!output.contains("<p"));
+    assert_eq!(output.matches("<li class=\"patto-line\">").count(), 2);
+}
+
+#[test]
+fn test_structured_lines_are_not_merged() {
+    let output = render(
+        "intro\n[* Heading]\nbody\n\titem\nafter\ntask {@task status=todo}\n[@code rust]\n\tfn main() {}\ntail\n",
+        true,
+    );
+
+    // Headings, lines with nested items, tasks and block commands break paragraphs
+    assert!(
+        output.contains(r#"<p class="patto-paragraph">intro</p>"#),
+        "{}",
+        output
+    );
+    assert!(
+        output.contains(r#"<p class="patto-paragraph">after</p>"#),
+        "{}",
+        output
+    );
+    assert!(
+        output.contains(r#"<p class="patto-paragraph">tail</p>"#),
+        "{}",
+        output
+    );
+    assert!(!output.contains("Heading</span> "), "{}", output);
+    assert!(output.contains("<li class=\"patto-item\">"));
+    assert_eq!(output.matches("<p ").count(), 3);
+}
@@ -0,0 +1,73 @@
+//! Tests for the HTML table of contents
+
+use patto::parser;
+use patto::renderer::{HtmlRenderer, HtmlRendererOptions, Renderer};
+
+/// Helper to render patto text to HTML with the table of contents enabled
+fn render_html_toc(patto_text: &str) -> String {
+    let result = parser::parse_text(patto_text);
+    assert!(
+        result.parse_errors.is_empty(),
+        "Parse errors: {:?}",
+        result.parse_errors
+    );
+
+    let renderer = HtmlRenderer::new(HtmlRendererOptions::default().with_toc(true));
+    let mut output = Vec::new();
+    renderer.format(&result.ast, &mut output).unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn test_toc_lists_headings() {
+    let output = render_html_toc("[** Getting Started]\nintro\n[* Install & Run]\nsteps");
+
+    assert!(output.starts_with("<nav class=\"patto-toc\"><ul>"));
+    assert!(output.contains(
+        "<li class=\"patto-toc-level-1\"><a href=\"#getting-started\">Getting Started</a></li>"
+    ));
+    assert!(output.contains(
+        "<li class=\"patto-toc-level-2\"><a href=\"#install-run\">Install &amp; Run</a></li>"
+    ));
+    assert!(output.contains("<div class=\"patto-task-row\" id=\"getting-started\">"));
+    assert!(output.contains("<div class=\"patto-task-row\" id=\"install-run\">"));
+}
+
+#[test]
+fn test_toc_duplicate_headings_get_suffixes() {
+    let output = render_html_toc("[* Notes]\na\n[* Notes]\nb\n[* Notes]");
+
+    assert!(output.contains("href=\"#notes\""));
+    assert!(output.contains("href=\"#notes-1\""));
+    assert!(output.contains("href=\"#notes-2\""));
+    assert!(output.contains("id=\"notes-2\""));
+}
+
+#[test]
+fn test_toc_uses_heading_anchor() {
+    let output = render_html_toc("[* Overview] #intro\ntext");
+
+    assert!(output.contains("<a href=\"#intro\">Overview</a>"));
+    // The anchor span already carries the id, so the row does not duplicate it
+    assert!(!output.contains("<div class=\"patto-task-row\" id="));
+}
+
+#[test]
+fn test_toc_omitted_without_headings() {
+    let output = render_html_toc("plain line\n\t[* nested bold]");
+
+    assert!(!output.contains("patto-toc"));
+}
+
+#[test]
+fn test_toc_disabled_by_default() {
+    let result = parser::parse_text("[* Heading]");
+    let mut output = Vec::new();
+    HtmlRenderer::new(HtmlRendererOptions::default())
+        .format(&result.ast, &mut output)
+        .unwrap();
+    let output = String::from_utf8(output).unwrap();
+
+    assert!(!output.contains("patto-toc"));
+    assert!(!output.contains("id=\"heading\""));
+}
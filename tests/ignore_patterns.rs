@@ -0,0 +1,76 @@
+//! Tests for the ignore rules applied while scanning the notes directory
+
+use patto::repository::{Repository, RepositoryMessage};
+use tempfile::TempDir;
+use tokio::sync::broadcast::error::RecvError;
+use tower_lsp::lsp_types::Url;
+
+/// Write `files` into a temporary workspace, scan it and return the number of
+/// ignored paths reported on completion
+async fn scanned_workspace(files: &[(&str, &str)]) -> (TempDir, Repository, usize) {
+    let dir = TempDir::new().unwrap();
+    for (name, content) in files {
+        let path = dir.path().join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    let repo = Repository::new(dir.path().to_path_buf());
+    let mut rx = repo.subscribe();
+    let ignored = loop {
+        match rx.recv().await {
+            Ok(RepositoryMessage::ScanCompleted { ignored, .. }) => break ignored,
+            Err(RecvError::Closed) => panic!("scan did not complete"),
+            _ => continue,
+        }
+    };
+    (dir, repo, ignored)
+}
+
+fn indexed(dir: &TempDir, repo: &Repository, name: &str) -> bool {
+    let uri = Url::from_file_path(dir.path().join(name)).unwrap();
+    repo.document_map.contains_key(&uri)
+}
+
+#[tokio::test]
+async fn test_scan_skips_ignored_paths() {
+    let (dir, repo, ignored) = scanned_workspace(&[
+        (".patto.toml", "ignore = [\"node_modules\"]\n"),
+        (
+            ".pattoignore",
+            "# archived notes\n/archive/\n!.templates/\n",
+        ),
+        ("note.pn", "[kept]\n"),
+        ("sub/kept.pn", "kept\n"),
+        ("node_modules/pkg/readme.pn", "ignored\n"),
+        ("archive/old.pn", "ignored\n"),
+        ("sub/archive/new.pn", "kept\n"),
+        (".hidden/secret.pn", "ignored\n"),
+        (".templates/daily.pn", "kept\n"),
+    ])
+    .await;
+
+    for name in [
+        "note.pn",
+        "sub/kept.pn",
+        "sub/archive/new.pn",
+        ".templates/daily.pn",
+    ] {
+        assert!(indexed(&dir, &repo, name), "{} should be indexed", name);
+    }
+    for name in [
+        "node_modules/pkg/readme.pn",
+        "archive/old.pn",
+        ".hidden/secret.pn",
+    ] {
+        assert!(!indexed(&dir, &repo, name), "{} should be ignored", name);
+    }
+    // node_modules, archive, .hidden, .patto.toml and .pattoignore
+    assert_eq!(ignored, 5);
+    assert!(repo.is_ignored_path(&dir.path().join("node_modules/pkg/readme.pn")));
+    assert!(!repo.is_ignored_path(&dir.path().join("sub/kept.pn")));
+
+    let state = repo.scan_state();
+    assert!(state.completed);
+    assert_eq!((state.scanned, state.total), (4, 4));
+}
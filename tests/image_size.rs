@@ -0,0 +1,45 @@
+//! Tests for the `width=` and `height=` options of `[@img]`
+
+use patto::parser;
+use patto::renderer::{HtmlRenderer, HtmlRendererOptions, PattoRenderer, Renderer};
+
+fn render_html(input: &str) -> String {
+    let result = parser::parse_text(input);
+    HtmlRenderer::new(HtmlRendererOptions::default()).render_to_string(&result.ast)
+}
+
+#[test]
+fn test_html_image_size_attributes() {
+    let html = render_html("[@img ./cat.png \"A cat\" width=300 height=200]\n");
+    assert!(
+        html.contains(
+            "<img class=\"patto-image\" alt=\"A cat\" src=\"./cat.png\" width=\"300\" height=\"200\"/>"
+        ),
+        "{}",
+        html
+    );
+
+    let html = render_html("[@img ./cat.png width=300]\n");
+    assert!(
+        html.contains("<img class=\"patto-image\" src=\"./cat.png\" width=\"300\"/>"),
+        "{}",
+        html
+    );
+}
+
+#[test]
+fn test_html_image_without_size() {
+    let html = render_html("[@img ./cat.png]\n");
+    assert!(
+        html.contains("<img class=\"patto-image\" src=\"./cat.png\"/>"),
+        "{}",
+        html
+    );
+}
+
+#[test]
+fn test_patto_roundtrip_keeps_size() {
+    let input = "[@img ./cat.png \"A cat\" width=300 height=200]\n";
+    let result = parser::parse_text(input);
+    assert_eq!(PattoRenderer::new().render_to_string(&result.ast), input);
+}
@@ -0,0 +1,232 @@
+//! Tests for incremental reparsing via `Repository::apply_edit`
+//!
+//! Each case applies an edit to a cached document and checks that the spliced
+//! AST matches a full parse of the edited text.
+
+use patto::parser;
+use patto::repository::Repository;
+use serde_json::Value;
+use tempfile::TempDir;
+use tower_lsp::lsp_types::{Position, Range, Url};
+
+/// Serialize an AST, dropping stable ids which only the incremental path assigns
+fn ast_json(ast: &parser::AstNode) -> Value {
+    fn strip(value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                map.remove("stable_id");
+                map.values_mut().for_each(strip);
+            }
+            Value::Array(items) => items.iter_mut().for_each(strip),
+            _ => {}
+        }
+    }
+    let mut value = serde_json::to_value(ast).unwrap();
+    strip(&mut value);
+    value
+}
+
+fn range(start: (u32, u32), end: (u32, u32)) -> Range {
+    Range::new(Position::new(start.0, start.1), Position::new(end.0, end.1))
+}
+
+/// Apply `edit` to `before` and assert the cached AST equals a full parse of `after`
+fn assert_edit(before: &str, edit: Range, new_text: &str, after: &str) {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("note.pn");
+    std::fs::write(&path, before).unwrap();
+
+    let repo = Repository::new(dir.path().to_path_buf());
+    repo.add_file_to_graph(&path, before);
+    let uri = Url::from_file_path(&path).unwrap();
+    repo.apply_edit(&uri, edit, new_text);
+
+    let uri = Repository::normalize_url_percent_encoding(&uri);
+    assert_eq!(repo.document_map.get(&uri).unwrap().to_string(), after);
+    let ast = repo.ast_map.get(&uri).unwrap().value().clone();
+    assert_eq!(ast_json(&ast), ast_json(&parser::parse_text(after).ast));
+}
+
+#[tokio::test]
+async fn test_edit_within_line() {
+    assert_edit(
+        "first\n\tchild [link]\nsecond\n",
+        range((1, 8), (1, 12)),
+        "other",
+        "first\n\tchild [other]\nsecond\n",
+    );
+}
+
+#[tokio::test]
+async fn test_edit_multibyte_columns() {
+    // Columns are UTF-16 code units
+    assert_edit(
+        "日本語 text\n\t😀 emoji\nlast\n",
+        range((1, 4), (1, 9)),
+        "smile",
+        "日本語 text\n\t😀 smile\nlast\n",
+    );
+}
+
+#[tokio::test]
+async fn test_edit_inside_code_block() {
+    assert_edit(
+        "intro\n[@code rust]\n\tfn main() {}\n\nafter\n",
+        range((2, 4), (2, 8)),
+        "run",
+        "intro\n[@code rust]\n\tfn run() {}\n\nafter\n",
+    );
+}
+
+#[tokio::test]
+async fn test_edit_opens_block_over_children() {
+    // Turning a parent line into a code block absorbs its indented children
+    assert_edit(
+        "top\nparent\n\tchild [link]\n\n\tgrandchild\nnext\n",
+        range((1, 0), (1, 6)),
+        "[@code]",
+        "top\n[@code]\n\tchild [link]\n\n\tgrandchild\nnext\n",
+    );
+}
+
+#[tokio::test]
+async fn test_edit_unindents_line() {
+    // Removing indentation makes the line a new top-level block
+    assert_edit(
+        "a\n\tb\n\tc\n\n\td\n",
+        range((2, 0), (2, 1)),
+        "",
+        "a\n\tb\nc\n\n\td\n",
+    );
+}
+
+#[tokio::test]
+async fn test_edit_indents_top_level_line() {
+    // Indenting a top-level line merges it into the previous block
+    assert_edit(
+        "a\n\n[@quote]\n\tquoted\nb\n\tchild\n",
+        range((4, 0), (4, 0)),
+        "\t",
+        "a\n\n[@quote]\n\tquoted\n\tb\n\tchild\n",
+    );
+}
+
+#[tokio::test]
+async fn test_edit_changing_line_count() {
+    assert_edit(
+        "a\n[@code]\n\tx\nb\n",
+        range((2, 2), (2, 2)),
+        "\n\ty",
+        "a\n[@code]\n\tx\n\ty\nb\n",
+    );
+}
+
+#[tokio::test]
+async fn test_edit_inserting_lines_moves_later_blocks() {
+    // Properties of the moved blocks follow them
+    assert_edit(
+        "a\n\tb\nc #anchor\n\td {@task status=todo due=2030-01-01}\ne [a#anchor]\n",
+        range((1, 2), (1, 2)),
+        "\n\tnew\nline",
+        "a\n\tb\n\tnew\nline\nc #anchor\n\td {@task status=todo due=2030-01-01}\ne [a#anchor]\n",
+    );
+}
+
+#[tokio::test]
+async fn test_edit_removing_lines_moves_later_blocks() {
+    assert_edit(
+        "a\nb\n\tb1\n\tb2\nc #anchor\n\td {@task status=todo}\n",
+        range((1, 0), (4, 0)),
+        "",
+        "a\nc #anchor\n\td {@task status=todo}\n",
+    );
+}
+
+#[tokio::test]
+async fn test_edit_joining_lines() {
+    assert_edit(
+        "a\n\tb\n\tc\nd\n\te\n",
+        range((1, 2), (2, 1)),
+        " ",
+        "a\n\tb c\nd\n\te\n",
+    );
+}
+
+#[tokio::test]
+async fn test_edit_appending_lines_at_end() {
+    assert_edit(
+        "a\nb",
+        range((1, 1), (1, 1)),
+        "\n\tc\nd\n",
+        "a\nb\n\tc\nd\n",
+    );
+}
+
+#[tokio::test]
+async fn test_edit_changing_line_count_keeps_untouched_nodes() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("note.pn");
+    let text = "first [a]\nsecond [b]\nthird [c]\n";
+    std::fs::write(&path, text).unwrap();
+
+    let repo = Repository::new(dir.path().to_path_buf());
+    repo.add_file_to_graph(&path, text);
+    let uri = Repository::normalize_url_percent_encoding(&Url::from_file_path(&path).unwrap());
+    let before = repo.ast_map.get(&uri).unwrap().value().clone();
+
+    repo.apply_edit(&uri, range((1, 0), (1, 0)), "inserted\n");
+
+    let after = repo.ast_map.get(&uri).unwrap().value().clone();
+    let before_children = before.value().children.lock().unwrap().clone();
+    let after_children = after.value().children.lock().unwrap().clone();
+    let rows: Vec<_> = after_children.iter().map(|c| c.location().row).collect();
+    assert_eq!(rows, vec![0, 1, 2, 3]);
+    // The block before the edit is reused as is, not reparsed
+    assert!(std::ptr::eq(
+        before_children[0].value(),
+        after_children[0].value()
+    ));
+}
+
+#[tokio::test]
+async fn test_edit_keeps_untouched_nodes() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("note.pn");
+    let text = "first [a]\nsecond [b]\nthird [c]\n";
+    std::fs::write(&path, text).unwrap();
+
+    let repo = Repository::new(dir.path().to_path_buf());
+    repo.add_file_to_graph(&path, text);
+    let uri = Repository::normalize_url_percent_encoding(&Url::from_file_path(&path).unwrap());
+    let before = repo.ast_map.get(&uri).unwrap().value().clone();
+
+    repo.apply_edit(&uri, range((1, 0), (1, 6)), "2nd");
+
+    let after = repo.ast_map.get(&uri).unwrap().value().clone();
+    let before_children = before.value().children.lock().unwrap().clone();
+    let after_children = after.value().children.lock().unwrap().clone();
+    assert_eq!(after_children.len(), 3);
+    assert!(std::ptr::eq(
+        before_children[0].value(),
+        after_children[0].value()
+    ));
+    assert!(!std::ptr::eq(
+        before_children[1].value(),
+        after_children[1].value()
+    ));
+    assert!(std::ptr::eq(
+        before_children[2].value(),
+        after_children[2].value()
+    ));
+
+    // Unchanged lines keep the same stable id across further edits
+    let third_id = *after_children[2].value().stable_id.lock().unwrap();
+    assert!(third_id.is_some());
+    repo.apply_edit(&uri, range((0, 0), (0, 5)), "1st");
+    let latest = repo.ast_map.get(&uri).unwrap().value().clone();
+    let latest_children = latest.value().children.lock().unwrap().clone();
+    assert_eq!(
+        *latest_children[2].value().stable_id.lock().unwrap(),
+        third_id
+    );
+}
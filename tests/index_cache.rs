@@ -0,0 +1,180 @@
+//! Tests for the on-disk index that lets a scan skip unchanged notes
+
+use std::path::PathBuf;
+
+use patto::index_cache::{content_hash, modified_time, CachedLink, CachedNote, IndexCache};
+use patto::repository::{Repository, RepositoryMessage, TaskFilter};
+use tempfile::TempDir;
+use tokio::sync::broadcast::error::RecvError;
+use tower_lsp::lsp_types::Url;
+
+/// Write `files` into the notes directory of a temporary workspace
+fn write_files(files: &[(&str, &str)]) -> TempDir {
+    let dir = TempDir::new().unwrap();
+    std::fs::create_dir(notes_dir(&dir)).unwrap();
+    for (name, content) in files {
+        std::fs::write(notes_dir(&dir).join(name), content).unwrap();
+    }
+    dir
+}
+
+fn notes_dir(dir: &TempDir) -> PathBuf {
+    dir.path().join("notes")
+}
+
+/// Where the tests keep the index, outside of the notes directory
+fn index_path(dir: &TempDir) -> PathBuf {
+    dir.path().join("cache").join("index.json")
+}
+
+/// Scan the notes of `dir` with the index cache enabled and return once the
+/// scan has completed
+async fn scan(dir: &TempDir) -> Repository {
+    wait_for_scan(Repository::with_index_cache(
+        notes_dir(dir),
+        "pn",
+        index_path(dir),
+    ))
+    .await
+}
+
+async fn wait_for_scan(repo: Repository) -> Repository {
+    let mut rx = repo.subscribe();
+    loop {
+        match rx.recv().await {
+            Ok(RepositoryMessage::ScanCompleted { .. }) => break,
+            Err(RecvError::Closed) => panic!("scan did not complete"),
+            _ => continue,
+        }
+    }
+    repo
+}
+
+fn link_to(name: &str) -> CachedLink {
+    CachedLink {
+        link: name.to_string(),
+        anchor: None,
+        line: 0,
+        col_range: (0, name.len() + 2),
+    }
+}
+
+fn back_links(dir: &TempDir, repo: &Repository, name: &str) -> Vec<String> {
+    let mut sources: Vec<String> = repo
+        .calculate_back_links(&notes_dir(dir).join(name))
+        .into_iter()
+        .map(|back_link| back_link.source_file)
+        .collect();
+    sources.sort();
+    sources
+}
+
+#[tokio::test]
+async fn test_save_cache_records_links() {
+    let dir = write_files(&[
+        ("a.pn", "[b] and [c#top]\n"),
+        ("b.pn", "- todo {@task status=todo}\n"),
+        ("c.pn", "c\n"),
+    ]);
+    let repo = scan(&dir).await;
+    repo.save_cache().unwrap();
+
+    let cache = IndexCache::load(&index_path(&dir));
+    assert_eq!(cache.notes.keys().collect::<Vec<_>>(), vec!["a", "b", "c"]);
+    let a = &cache.notes["a"];
+    assert_eq!(a.hash, content_hash("[b] and [c#top]\n"));
+    assert_eq!(
+        a.modified,
+        modified_time(&notes_dir(&dir).join("a.pn")).unwrap()
+    );
+    let links: Vec<_> = a
+        .links
+        .iter()
+        .map(|l| (l.link.as_str(), l.anchor.as_deref()))
+        .collect();
+    assert_eq!(links, vec![("b", None), ("c", Some("top"))]);
+    assert!(cache.notes["b"].links.is_empty());
+}
+
+#[tokio::test]
+async fn test_scan_restores_unchanged_notes_from_cache() {
+    let dir = write_files(&[
+        ("a.pn", "[b]\n"),
+        ("b.pn", "- todo {@task status=todo}\n"),
+        ("c.pn", "c\n"),
+        ("d.pn", "[b]\n"),
+    ]);
+
+    // The cached links deliberately differ from the content, so a note whose
+    // links point to `c` was restored from the cache rather than parsed
+    let mut cache = IndexCache::default();
+    cache.notes.insert(
+        "a".to_string(),
+        CachedNote {
+            modified: modified_time(&notes_dir(&dir).join("a.pn")).unwrap(),
+            hash: String::new(),
+            links: vec![link_to("c")],
+        },
+    );
+    // Touched but unchanged: trusted by its hash
+    cache.notes.insert(
+        "b".to_string(),
+        CachedNote {
+            modified: 0,
+            hash: content_hash("- todo {@task status=todo}\n"),
+            links: vec![link_to("c")],
+        },
+    );
+    // Changed since it was cached: parsed again
+    cache.notes.insert(
+        "d".to_string(),
+        CachedNote {
+            modified: 0,
+            hash: content_hash("[c]\n"),
+            links: vec![link_to("c")],
+        },
+    );
+    cache.save(&index_path(&dir)).unwrap();
+
+    let repo = scan(&dir).await;
+    assert_eq!(back_links(&dir, &repo, "c.pn"), vec!["a", "b"]);
+    assert_eq!(back_links(&dir, &repo, "b.pn"), vec!["d"]);
+
+    // Queries parse the restored notes first
+    let tasks = repo.aggregate_tasks(&TaskFilter::default());
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(back_links(&dir, &repo, "c.pn"), Vec::<String>::new());
+    assert_eq!(back_links(&dir, &repo, "b.pn"), vec!["a", "d"]);
+
+    repo.save_cache().unwrap();
+    let saved = IndexCache::load(&index_path(&dir));
+    assert_eq!(saved.notes["a"].links, vec![link_to("b")]);
+    assert_eq!(saved.notes["d"].hash, content_hash("[b]\n"));
+}
+
+#[tokio::test]
+async fn test_restored_notes_are_parsed_on_first_use() {
+    let dir = write_files(&[("a.pn", "[b]\n"), ("b.pn", "b\n")]);
+    scan(&dir).await.save_cache().unwrap();
+
+    let repo = scan(&dir).await;
+    let a = Url::from_file_path(notes_dir(&dir).join("a.pn")).unwrap();
+    let a = Repository::normalize_url_percent_encoding(&a);
+    assert!(repo.ast_map.get(&a).is_none());
+    assert!(repo.ast(&a).is_some());
+    assert!(repo.ast_map.get(&a).is_some());
+}
+
+#[tokio::test]
+async fn test_index_cache_is_opt_in() {
+    let dir = write_files(&[("a.pn", "[b]\n"), ("b.pn", "b\n")]);
+    let repo = wait_for_scan(Repository::new(notes_dir(&dir))).await;
+    repo.save_cache().unwrap();
+
+    assert!(!index_path(&dir).exists());
+    let written: Vec<_> = std::fs::read_dir(notes_dir(&dir))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(written.len(), 2, "unexpected files: {:?}", written);
+}
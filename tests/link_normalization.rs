@@ -0,0 +1,110 @@
+//! Tests for loose WikiLink resolution (`Repository::set_link_normalization`)
+
+use patto::repository::{LinkNormalization, Repository};
+use tempfile::TempDir;
+use tower_lsp::lsp_types::Url;
+
+const LOOSE: LinkNormalization = LinkNormalization {
+    ignore_case: true,
+    ignore_separators: true,
+};
+
+/// Write `files` into a temporary workspace and index them in order
+fn workspace(files: &[(&str, &str)], normalization: LinkNormalization) -> (TempDir, Repository) {
+    let dir = TempDir::new().unwrap();
+    let repo = Repository::new(dir.path().to_path_buf());
+    repo.set_link_normalization(normalization);
+    for (name, content) in files {
+        std::fs::write(dir.path().join(name), content).unwrap();
+    }
+    for (name, content) in files {
+        repo.add_file_to_graph(&dir.path().join(name), content);
+    }
+    (dir, repo)
+}
+
+fn uri(dir: &TempDir, name: &str) -> Url {
+    Url::from_file_path(dir.path().join(name)).unwrap()
+}
+
+#[tokio::test]
+async fn test_exact_resolution_by_default() {
+    let (dir, repo) = workspace(
+        &[("my_note.pn", "target\n"), ("source.pn", "[My Note]\n")],
+        LinkNormalization::default(),
+    );
+    assert!(repo.link_to_path("My Note").is_none());
+    assert_eq!(
+        repo.calculate_back_links(&dir.path().join("my_note.pn"))
+            .len(),
+        0
+    );
+}
+
+#[tokio::test]
+async fn test_loose_resolution() {
+    let (dir, repo) = workspace(
+        &[("my_note.pn", "target\n"), ("source.pn", "[My Note]\n")],
+        LOOSE,
+    );
+    assert_eq!(
+        repo.link_to_path("My Note"),
+        Some(dir.path().join("my_note.pn"))
+    );
+    let root_uri = Url::from_directory_path(dir.path()).unwrap();
+    assert_eq!(
+        repo.link_to_uri("MY NOTE", &root_uri),
+        Some(uri(&dir, "my_note.pn"))
+    );
+    assert_eq!(
+        repo.calculate_back_links(&dir.path().join("my_note.pn"))
+            .len(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn test_exact_match_wins_and_collisions_are_reported() {
+    let (dir, repo) = workspace(
+        &[
+            ("my_note.pn", "lower\n"),
+            ("My Note.pn", "spaced\n"),
+            ("other.pn", "alone\n"),
+        ],
+        LOOSE,
+    );
+    assert_eq!(
+        repo.link_to_path("My Note"),
+        Some(dir.path().join("My Note.pn"))
+    );
+    assert_eq!(
+        repo.link_to_path("my_note"),
+        Some(dir.path().join("my_note.pn"))
+    );
+    assert_eq!(
+        repo.link_collisions(&uri(&dir, "my_note.pn")),
+        vec!["My Note"]
+    );
+    assert_eq!(
+        repo.link_collisions(&uri(&dir, "My Note.pn")),
+        vec!["my_note"]
+    );
+    assert!(repo.link_collisions(&uri(&dir, "other.pn")).is_empty());
+}
+
+#[tokio::test]
+async fn test_changing_normalization_relinks_notes() {
+    let (dir, repo) = workspace(
+        &[("my_note.pn", "target\n"), ("source.pn", "[My Note]\n")],
+        LinkNormalization::default(),
+    );
+    let target = dir.path().join("my_note.pn");
+    assert_eq!(repo.calculate_back_links(&target).len(), 0);
+
+    repo.set_link_normalization(LOOSE);
+    assert_eq!(repo.calculate_back_links(&target).len(), 1);
+
+    repo.set_link_normalization(LinkNormalization::default());
+    assert_eq!(repo.calculate_back_links(&target).len(), 0);
+    assert!(repo.link_collisions(&uri(&dir, "my_note.pn")).is_empty());
+}
@@ -206,3 +206,93 @@ async fn test_completion_img_command() {
 
     println!("✅ Image command completion test passed");
 }
+
+#[tokio::test]
+async fn test_completion_img_paths() {
+    let mut workspace = TestWorkspace::new();
+    let assets = workspace.root_path().join("assets");
+    std::fs::create_dir(&assets).unwrap();
+    std::fs::write(assets.join("diagram.png"), b"").unwrap();
+    std::fs::write(assets.join("photo.JPG"), b"").unwrap();
+    std::fs::write(assets.join("notes.txt"), b"").unwrap();
+    workspace.create_file("test.pn", "[@img dia\n");
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+
+    let uri = workspace.get_uri("test.pn");
+    client
+        .did_open(uri.clone(), "[@img dia\n".to_string())
+        .await;
+
+    let response = client.completion(uri.clone(), 0, 9).await;
+
+    assert!(response.is_some(), "No result in completion");
+    let items = match response.unwrap() {
+        CompletionResponse::Array(items) => items,
+        CompletionResponse::List(list) => list.items,
+    };
+    let labels: Vec<&str> = items.iter().map(|item| item.label.as_str()).collect();
+    assert_eq!(labels, vec!["./assets/diagram.png"]);
+
+    // Without a query every image is offered, but nothing else
+    client
+        .did_change(uri.clone(), 2, "[@img \n".to_string())
+        .await;
+    let items = match client.completion(uri, 0, 6).await.unwrap() {
+        CompletionResponse::Array(items) => items,
+        CompletionResponse::List(list) => list.items,
+    };
+    let labels: Vec<&str> = items.iter().map(|item| item.label.as_str()).collect();
+    assert_eq!(labels, vec!["./assets/diagram.png", "./assets/photo.JPG"]);
+}
+
+#[tokio::test]
+async fn test_completion_heading_anchors() {
+    let mut workspace = TestWorkspace::new();
+    workspace.create_file(
+        "target.pn",
+        "[* Getting Started]\nContent\n[* Usage] #usage\n#explicit\n",
+    );
+    workspace.create_file("source.pn", "See [target#\n");
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+
+    let source_uri = workspace.get_uri("source.pn");
+    client
+        .did_open(source_uri.clone(), "See [target#\n".to_string())
+        .await;
+
+    let items = match client.completion(source_uri, 0, 12).await.unwrap() {
+        CompletionResponse::Array(items) => items,
+        CompletionResponse::List(list) => list.items,
+    };
+    let labels: Vec<&str> = items.iter().map(|item| item.label.as_str()).collect();
+    // Headings without an anchor are offered by their generated id, in document order
+    assert_eq!(labels, vec!["#getting-started", "#usage", "#explicit"]);
+}
+
+#[tokio::test]
+async fn test_completion_note_names_with_custom_extension() {
+    let mut workspace = TestWorkspace::new();
+    workspace.create_file("note_one.patto", "Content 1\n");
+    workspace.create_file("source.patto", "Link [no\n");
+
+    let mut client = InProcessLspClient::with_note_extension(&workspace, "patto").await;
+
+    let source_uri = workspace.get_uri("source.patto");
+    client
+        .did_open(source_uri.clone(), "Link [no\n".to_string())
+        .await;
+
+    let items = match client.completion(source_uri, 0, 8).await.unwrap() {
+        CompletionResponse::Array(items) => items,
+        CompletionResponse::List(list) => list.items,
+    };
+    let labels: Vec<&str> = items.iter().map(|item| item.label.as_str()).collect();
+    assert!(labels.contains(&"note_one"), "{:?}", labels);
+    assert!(
+        !labels.iter().any(|l| l.ends_with(".patto")),
+        "{:?}",
+        labels
+    );
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use common::*;
+
+const CONTENT: &str = "intro #sec\nsee [#sec] and [note#sec]\n[other] then [other#x] and [#top]\n";
+
+/// Highlighted ranges as `(line, start, end)`
+async fn highlights(line: u32, character: u32) -> Vec<(u32, u32, u32)> {
+    let mut workspace = TestWorkspace::new();
+    workspace.create_file("note.pn", CONTENT);
+    workspace.create_file("other.pn", "Other\n");
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+    let uri = workspace.get_uri("note.pn");
+    client.did_open(uri.clone(), CONTENT.to_string()).await;
+
+    client
+        .document_highlight(uri, line, character)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|h| {
+            (
+                h.range.start.line,
+                h.range.start.character,
+                h.range.end.character,
+            )
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_highlight_anchor_definition_and_references() {
+    let expected = vec![(0, 6, 10), (1, 4, 10), (1, 15, 25)];
+    // On the definition
+    assert_eq!(highlights(0, 7).await, expected);
+    // On a self-link and on a link spelled with the note name
+    assert_eq!(highlights(1, 6).await, expected);
+    assert_eq!(highlights(1, 17).await, expected);
+}
+
+#[tokio::test]
+async fn test_highlight_links_to_same_note() {
+    assert_eq!(highlights(2, 2).await, vec![(2, 0, 7), (2, 13, 22)]);
+}
+
+#[tokio::test]
+async fn test_highlight_nothing_on_plain_text() {
+    assert!(highlights(0, 2).await.is_empty());
+}
@@ -1,6 +1,7 @@
 mod common;
 
 use common::*;
+use tower_lsp::lsp_types::{Position, Range, Url};
 
 #[tokio::test]
 async fn test_did_open_and_close() {
@@ -64,6 +65,49 @@ async fn test_did_change() {
     println!("✅ Did change test passed");
 }
 
+#[tokio::test]
+async fn test_did_change_with_ranges() {
+    let mut workspace = TestWorkspace::new();
+    workspace.create_file("test.pn", "first\n\tchild\nlast [old]\n");
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+
+    let uri = workspace.get_uri("test.pn");
+    client
+        .did_open(uri.clone(), "first\n\tchild\nlast [old]\n".to_string())
+        .await;
+
+    // Edits apply in order, each against the text left by the previous one
+    client
+        .did_change_ranges(
+            uri.clone(),
+            2,
+            vec![
+                (
+                    Range::new(Position::new(1, 6), Position::new(1, 6)),
+                    "\n\tadded",
+                ),
+                (Range::new(Position::new(3, 6), Position::new(3, 9)), "new"),
+            ],
+        )
+        .await;
+
+    let ast = client.get_ast(&uri).unwrap();
+    let expected = patto::parser::parse_text("first\n\tchild\n\tadded\nlast [new]\n").ast;
+    let outline = |ast: &patto::parser::AstNode| {
+        let blocks = ast.value().children.lock().unwrap().clone();
+        blocks
+            .iter()
+            .map(|block| (block.location().row, block.extract_str().to_string()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(outline(&ast), outline(&expected));
+    assert_eq!(outline(&ast)[1], (3, "last [new]".to_string()));
+
+    let response = client.definition(uri.clone(), 3, 7).await;
+    assert!(response.is_some());
+}
+
 #[tokio::test]
 async fn test_did_save() {
     let mut workspace = TestWorkspace::new();
@@ -119,3 +163,78 @@ async fn test_multiple_documents() {
 
     println!("✅ Multiple documents test passed");
 }
+
+async fn save(client: &mut InProcessLspClient, uri: &Url) {
+    client
+        .notify(
+            "textDocument/didSave",
+            serde_json::json!({ "textDocument": { "uri": uri.to_string() } }),
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn test_did_save_exports_note() {
+    let mut workspace = TestWorkspace::new();
+    std::fs::create_dir(workspace.root_path().join("notes")).unwrap();
+    workspace.create_file("notes/plan.pn", "Plan [target]\n");
+    workspace.create_file("target.pn", "Target\n");
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+    let uri = workspace.get_uri("notes/plan.pn");
+    client
+        .did_open(uri.clone(), "Plan [target]\n".to_string())
+        .await;
+
+    // Nothing is exported until an output directory is configured
+    save(&mut client, &uri).await;
+    assert!(!workspace.root_path().join("site").exists());
+
+    client
+        .notify(
+            "workspace/didChangeConfiguration",
+            serde_json::json!({
+                "settings": { "patto": { "export": { "outputDir": "site", "format": "html" } } }
+            }),
+        )
+        .await;
+
+    // The graph follows the saved file, even if the editor never sent the change
+    workspace.create_file("notes/plan.pn", "Plan [other]\n");
+    save(&mut client, &uri).await;
+
+    let html = std::fs::read_to_string(workspace.root_path().join("site/notes/plan.html"))
+        .expect("exported page");
+    assert!(html.contains("<title>plan</title>"));
+    assert!(html.contains("other"));
+    assert!(!html.contains("target"));
+}
+
+#[tokio::test]
+async fn test_did_save_exports_markdown_in_default_flavor() {
+    let mut workspace = TestWorkspace::new();
+    workspace.create_file("plan.pn", "Plan [target]\n");
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+    client
+        .notify(
+            "workspace/didChangeConfiguration",
+            serde_json::json!({
+                "settings": { "patto": {
+                    "markdown": { "defaultFlavor": "obsidian" },
+                    "export": { "outputDir": "out" }
+                } }
+            }),
+        )
+        .await;
+
+    let uri = workspace.get_uri("plan.pn");
+    client
+        .did_open(uri.clone(), "Plan [target]\n".to_string())
+        .await;
+    save(&mut client, &uri).await;
+
+    let markdown = std::fs::read_to_string(workspace.root_path().join("out/plan.md"))
+        .expect("exported markdown");
+    assert!(markdown.contains("Plan [[target]]"), "{}", markdown);
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use common::*;
+use tower_lsp::lsp_types::{Position, Range};
+
+#[tokio::test]
+async fn test_document_links() {
+    let content = "see [target] and [missing note#sec]\n\t[https://example.com Example] [./docs/spec.pdf]\n[@img https://example.com/a.png] [@img ./local.png] [#self]\n";
+
+    let mut workspace = TestWorkspace::new();
+    workspace.create_file("note.pn", content);
+    workspace.create_file("target.pn", "Target\n");
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+    let uri = workspace.get_uri("note.pn");
+    client.did_open(uri.clone(), content.to_string()).await;
+
+    let links = client
+        .document_link(uri)
+        .await
+        .expect("document_link returned None");
+    let targets: Vec<(Range, String, Option<String>)> = links
+        .into_iter()
+        .map(|link| (link.range, link.target.unwrap().to_string(), link.tooltip))
+        .collect();
+
+    // URL and local file links cover the text inside the brackets
+    let root = workspace.root_uri().to_string();
+    let root = root.trim_end_matches('/');
+    assert_eq!(
+        targets,
+        vec![
+            (
+                Range::new(Position::new(0, 4), Position::new(0, 12)),
+                format!("{}/target.pn", root),
+                None
+            ),
+            (
+                Range::new(Position::new(0, 17), Position::new(0, 35)),
+                format!("{}/missing%20note.pn", root),
+                Some("Create note 'missing note'".to_string())
+            ),
+            (
+                Range::new(Position::new(1, 2), Position::new(1, 29)),
+                "https://example.com/".to_string(),
+                None
+            ),
+            (
+                Range::new(Position::new(1, 32), Position::new(1, 47)),
+                format!("{}/docs/spec.pdf", root),
+                None
+            ),
+            (
+                Range::new(Position::new(2, 0), Position::new(2, 32)),
+                "https://example.com/a.png".to_string(),
+                None
+            ),
+        ]
+    );
+}
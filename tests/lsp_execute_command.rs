@@ -150,6 +150,126 @@ async fn test_aggregate_tasks_sorting() {
     println!("✅ Aggregate tasks sorting test passed");
 }
 
+#[tokio::test]
+async fn test_aggregate_tasks_includes_scheduled() {
+    let mut workspace = TestWorkspace::new();
+    workspace.create_file(
+        "tasks.pn",
+        "Later {@task status=todo due=2024-12-31 scheduled=2024-12-01}
+Short !2024-12-25
+",
+    );
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    let response = client.aggregate_tasks().await;
+    let result = response.unwrap().unwrap();
+    let tasks = result.as_array().unwrap();
+    assert_eq!(tasks.len(), 2);
+
+    // Sorted by due, not by scheduled; the `!date` form sets only the due date
+    assert_eq!(tasks[0]["due"]["Date"], "2024-12-25");
+    assert!(tasks[0].get("scheduled").is_none());
+    assert_eq!(tasks[1]["due"]["Date"], "2024-12-31");
+    assert_eq!(tasks[1]["scheduled"]["Date"], "2024-12-01");
+}
+
+#[tokio::test]
+async fn test_aggregate_tasks_filtered() {
+    let mut workspace = TestWorkspace::new();
+    workspace.create_file(
+        "tasks.pn",
+        "Early {@task status=todo due=2024-12-01}
+Doing {@task status=doing due=2024-12-10}
+Late {@task status=todo due=2024-12-31}
+Undated {@task status=doing}
+Finished {@task status=done due=2024-12-05}
+",
+    );
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    let texts = |result: Option<Option<serde_json::Value>>| -> Vec<String> {
+        result
+            .unwrap()
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|task| task["text"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    let doing = texts(
+        client
+            .aggregate_tasks_filtered(serde_json::json!({ "status": "doing" }))
+            .await,
+    );
+    assert_eq!(doing, vec!["Doing", "Undated"]);
+
+    // The range is inclusive and drops undated tasks
+    let range = texts(
+        client
+            .aggregate_tasks_filtered(
+                serde_json::json!({ "from": "2024-12-01", "to": "2024-12-10" }),
+            )
+            .await,
+    );
+    assert_eq!(range, vec!["Early", "Doing"]);
+
+    let with_done = texts(
+        client
+            .aggregate_tasks_filtered(serde_json::json!({
+                "from": "2024-12-01",
+                "to": "2024-12-10",
+                "includeDone": true
+            }))
+            .await,
+    );
+    assert_eq!(with_done, vec!["Early", "Finished", "Doing"]);
+
+    // An empty filter behaves like no arguments
+    let all = texts(client.aggregate_tasks_filtered(serde_json::json!({})).await);
+    assert_eq!(all, texts(client.aggregate_tasks().await));
+    assert_eq!(all.len(), 4);
+}
+
+#[tokio::test]
+async fn test_aggregate_tasks_include_note_and_heading() {
+    let mut workspace = TestWorkspace::new();
+    workspace.create_file(
+        "project.pn",
+        "Loose {@task status=todo due=2024-12-01}
+[* Project X]
+Subtask {@task status=todo due=2024-12-02}
+\tNested {@task status=todo due=2024-12-03}
+[* Later]
+Other {@task status=todo due=2024-12-04}
+",
+    );
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    let result = client.aggregate_tasks().await.unwrap().unwrap();
+    let tasks = result.as_array().unwrap();
+    assert_eq!(tasks.len(), 4);
+    assert!(tasks.iter().all(|task| task["note"] == "project"));
+
+    let headings: Vec<_> = tasks.iter().map(|task| task.get("heading")).collect();
+    assert_eq!(
+        headings,
+        vec![
+            None,
+            Some(&serde_json::json!("Project X")),
+            Some(&serde_json::json!("Project X")),
+            Some(&serde_json::json!("Later")),
+        ]
+    );
+}
+
 #[tokio::test]
 async fn test_two_hop_links_basic() {
     let mut workspace = TestWorkspace::new();
@@ -257,3 +377,424 @@ async fn test_two_hop_links_no_connections() {
 
     println!("✅ Two-hop links no connections test passed");
 }
+
+#[tokio::test]
+async fn test_two_hop_links_with_snippets() {
+    let mut workspace = TestWorkspace::new();
+    workspace.create_file("source.pn", "Link to [target]\n");
+    workspace.create_file("target.pn", "Target content\n");
+    workspace.create_file("other.pn", "intro\n\tAlso links to [target]\n");
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    let source_uri = workspace.get_uri("source.pn");
+    let result = client
+        .two_hop_links_with_snippets(source_uri)
+        .await
+        .unwrap()
+        .unwrap();
+    let links = result.as_array().unwrap();
+    assert_eq!(links.len(), 1);
+    assert!(links[0]["via"].as_str().unwrap().ends_with("target.pn"));
+    let notes = links[0]["notes"].as_array().unwrap();
+    assert_eq!(notes.len(), 1);
+    assert!(notes[0]["uri"].as_str().unwrap().ends_with("other.pn"));
+    assert_eq!(notes[0]["snippet"], "Also links to [target]");
+}
+
+#[tokio::test]
+async fn test_search_ranks_hits() {
+    let mut workspace = TestWorkspace::new();
+    workspace.create_file("b.pn", "unrelated\nrust borrow checker\n");
+    workspace.create_file("a.pn", "rust borrow checker\nrusty\n");
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+
+    // Wait for workspace scan
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    let response = client
+        .execute_command(
+            "patto/search",
+            vec![serde_json::json!("borrow"), serde_json::json!(10)],
+        )
+        .await;
+
+    assert!(response.is_some(), "No result in search");
+    let result = response.unwrap().unwrap();
+    let hits = result.as_array().expect("Result should be array");
+    assert_eq!(hits.len(), 2, "Got hits: {:?}", hits);
+
+    // Equal scores are ordered by path
+    assert!(hits[0]["uri"].as_str().unwrap().ends_with("a.pn"));
+    assert_eq!(hits[0]["line"], 0);
+    assert!(hits[1]["uri"].as_str().unwrap().ends_with("b.pn"));
+    assert_eq!(hits[1]["line"], 1);
+    assert_eq!(hits[1]["text"], "rust borrow checker");
+    assert_eq!(hits[0]["score"], hits[1]["score"]);
+}
+
+#[tokio::test]
+async fn test_search_limit_and_empty_query() {
+    let mut workspace = TestWorkspace::new();
+    workspace.create_file("note.pn", "apple\napple pie\napple tart\n");
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    let response = client
+        .execute_command(
+            "patto/search",
+            vec![serde_json::json!("apple"), serde_json::json!(2)],
+        )
+        .await;
+    let result = response.unwrap().unwrap();
+    let hits = result.as_array().unwrap();
+    assert_eq!(hits.len(), 2);
+    assert!(hits[0]["score"].as_i64() >= hits[1]["score"].as_i64());
+
+    let response = client
+        .execute_command("patto/search", vec![serde_json::json!("  ")])
+        .await;
+    let result = response.unwrap().unwrap();
+    assert_eq!(result.as_array().unwrap().len(), 0);
+}
+
+#[tokio::test]
+async fn test_query_by_tag() {
+    let mut workspace = TestWorkspace::new();
+    workspace.create_file(
+        "work.pn",
+        "Ship release {@tag project=patto priority=high}\nWrite docs {@tag project=patto priority=low}\nUntagged line\n",
+    );
+    workspace.create_file(
+        "home.pn",
+        "Fix sink {@tag project=house priority=high urgent}\n",
+    );
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    let response = client
+        .execute_command("patto/queryByTag", vec![serde_json::json!("priority=high")])
+        .await;
+    let result = response.unwrap().unwrap();
+    let lines = result.as_array().expect("Result should be array");
+    assert_eq!(lines.len(), 2, "Got lines: {:?}", lines);
+    assert!(lines[0]["location"]["uri"]
+        .as_str()
+        .unwrap()
+        .ends_with("home.pn"));
+    assert!(lines[1]["text"]
+        .as_str()
+        .unwrap()
+        .starts_with("Ship release"));
+    assert_eq!(lines[1]["tags"][0]["name"], "tag");
+
+    // Every term must match; bare terms match keys and positional arguments
+    let response = client
+        .execute_command(
+            "patto/queryByTag",
+            vec![serde_json::json!("priority=high urgent")],
+        )
+        .await;
+    let result = response.unwrap().unwrap();
+    let lines = result.as_array().unwrap();
+    assert_eq!(lines.len(), 1);
+    assert!(lines[0]["text"].as_str().unwrap().starts_with("Fix sink"));
+}
+
+#[tokio::test]
+async fn test_backlinks_with_context() {
+    let mut workspace = TestWorkspace::new();
+    workspace.create_file("target.pn", "Target content\n");
+    workspace.create_file("source.pn", "intro\n\t日本語 see [target#sec] here\n");
+    workspace.create_file("other.pn", "unrelated\n");
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    let target_uri = workspace.get_uri("target.pn");
+    let response = client
+        .execute_command(
+            "patto/backlinksWithContext",
+            vec![serde_json::json!(target_uri.to_string())],
+        )
+        .await;
+    let result = response.unwrap().unwrap();
+    let links = result.as_array().expect("Result should be array");
+    assert_eq!(links.len(), 1, "Got links: {:?}", links);
+
+    let link = &links[0];
+    assert_eq!(link["source_file"], "source");
+    assert_eq!(link["line"], 1);
+    assert_eq!(link["context"], "日本語 see [target#sec] here");
+    assert_eq!(link["target_anchor"], "sec");
+    assert!(link["location"]["uri"]
+        .as_str()
+        .unwrap()
+        .ends_with("source.pn"));
+    // Columns are UTF-16 code units: tab + 3 CJK chars + " see " = 9
+    assert_eq!(link["location"]["range"]["start"]["character"], 9);
+    assert_eq!(link["location"]["range"]["end"]["character"], 21);
+}
+
+#[tokio::test]
+async fn test_export_graph() {
+    let mut workspace = TestWorkspace::new();
+    workspace.create_file("a.pn", "[b] and [b#sec]\n[c]\n");
+    workspace.create_file("b.pn", "back to [a]\n");
+    workspace.create_file("c.pn", "leaf\n");
+    workspace.create_file("lonely.pn", "no links\n");
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    let response = client
+        .execute_command("patto/exportGraph", vec![serde_json::json!("json")])
+        .await;
+    let result = response.unwrap().unwrap();
+    let graph: serde_json::Value = serde_json::from_str(result.as_str().unwrap()).unwrap();
+    assert_eq!(
+        graph["nodes"],
+        serde_json::json!([{"id": "a.pn"}, {"id": "b.pn"}, {"id": "c.pn"}, {"id": "lonely.pn"}])
+    );
+    assert_eq!(
+        graph["edges"],
+        serde_json::json!([
+            {"source": "a.pn", "target": "b.pn", "count": 2},
+            {"source": "a.pn", "target": "c.pn", "count": 1},
+            {"source": "b.pn", "target": "a.pn", "count": 1},
+        ])
+    );
+
+    let response = client.execute_command("patto/exportGraph", vec![]).await;
+    let result = response.unwrap().unwrap();
+    let dot = result.as_str().unwrap();
+    assert!(dot.starts_with("digraph patto {\n"), "{}", dot);
+    assert!(dot.contains("  \"lonely.pn\";\n"), "{}", dot);
+    assert!(
+        dot.contains("  \"a.pn\" -> \"b.pn\" [label=\"2\", weight=2];\n"),
+        "{}",
+        dot
+    );
+    assert!(dot.ends_with("}\n"));
+}
+
+#[tokio::test]
+async fn test_list_orphans() {
+    let mut workspace = TestWorkspace::new();
+    workspace.create_file("a.pn", "[b]\n");
+    workspace.create_file("b.pn", "target\n");
+    workspace.create_file("lonely.pn", "no links\n");
+    workspace.create_file("selfish.pn", "top #top\n[#top] [selfish]\n");
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    let orphan_names = |result: serde_json::Value| {
+        result
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|loc| {
+                assert_eq!(loc["range"]["start"]["line"], 0);
+                let uri = loc["uri"].as_str().unwrap();
+                uri.rsplit('/').next().unwrap().to_string()
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let response = client.execute_command("patto/listOrphans", vec![]).await;
+    let orphans = orphan_names(response.unwrap().unwrap());
+    assert_eq!(orphans, vec!["lonely.pn", "selfish.pn"]);
+
+    let response = client
+        .execute_command("patto/listOrphans", vec![serde_json::json!(true)])
+        .await;
+    let orphans = orphan_names(response.unwrap().unwrap());
+    assert_eq!(orphans, vec!["lonely.pn"]);
+
+    // Links added in an unsaved buffer count
+    let lonely_uri = workspace.get_uri("lonely.pn");
+    client
+        .did_open(lonely_uri.clone(), "no links\n".to_string())
+        .await;
+    client
+        .did_change(lonely_uri, 2, "now [a]\n".to_string())
+        .await;
+    let response = client.execute_command("patto/listOrphans", vec![]).await;
+    let orphans = orphan_names(response.unwrap().unwrap());
+    assert_eq!(orphans, vec!["selfish.pn"]);
+}
+
+#[tokio::test]
+async fn test_note_stats() {
+    let mut workspace = TestWorkspace::new();
+    workspace.create_file(
+        "note.pn",
+        "週報 weekly report\n[@code]\n\tnot counted\nreview {@task status=doing}\n",
+    );
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    let uri = workspace.get_uri("note.pn");
+    let response = client
+        .execute_command("patto/noteStats", vec![serde_json::json!(uri.to_string())])
+        .await;
+    let stats = response.unwrap().unwrap();
+    assert_eq!(stats["words"], 5);
+    assert_eq!(stats["lines"], 4);
+    assert_eq!(stats["tasks"]["doing"], 1);
+    assert_eq!(stats["reading_time_minutes"], 1);
+}
+
+#[tokio::test]
+async fn test_neighborhood() {
+    let mut workspace = TestWorkspace::new();
+    workspace.create_file("a.pn", "[b] and [b#sec]\n[c]\n");
+    workspace.create_file("b.pn", "back to [a]\n");
+    workspace.create_file("c.pn", "leaf\n");
+    std::fs::create_dir(workspace.root_path().join("sub")).unwrap();
+    workspace.create_file("sub/d.pn", "also [c]\n");
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    let uri = workspace.get_uri("a.pn");
+    let response = client
+        .execute_command(
+            "patto/neighborhood",
+            vec![serde_json::json!(uri.to_string())],
+        )
+        .await;
+    let result = response.unwrap().unwrap();
+    assert_eq!(result["name"], "a");
+    let names_and_counts = |links: &serde_json::Value| {
+        links
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|link| {
+                assert!(link["uri"].as_str().unwrap().starts_with("file://"));
+                (
+                    link["name"].as_str().unwrap().to_string(),
+                    link["count"].as_u64().unwrap(),
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        names_and_counts(&result["outgoing"]),
+        vec![("b".to_string(), 2), ("c".to_string(), 1)]
+    );
+    assert_eq!(
+        names_and_counts(&result["incoming"]),
+        vec![("b".to_string(), 1)]
+    );
+
+    let two_hop = result["two_hop"].as_array().unwrap();
+    assert_eq!(two_hop.len(), 1, "{:?}", two_hop);
+    assert_eq!(two_hop[0]["via"]["name"], "c");
+    assert_eq!(two_hop[0]["notes"][0]["name"], "sub/d");
+
+    // Unknown notes have no neighborhood
+    let missing = workspace.root_uri().join("missing.pn").unwrap();
+    let response = client
+        .execute_command(
+            "patto/neighborhood",
+            vec![serde_json::json!(missing.to_string())],
+        )
+        .await;
+    assert_eq!(response.unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_insert_citation_without_provider() {
+    let mut workspace = TestWorkspace::new();
+    workspace.create_file("note.pn", "cite here\n");
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+
+    // No paper provider is configured in tests, so the command reports an error
+    let uri = workspace.get_uri("note.pn");
+    let response = client
+        .execute_command(
+            "patto/insertCitation",
+            vec![
+                serde_json::json!(uri.to_string()),
+                serde_json::json!({"line": 0, "character": 9}),
+                serde_json::json!("vaswani2017"),
+            ],
+        )
+        .await;
+    assert!(response.is_none(), "expected an error, got {:?}", response);
+}
+
+#[tokio::test]
+async fn test_toggle_task() {
+    let mut workspace = TestWorkspace::new();
+    let content =
+        "buy milk !2024-12-31\nwrite report {@task status=doing due=2024-12-25}\nplain line\n";
+    workspace.create_file("tasks.pn", content);
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+    let uri = workspace.get_uri("tasks.pn");
+    client.did_open(uri.clone(), content.to_string()).await;
+
+    let toggle = |line: u32, options: serde_json::Value| {
+        vec![
+            serde_json::json!(uri.to_string()),
+            serde_json::json!({"line": line, "character": 0}),
+            options,
+        ]
+    };
+    let edits = |response: Option<Option<serde_json::Value>>| {
+        let edit = response.unwrap().expect("expected a workspace edit");
+        edit["changes"]
+            .as_object()
+            .unwrap()
+            .values()
+            .next()
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .clone()
+    };
+
+    // Abbreviated tasks get their leading symbol rewritten
+    let response = client
+        .execute_command("patto/toggleTask", toggle(0, serde_json::json!({})))
+        .await;
+    let changes = edits(response);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0]["newText"], "*");
+    assert_eq!(changes[0]["range"]["start"]["character"], 9);
+    assert_eq!(changes[0]["range"]["end"]["character"], 10);
+
+    // {@task} blocks get status= rewritten, optionally stamping the completion
+    let response = client
+        .execute_command("patto/toggleTask", toggle(1, serde_json::json!({})))
+        .await;
+    assert_eq!(
+        edits(response)[0]["newText"],
+        "{@task status=done due=2024-12-25}"
+    );
+    let response = client
+        .execute_command(
+            "patto/toggleTask",
+            toggle(1, serde_json::json!({"stampCompletion": true})),
+        )
+        .await;
+    let text = edits(response)[0]["newText"].as_str().unwrap().to_string();
+    assert!(text.contains("status=done"), "{text}");
+    assert!(text.contains("completed_at="), "{text}");
+
+    // Lines without a task yield no edit
+    let response = client
+        .execute_command("patto/toggleTask", toggle(2, serde_json::json!({})))
+        .await;
+    assert_eq!(response.unwrap(), None);
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use common::*;
+use tower_lsp::lsp_types::{Position, Range, TextEdit};
+
+async fn format(content: &str, tab_size: u32) -> Vec<TextEdit> {
+    let mut workspace = TestWorkspace::new();
+    workspace.create_file("note.pn", content);
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+    let uri = workspace.get_uri("note.pn");
+    client.did_open(uri.clone(), content.to_string()).await;
+
+    client
+        .formatting(uri, tab_size)
+        .await
+        .expect("formatting returned None")
+}
+
+/// Edited rows with their new text
+fn edited_lines(edits: &[TextEdit]) -> Vec<(u32, &str)> {
+    edits
+        .iter()
+        .map(|edit| (edit.range.start.line, edit.new_text.as_str()))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_formatting_normalizes_indentation() {
+    let content = "parent \n  child\n    grandchild\t\n\tsibling\n\n";
+    let edits = format(content, 2).await;
+
+    assert_eq!(
+        edited_lines(&edits),
+        vec![
+            (0, "parent"),
+            (1, "\tchild"),
+            (2, "\t\tgrandchild"),
+            (3, "\n")
+        ]
+    );
+    // The trailing blank line is dropped
+    let last = edits.last().unwrap();
+    assert_eq!((last.range.start.line, last.range.start.character), (3, 8));
+    assert_eq!((last.range.end.line, last.range.end.character), (5, 0));
+}
+
+#[tokio::test]
+async fn test_formatting_leaves_code_block_bodies_untouched() {
+    let content = "notes  \n\t[@code rust]\n\t\tfn main() {  \n\t\t    let x = 1;\t\n\n\t\t}\n\t[@math]\n\t\tx = y  \n  done\n";
+    let edits = format(content, 4).await;
+
+    assert_eq!(edited_lines(&edits), vec![(0, "notes"), (8, "done")]);
+}
+
+#[tokio::test]
+async fn test_formatting_formatted_document() {
+    let content = "parent\n\tchild [link]\n\t[@code]\n\t\t  indented  \n";
+    assert!(format(content, 4).await.is_empty());
+}
+
+#[tokio::test]
+async fn test_range_formatting_aligns_table() {
+    let content = "[@table caption=\"members\"]\n\tname\trole\n\t山田太郎\tlead\n\tbob\tdev\n";
+    let mut workspace = TestWorkspace::new();
+    workspace.create_file("note.pn", content);
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+    let uri = workspace.get_uri("note.pn");
+    client.did_open(uri.clone(), content.to_string()).await;
+
+    let range = Range::new(Position::new(2, 0), Position::new(3, 0));
+    let edits = client
+        .range_formatting(uri.clone(), range)
+        .await
+        .expect("range_formatting returned None");
+    assert_eq!(
+        edited_lines(&edits),
+        vec![(1, "name    \trole"), (3, "bob     \tdev")]
+    );
+    // The indentation of the rows is kept
+    assert!(edits.iter().all(|edit| edit.range.start.character == 1));
+
+    let outside = Range::new(Position::new(5, 0), Position::new(5, 0));
+    let edits = client.range_formatting(uri, outside).await.unwrap();
+    assert!(edits.is_empty());
+}
@@ -80,6 +80,36 @@ async fn test_goto_definition_with_anchor() {
     println!("✅ Goto definition with anchor test passed");
 }
 
+#[tokio::test]
+async fn test_goto_definition_with_heading_anchor() {
+    let mut workspace = TestWorkspace::new();
+    workspace.create_file("source.pn", "See [target#getting-started]\n");
+    workspace.create_file(
+        "target.pn",
+        "Intro\n[* Getting Started]\nLine 2\n[* Usage] #usage\n",
+    );
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+
+    let source_uri = workspace.get_uri("source.pn");
+    client
+        .did_open(
+            source_uri.clone(),
+            "See [target#getting-started]\n".to_string(),
+        )
+        .await;
+
+    let response = client.definition(source_uri, 0, 8).await;
+
+    let location = match response.expect("No result in definition") {
+        GotoDefinitionResponse::Scalar(loc) => loc,
+        GotoDefinitionResponse::Array(locs) => locs[0].clone(),
+        GotoDefinitionResponse::Link(_) => panic!("Unexpected Link response"),
+    };
+    assert!(location.uri.as_str().contains("target.pn"));
+    assert_eq!(location.range.start.line, 1);
+}
+
 #[tokio::test]
 async fn test_goto_definition_nonexistent_note() {
     let mut workspace = TestWorkspace::new();
@@ -129,3 +159,72 @@ async fn test_goto_definition_self_link_no_deadlock() {
 
     println!("✅ Self-link goto definition no-deadlock test passed");
 }
+
+#[tokio::test]
+async fn test_goto_definition_with_link_normalization() {
+    let mut workspace = TestWorkspace::new();
+    workspace.create_file("source.pn", "See [My Note]\n");
+    workspace.create_file("my_note.pn", "Target content\n");
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+    client
+        .notify(
+            "workspace/didChangeConfiguration",
+            serde_json::json!({
+                "settings": {
+                    "patto": { "links": { "ignoreCase": true, "ignoreSeparators": true } }
+                }
+            }),
+        )
+        .await;
+
+    let source_uri = workspace.get_uri("source.pn");
+    client
+        .did_open(source_uri.clone(), "See [My Note]\n".to_string())
+        .await;
+
+    let response = client.definition(source_uri, 0, 7).await;
+
+    let location = match response.expect("No result in definition") {
+        GotoDefinitionResponse::Scalar(loc) => loc,
+        GotoDefinitionResponse::Array(locs) => locs[0].clone(),
+        GotoDefinitionResponse::Link(_) => panic!("Unexpected Link response"),
+    };
+    assert_eq!(location.uri, workspace.get_uri("my_note.pn"));
+}
+
+#[tokio::test]
+async fn test_goto_definition_percent_encoding() {
+    let mut workspace = TestWorkspace::new();
+    let content = "[my note] [日記]\n";
+    workspace.create_file("日記.pn", content);
+    workspace.create_file("my note.pn", "Target content\n");
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+
+    // Open the note under neovim's lowercase spelling of its URI
+    let diary_uri = workspace.get_uri("日記.pn");
+    let nvim_uri = tower_lsp::lsp_types::Url::parse(
+        &diary_uri
+            .as_str()
+            .replace("%E6%97%A5%E8%A8%98", "%e6%97%a5%e8%a8%98"),
+    )
+    .unwrap();
+    assert_ne!(nvim_uri.as_str(), diary_uri.as_str());
+    client.did_open(nvim_uri.clone(), content.to_string()).await;
+
+    let location_of = |response: Option<GotoDefinitionResponse>| match response
+        .expect("No result in definition")
+    {
+        GotoDefinitionResponse::Scalar(loc) => loc,
+        GotoDefinitionResponse::Array(locs) => locs[0].clone(),
+        GotoDefinitionResponse::Link(_) => panic!("Unexpected Link response"),
+    };
+
+    let note = location_of(client.definition(nvim_uri.clone(), 0, 3).await);
+    assert!(note.uri.as_str().ends_with("/my%20note.pn"), "{}", note.uri);
+    assert_eq!(note.uri, workspace.get_uri("my note.pn"));
+
+    let diary = location_of(client.definition(nvim_uri.clone(), 0, 12).await);
+    assert_eq!(diary.uri, nvim_uri);
+}
@@ -858,3 +858,61 @@ async fn test_multiline_content_rename() {
 
     println!("✅ Multiline content rename test passed");
 }
+
+#[tokio::test]
+async fn test_rename_from_anchored_link_keeps_anchor() {
+    let mut workspace = TestWorkspace::new();
+    let content = "see [old#sec] now\n";
+    workspace.create_file("source.pn", content);
+    workspace.create_file("old.pn", "Heading #sec\n");
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+
+    let uri = workspace.get_uri("source.pn");
+    client.did_open(uri.clone(), content.to_string()).await;
+
+    // Cursor on the note name of the anchored link
+    let response = client.rename(uri, 0, 6, "new").await;
+
+    let doc_changes =
+        serde_json::to_value(response.expect("Rename failed").document_changes.unwrap()).unwrap();
+    let edits = doc_changes
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|change| {
+            change["textDocument"]["uri"]
+                .as_str()
+                .is_some_and(|uri| uri.ends_with("source.pn"))
+        })
+        .flat_map(|change| change["edits"].as_array().unwrap().clone())
+        .collect::<Vec<_>>();
+    assert_eq!(edits.len(), 1, "{:?}", edits);
+    // The whole link is replaced, and the anchor is written back exactly once
+    assert_eq!(edits[0]["newText"], "[new#sec]");
+    assert_eq!(edits[0]["range"]["start"]["character"], 4);
+    assert_eq!(edits[0]["range"]["end"]["character"], 13);
+}
+
+#[tokio::test]
+async fn test_rename_anchor_keeps_link_spelling() {
+    let mut workspace = TestWorkspace::new();
+    let content = "Title #old\n";
+    workspace.create_file("日記.pn", content);
+    workspace.create_file("source.pn", "see [日記#old]\n");
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+
+    let uri = workspace.get_uri("日記.pn");
+    client.did_open(uri.clone(), content.to_string()).await;
+
+    let response = client.rename(uri, 0, 7, "new").await;
+
+    let doc_changes =
+        serde_json::to_value(response.expect("Rename failed").document_changes.unwrap()).unwrap();
+    assert!(
+        assert_has_text_edit(&doc_changes, "source.pn", "[日記#new]"),
+        "Anchored link not updated: {}",
+        doc_changes
+    );
+}
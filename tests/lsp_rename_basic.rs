@@ -176,3 +176,41 @@ async fn test_rename_rejects_pn_extension() {
 
     println!("✅ Reject .pn extension test passed");
 }
+
+#[tokio::test]
+async fn test_rename_note_with_custom_extension() {
+    let mut workspace = TestWorkspace::new();
+    workspace.create_file("note_a.patto", "See [note_b]\n");
+    workspace.create_file("note_b.patto", "Content of note B\n");
+    // Files with the default extension are not notes in this vault
+    workspace.create_file("stray.pn", "Not a note [note_b]\n");
+
+    let mut client = InProcessLspClient::with_note_extension(&workspace, "patto").await;
+
+    let uri_a = workspace.get_uri("note_a.patto");
+    client
+        .did_open(uri_a.clone(), "See [note_b]\n".to_string())
+        .await;
+
+    let response = client.rename(uri_a.clone(), 0, 6, "renamed").await;
+
+    let workspace_edit = response.expect("Rename failed");
+    let doc_changes = serde_json::to_value(workspace_edit.document_changes.unwrap()).unwrap();
+    assert!(
+        assert_has_file_rename(&doc_changes, "note_b.patto", "renamed.patto"),
+        "File rename not found: {}",
+        doc_changes
+    );
+    assert!(assert_has_text_edit(
+        &doc_changes,
+        "note_a.patto",
+        "[renamed]"
+    ));
+    assert!(
+        !assert_has_text_edit(&doc_changes, "stray.pn", "[renamed]"),
+        "Files with another extension should not be edited"
+    );
+
+    // The extension is rejected in new names
+    assert!(client.rename(uri_a, 0, 6, "renamed.patto").await.is_none());
+}
@@ -39,6 +39,55 @@ async fn test_prepare_rename_on_current_file() {
     println!("✅ Prepare rename on current file test passed");
 }
 
+#[tokio::test]
+async fn test_prepare_rename_rejects_plain_text() {
+    let mut workspace = TestWorkspace::new();
+    let content = "Title line\nJust some plain text\n";
+    workspace.create_file("note_b.pn", content);
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+
+    let uri_b = workspace.get_uri("note_b.pn");
+    client.did_open(uri_b.clone(), content.to_string()).await;
+
+    // Position: line 1, char 5 (plain text, not on the first line)
+    let response = client.prepare_rename(uri_b, 1, 5).await;
+    assert!(
+        response.is_none(),
+        "Plain text should not be renameable: {:?}",
+        response
+    );
+}
+
+#[tokio::test]
+async fn test_prepare_rename_always_rename_file_setting() {
+    let mut workspace = TestWorkspace::new();
+    let content = "Title line\nJust some plain text\n";
+    workspace.create_file("note_b.pn", content);
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+    client
+        .notify(
+            "workspace/didChangeConfiguration",
+            serde_json::json!({
+                "settings": { "patto": { "rename": { "alwaysRenameFile": true } } }
+            }),
+        )
+        .await;
+
+    let uri_b = workspace.get_uri("note_b.pn");
+    client.did_open(uri_b.clone(), content.to_string()).await;
+
+    let response = client.prepare_rename(uri_b, 1, 5).await;
+    match response {
+        Some(tower_lsp::lsp_types::PrepareRenameResponse::RangeWithPlaceholder {
+            placeholder,
+            ..
+        }) => assert_eq!(placeholder, "note_b"),
+        other => panic!("Expected the file name as placeholder, got {:?}", other),
+    }
+}
+
 #[tokio::test]
 async fn test_rename_current_file() {
     let mut workspace = TestWorkspace::new();
@@ -143,3 +192,56 @@ async fn test_rename_current_file_with_anchors() {
 
     println!("✅ Rename current file with anchors test passed");
 }
+
+#[tokio::test]
+async fn test_rename_unsaved_note() {
+    let mut workspace = TestWorkspace::new();
+    workspace.create_file("note_a.pn", "Reference to [draft] and [draft#todo]\n");
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+
+    // The note only exists as an open buffer; it was never written to disk
+    let draft_uri = workspace.root_uri().join("draft.pn").unwrap();
+    client
+        .did_open(draft_uri.clone(), "Fresh idea #todo\n".to_string())
+        .await;
+
+    let response = client.rename(draft_uri, 0, 0, "idea").await;
+
+    assert!(response.is_some(), "Rename of an unsaved note failed");
+    let doc_changes = serde_json::to_value(response.unwrap().document_changes.unwrap()).unwrap();
+    assert!(
+        assert_has_file_rename(&doc_changes, "draft.pn", "idea.pn"),
+        "File rename not found: {}",
+        doc_changes
+    );
+    assert!(
+        assert_has_text_edit(&doc_changes, "note_a.pn", "[idea]"),
+        "Link edit not found: {}",
+        doc_changes
+    );
+    assert!(
+        assert_has_text_edit(&doc_changes, "note_a.pn", "[idea#todo]"),
+        "Anchored link edit not found: {}",
+        doc_changes
+    );
+}
+
+#[tokio::test]
+async fn test_rename_rejects_unsaved_target() {
+    let mut workspace = TestWorkspace::new();
+    workspace.create_file("note_a.pn", "Content\n");
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+
+    // An open but unsaved buffer already claims the new name
+    let taken_uri = workspace.root_uri().join("taken.pn").unwrap();
+    client.did_open(taken_uri, "Unsaved\n".to_string()).await;
+
+    let uri_a = workspace.get_uri("note_a.pn");
+    client
+        .did_open(uri_a.clone(), "Content\n".to_string())
+        .await;
+    let response = client.rename(uri_a, 0, 0, "taken").await;
+    assert!(response.is_none(), "Rename onto an open note should fail");
+}
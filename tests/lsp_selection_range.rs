@@ -0,0 +1,65 @@
+mod common;
+
+use common::*;
+use tower_lsp::lsp_types::{Position, Range, SelectionRange};
+
+/// Ranges of the selection chain as `(start line, start char, end line, end char)`,
+/// innermost first
+fn chain(selection: &SelectionRange) -> Vec<(u32, u32, u32, u32)> {
+    let mut ranges = vec![];
+    let mut current = Some(selection);
+    while let Some(selection) = current {
+        let Range { start, end } = selection.range;
+        ranges.push((start.line, start.character, end.line, end.character));
+        current = selection.parent.as_deref();
+    }
+    ranges
+}
+
+async fn selection_chains(
+    content: &str,
+    positions: Vec<Position>,
+) -> Vec<Vec<(u32, u32, u32, u32)>> {
+    let mut workspace = TestWorkspace::new();
+    workspace.create_file("note.pn", content);
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+    let uri = workspace.get_uri("note.pn");
+    client.did_open(uri.clone(), content.to_string()).await;
+
+    client
+        .selection_range(uri, positions)
+        .await
+        .expect("selection_range returned None")
+        .iter()
+        .map(chain)
+        .collect()
+}
+
+#[tokio::test]
+async fn test_selection_expands_from_word_to_subtree() {
+    let content = "parent line\n\tchild [link] word\n\t\tgrandchild\n";
+    let chains = selection_chains(content, vec![Position::new(1, 9)]).await;
+
+    assert_eq!(
+        chains[0],
+        vec![
+            // Word and WikiLink
+            (1, 8, 1, 12),
+            (1, 7, 1, 13),
+            // Child line, then its subtree
+            (1, 0, 1, 18),
+            (1, 0, 2, 12),
+            // Parent subtree
+            (0, 0, 2, 12),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_selection_uses_utf16_offsets() {
+    let content = "日本語 [メモ] テスト\n";
+    let chains = selection_chains(content, vec![Position::new(0, 6)]).await;
+
+    assert_eq!(chains[0], vec![(0, 5, 0, 7), (0, 4, 0, 8), (0, 0, 0, 12)]);
+}
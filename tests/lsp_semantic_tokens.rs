@@ -1,7 +1,9 @@
 mod common;
 
 use common::*;
-use tower_lsp::lsp_types::{Position, Range, SemanticTokensRangeResult, SemanticTokensResult};
+use tower_lsp::lsp_types::{
+    Position, Range, SemanticToken, SemanticTokensRangeResult, SemanticTokensResult,
+};
 
 #[tokio::test]
 async fn test_semantic_tokens_full() {
@@ -117,3 +119,108 @@ async fn test_semantic_tokens_empty_file() {
 
     println!("✅ Semantic tokens empty file test passed");
 }
+
+/// Decode delta-encoded tokens into (line, start, length, type, modifiers)
+fn absolute_tokens(tokens: &[SemanticToken]) -> Vec<(u32, u32, u32, u32, u32)> {
+    let (mut line, mut start) = (0, 0);
+    tokens
+        .iter()
+        .map(|t| {
+            if t.delta_line > 0 {
+                start = 0;
+            }
+            line += t.delta_line;
+            start += t.delta_start;
+            (
+                line,
+                start,
+                t.length,
+                t.token_type,
+                t.token_modifiers_bitset,
+            )
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_semantic_tokens_task_status_and_deadline() {
+    let text = "Write {@task status=todo due=2000-01-01}\nShip -2000-01-01\nPlan !2999-01-01\n";
+    let mut workspace = TestWorkspace::new();
+    workspace.create_file("tasks.pn", text);
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+
+    let uri = workspace.get_uri("tasks.pn");
+    client.did_open(uri.clone(), text.to_string()).await;
+
+    let Some(SemanticTokensResult::Tokens(tokens)) = client.semantic_tokens(uri).await else {
+        panic!("Expected full semantic tokens");
+    };
+
+    // Token types: 3 = comment, 12 = deadline
+    // Modifier bits: 1 = taskTodo, 8 = taskDone, 16 = overdue
+    assert_eq!(
+        absolute_tokens(&tokens.data),
+        vec![
+            (0, 6, 23, 3, 1),
+            (0, 29, 10, 12, 1 | 16),
+            (0, 39, 1, 3, 1),
+            // Done tasks are never overdue
+            (1, 5, 1, 3, 8),
+            (1, 6, 10, 12, 8),
+            (2, 5, 1, 3, 1),
+            (2, 6, 10, 12, 1),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_semantic_tokens_on_tab_indented_lines() {
+    let text = "root\n\t\tdeep [link] x\n\t日本 [other] {@task status=todo due=2999-01-01}\n[@quote]\n\t\t引用 [quoted]\n";
+    let mut workspace = TestWorkspace::new();
+    workspace.create_file("indented.pn", text);
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+
+    let uri = workspace.get_uri("indented.pn");
+    client.did_open(uri.clone(), text.to_string()).await;
+
+    let Some(SemanticTokensResult::Tokens(tokens)) = client.semantic_tokens(uri).await else {
+        panic!("Expected full semantic tokens");
+    };
+
+    // Columns are UTF-16 units from the start of the line: every tab counts
+    // as one, so tokens land on the links rather than being shifted
+    let tokens = absolute_tokens(&tokens.data);
+    // Token types: 3 = comment, 5 = operator (wiki link), 12 = deadline
+    let links: Vec<_> = tokens
+        .iter()
+        .filter(|token| token.3 == 5)
+        .map(|token| (token.0, token.1, token.2))
+        .collect();
+    assert_eq!(links, vec![(1, 7, 6), (2, 4, 7), (4, 5, 8)]);
+    assert!(tokens.contains(&(2, 12, 23, 3, 1)), "{:?}", tokens);
+    assert!(tokens.contains(&(2, 35, 10, 12, 1)), "{:?}", tokens);
+}
+
+#[tokio::test]
+async fn test_semantic_tokens_deadline_with_space() {
+    let text = "Call {@task due=2999-01-01 09:00 status=todo}\n";
+    let mut workspace = TestWorkspace::new();
+    workspace.create_file("spaced.pn", text);
+
+    let mut client = InProcessLspClient::new(&workspace).await;
+
+    let uri = workspace.get_uri("spaced.pn");
+    client.did_open(uri.clone(), text.to_string()).await;
+
+    let Some(SemanticTokensResult::Tokens(tokens)) = client.semantic_tokens(uri).await else {
+        panic!("Expected full semantic tokens");
+    };
+
+    // The deadline covers the time after the space too
+    assert_eq!(
+        absolute_tokens(&tokens.data),
+        vec![(0, 5, 11, 3, 1), (0, 16, 16, 12, 1), (0, 32, 13, 3, 1)]
+    );
+}
@@ -98,6 +98,15 @@ mod structure {
         assert!(output.contains("      - L3"));
         assert!(output.contains("        - L4"));
     }
+
+    #[test]
+    fn test_definition_item() {
+        let output = render_markdown_no_frontmatter(
+            "Glossary\n\tpatto:: a plain text format",
+            MarkdownFlavor::Standard,
+        );
+        assert!(output.contains("  - **patto**: a plain text format"));
+    }
 }
 
 // =============================================================================
@@ -144,7 +153,7 @@ mod code_blocks {
         let input = "Use [` some_function() `] here";
         let output = render_markdown(input, MarkdownFlavor::Standard);
 
-        assert!(output.contains("`some_function() `"));
+        assert!(output.contains("`some_function()`"));
     }
 
     #[test]
@@ -394,6 +403,14 @@ mod wikilinks {
 
         assert!(output.contains("[other note](other note.md)"));
     }
+
+    #[test]
+    fn test_wikilink_with_anchor_github() {
+        let input = "Link to [note#section]";
+        let output = render_markdown(input, MarkdownFlavor::GitHub);
+
+        assert!(output.contains("[note#section](note.md#section)"));
+    }
 }
 
 // =============================================================================
@@ -466,6 +483,14 @@ mod anchors {
 
         assert!(output.contains("<a id=\"myanchor\"></a>"));
     }
+
+    #[test]
+    fn test_tag_kept_as_comment() {
+        let input = "Line with tag {@tag project=patto}";
+        let output = render_markdown(input, MarkdownFlavor::Standard);
+
+        assert!(output.contains("<!-- {@tag project=patto} -->"));
+    }
 }
 
 // =============================================================================
@@ -563,6 +588,40 @@ mod images {
 
         assert!(output.contains("![](https://example.com/img.png)"));
     }
+
+    #[test]
+    fn test_local_image_obsidian_embed() {
+        let input = "[@img ./images/cat.png]";
+        let output = render_markdown_no_frontmatter(input, MarkdownFlavor::Obsidian);
+
+        assert!(output.contains("![[images/cat.png]]"));
+    }
+
+    #[test]
+    fn test_local_image_obsidian_embed_with_alt() {
+        let input = "[@img ./cat.png \"A cat\"]";
+        let output = render_markdown_no_frontmatter(input, MarkdownFlavor::Obsidian);
+
+        assert!(output.contains("![[cat.png|A cat]]"));
+    }
+
+    #[test]
+    fn test_remote_image_obsidian_stays_markdown() {
+        let input = "[@img https://example.com/img.png]";
+        let output = render_markdown_no_frontmatter(input, MarkdownFlavor::Obsidian);
+
+        assert!(output.contains("![](https://example.com/img.png)"));
+    }
+
+    #[test]
+    fn test_local_image_standard_and_github() {
+        let input = "[@img ./cat.png]";
+
+        for flavor in [MarkdownFlavor::Standard, MarkdownFlavor::GitHub] {
+            let output = render_markdown(input, flavor);
+            assert!(output.contains("![](./cat.png)"), "{}: {}", flavor, output);
+        }
+    }
 }
 
 // =============================================================================
@@ -624,6 +683,52 @@ mod frontmatter {
         assert!(!output.starts_with("---"));
         assert!(!output.contains("patto_source"));
     }
+
+    fn render_with_metadata(patto_text: &str, options: MarkdownRendererOptions) -> String {
+        let result = parser::parse_text(patto_text);
+        let mut output = Vec::new();
+        MarkdownRenderer::new(options.with_metadata(true))
+            .format(&result.ast, &mut output)
+            .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_metadata_title_and_tasks() {
+        let input = "[** Plan]\nwrite {@task status=todo due=2024-12-31}\n\treview {@task status=doing due=2024-11-30}\nship {@task status=done due=2024-10-01}\n";
+        let output = render_with_metadata(
+            input,
+            MarkdownRendererOptions::new(MarkdownFlavor::Obsidian),
+        );
+
+        assert!(output.starts_with("---\npatto_source: true\nflavor: obsidian\n"));
+        assert!(output.contains("title: \"Plan\"\n"));
+        assert!(
+            output.contains("tasks:\n  total: 3\n  todo: 1\n  doing: 1\n  paused: 0\n  done: 1\n")
+        );
+        // Done tasks don't count as upcoming
+        assert!(output.contains("next_due: \"2024-11-30\"\n---\n"));
+    }
+
+    #[test]
+    fn test_metadata_title_falls_back_and_is_escaped() {
+        let input = "no heading here\n";
+        let options = MarkdownRendererOptions::new(MarkdownFlavor::Obsidian)
+            .with_title("a \"quoted\" \\ note: #1");
+        let output = render_with_metadata(input, options);
+
+        assert!(output.contains("title: \"a \\\"quoted\\\" \\\\ note: #1\"\n"));
+        assert!(output.contains("  total: 0\n"));
+        assert!(!output.contains("next_due"));
+    }
+
+    #[test]
+    fn test_metadata_needs_frontmatter() {
+        let options = MarkdownRendererOptions::new(MarkdownFlavor::Standard);
+        let output = render_with_metadata("[** Plan]\n", options);
+
+        assert!(!output.contains("title:"));
+    }
 }
 
 // =============================================================================
@@ -1004,6 +1109,22 @@ mod task_property_fields {
         assert!(out.contains("✅ 2024-03-15"), "should have completed emoji");
     }
 
+    #[test]
+    fn test_patto_renderer_preserves_tags() {
+        use patto::renderer::{PattoRenderer, Renderer};
+        let r = PattoRenderer::new();
+        let input = "plan {@task status=todo due=2024-12-31} {@tag project=patto urgent}";
+        let result = patto::parser::parse_text(input);
+        let mut out = Vec::new();
+        r.format(&result.ast, &mut out).unwrap();
+        let s = String::from_utf8(out).unwrap();
+        assert!(
+            s.contains("{@task status=todo due=2024-12-31} {@tag project=patto urgent}"),
+            "tag preserved: {}",
+            s
+        );
+    }
+
     #[test]
     fn test_patto_renderer_preserves_scheduled_and_completed_at() {
         use patto::renderer::{PattoRenderer, Renderer};
@@ -1021,3 +1142,71 @@ mod task_property_fields {
         );
     }
 }
+
+// =============================================================================
+// Callout Tests
+// =============================================================================
+
+mod callouts {
+    use super::*;
+
+    fn render_with_callouts(patto_text: &str, callouts: bool) -> String {
+        let result = parser::parse_text(patto_text);
+        let options = MarkdownRendererOptions::new(MarkdownFlavor::GitHub).with_callouts(callouts);
+        let mut output = Vec::new();
+        MarkdownRenderer::new(options)
+            .format(&result.ast, &mut output)
+            .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_note_quote_becomes_alert() {
+        let input = "[@quote]\n\tNOTE: Mind the gap\n\tsecond line";
+        let output = render_with_callouts(input, true);
+
+        assert!(output.contains("> [!NOTE]\n> Mind the gap\n> second line\n"));
+    }
+
+    #[test]
+    fn test_keyword_alone_on_first_line() {
+        let input = "[@quote]\n\tWarning:\n\tDo not push to main";
+        let output = render_with_callouts(input, true);
+
+        assert!(output.contains("> [!WARNING]\n> Do not push to main\n"));
+    }
+
+    #[test]
+    fn test_plain_quote_unchanged() {
+        let input = "[@quote]\n\tJust a quote";
+        let output = render_with_callouts(input, true);
+
+        assert!(!output.contains("[!"));
+        assert!(output.contains("> Just a quote"));
+    }
+
+    #[test]
+    fn test_callouts_off_by_default() {
+        let input = "[@quote]\n\tNOTE: Mind the gap";
+        let output = render_markdown(input, MarkdownFlavor::GitHub);
+
+        assert!(!output.contains("[!NOTE]"));
+        assert!(output.contains("> NOTE: Mind the gap"));
+    }
+
+    #[test]
+    fn test_format_range_respects_callouts() {
+        let input = "intro\n[@quote]\n\tTIP: Use format_range\nouter";
+        let result = parser::parse_text(input);
+        let options = MarkdownRendererOptions::new(MarkdownFlavor::GitHub).with_callouts(true);
+        let mut output = Vec::new();
+        MarkdownRenderer::new(options)
+            .format_range(&result.ast, &mut output, 1, 2)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(!output.contains("intro"));
+        assert!(output.contains("> [!TIP]\n> Use format_range\n"));
+        assert!(!output.contains("outer"));
+    }
+}
@@ -1,6 +1,8 @@
 //! Integration tests for markdown import
 
-use patto::importer::{ImportMode, ImportOptions, MarkdownImporter, MarkdownInputFlavor};
+use patto::importer::{
+    roundtrip_check, ImportMode, ImportOptions, MarkdownImporter, MarkdownInputFlavor,
+};
 
 fn import(md: &str, mode: ImportMode) -> String {
     let options = ImportOptions::new(mode);
@@ -61,8 +63,8 @@ def hello():
         "H1 should become text + horizontal line"
     );
     assert!(
-        patto.contains("[* Section 1]"),
-        "H2 should become emphasized text"
+        patto.contains("[** Section 1]"),
+        "H2 should become emphasized text with fontsize 2"
     );
 
     // Check content
@@ -481,7 +483,7 @@ fn test_headings_not_indented_by_lists() {
 
     // Heading should not be indented
     assert!(
-        patto.contains("\n[* Heading]"),
+        patto.contains("\n[** Heading]"),
         "Heading should not be indented"
     );
 }
@@ -635,3 +637,30 @@ fn test_table_inline_code_in_cells() {
         patto
     );
 }
+
+#[test]
+fn test_roundtrip_check_corpus() {
+    // Documents whose blocks survive markdown → patto → markdown unchanged
+    let corpus = [
+        "A single paragraph with **bold** text.\n",
+        "```python\nprint('hello')\n```\n",
+        "> quoted line\n",
+    ];
+    for md in corpus {
+        let report = roundtrip_check(md, MarkdownInputFlavor::Standard);
+        assert!(
+            report.is_stable(),
+            "{:?} diverged: {:?}",
+            md,
+            report.divergences
+        );
+    }
+}
+
+#[test]
+fn test_roundtrip_check_reports_block_types() {
+    let report = roundtrip_check("text\n\n<div>html</div>\n", MarkdownInputFlavor::Standard);
+    assert!(!report.is_stable());
+    assert_eq!(report.diverged_block_types(), vec!["html"]);
+    assert!(report.patto_content.contains("text"));
+}
@@ -0,0 +1,274 @@
+//! Tests for the Org-mode exporter
+
+use patto::parser;
+use patto::renderer::{OrgRenderer, Renderer};
+
+/// Helper to render patto text to Org-mode
+fn render_org(patto_text: &str) -> String {
+    let result = parser::parse_text(patto_text);
+    assert!(
+        result.parse_errors.is_empty(),
+        "Parse errors: {:?}",
+        result.parse_errors
+    );
+
+    let mut output = Vec::new();
+    OrgRenderer::new().format(&result.ast, &mut output).unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+// =============================================================================
+// Basic Structure Tests
+// =============================================================================
+
+mod structure {
+    use super::*;
+
+    #[test]
+    fn test_simple_line() {
+        let output = render_org("Hello world.");
+        assert_eq!(output, "Hello world.\n");
+    }
+
+    #[test]
+    fn test_nested_structure() {
+        let input = "Root\n\tChild1\n\t\tGrandchild\n\tChild2";
+        let output = render_org(input);
+
+        assert!(output.contains("- Root\n"));
+        assert!(output.contains("  - Child1\n"));
+        assert!(output.contains("    - Grandchild\n"));
+        assert!(output.contains("  - Child2\n"));
+    }
+
+    #[test]
+    fn test_heading() {
+        let output = render_org("[* Heading]\n[** Title]\n");
+
+        assert!(output.contains("** Heading <<heading>>\n"));
+        assert!(output.contains("* Title <<title>>\n"));
+    }
+
+    #[test]
+    fn test_heading_targets_match_html_ids() {
+        let output =
+            render_org("[** Getting Started]\n[** Getting Started]\n[** はじめに] #intro\n");
+
+        assert!(output.contains("* Getting Started <<getting-started>>\n"));
+        assert!(output.contains("* Getting Started <<getting-started-1>>\n"));
+        // An explicit anchor is the heading's only target
+        assert!(output.contains("* はじめに <<intro>>\n"));
+    }
+
+    #[test]
+    fn test_nested_bold_is_not_heading() {
+        let output = render_org("Parent\n\t[* bold]");
+
+        assert!(output.contains("  - *bold*"));
+    }
+
+    #[test]
+    fn test_definition_item() {
+        let output = render_org("Glossary\n\tpatto:: a [/ plain] text format");
+        assert!(output.contains("  - patto :: a /plain/ text format\n"));
+    }
+
+    #[test]
+    fn test_horizontal_line() {
+        let output = render_org("before\n-----\nafter");
+        assert!(output.contains("before\n-----\nafter\n"));
+    }
+}
+
+// =============================================================================
+// Block Tests
+// =============================================================================
+
+mod blocks {
+    use super::*;
+
+    #[test]
+    fn test_code_block() {
+        let input = "[@code rust]\n\tfn main() {}";
+        let output = render_org(input);
+
+        assert!(output.contains("#+begin_src rust\nfn main() {}\n#+end_src\n"));
+        assert!(!output.contains("- #+begin_src"));
+    }
+
+    #[test]
+    fn test_code_block_empty_lang() {
+        let output = render_org("[@code]\n\tplain code");
+        assert!(output.contains("#+begin_example\nplain code\n#+end_example\n"));
+    }
+
+    #[test]
+    fn test_inline_code_and_math() {
+        let output = render_org("Use [` foo `] and [$ x^2 $]");
+
+        assert!(output.contains("~foo~"));
+        assert!(output.contains("\\(x^2\\)"));
+    }
+
+    #[test]
+    fn test_quote() {
+        let output = render_org("[@quote]\n\tLine 1\n\tLine 2");
+        assert!(output.contains("#+begin_quote\nLine 1\nLine 2\n#+end_quote\n"));
+    }
+
+    #[test]
+    fn test_table() {
+        let input = "[@table caption=\"My Table\"]\n\ta\tb\n\t1\t2";
+        let output = render_org(input);
+
+        assert!(output.contains("#+caption: My Table\n| a | b |\n|---+---|\n| 1 | 2 |\n"));
+    }
+}
+
+// =============================================================================
+// Task Tests
+// =============================================================================
+
+mod tasks {
+    use super::*;
+
+    #[test]
+    fn test_task_todo_with_deadline() {
+        let output = render_org("Write report {@task status=todo due=2024-12-31}");
+
+        assert!(output.contains("* TODO Write report"));
+        assert!(output.contains("DEADLINE: <2024-12-31 Tue>"));
+    }
+
+    #[test]
+    fn test_task_scheduled_datetime() {
+        let output =
+            render_org("Meeting {@task status=doing due=2024-12-31T10:00 scheduled=2024-12-30}");
+
+        assert!(output.contains("* TODO Meeting"));
+        assert!(output.contains("DEADLINE: <2024-12-31 Tue 10:00> SCHEDULED: <2024-12-30 Mon>"));
+    }
+
+    #[test]
+    fn test_task_done_closed() {
+        let output = render_org("Buy milk {@task status=done completed_at=2024-01-15}");
+
+        assert!(output.contains("* DONE Buy milk"));
+        assert!(output.contains("CLOSED: [2024-01-15 Mon]"));
+        assert!(!output.contains("DEADLINE"));
+    }
+
+    #[test]
+    fn test_task_weekly_on_several_days() {
+        let output = render_org("Gym {@task status=todo due=weekly:mon/thu}");
+
+        // One repeating timestamp per weekday; a planning line holds only one
+        assert!(!output.contains("DEADLINE"));
+        let stamps: Vec<&str> = output
+            .lines()
+            .find(|line| line.starts_with('<'))
+            .expect("timestamp line")
+            .split("> <")
+            .collect();
+        assert_eq!(stamps.len(), 2);
+        assert!(stamps.iter().all(|stamp| stamp.contains(" +1w")));
+        assert!(stamps.iter().any(|stamp| stamp.contains(" Mon +1w")));
+        assert!(stamps.iter().any(|stamp| stamp.contains(" Thu +1w")));
+    }
+
+    #[test]
+    fn test_task_weekly_on_one_day() {
+        let output = render_org("Review {@task status=todo due=weekly:fri}");
+
+        assert!(output.contains("DEADLINE: <"));
+        assert!(output.contains(" Fri +1w>"));
+    }
+
+    #[test]
+    fn test_nested_task_level() {
+        let output = render_org("Project\n\tSubtask {@task status=todo}");
+
+        assert!(output.contains("- Project\n"));
+        assert!(output.contains("** TODO Subtask"));
+    }
+}
+
+// =============================================================================
+// Link Tests
+// =============================================================================
+
+mod links {
+    use super::*;
+
+    #[test]
+    fn test_wikilink() {
+        let output = render_org("See [note]");
+        assert!(output.contains("[[file:note.org][note]]"));
+    }
+
+    #[test]
+    fn test_wikilink_with_anchor() {
+        let output = render_org("See [note#section]");
+        assert!(output.contains("[[file:note.org::section][note#section]]"));
+    }
+
+    #[test]
+    fn test_self_anchor_link_and_target() {
+        let output = render_org("Target line #here\nJump [#here]");
+
+        assert!(output.contains("Target line <<here>>"));
+        assert!(output.contains("[[here][#here]]"));
+    }
+
+    #[test]
+    fn test_url_link() {
+        let output = render_org("[Example https://example.com]");
+        assert!(output.contains("[[https://example.com][Example]]"));
+    }
+
+    #[test]
+    fn test_decorations() {
+        let output = render_org("[/ italic] [- gone] [_ under]");
+
+        assert!(output.contains("/italic/"));
+        assert!(output.contains("+gone+"));
+        assert!(output.contains("_under_"));
+    }
+}
+
+// =============================================================================
+// Range Tests
+// =============================================================================
+
+mod range {
+    use super::*;
+
+    #[test]
+    fn test_format_range() {
+        let result = parser::parse_text("first\nsecond\n\tchild\nthird");
+        let mut output = Vec::new();
+        OrgRenderer::new()
+            .format_range(&result.ast, &mut output, 1, 2)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(!output.contains("first"));
+        assert!(output.contains("- second\n"));
+        assert!(output.contains("  - child\n"));
+        assert!(!output.contains("third"));
+    }
+}
+
+// =============================================================================
+// Tag Tests
+// =============================================================================
+
+mod tags {
+    use super::*;
+
+    #[test]
+    fn test_tag_kept_verbatim() {
+        let output = render_org("Plan {@tag project=patto}");
+        assert!(output.contains(" ={@tag project=patto}=\n"));
+    }
+}
@@ -0,0 +1,49 @@
+//! Tests for `parser::parse_file`
+
+use std::io::ErrorKind;
+
+use patto::parser;
+use tempfile::TempDir;
+
+#[test]
+fn test_parse_file() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("note.pn");
+    std::fs::write(&path, "title\n\tchild [link]\n").unwrap();
+
+    let result = parser::parse_file(&path).unwrap();
+    assert!(result.parse_errors.is_empty());
+    assert_eq!(result.ast.value().children.lock().unwrap().len(), 1);
+}
+
+#[test]
+fn test_parse_large_file() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("large.pn");
+    // Well over a megabyte of notes
+    let text = "line with [link] and some padding text\n".repeat(40_000);
+    std::fs::write(&path, &text).unwrap();
+
+    let result = parser::parse_file(&path).unwrap();
+    let children = result.ast.value().children.lock().unwrap();
+    assert_eq!(children.len(), 40_000);
+    assert_eq!(
+        children[39_999].extract_str(),
+        "line with [link] and some padding text"
+    );
+}
+
+#[test]
+fn test_parse_file_errors() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("latin1.pn");
+    std::fs::write(&path, b"caf\xe9\n").unwrap();
+
+    let err = parser::parse_file(&path).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert!(err.to_string().contains("not valid UTF-8"), "{}", err);
+    assert!(err.to_string().contains("offset 3"), "{}", err);
+
+    let err = parser::parse_file(&dir.path().join("missing.pn")).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
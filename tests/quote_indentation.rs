@@ -110,11 +110,7 @@ mod patto_roundtrip {
     fn test_simple_quote_roundtrip() {
         let input = "[@quote]\n\tLine 1\n\tLine 2\n";
         let output = render_patto(input);
-        // Note: PattoRenderer adds trailing newline - this is existing behavior
-        assert_eq!(
-            output, "[@quote]\n\tLine 1\n\tLine 2\n\n",
-            "Simple quote round-trip (with trailing newline)"
-        );
+        assert_eq!(output, input, "Simple quote round-trip");
     }
 
     #[test]
@@ -122,10 +118,9 @@ mod patto_roundtrip {
         let input = "[@quote]\n\tLine 1\n\t\tNested line\n\tLine 2\n";
         let output = render_patto(input);
         // Expected: same structure with nested indentation preserved
-        // Note: trailing newline is existing behavior
         assert_eq!(
-            output, "[@quote]\n\tLine 1\n\t\tNested line\n\tLine 2\n\n",
-            "Quote with nested indentation should round-trip (with trailing newline)"
+            output, input,
+            "Quote with nested indentation should round-trip"
         );
     }
 
@@ -133,21 +128,14 @@ mod patto_roundtrip {
     fn test_deeply_nested_quote_roundtrip() {
         let input = "[@quote]\n\tLevel 1\n\t\tLevel 2\n\t\t\tLevel 3\n\t\tBack to 2\n\tBack to 1\n";
         let output = render_patto(input);
-        assert_eq!(
-            output,
-            "[@quote]\n\tLevel 1\n\t\tLevel 2\n\t\t\tLevel 3\n\t\tBack to 2\n\tBack to 1\n\n",
-            "Deeply nested quote should round-trip (with trailing newline)"
-        );
+        assert_eq!(output, input, "Deeply nested quote should round-trip");
     }
 
     #[test]
     fn test_quote_with_formatting_roundtrip() {
         let input = "[@quote]\n\t[* bold text]\n\t\t[/ italic nested]\n";
         let output = render_patto(input);
-        assert_eq!(
-            output, "[@quote]\n\t[* bold text]\n\t\t[/ italic nested]\n\n",
-            "Quote with formatting should round-trip (with trailing newline)"
-        );
+        assert_eq!(output, input, "Quote with formatting should round-trip");
     }
 }
 
@@ -404,25 +392,17 @@ mod edge_cases {
     fn test_quote_inside_list() {
         let input = "Parent\n\t[@quote]\n\t\tQuoted under parent\n";
         let output = render_patto(input);
-        // BUG: Currently loses one level of indentation
-        // Expected: "Parent\n\t[@quote]\n\t\tQuoted under parent\n\n"
-        // Actual: "Parent\n\t[@quote]\n\tQuoted under parent\n\n"
-        // This test documents current (broken) behavior
-        assert_eq!(
-            output, "Parent\n\t[@quote]\n\tQuoted under parent\n\n",
-            "Quote inside list - CURRENT BEHAVIOR (loses indent)"
-        );
+        assert_eq!(output, input, "Quote inside list keeps its indentation");
     }
 
     #[test]
     fn test_mixed_content_in_nested_quote() {
         let input = "[@quote]\n\tPlain text\n\t\t[* bold nested]\n\t\t\t[` code deeply nested`]\n";
         let output = render_patto(input);
-        // Note: code inline adds trailing space - existing behavior
+        // Note: inline code is written with a space before "`]"
         assert_eq!(
-            output,
-            "[@quote]\n\tPlain text\n\t\t[* bold nested]\n\t\t\t[` code deeply nested `]\n\n",
-            "Mixed content at different levels (with trailing newline and code space)"
+            output, "[@quote]\n\tPlain text\n\t\t[* bold nested]\n\t\t\t[` code deeply nested `]\n",
+            "Mixed content at different levels"
         );
     }
 }
@@ -0,0 +1,67 @@
+//! Tests for `Renderer::format_node` on subtrees of a document
+
+use patto::markdown::{MarkdownFlavor, MarkdownRendererOptions};
+use patto::parser::{self, AstNode};
+use patto::renderer::{
+    HtmlRenderer, HtmlRendererOptions, MarkdownRenderer, OrgRenderer, PattoRenderer, Renderer,
+};
+
+const NOTE: &str = "first line\n\tsecond [link]\n\t\tgrandchild\nlast line\n";
+
+/// The second line of `NOTE` with its child
+fn second_line(root: &AstNode) -> AstNode {
+    let first = root.value().children.lock().unwrap()[0].clone();
+    let second = first.value().children.lock().unwrap()[0].clone();
+    second
+}
+
+fn render_node(renderer: &dyn Renderer, node: &AstNode) -> String {
+    let mut output = Vec::new();
+    renderer.format_node(node, &mut output).unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn test_format_node_renders_subtree() {
+    let result = parser::parse_text(NOTE);
+    let node = second_line(&result.ast);
+
+    let markdown = MarkdownRenderer::new(MarkdownRendererOptions::new(MarkdownFlavor::Standard));
+    // No frontmatter for a fragment
+    assert_eq!(
+        render_node(&markdown, &node),
+        "- second [link](link.md)\n  - grandchild\n"
+    );
+    assert_eq!(
+        render_node(&PattoRenderer::new(), &node),
+        "second [link]\n\tgrandchild\n"
+    );
+    assert_eq!(
+        render_node(&OrgRenderer::new(), &node),
+        "- second [[file:link.org][link]]\n  - grandchild\n"
+    );
+
+    // No document list and no table of contents for a fragment
+    let html = HtmlRenderer::new(HtmlRendererOptions::default().with_toc(true));
+    let rendered = render_node(&html, &node);
+    assert!(
+        rendered.starts_with("<div class=\"patto-task-row\">"),
+        "{}",
+        rendered
+    );
+    assert!(rendered.contains("grandchild"), "{}", rendered);
+    assert!(!rendered.contains("first line"), "{}", rendered);
+    assert!(!rendered.contains("patto-document"), "{}", rendered);
+}
+
+#[test]
+fn test_format_node_on_root_matches_format() {
+    let result = parser::parse_text(NOTE);
+    let renderer = PattoRenderer::new();
+    let mut output = Vec::new();
+    renderer.format(&result.ast, &mut output).unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        render_node(&renderer, &result.ast)
+    );
+}
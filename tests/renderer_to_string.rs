@@ -0,0 +1,42 @@
+//! Tests for `Renderer::render_to_string` and using renderers as trait objects
+
+use patto::markdown::{MarkdownFlavor, MarkdownRendererOptions};
+use patto::parser;
+use patto::renderer::{
+    HtmlRenderer, HtmlRendererOptions, MarkdownRenderer, OrgRenderer, PattoRenderer, Renderer,
+};
+
+const NOTE: &str = "first line\n\tsecond [link]\n";
+
+#[test]
+fn test_render_to_string_matches_format() {
+    let result = parser::parse_text(NOTE);
+    let renderer = PattoRenderer::new();
+    let mut output = Vec::new();
+    renderer.format(&result.ast, &mut output).unwrap();
+    assert_eq!(
+        renderer.render_to_string(&result.ast),
+        String::from_utf8(output).unwrap()
+    );
+}
+
+#[test]
+fn test_renderers_as_trait_objects() {
+    let result = parser::parse_text(NOTE);
+    let renderers: Vec<Box<dyn Renderer>> = vec![
+        Box::new(HtmlRenderer::new(HtmlRendererOptions::default())),
+        Box::new(MarkdownRenderer::new(MarkdownRendererOptions::new(
+            MarkdownFlavor::Standard,
+        ))),
+        Box::new(PattoRenderer::new()),
+        Box::new(OrgRenderer::new()),
+    ];
+    let outputs: Vec<String> = renderers
+        .iter()
+        .map(|r| r.render_to_string(&result.ast))
+        .collect();
+    assert!(outputs[0].contains("<li"), "{}", outputs[0]);
+    assert!(outputs[1].contains("[link](link.md)"), "{}", outputs[1]);
+    assert_eq!(outputs[2], NOTE);
+    assert!(outputs[3].contains("second"), "{}", outputs[3]);
+}
@@ -0,0 +1,67 @@
+//! Tests that range semantic tokens match the full computation
+
+use patto::parser;
+use patto::semantic_token::{get_semantic_tokens, get_semantic_tokens_range};
+use tower_lsp::lsp_types::SemanticToken;
+
+/// Decode delta-encoded tokens into (line, start, length, type, modifiers)
+fn absolute_tokens(tokens: &[SemanticToken]) -> Vec<(u32, u32, u32, u32, u32)> {
+    let (mut line, mut start) = (0, 0);
+    tokens
+        .iter()
+        .map(|t| {
+            if t.delta_line > 0 {
+                start = 0;
+            }
+            line += t.delta_line;
+            start += t.delta_start;
+            (
+                line,
+                start,
+                t.length,
+                t.token_type,
+                t.token_modifiers_bitset,
+            )
+        })
+        .collect()
+}
+
+const NOTE: &str = "\
+[* heading] {@anchor top}
+intro [link] and [https://example.com]
+\tnested [a] {@task status=todo due=2999-01-01}
+\t\tdeeper [b#sec] `code`
+\t\t\tdeepest [c]
+\tback [d]
+[@code rust]
+\tfn main() {}
+\tlet x = 1;
+[@quote]
+\tquoted [e]
+\t\tmore [f]
+[@table]
+\t[g]\tcell
+\tcell\t[h]
+[@math]
+\tx^2
+last [i] $x$ [/ italic]
+";
+
+#[test]
+fn test_range_matches_full_tokens() {
+    let ast = parser::parse_text(NOTE).ast;
+    let full = absolute_tokens(&get_semantic_tokens(&ast));
+    let lines = NOTE.lines().count() as u32;
+
+    for start in 0..=lines {
+        for end in start..=lines + 1 {
+            let expected: Vec<_> = full
+                .iter()
+                .filter(|token| start <= token.0 && token.0 <= end)
+                .cloned()
+                .collect();
+            let range = absolute_tokens(&get_semantic_tokens_range(&ast, start, end));
+            assert_eq!(range, expected, "lines {}..={}", start, end);
+        }
+    }
+}
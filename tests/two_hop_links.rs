@@ -0,0 +1,82 @@
+//! Tests for `Repository::two_hop_notes` and `calculate_two_hop_links`
+
+use patto::repository::Repository;
+use tempfile::TempDir;
+use tower_lsp::lsp_types::Url;
+
+/// Write `files` into a temporary workspace and index them in order
+fn workspace(files: &[(&str, &str)]) -> (TempDir, Repository) {
+    let dir = TempDir::new().unwrap();
+    let repo = Repository::new(dir.path().to_path_buf());
+    for (name, content) in files {
+        std::fs::write(dir.path().join(name), content).unwrap();
+    }
+    for (name, content) in files {
+        repo.add_file_to_graph(&dir.path().join(name), content);
+    }
+    (dir, repo)
+}
+
+fn uri(dir: &TempDir, name: &str) -> Url {
+    Url::from_file_path(dir.path().join(name)).unwrap()
+}
+
+/// `source` links to `hub`, `alpha` and `beta`; `hub` is shared with two notes,
+/// the others with one each, so only the order of `alpha` and `beta` is a tie
+const FILES: &[(&str, &str)] = &[
+    ("source.pn", "[beta]\n[hub]\n[alpha]\n"),
+    ("hub.pn", "hub\n"),
+    ("alpha.pn", "alpha\n"),
+    ("beta.pn", "beta\n"),
+    (
+        "zeta.pn",
+        "first line\n  see [hub] and [hub] again\nalso [alpha]\n",
+    ),
+    ("eta.pn", "[hub]\n[beta]\n"),
+];
+
+#[tokio::test]
+async fn test_two_hop_notes_ordering() {
+    let (dir, repo) = workspace(FILES);
+
+    let notes = repo.two_hop_notes(&uri(&dir, "source.pn"));
+    let shape: Vec<(Url, Vec<Url>)> = notes
+        .iter()
+        .map(|(via, notes)| (via.clone(), notes.iter().map(|(n, _)| n.clone()).collect()))
+        .collect();
+    assert_eq!(
+        shape,
+        vec![
+            (
+                uri(&dir, "hub.pn"),
+                vec![uri(&dir, "eta.pn"), uri(&dir, "zeta.pn")]
+            ),
+            (uri(&dir, "alpha.pn"), vec![uri(&dir, "zeta.pn")]),
+            (uri(&dir, "beta.pn"), vec![uri(&dir, "eta.pn")]),
+        ]
+    );
+
+    // The snippet is the first line linking to the intermediary
+    let (_, hub_notes) = &notes[0];
+    assert_eq!(hub_notes[0].1.as_deref(), Some("[hub]"));
+    assert_eq!(hub_notes[1].1.as_deref(), Some("see [hub] and [hub] again"));
+}
+
+#[tokio::test]
+async fn test_two_hop_links_are_deterministic() {
+    let (dir, repo) = workspace(FILES);
+    let expected = vec![
+        (
+            "hub".to_string(),
+            vec!["eta".to_string(), "zeta".to_string()],
+        ),
+        ("alpha".to_string(), vec!["zeta".to_string()]),
+        ("beta".to_string(), vec!["eta".to_string()]),
+    ];
+    for _ in 0..5 {
+        let links = repo
+            .calculate_two_hop_links(&dir.path().join("source.pn"))
+            .await;
+        assert_eq!(links, expected);
+    }
+}